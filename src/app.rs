@@ -1,12 +1,31 @@
 use iced::{Element, Subscription, Task};
 use iced_multi_window::Window;
-use crate::state::{RealOne, Message};
-use crate::ui::windows::{MainWindow, SettingsWindow};
-use crate::hash::calculate_hashes_parallel_streaming;
+use crate::state::{InputMode, RealOne, Message};
+use crate::ui::windows::{ArchiveWindow, BinaryWindow, CanonicalJsonWindow, CdcWindow, FollowWindow, ImageCompareWindow, MainWindow, OciWindow, PiecewiseWindow, RdiffWindow, SettingsWindow, SimilarityWindow, SplitPartsWindow, TorrentWindow};
+use crate::hash::piecewise::{compare_manifests, PiecewiseManifest, PiecewiseOutcome};
+use crate::hash::cdc::analyze_files;
+use crate::hash::fuzzy::{FuzzyDigests, SimilarityReport};
+use crate::hash::perceptual::{image_hashes, is_image_file, parse_perceptual_hash, HashDistance};
+use crate::binary::analyze_binary;
+use crate::archive::{hash_members, list_archive};
+use crate::archive::compression::Compression;
+use crate::hash::jcs::canonical_json_of_file;
+use crate::rdiff::{apply_delta, create_delta, RdiffOutcome, Signature};
+use crate::torrent::verify_torrent;
+use crate::oci::verify_layout;
+use crate::hash::{calculate_hash_from_data, calculate_hashes_parallel_streaming};
+use crate::hash::text::{decode_text_input, TextEncoding};
 use crate::ui::views;
 use crate::config::save_config;
-use std::time::Instant;
-use std::collections::HashMap;
+use crate::export::ExportedResults;
+use crate::hash::range::ByteRange;
+use crate::hash::split::{hash_split_set, SplitHashes, SplitSet};
+use crate::hash::checkpoint::{hash_with_resume, ResumableHashes};
+use crate::hash::follow::{Follower, POLL_INTERVAL};
+use crate::hash::normalize::Normalization;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::path::PathBuf;
 use std::process;
 
 pub fn update(state: &mut RealOne, message: Message) -> Task<Message> {
@@ -22,89 +41,1202 @@ pub fn update(state: &mut RealOne, message: Message) -> Task<Message> {
                 Message::FileSelected,
             )
         }
+        Message::BrowseFolder => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_folder()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::FileSelected,
+            )
+        }
         Message::FileSelected(path) => {
+            state.file_is_image = path.as_deref().is_some_and(is_image_file);
             state.file_path = path;
             state.hash_results.clear();
+            state.split_set = None;
+            state.split_parts.clear();
             state.file_error = None; // Clear error when a file is selected
             Task::none()
         }
-        Message::CheckHashChanged(hash) => {
-            state.check_hash = hash;
+        Message::CheckHashChanged(hash) => {
+            state.check_hash = hash;
+            Task::none()
+        }
+        Message::CheckButtonPressed if state.input_mode == InputMode::Text => hash_entered_text(state),
+        Message::CheckButtonPressed => {
+            let range = match ByteRange::from_fields(state.range_mode, &state.range_first, &state.range_second) {
+                Ok(range) => range,
+                Err(e) => {
+                    state.file_error = Some(format!("Invalid range: {}", e));
+                    return Task::none();
+                }
+            };
+            
+            if let Some(ref path) = state.file_path {
+                let algorithms = state.active_algorithms();
+                let options = state.hash_options.clone();
+                let path_clone = path.clone();
+                let normalization = state.normalization;
+                let decompress = state.decompress;
+                
+                // Clear error if file exists
+                state.file_error = None;
+                
+                // Start calculation - save start time
+                state.calculation_start = Some(Instant::now());
+                state.hash_results.clear(); // Clear previous results
+                state.results_range = range;
+                state.results_normalization = normalization;
+                state.export_status = None;
+                
+                // A numbered part (image.7z.001, x.part01.rar) stands for the
+                // whole set, which is hashed as one file
+                state.split_set = SplitSet::detect(path);
+                state.split_parts.clear();
+                state.results_resumed_from = None;
+                state.results_resume_skipped = None;
+                let first_part = state.split_set.as_ref().map_or(path, |set| &set.parts[0]);
+                state.results_compression = if decompress { Compression::detect_file(first_part) } else { Compression::None };
+                
+                // OPTIMIZATION: Read file ONCE using streaming
+                // Process all algorithms in a single pass, without loading everything into memory
+                let algorithms_for_thread = algorithms.clone();
+                let algorithms_for_error = algorithms.clone();
+                
+                if let Some(set) = state.split_set.clone() {
+                    return Task::perform(
+                        async move {
+                            std::thread::spawn(move || {
+                                hash_split_set(&set, &algorithms_for_thread, &options, range.as_ref(), normalization, decompress)
+                            })
+                            .join()
+                            .unwrap_or_else(|_| SplitHashes {
+                                results: algorithms_for_error
+                                    .iter()
+                                    .map(|alg| (alg.clone(), "Error: Thread panicked".to_string()))
+                                    .collect(),
+                                parts: Vec::new(),
+                            })
+                        },
+                        Message::SplitSetHashed,
+                    );
+                }
+                
+                if state.resume {
+                    return Task::perform(
+                        async move {
+                            std::thread::spawn(move || {
+                                hash_with_resume(
+                                    &path_clone,
+                                    &algorithms_for_thread,
+                                    &options,
+                                    range.as_ref(),
+                                    normalization,
+                                    decompress,
+                                )
+                            })
+                            .join()
+                            .unwrap_or_else(|_| ResumableHashes {
+                                results: algorithms_for_error
+                                    .iter()
+                                    .map(|alg| (alg.clone(), "Error: Thread panicked".to_string()))
+                                    .collect(),
+                                resumed_from: None,
+                                skipped: None,
+                            })
+                        },
+                        Message::ResumableHashed,
+                    );
+                }
+                
+                Task::perform(
+                    async move {
+                        // Execute in a separate thread to avoid blocking the UI
+                        std::thread::spawn(move || {
+                            calculate_hashes_parallel_streaming(
+                                &path_clone,
+                                &algorithms_for_thread,
+                                &options,
+                                range.as_ref(),
+                                normalization,
+                                decompress,
+                            )
+                        })
+                        .join()
+                        .unwrap_or_else(|_| {
+                            algorithms_for_error.iter().map(|alg| (alg.clone(), "Error: Thread panicked".to_string())).collect()
+                        })
+                    },
+                    |results| Message::HashesCalculated(results),
+                )
+            } else {
+                // No file selected - show error
+                state.file_error = Some("Missing file".to_string());
+                Task::none()
+            }
+        }
+        Message::HashesCalculated(results) => {
+            store_results(state, results);
+            // Clear calculation state
+            state.calculation_start = None;
+            Task::none()
+        }
+        Message::SplitSetHashed(hashes) => {
+            store_results(state, hashes.results);
+            state.split_parts = hashes.parts;
+            state.calculation_start = None;
+            Task::none()
+        }
+        Message::TextHashed(generation, results) => {
+            // Results of an edit that has since been superseded are dropped
+            if generation == state.text_generation && state.input_mode == InputMode::Text {
+                store_results(state, results);
+            }
+            Task::none()
+        }
+        Message::ResumableHashed(hashes) => {
+            store_results(state, hashes.results);
+            state.results_resumed_from = hashes.resumed_from;
+            state.results_resume_skipped = hashes.skipped;
+            state.calculation_start = None;
+            Task::none()
+        }
+        Message::ViewSplitParts => {
+            if !state.window_manager.any_of(&SplitPartsWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(SplitPartsWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::FollowButtonPressed => {
+            if !state.window_manager.any_of(&FollowWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(FollowWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::BrowseFollowFile => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::FollowFileSelected,
+            )
+        }
+        Message::FollowFileSelected(path) => {
+            if path.is_some() {
+                state.follow_file = path;
+                state.follower = None;
+                state.follow_update = None;
+                state.follow_error = None;
+            }
+            Task::none()
+        }
+        Message::StartFollowing => {
+            let path = match (&state.follow_file, &state.follower) {
+                (Some(path), None) => path.clone(),
+                _ => return Task::none(),
+            };
+            
+            let follower = Arc::new(Mutex::new(Follower::new(&path, &state.selected_algorithms, &state.hash_options)));
+            state.follower = Some(follower.clone());
+            state.follow_generation += 1;
+            state.follow_update = None;
+            state.follow_error = None;
+            
+            poll_follower(follower, state.follow_generation, Duration::ZERO)
+        }
+        Message::StopFollowing => {
+            state.follower = None;
+            Task::none()
+        }
+        Message::FollowPolled(generation, result) => {
+            // A poll that finishes after Stop, or after a new start, is dropped
+            let follower = match &state.follower {
+                Some(follower) if generation == state.follow_generation => follower.clone(),
+                _ => return Task::none(),
+            };
+            
+            match result {
+                Ok(mut update) => {
+                    // Digests are only recomputed when the file changed
+                    if update.results.is_none() {
+                        update.results = state.follow_update.as_mut().and_then(|previous| previous.results.take());
+                    }
+                    state.follow_update = Some(update);
+                    state.follow_error = None;
+                }
+                // The file can be missing for a moment while it is rotated, so
+                // polling goes on
+                Err(e) => state.follow_error = Some(e),
+            }
+            
+            poll_follower(follower, generation, POLL_INTERVAL)
+        }
+        Message::ProgressUpdate => {
+            // No longer used, but kept for compatibility
+            Task::none()
+        }
+        Message::HashCalculated(algorithm, hash) => {
+            state.hash_results.insert(algorithm, hash);
+            Task::none()
+        }
+        Message::SettingsButtonPressed => {
+            if !state.window_manager.any_of(&SettingsWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(SettingsWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::TorrentButtonPressed => {
+            if !state.window_manager.any_of(&TorrentWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(TorrentWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::BrowseTorrent => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .add_filter("Torrent", &["torrent"])
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::TorrentSelected,
+            )
+        }
+        Message::TorrentSelected(path) => {
+            if path.is_some() {
+                state.torrent_path = path;
+                state.torrent_result = None;
+            }
+            Task::none()
+        }
+        Message::BrowseTorrentContentFile => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::TorrentContentSelected,
+            )
+        }
+        Message::BrowseTorrentContentFolder => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_folder()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::TorrentContentSelected,
+            )
+        }
+        Message::TorrentContentSelected(path) => {
+            if path.is_some() {
+                state.torrent_content = path;
+                state.torrent_result = None;
+            }
+            Task::none()
+        }
+        Message::VerifyTorrent => {
+            match (state.torrent_path.clone(), state.torrent_content.clone()) {
+                (Some(torrent_path), Some(content)) if !state.torrent_running => {
+                    state.torrent_running = true;
+                    state.torrent_result = None;
+                    
+                    Task::perform(
+                        async move {
+                            // Execute in a separate thread to avoid blocking the UI
+                            std::thread::spawn(move || verify_torrent(&torrent_path, &content))
+                                .join()
+                                .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+                        },
+                        Message::TorrentVerified,
+                    )
+                }
+                _ => Task::none(),
+            }
+        }
+        Message::TorrentVerified(result) => {
+            state.torrent_running = false;
+            state.torrent_result = Some(result);
+            Task::none()
+        }
+        Message::OciButtonPressed => {
+            if !state.window_manager.any_of(&OciWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(OciWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::BrowseOciLayout => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_folder()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::OciLayoutSelected,
+            )
+        }
+        Message::OciLayoutSelected(path) => {
+            if path.is_some() {
+                state.oci_layout = path;
+                state.oci_result = None;
+            }
+            Task::none()
+        }
+        Message::VerifyOciLayout => {
+            match state.oci_layout.clone() {
+                Some(layout) if !state.oci_running => {
+                    state.oci_running = true;
+                    state.oci_result = None;
+                    
+                    Task::perform(
+                        async move {
+                            // Execute in a separate thread to avoid blocking the UI
+                            std::thread::spawn(move || verify_layout(&layout))
+                                .join()
+                                .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+                        },
+                        Message::OciVerified,
+                    )
+                }
+                _ => Task::none(),
+            }
+        }
+        Message::OciVerified(result) => {
+            state.oci_running = false;
+            state.oci_result = Some(result);
+            Task::none()
+        }
+        Message::CdcButtonPressed => {
+            if !state.window_manager.any_of(&CdcWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(CdcWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::AddCdcFiles => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_files()
+                        .await
+                        .map(|files| files.iter().map(|f| f.path().to_path_buf()).collect())
+                },
+                Message::CdcFilesSelected,
+            )
+        }
+        Message::CdcFilesSelected(paths) => {
+            for path in paths.unwrap_or_default() {
+                if !state.cdc_files.contains(&path) {
+                    state.cdc_files.push(path);
+                    state.cdc_result = None;
+                }
+            }
+            Task::none()
+        }
+        Message::ClearCdcFiles => {
+            state.cdc_files.clear();
+            state.cdc_result = None;
+            Task::none()
+        }
+        Message::CdcMinChanged(value) => {
+            state.cdc_min = value;
+            Task::none()
+        }
+        Message::CdcAvgChanged(value) => {
+            state.cdc_avg = value;
+            Task::none()
+        }
+        Message::CdcMaxChanged(value) => {
+            state.cdc_max = value;
+            Task::none()
+        }
+        Message::CdcAlgorithmChanged(algorithm) => {
+            state.cdc_algorithm = algorithm;
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            Task::none()
+        }
+        Message::AnalyzeCdc => {
+            if state.cdc_running || state.cdc_files.is_empty() {
+                return Task::none();
+            }
+            let params = match state.cdc_params() {
+                Ok(params) => params,
+                Err(e) => {
+                    state.cdc_result = Some(Err(e));
+                    return Task::none();
+                }
+            };
+            
+            // Remember sizes that worked for the next session
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            let files = state.cdc_files.clone();
+            let algorithm = state.cdc_algorithm.clone();
+            let options = state.hash_options.clone();
+            state.cdc_running = true;
+            state.cdc_result = None;
+            
+            Task::perform(
+                async move {
+                    // Execute in a separate thread to avoid blocking the UI
+                    std::thread::spawn(move || analyze_files(&files, params, &algorithm, &options))
+                        .join()
+                        .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+                },
+                Message::CdcFinished,
+            )
+        }
+        Message::CdcFinished(result) => {
+            state.cdc_running = false;
+            state.cdc_result = Some(result);
+            Task::none()
+        }
+        Message::RdiffButtonPressed => {
+            if !state.window_manager.any_of(&RdiffWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(RdiffWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::BrowseRdiffFile => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::RdiffFileSelected,
+            )
+        }
+        Message::RdiffFileSelected(path) => {
+            if path.is_some() {
+                state.rdiff_file = path;
+                state.rdiff_result = None;
+            }
+            Task::none()
+        }
+        Message::RdiffFormatChanged(format) => {
+            state.rdiff_format = format;
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            Task::none()
+        }
+        Message::RdiffBlockLengthChanged(block_length) => {
+            state.rdiff_block_length = block_length;
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            Task::none()
+        }
+        Message::CreateRdiffSignature => save_rdiff_output(state, "sig", Message::RdiffSignatureTargetSelected),
+        Message::RdiffSignatureTargetSelected(target) => {
+            match (state.rdiff_file.clone(), target) {
+                (Some(path), Some(target)) => {
+                    let format = state.rdiff_format;
+                    let block_length = state.rdiff_block_length;
+                    
+                    run_rdiff(state, move || {
+                        let signature = Signature::compute(&path, format, block_length)?;
+                        signature.save(&target)?;
+                        Ok(RdiffOutcome::Signature {
+                            path: target,
+                            format,
+                            block_length,
+                            blocks: signature.blocks.len(),
+                        })
+                    })
+                }
+                _ => Task::none(),
+            }
+        }
+        Message::CreateRdiffDelta => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Signature of the old file")
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::RdiffDeltaSignatureSelected,
+            )
+        }
+        Message::RdiffDeltaSignatureSelected(signature) => {
+            if signature.is_none() {
+                return Task::none();
+            }
+            state.rdiff_input = signature;
+            save_rdiff_output(state, "delta", Message::RdiffDeltaTargetSelected)
+        }
+        Message::RdiffDeltaTargetSelected(target) => {
+            match (state.rdiff_file.clone(), state.rdiff_input.take(), target) {
+                (Some(path), Some(signature_path), Some(target)) => {
+                    run_rdiff(state, move || {
+                        let signature = Signature::load(&signature_path)?;
+                        let stats = create_delta(&signature, &path, &target)?;
+                        Ok(RdiffOutcome::Delta { path: target, stats })
+                    })
+                }
+                _ => Task::none(),
+            }
+        }
+        Message::ApplyRdiffDelta => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .set_title("Delta to apply")
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::RdiffPatchDeltaSelected,
+            )
+        }
+        Message::RdiffPatchDeltaSelected(delta) => {
+            if delta.is_none() {
+                return Task::none();
+            }
+            state.rdiff_input = delta;
+            save_rdiff_output(state, "patched", Message::RdiffPatchTargetSelected)
+        }
+        Message::RdiffPatchTargetSelected(target) => {
+            match (state.rdiff_file.clone(), state.rdiff_input.take(), target) {
+                (Some(basis), Some(delta), Some(target)) => {
+                    let algorithms = state.selected_algorithms.clone();
+                    let options = state.hash_options.clone();
+                    
+                    // The rebuilt file is checked with the algorithms selected in the main window
+                    run_rdiff(state, move || {
+                        let size = apply_delta(&basis, &delta, &target)?;
+                        let hashes = calculate_hashes_parallel_streaming(
+                            &target,
+                            &algorithms,
+                            &options,
+                            None,
+                            Normalization::default(),
+                            false,
+                        );
+                        Ok(RdiffOutcome::Patched { path: target, size, hashes })
+                    })
+                }
+                _ => Task::none(),
+            }
+        }
+        Message::RdiffFinished(result) => {
+            state.rdiff_running = false;
+            state.rdiff_result = Some(result);
+            Task::none()
+        }
+        Message::SimilarityButtonPressed => {
+            if !state.window_manager.any_of(&SimilarityWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(SimilarityWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::BrowseSimilarityFirst => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::SimilarityFirstSelected,
+            )
+        }
+        Message::SimilarityFirstSelected(path) => {
+            if path.is_some() {
+                state.similarity_first = path;
+                state.similarity_result = None;
+            }
+            Task::none()
+        }
+        Message::BrowseSimilaritySecond => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::SimilaritySecondSelected,
+            )
+        }
+        Message::SimilaritySecondSelected(path) => {
+            if path.is_some() {
+                state.similarity_second = path;
+                state.similarity_result = None;
+            }
+            Task::none()
+        }
+        Message::SimilarityHashChanged(value) => {
+            state.similarity_hash = value;
+            state.similarity_result = None;
+            Task::none()
+        }
+        Message::CompareSimilarity => {
+            let first = match (&state.similarity_first, state.similarity_running) {
+                (Some(first), false) => first.clone(),
+                _ => return Task::none(),
+            };
+            
+            // A pasted hash takes the place of the second file
+            let pasted = if state.similarity_hash.trim().is_empty() {
+                None
+            } else {
+                match FuzzyDigests::parse(&state.similarity_hash) {
+                    Ok(digests) => Some(digests),
+                    Err(e) => {
+                        state.similarity_result = Some(Err(e));
+                        return Task::none();
+                    }
+                }
+            };
+            let second = state.similarity_second.clone();
+            if pasted.is_none() && second.is_none() {
+                return Task::none();
+            }
+            let options = state.hash_options.clone();
+            
+            state.similarity_running = true;
+            state.similarity_result = None;
+            
+            Task::perform(
+                async move {
+                    std::thread::spawn(move || {
+                        let first = FuzzyDigests::of_file(&first, &options);
+                        let second = match (pasted, second) {
+                            (Some(digests), _) => digests,
+                            (None, Some(path)) => FuzzyDigests::of_file(&path, &options),
+                            (None, None) => return Err("nothing to compare with".to_string()),
+                        };
+                        Ok(SimilarityReport::compare(first, second))
+                    })
+                    .join()
+                    .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+                },
+                Message::SimilarityFinished,
+            )
+        }
+        Message::SimilarityFinished(result) => {
+            state.similarity_running = false;
+            state.similarity_result = Some(result);
+            Task::none()
+        }
+        Message::ImageCompareButtonPressed => {
+            if !state.window_manager.any_of(&ImageCompareWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(ImageCompareWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::BrowseImageFirst => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::ImageFirstSelected,
+            )
+        }
+        Message::ImageFirstSelected(path) => {
+            if path.is_some() {
+                state.image_first = path;
+                state.image_result = None;
+            }
+            Task::none()
+        }
+        Message::BrowseImageSecond => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::ImageSecondSelected,
+            )
+        }
+        Message::ImageSecondSelected(path) => {
+            if path.is_some() {
+                state.image_second = path;
+                state.image_result = None;
+            }
+            Task::none()
+        }
+        Message::ImageHashChanged(value) => {
+            state.image_hash = value;
+            state.image_result = None;
+            Task::none()
+        }
+        Message::ImageAlgorithmChanged(algorithm) => {
+            state.image_algorithm = algorithm;
+            state.image_result = None;
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            Task::none()
+        }
+        Message::CompareImages => {
+            let first = match (&state.image_first, state.image_running) {
+                (Some(first), false) => first.clone(),
+                _ => return Task::none(),
+            };
+            
+            // A pasted hash takes the place of the second image
+            let pasted = if state.image_hash.trim().is_empty() {
+                None
+            } else {
+                match parse_perceptual_hash(&state.image_hash) {
+                    Ok(hash) => Some((state.image_algorithm.clone(), hash)),
+                    Err(e) => {
+                        state.image_result = Some(Err(e));
+                        return Task::none();
+                    }
+                }
+            };
+            let second = state.image_second.clone();
+            if pasted.is_none() && second.is_none() {
+                return Task::none();
+            }
+            
+            state.image_running = true;
+            state.image_result = None;
+            
+            Task::perform(
+                async move {
+                    std::thread::spawn(move || {
+                        let first = image_hashes(&first)?;
+                        match (pasted, second) {
+                            (Some((algorithm, expected)), _) => Ok(first
+                                .into_iter()
+                                .filter(|(alg, _)| *alg == algorithm)
+                                .map(|(alg, hash)| HashDistance::new(alg, hash, expected))
+                                .collect()),
+                            (None, Some(path)) => Ok(first
+                                .into_iter()
+                                .zip(image_hashes(&path)?)
+                                .map(|((alg, a), (_, b))| HashDistance::new(alg, a, b))
+                                .collect()),
+                            (None, None) => Err("nothing to compare with".to_string()),
+                        }
+                    })
+                    .join()
+                    .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+                },
+                Message::ImagesCompared,
+            )
+        }
+        Message::ImagesCompared(result) => {
+            state.image_running = false;
+            state.image_result = Some(result);
+            Task::none()
+        }
+        Message::BinaryButtonPressed => {
+            if !state.window_manager.any_of(&BinaryWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(BinaryWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::BrowseBinaryFile => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::BinaryFileSelected,
+            )
+        }
+        Message::BinaryFileSelected(path) => {
+            if path.is_some() {
+                state.binary_file = path;
+                state.binary_result = None;
+            }
+            Task::none()
+        }
+        Message::AnalyzeBinary => {
+            let path = match (&state.binary_file, state.binary_running) {
+                (Some(path), false) => path.clone(),
+                _ => return Task::none(),
+            };
+            
+            state.binary_running = true;
+            state.binary_result = None;
+            
+            Task::perform(
+                async move {
+                    std::thread::spawn(move || analyze_binary(&path))
+                        .join()
+                        .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+                },
+                Message::BinaryAnalyzed,
+            )
+        }
+        Message::BinaryAnalyzed(result) => {
+            state.binary_running = false;
+            state.binary_result = Some(result);
+            Task::none()
+        }
+        Message::ViewCanonicalJson => {
+            let open = if !state.window_manager.any_of(&CanonicalJsonWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(CanonicalJsonWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            };
+            
+            // Rebuilt with the settings the current results were computed with
+            let normalization = Normalization { canonical_json: true, ..state.results_normalization };
+            state.canonical_json = None;
+            let load = match state.input_mode {
+                InputMode::Text => {
+                    // The canonical text is the same before it is encoded as UTF-16
+                    let encoding = match state.text_encoding {
+                        TextEncoding::Utf16Le => TextEncoding::Utf8,
+                        other => other,
+                    };
+                    let result = decode_text_input(&state.entered_text(), encoding, state.trailing_newline, normalization)
+                        .map(|data| String::from_utf8_lossy(&data).into_owned());
+                    state.canonical_json = Some(result);
+                    Task::none()
+                }
+                InputMode::File => match state.file_path.clone() {
+                    Some(path) => {
+                        let range = state.results_range;
+                        let compression = state.results_compression;
+                        Task::perform(
+                            async move {
+                                std::thread::spawn(move || {
+                                    canonical_json_of_file(&path, range.as_ref(), normalization, compression)
+                                })
+                                    .join()
+                                    .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+                            },
+                            Message::CanonicalJsonLoaded,
+                        )
+                    }
+                    None => Task::none(),
+                },
+            };
+            Task::batch([open, load])
+        }
+        Message::CanonicalJsonLoaded(result) => {
+            state.canonical_json = Some(result);
+            Task::none()
+        }
+        Message::ArchiveButtonPressed => {
+            if !state.window_manager.any_of(&ArchiveWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(ArchiveWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
+            } else {
+                Task::none()
+            }
+        }
+        Message::BrowseArchive => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .add_filter("Archives", &["zip", "tar", "gz", "tgz", "xz", "txz", "zst", "tzst"])
+                        .add_filter("All files", &["*"])
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::ArchiveSelected,
+            )
+        }
+        Message::ArchiveSelected(path) => {
+            let path = match (path, state.archive_running) {
+                (Some(path), false) => path,
+                _ => return Task::none(),
+            };
+            state.archive_path = Some(path.clone());
+            state.archive_listing = None;
+            state.archive_selected.clear();
+            state.archive_results = None;
+            state.archive_running = true;
+            
+            // Compressed tar files have to be read to the end to list them
+            Task::perform(
+                async move {
+                    std::thread::spawn(move || list_archive(&path))
+                        .join()
+                        .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+                },
+                Message::ArchiveListed,
+            )
+        }
+        Message::ArchiveListed(result) => {
+            state.archive_running = false;
+            state.archive_listing = Some(result);
+            Task::none()
+        }
+        Message::ArchiveMemberToggled(index, checked) => {
+            state.archive_selected.retain(|&selected| selected != index);
+            if checked {
+                state.archive_selected.push(index);
+            }
             Task::none()
         }
-        Message::CheckButtonPressed => {
-            if let Some(ref path) = state.file_path {
-                let algorithms = state.selected_algorithms.clone();
-                let gost_variant = state.gost_variant;
-                let tiger_variant = state.tiger_variant;
-                let path_clone = path.clone();
-                
-                // Clear error if file exists
-                state.file_error = None;
-                
-                // Start calculation - save start time
-                state.calculation_start = Some(Instant::now());
-                state.hash_results.clear(); // Clear previous results
-                
-                // OPTIMIZATION: Read file ONCE using streaming
-                // Process all algorithms in a single pass, without loading everything into memory
-                let algorithms_for_thread = algorithms.clone();
-                let algorithms_for_error = algorithms.clone();
-                
-                Task::perform(
-                    async move {
-                        // Execute in a separate thread to avoid blocking the UI
-                        std::thread::spawn(move || {
-                            calculate_hashes_parallel_streaming(&path_clone, &algorithms_for_thread, gost_variant, tiger_variant)
-                        })
+        Message::HashSelectedMembers | Message::HashAllMembers => {
+            let (path, listing) = match (&state.archive_path, &state.archive_listing, state.archive_running) {
+                (Some(path), Some(Ok(listing)), false) => (path.clone(), listing.clone()),
+                _ => return Task::none(),
+            };
+            let indexes = match message {
+                Message::HashSelectedMembers if state.archive_selected.is_empty() => return Task::none(),
+                Message::HashSelectedMembers => Some(state.archive_selected.clone()),
+                _ => None,
+            };
+            // Perceptual hashes are left out, as for piecewise hashing
+            let algorithms: Vec<_> = state
+                .selected_algorithms
+                .iter()
+                .filter(|algorithm| !algorithm.is_perceptual())
+                .cloned()
+                .collect();
+            let options = state.hash_options.clone();
+            
+            state.archive_running = true;
+            state.archive_results = None;
+            
+            Task::perform(
+                async move {
+                    std::thread::spawn(move || hash_members(&path, &listing, indexes.as_deref(), &algorithms, &options))
                         .join()
-                        .unwrap_or_else(|_| {
-                            algorithms_for_error.iter().map(|alg| (alg.clone(), "Error: Thread panicked".to_string())).collect()
-                        })
-                    },
-                    |results| Message::HashesCalculated(results),
-                )
+                        .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+                },
+                Message::ArchiveMembersHashed,
+            )
+        }
+        Message::ArchiveMembersHashed(result) => {
+            state.archive_running = false;
+            state.archive_results = Some(result);
+            Task::none()
+        }
+        Message::CopyCanonicalJson => {
+            match &state.canonical_json {
+                Some(Ok(canonical)) => copy_to_clipboard(canonical.clone()),
+                _ => Task::none(),
+            }
+        }
+        Message::PiecewiseButtonPressed => {
+            if !state.window_manager.any_of(&PiecewiseWindow) {
+                let (_id, task) = state.window_manager.open(Box::new(PiecewiseWindow));
+                task.map(|_id| {
+                    Message::WindowClosed(iced::window::Id::unique())
+                })
             } else {
-                // No file selected - show error
-                state.file_error = Some("Missing file".to_string());
                 Task::none()
             }
         }
-        Message::HashesCalculated(results) => {
-            // Update all results at once
-            // Hashes come in lowercase hex format; convert them to the selected format
-            for (algorithm, hash_hex) in results {
-                // Decode hex and convert to selected format
-                if let Ok(hash_bytes) = hex::decode(&hash_hex) {
-                    let formatted = state.hash_format.format_hash(&hash_bytes);
-                    state.hash_results.insert(algorithm, formatted);
-                } else {
-                    // If not valid hex (may be "Not implemented"), keep it as is
-                    state.hash_results.insert(algorithm, hash_hex);
-                }
+        Message::BrowsePiecewiseFile => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::PiecewiseFileSelected,
+            )
+        }
+        Message::PiecewiseFileSelected(path) => {
+            if path.is_some() {
+                state.piecewise_file = path;
+                state.piecewise_result = None;
             }
-            // Clear calculation state
-            state.calculation_start = None;
             Task::none()
         }
-        Message::ProgressUpdate => {
-            // No longer used, but kept for compatibility
+        Message::PiecewiseAlgorithmChanged(algorithm) => {
+            state.piecewise_algorithm = algorithm;
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
             Task::none()
         }
-        Message::HashCalculated(algorithm, hash) => {
-            state.hash_results.insert(algorithm, hash);
+        Message::PiecewiseWindowChanged(window) => {
+            state.piecewise_window = window;
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
             Task::none()
         }
-        Message::SettingsButtonPressed => {
-            if !state.window_manager.any_of(&SettingsWindow) {
-                let (_id, task) = state.window_manager.open(Box::new(SettingsWindow));
-                task.map(|_id| {
-                    Message::WindowClosed(iced::window::Id::unique())
-                })
-            } else {
-                Task::none()
+        Message::CreatePiecewiseManifest => {
+            let file_name = state
+                .piecewise_file
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map(|name| format!("{}.piecewise.txt", name.to_string_lossy()))
+                .unwrap_or_default();
+            Task::perform(
+                async move {
+                    rfd::AsyncFileDialog::new()
+                        .set_file_name(file_name)
+                        .save_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::PiecewiseManifestTargetSelected,
+            )
+        }
+        Message::PiecewiseManifestTargetSelected(target) => {
+            match (state.piecewise_file.clone(), target) {
+                (Some(path), Some(target)) => {
+                    let algorithm = state.piecewise_algorithm.clone();
+                    let options = state.hash_options.clone();
+                    let window = state.piecewise_window.bytes();
+                    
+                    run_piecewise(state, move || {
+                        let manifest = PiecewiseManifest::compute(&path, &algorithm, &options, window)?;
+                        let file_name = path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        manifest.save(&target, &file_name)?;
+                        Ok(PiecewiseOutcome::Created {
+                            path: target,
+                            windows: manifest.windows.len(),
+                            total: manifest.total,
+                        })
+                    })
+                }
+                _ => Task::none(),
+            }
+        }
+        Message::CompareWithManifest => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::PiecewiseManifestSelected,
+            )
+        }
+        Message::PiecewiseManifestSelected(manifest_path) => {
+            match (state.piecewise_file.clone(), manifest_path) {
+                (Some(path), Some(manifest_path)) => {
+                    let options = state.hash_options.clone();
+                    
+                    // The file is hashed with the manifest's algorithm and window
+                    run_piecewise(state, move || {
+                        let expected = PiecewiseManifest::load(&manifest_path)?;
+                        let actual = PiecewiseManifest::compute(&path, &expected.algorithm, &options, expected.window)?;
+                        compare_manifests(&expected, &actual).map(PiecewiseOutcome::Compared)
+                    })
+                }
+                _ => Task::none(),
+            }
+        }
+        Message::CompareManifests => {
+            Task::perform(
+                async {
+                    rfd::AsyncFileDialog::new()
+                        .pick_files()
+                        .await
+                        .map(|files| files.iter().map(|f| f.path().to_path_buf()).collect())
+                },
+                Message::PiecewiseManifestsSelected,
+            )
+        }
+        Message::PiecewiseManifestsSelected(paths) => {
+            match paths.as_deref() {
+                Some([expected, actual]) => {
+                    let (expected, actual) = (expected.clone(), actual.clone());
+                    run_piecewise(state, move || {
+                        let expected = PiecewiseManifest::load(&expected)?;
+                        let actual = PiecewiseManifest::load(&actual)?;
+                        compare_manifests(&expected, &actual).map(PiecewiseOutcome::Compared)
+                    })
+                }
+                Some(_) => {
+                    state.piecewise_result = Some(Err("Select exactly two manifests to compare".to_string()));
+                    Task::none()
+                }
+                None => Task::none(),
             }
         }
+        Message::PiecewiseFinished(result) => {
+            state.piecewise_running = false;
+            state.piecewise_result = Some(result);
+            Task::none()
+        }
         Message::WindowClosed(id) => {
             // Check if the main window was closed BEFORE calling was_closed
             // (was_closed removes the window from the list)
@@ -114,6 +1246,11 @@ pub fn update(state: &mut RealOne, message: Message) -> Task<Message> {
             // Now mark the window as closed
             state.window_manager.was_closed(id);
             
+            // Nobody sees the running digests once their window is gone
+            if !state.window_manager.any_of(&FollowWindow) {
+                state.follower = None;
+            }
+            
             // If the main window was closed, exit the application immediately
             // (even if the settings window is still open)
             if is_main_window {
@@ -130,86 +1267,467 @@ pub fn update(state: &mut RealOne, message: Message) -> Task<Message> {
             } else {
                 state.selected_algorithms.retain(|alg| *alg != algorithm);
             }
+            let task = refresh_text_results(state);
             
             // Save configuration when changed
-            if let Err(e) = save_config(&state.selected_algorithms, state.hash_format, state.gost_variant, state.tiger_variant) {
+            if let Err(e) = save_config(state) {
                 eprintln!("Error saving configuration: {}", e);
             }
             
-            Task::none()
+            task
         }
         Message::FormatChanged(format) => {
-            state.hash_format = format;
-            
-            // Reconvert all existing hashes to the new format
-            let mut new_results = HashMap::new();
-            for (algorithm, hash_str) in &state.hash_results {
-                // Try to decode the current hash and convert it to the new format
-                // First try as hex, then as base64
-                use base64::Engine;
-                if let Ok(bytes) = hex::decode(hash_str.trim().replace(' ', "")) {
-                    new_results.insert(algorithm.clone(), format.format_hash(&bytes));
-                } else if let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(hash_str.trim().replace(' ', "")) {
-                    new_results.insert(algorithm.clone(), format.format_hash(&bytes));
-                } else {
-                    // If it cannot be decoded, keep it as is
-                    new_results.insert(algorithm.clone(), hash_str.clone());
-                }
-            }
-            state.hash_results = new_results;
+            state.change_hash_format(format);
             
             // Save configuration
-            if let Err(e) = save_config(&state.selected_algorithms, state.hash_format, state.gost_variant, state.tiger_variant) {
+            if let Err(e) = save_config(state) {
                 eprintln!("Error saving configuration: {}", e);
             }
             
             Task::none()
         }
         Message::GostVariantChanged(variant) => {
-            state.gost_variant = variant;
+            state.hash_options.gost_variant = variant;
             // Changing variant invalidates existing GOST hashes; clear them
             state.hash_results.remove(&crate::hash::Algorithm::GOST);
+            let task = refresh_text_results(state);
             
-            if let Err(e) = save_config(&state.selected_algorithms, state.hash_format, state.gost_variant, state.tiger_variant) {
+            if let Err(e) = save_config(state) {
                 eprintln!("Error saving configuration: {}", e);
             }
             
-            Task::none()
+            task
         }
         Message::TigerVariantChanged(variant) => {
-            state.tiger_variant = variant;
+            state.hash_options.tiger_variant = variant;
             state.hash_results.remove(&crate::hash::Algorithm::TIGER192);
+            let task = refresh_text_results(state);
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            task
+        }
+        Message::Blake2bParamsChanged(params) => {
+            state.hash_options.blake2b = params;
+            state.hash_results.remove(&crate::hash::Algorithm::BLAKE2b);
+            let task = refresh_text_results(state);
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            task
+        }
+        Message::Blake2sParamsChanged(params) => {
+            state.hash_options.blake2s = params;
+            state.hash_results.remove(&crate::hash::Algorithm::BLAKE2s);
+            let task = refresh_text_results(state);
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            task
+        }
+        Message::CrcModelChanged(model) => {
+            state.hash_options.crc_model = model;
+            state.hash_results.remove(&crate::hash::Algorithm::CRC);
+            let task = refresh_text_results(state);
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            task
+        }
+        Message::HavalVariantChanged(variant) => {
+            state.hash_options.haval_variant = variant;
+            state.hash_results.remove(&crate::hash::Algorithm::HAVAL);
+            let task = refresh_text_results(state);
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            task
+        }
+        Message::InputModeChanged(mode) => {
+            state.input_mode = mode;
+            state.file_error = None;
+            state.hash_results.clear();
+            state.results_range = None;
+            refresh_text_results(state)
+        }
+        Message::TextInputEdited(action) => {
+            let is_edit = action.is_edit();
+            state.text_input.perform(action);
+            if is_edit {
+                refresh_text_results(state)
+            } else {
+                Task::none()
+            }
+        }
+        Message::TextEncodingChanged(encoding) => {
+            state.text_encoding = encoding;
+            refresh_text_results(state)
+        }
+        Message::TrailingNewlineChanged(newline) => {
+            state.trailing_newline = newline;
+            refresh_text_results(state)
+        }
+        Message::NormalizationChanged(normalization) => {
+            state.normalization = normalization;
+            let task = refresh_text_results(state);
             
-            if let Err(e) = save_config(&state.selected_algorithms, state.hash_format, state.gost_variant, state.tiger_variant) {
+            if let Err(e) = save_config(state) {
                 eprintln!("Error saving configuration: {}", e);
             }
             
+            task
+        }
+        Message::DecompressChanged(decompress) => {
+            state.decompress = decompress;
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            Task::none()
+        }
+        Message::ResumeChanged(resume) => {
+            state.resume = resume;
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            Task::none()
+        }
+        Message::RangeModeChanged(mode) => {
+            state.range_mode = mode;
+            state.file_error = None;
+            Task::none()
+        }
+        Message::RangeFirstChanged(value) => {
+            state.range_first = value;
+            state.file_error = None;
+            Task::none()
+        }
+        Message::RangeSecondChanged(value) => {
+            state.range_second = value;
+            state.file_error = None;
+            Task::none()
+        }
+        Message::ExportResults => {
+            let file_name = state
+                .file_path
+                .as_ref()
+                .and_then(|p| p.file_name())
+                .map(|name| format!("{}.hashes.json", name.to_string_lossy()))
+                .unwrap_or_default();
+            Task::perform(
+                async move {
+                    rfd::AsyncFileDialog::new()
+                        .add_filter("JSON", &["json"])
+                        .set_file_name(file_name)
+                        .save_file()
+                        .await
+                        .map(|f| f.path().to_path_buf())
+                },
+                Message::ExportTargetSelected,
+            )
+        }
+        Message::ExportTargetSelected(target) => {
+            if let Some(target) = target {
+                let result = ExportedResults::from_state(state).and_then(|export| export.save(&target));
+                state.export_status = Some(match result {
+                    Ok(()) => format!("Exported to {}", target.display()),
+                    Err(e) => format!("Export failed: {}", e),
+                });
+            }
             Task::none()
         }
+        Message::Ed2kVariantChanged(variant) => {
+            state.hash_options.ed2k_variant = variant;
+            state.hash_results.remove(&crate::hash::Algorithm::ED2K);
+            let task = refresh_text_results(state);
+            
+            if let Err(e) = save_config(state) {
+                eprintln!("Error saving configuration: {}", e);
+            }
+            
+            task
+        }
         Message::CopyHash(algorithm) => {
             if let Some(hash) = state.hash_results.get(&algorithm) {
-                let hash_clone = hash.clone();
-                Task::perform(
-                    async move {
-                        let mut clipboard = arboard::Clipboard::new().ok()?;
-                        clipboard.set_text(hash_clone).ok()?;
-                        Some(())
-                    },
-                    |_| Message::WindowClosed(iced::window::Id::unique()), // Dummy message, doesn't matter
-                )
+                copy_to_clipboard(hash.clone())
             } else {
                 Task::none()
             }
         }
+        Message::CopyLink(algorithm) => {
+            // The stored result is already formatted, so decode it back to bytes
+            let hash = state
+                .hash_results
+                .get(&algorithm)
+                .and_then(|hash| state.result_format(&algorithm).parse_hash(hash).ok());
+            
+            match (&state.file_path, hash) {
+                (Some(path), Some(hash)) if state.results_cover_whole_file() => {
+                    // A split set was hashed as the joined file, so the link names that
+                    let name_and_size = match &state.split_set {
+                        Some(set) => Ok((set.name.clone(), set.total_size())),
+                        None => std::fs::metadata(path).map(|metadata| {
+                            let file_name = path
+                                .file_name()
+                                .map(|name| name.to_string_lossy().into_owned())
+                                .unwrap_or_default();
+                            (file_name, metadata.len())
+                        }),
+                    };
+                    
+                    match name_and_size {
+                        Ok((file_name, size)) => match algorithm.link(&file_name, size, &hash) {
+                            Some(link) => copy_to_clipboard(link),
+                            None => Task::none(),
+                        },
+                        Err(e) => {
+                            state.file_error = Some(format!("Error: {}", e));
+                            Task::none()
+                        }
+                    }
+                }
+                _ => Task::none(),
+            }
+        }
+    }
+}
+
+// Stores hashes that arrive as lowercase hex, converted to the selected format
+fn store_results(state: &mut RealOne, results: Vec<(crate::hash::Algorithm, String)>) {
+    for (algorithm, hash_hex) in results {
+        // Decode hex and convert to selected format
+        if let Ok(hash_bytes) = hex::decode(&hash_hex) {
+            let formatted = state.result_format(&algorithm).format_hash(&hash_bytes);
+            state.hash_results.insert(algorithm, formatted);
+        } else {
+            // If not valid hex (may be "Not implemented"), keep it as is
+            state.hash_results.insert(algorithm, hash_hex);
+        }
+    }
+}
+
+// Entered text is hashed on a worker thread like files are, since a large
+// paste through the slower algorithms would otherwise hold up the window.
+// Every call starts a new generation, so only the latest edit's results land
+fn hash_entered_text(state: &mut RealOne) -> Task<Message> {
+    state.text_generation += 1;
+    state.hash_results.clear();
+    state.results_range = None;
+    state.results_normalization = state.normalization;
+    state.results_compression = Compression::None;
+    state.results_resumed_from = None;
+    state.results_resume_skipped = None;
+    state.split_set = None;
+    state.split_parts.clear();
+    state.export_status = None;
+    
+    match decode_text_input(&state.entered_text(), state.text_encoding, state.trailing_newline, state.normalization) {
+        Ok(data) => {
+            state.file_error = None;
+            let generation = state.text_generation;
+            let algorithms = state.active_algorithms();
+            let algorithms_for_error = algorithms.clone();
+            let options = state.hash_options.clone();
+            Task::perform(
+                async move {
+                    std::thread::spawn(move || {
+                        algorithms
+                            .iter()
+                            .map(|algorithm| (algorithm.clone(), calculate_hash_from_data(&data, algorithm, &options)))
+                            .collect()
+                    })
+                    .join()
+                    .unwrap_or_else(|_| {
+                        algorithms_for_error.iter().map(|alg| (alg.clone(), "Error: Thread panicked".to_string())).collect()
+                    })
+                },
+                move |results| Message::TextHashed(generation, results),
+            )
+        }
+        Err(e) => {
+            state.file_error = Some(format!("Invalid input: {}", e));
+            Task::none()
+        }
+    }
+}
+
+fn refresh_text_results(state: &mut RealOne) -> Task<Message> {
+    if state.input_mode == InputMode::Text {
+        hash_entered_text(state)
+    } else {
+        Task::none()
     }
 }
 
+// Runs a piecewise job on its own thread and reports back with PiecewiseFinished
+fn run_piecewise(
+    state: &mut RealOne,
+    job: impl FnOnce() -> Result<PiecewiseOutcome, String> + Send + 'static,
+) -> Task<Message> {
+    state.piecewise_running = true;
+    state.piecewise_result = None;
+    
+    Task::perform(
+        async move {
+            std::thread::spawn(job)
+                .join()
+                .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+        },
+        Message::PiecewiseFinished,
+    )
+}
+
+// Asks where to save the output of an rdiff step, suggesting the selected
+// file's name with the given extension
+fn save_rdiff_output(
+    state: &RealOne,
+    extension: &str,
+    on_selected: fn(Option<PathBuf>) -> Message,
+) -> Task<Message> {
+    let file_name = state
+        .rdiff_file
+        .as_ref()
+        .and_then(|p| p.file_name())
+        .map(|name| format!("{}.{}", name.to_string_lossy(), extension))
+        .unwrap_or_default();
+    Task::perform(
+        async move {
+            rfd::AsyncFileDialog::new()
+                .set_file_name(file_name)
+                .save_file()
+                .await
+                .map(|f| f.path().to_path_buf())
+        },
+        on_selected,
+    )
+}
+
+fn run_rdiff(
+    state: &mut RealOne,
+    job: impl FnOnce() -> Result<RdiffOutcome, String> + Send + 'static,
+) -> Task<Message> {
+    state.rdiff_running = true;
+    state.rdiff_result = None;
+    
+    Task::perform(
+        async move {
+            std::thread::spawn(job)
+                .join()
+                .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+        },
+        Message::RdiffFinished,
+    )
+}
+
+// Waits for the delay, then reads what was appended on a worker thread; the
+// lock is only contended if a poll outlives the next one being scheduled
+fn poll_follower(follower: Arc<Mutex<Follower>>, generation: u64, delay: Duration) -> Task<Message> {
+    Task::perform(
+        async move {
+            std::thread::spawn(move || {
+                std::thread::sleep(delay);
+                match follower.lock() {
+                    Ok(mut follower) => follower.poll(),
+                    Err(_) => Err("a previous poll panicked".to_string()),
+                }
+            })
+            .join()
+            .unwrap_or_else(|_| Err("Thread panicked".to_string()))
+        },
+        move |result| Message::FollowPolled(generation, result),
+    )
+}
+
+fn copy_to_clipboard(content: String) -> Task<Message> {
+    Task::perform(
+        async move {
+            let mut clipboard = arboard::Clipboard::new().ok()?;
+            clipboard.set_text(content).ok()?;
+            Some(())
+        },
+        |_| Message::WindowClosed(iced::window::Id::unique()), // Dummy message, doesn't matter
+    )
+}
+
 pub fn view(state: &RealOne, window_id: iced::window::Id) -> Element<'_, Message, iced::Theme> {
     let settings_instances = state.window_manager.instances_of(&SettingsWindow);
     let is_settings_window = settings_instances.iter().any(|(id, _)| *id == &window_id);
     
+    let torrent_instances = state.window_manager.instances_of(&TorrentWindow);
+    let is_torrent_window = torrent_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let piecewise_instances = state.window_manager.instances_of(&PiecewiseWindow);
+    let is_piecewise_window = piecewise_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let oci_instances = state.window_manager.instances_of(&OciWindow);
+    let is_oci_window = oci_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let cdc_instances = state.window_manager.instances_of(&CdcWindow);
+    let is_cdc_window = cdc_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let rdiff_instances = state.window_manager.instances_of(&RdiffWindow);
+    let is_rdiff_window = rdiff_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let similarity_instances = state.window_manager.instances_of(&SimilarityWindow);
+    let is_similarity_window = similarity_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let image_instances = state.window_manager.instances_of(&ImageCompareWindow);
+    let is_image_window = image_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let binary_instances = state.window_manager.instances_of(&BinaryWindow);
+    let is_binary_window = binary_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let canonical_instances = state.window_manager.instances_of(&CanonicalJsonWindow);
+    let is_canonical_window = canonical_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let archive_instances = state.window_manager.instances_of(&ArchiveWindow);
+    let is_archive_window = archive_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let split_instances = state.window_manager.instances_of(&SplitPartsWindow);
+    let is_split_window = split_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let follow_instances = state.window_manager.instances_of(&FollowWindow);
+    let is_follow_window = follow_instances.iter().any(|(id, _)| *id == &window_id);
+    
     if is_settings_window {
         SettingsWindow.view(state)
+    } else if is_torrent_window {
+        TorrentWindow.view(state)
+    } else if is_piecewise_window {
+        PiecewiseWindow.view(state)
+    } else if is_oci_window {
+        OciWindow.view(state)
+    } else if is_cdc_window {
+        CdcWindow.view(state)
+    } else if is_rdiff_window {
+        RdiffWindow.view(state)
+    } else if is_similarity_window {
+        SimilarityWindow.view(state)
+    } else if is_image_window {
+        ImageCompareWindow.view(state)
+    } else if is_binary_window {
+        BinaryWindow.view(state)
+    } else if is_canonical_window {
+        CanonicalJsonWindow.view(state)
+    } else if is_archive_window {
+        ArchiveWindow.view(state)
+    } else if is_split_window {
+        SplitPartsWindow.view(state)
+    } else if is_follow_window {
+        FollowWindow.view(state)
     } else {
         let main_instances = state.window_manager.instances_of(&MainWindow);
         let is_main_window = main_instances.iter().any(|(id, _)| *id == &window_id);
@@ -226,8 +1744,68 @@ pub fn title(state: &RealOne, window_id: iced::window::Id) -> String {
     let settings_instances = state.window_manager.instances_of(&SettingsWindow);
     let is_settings_window = settings_instances.iter().any(|(id, _)| *id == &window_id);
     
+    let torrent_instances = state.window_manager.instances_of(&TorrentWindow);
+    let is_torrent_window = torrent_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let piecewise_instances = state.window_manager.instances_of(&PiecewiseWindow);
+    let is_piecewise_window = piecewise_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let oci_instances = state.window_manager.instances_of(&OciWindow);
+    let is_oci_window = oci_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let cdc_instances = state.window_manager.instances_of(&CdcWindow);
+    let is_cdc_window = cdc_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let rdiff_instances = state.window_manager.instances_of(&RdiffWindow);
+    let is_rdiff_window = rdiff_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let similarity_instances = state.window_manager.instances_of(&SimilarityWindow);
+    let is_similarity_window = similarity_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let image_instances = state.window_manager.instances_of(&ImageCompareWindow);
+    let is_image_window = image_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let binary_instances = state.window_manager.instances_of(&BinaryWindow);
+    let is_binary_window = binary_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let canonical_instances = state.window_manager.instances_of(&CanonicalJsonWindow);
+    let is_canonical_window = canonical_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let archive_instances = state.window_manager.instances_of(&ArchiveWindow);
+    let is_archive_window = archive_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let split_instances = state.window_manager.instances_of(&SplitPartsWindow);
+    let is_split_window = split_instances.iter().any(|(id, _)| *id == &window_id);
+    
+    let follow_instances = state.window_manager.instances_of(&FollowWindow);
+    let is_follow_window = follow_instances.iter().any(|(id, _)| *id == &window_id);
+    
     if is_settings_window {
         SettingsWindow.title(state)
+    } else if is_torrent_window {
+        TorrentWindow.title(state)
+    } else if is_piecewise_window {
+        PiecewiseWindow.title(state)
+    } else if is_oci_window {
+        OciWindow.title(state)
+    } else if is_cdc_window {
+        CdcWindow.title(state)
+    } else if is_rdiff_window {
+        RdiffWindow.title(state)
+    } else if is_similarity_window {
+        SimilarityWindow.title(state)
+    } else if is_image_window {
+        ImageCompareWindow.title(state)
+    } else if is_binary_window {
+        BinaryWindow.title(state)
+    } else if is_canonical_window {
+        CanonicalJsonWindow.title(state)
+    } else if is_archive_window {
+        ArchiveWindow.title(state)
+    } else if is_split_window {
+        SplitPartsWindow.title(state)
+    } else if is_follow_window {
+        FollowWindow.title(state)
     } else {
         let main_instances = state.window_manager.instances_of(&MainWindow);
         let is_main_window = main_instances.iter().any(|(id, _)| *id == &window_id);
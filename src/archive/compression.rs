@@ -0,0 +1,148 @@
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const BZIP2_MAGIC: &[u8] = b"BZh"; // Followed by the block size, '1' to '9'
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+// Stream compression recognised by its magic bytes, whatever the file is called
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if header.starts_with(BZIP2_MAGIC) && header.get(3).is_some_and(|level| (b'1'..=b'9').contains(level)) {
+            Compression::Bzip2
+        } else if header.starts_with(XZ_MAGIC) {
+            Compression::Xz
+        } else if header.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+
+    // Compression of a file, from its first bytes; folders and unreadable
+    // files count as uncompressed
+    pub fn detect_file(path: &Path) -> Self {
+        let mut header = Vec::with_capacity(XZ_MAGIC.len());
+        match File::open(path).and_then(|file| file.take(XZ_MAGIC.len() as u64).read_to_end(&mut header)) {
+            Ok(_) => Compression::detect(&header),
+            Err(_) => Compression::None,
+        }
+    }
+
+    // Decompresses everything in the stream: concatenated gzip members, bzip2
+    // and xz streams, and every zstd frame, the same as gzip -d, bzip2 -d,
+    // xz -d and zstd -d
+    pub fn decoder<'a>(&self, reader: impl Read + 'a) -> Result<Box<dyn Read + 'a>, String> {
+        let decoder: Box<dyn Read + 'a> = match self {
+            Compression::None => Box::new(reader),
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            Compression::Xz => Box::new(lzma_rust2::XzReader::new(reader, true)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader).map_err(|e| e.to_string())?),
+        };
+        Ok(decoder)
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Bzip2 => "bzip2",
+            Compression::Xz => "xz",
+            Compression::Zstd => "zstd",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+
+    const TEXT: &[u8] = b"The quick brown fox jumps over the lazy dog\n";
+
+    // TEXT compressed by `gzip -9 -n`, `bzip2 -9`, `xz` and `zstd -19`
+    const GZIP: &str = "1f8b08000000000002030bc94855282ccd4cce56482aca2fcf5348cbaf50c82acd2d2856c82f4b2d5228014ae7\
+        2456552aa4e4a773010038c1936d2c000000";
+    const BZIP2: &str = "425a683931415926535920d0ec81000004d3800010400004003ffffff02000314000d000018f4d23464d32686d\
+        1b51cc4783a424e3d7d0a9a2b2b79ab7e260a9c52e73b29812d718c8bb9229c2848106876408";
+    const XZ: &str = "fd377a585a000004e6d6b44604c0302c21011600000000000000000056834e5601002b54686520717569636b20\
+        62726f776e20666f78206a756d7073206f76657220746865206c617a7920646f670a0015bf17324ef1d9ca00014c2cc1\
+        d9924e1fb6f37d010000000004595a";
+    const ZSTD: &str = "28b52ffd242c61010054686520717569636b2062726f776e20666f78206a756d7073206f76657220746865206c\
+        617a7920646f670ae4a7bc87";
+
+    // Detects the format and decodes two copies of the stream back to back,
+    // which the tools decompress to the text twice
+    fn check(compressed: &str, format: Compression) {
+        let compressed = hex::decode(compressed).unwrap();
+        assert_eq!(Compression::detect(&compressed), format);
+
+        let mut decoded = Vec::new();
+        format.decoder(&compressed[..]).unwrap().read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, TEXT);
+
+        let twice = [compressed.as_slice(), compressed.as_slice()].concat();
+        let mut decoded = Vec::new();
+        format.decoder(&twice[..]).unwrap().read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, [TEXT, TEXT].concat());
+    }
+
+    #[test]
+    fn gzip() {
+        check(GZIP, Compression::Gzip);
+    }
+
+    #[test]
+    fn bzip2() {
+        check(BZIP2, Compression::Bzip2);
+    }
+
+    #[test]
+    fn xz() {
+        check(XZ, Compression::Xz);
+    }
+
+    #[test]
+    fn zstd() {
+        check(ZSTD, Compression::Zstd);
+    }
+
+    #[test]
+    fn anything_else_is_uncompressed() {
+        assert_eq!(Compression::detect(b""), Compression::None);
+        assert_eq!(Compression::detect(TEXT), Compression::None);
+        assert_eq!(Compression::detect(&[0x1f]), Compression::None);
+        // "BZh" needs a block size after it
+        assert_eq!(Compression::detect(b"BZh0"), Compression::None);
+        assert_eq!(Compression::detect(b"BZh"), Compression::None);
+        assert_eq!(Compression::detect(&[0xfd, b'7', b'z', b'X', b'Z']), Compression::None);
+
+        let mut decoded = Vec::new();
+        Compression::None.decoder(TEXT).unwrap().read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, TEXT);
+
+        let dir = ScratchDir::new("compression-detect");
+        assert_eq!(Compression::detect_file(&dir.write("fox.txt.gz", &hex::decode(GZIP).unwrap())), Compression::Gzip);
+        assert_eq!(Compression::detect_file(&dir.write("fox.gz", TEXT)), Compression::None);
+        assert_eq!(Compression::detect_file(dir.path()), Compression::None);
+        assert_eq!(Compression::detect_file(&dir.join("missing")), Compression::None);
+    }
+}
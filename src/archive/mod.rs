@@ -0,0 +1,154 @@
+pub mod compression;
+pub mod tarball;
+pub mod zipfile;
+
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::hash::{Algorithm, HashOptions};
+use compression::Compression;
+
+const TAR_BLOCK: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar(Compression),
+}
+
+impl fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchiveFormat::Zip => write!(f, "zip"),
+            ArchiveFormat::Tar(Compression::None) => write!(f, "tar"),
+            ArchiveFormat::Tar(compression) => write!(f, "tar ({})", compression),
+        }
+    }
+}
+
+// A regular file inside an archive; directories, links and devices are left out
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+    pub index: usize, // Position among the listed members, the same in every pass
+    pub name: String,
+    pub size: u64,
+    pub stored_crc32: Option<u32>, // Zip members record a CRC-32 of their contents
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveListing {
+    pub format: ArchiveFormat,
+    pub members: Vec<ArchiveMember>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemberHashes {
+    pub member: ArchiveMember,
+    pub results: Result<Vec<(Algorithm, String)>, String>,
+    pub computed_crc32: Option<u32>, // Only for zip members, to compare with stored_crc32
+}
+
+impl MemberHashes {
+    // None when there is nothing to compare
+    pub fn crc_matches(&self) -> Option<bool> {
+        match (self.member.stored_crc32, self.computed_crc32) {
+            (Some(stored), Some(computed)) => Some(stored == computed),
+            _ => None,
+        }
+    }
+}
+
+pub fn list_archive(path: &Path) -> Result<ArchiveListing, String> {
+    let format = detect_format(path)?;
+    let members = match format {
+        ArchiveFormat::Zip => zipfile::list(path),
+        ArchiveFormat::Tar(compression) => tarball::list(path, compression),
+    }
+    .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(ArchiveListing { format, members })
+}
+
+// Hashes the members whose indexes are given (all of them for None) straight
+// from the archive, decompressing into the hashers without temporary files
+pub fn hash_members(
+    path: &Path,
+    listing: &ArchiveListing,
+    indexes: Option<&[usize]>,
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+) -> Result<Vec<MemberHashes>, String> {
+    let wanted: Vec<&ArchiveMember> = listing
+        .members
+        .iter()
+        .filter(|member| indexes.is_none_or(|indexes| indexes.contains(&member.index)))
+        .collect();
+    match listing.format {
+        ArchiveFormat::Zip => zipfile::hash(path, &wanted, algorithms, options),
+        ArchiveFormat::Tar(compression) => tarball::hash(path, compression, &wanted, algorithms, options),
+    }
+    .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Zip archives are found by their signature (or, for self-extracting ones, by
+// their central directory); anything else must be a tar file, possibly
+// compressed, with a valid first header
+fn detect_format(path: &Path) -> Result<ArchiveFormat, String> {
+    let error = |e: String| format!("{}: {}", path.display(), e);
+    let header = read_header(File::open(path).map_err(|e| error(e.to_string()))?).map_err(error)?;
+    if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+        return Ok(ArchiveFormat::Zip);
+    }
+
+    let compression = Compression::detect(&header);
+    let file = File::open(path).map_err(|e| error(e.to_string()))?;
+    let block = read_header(compression.decoder(file).map_err(error)?).map_err(error)?;
+    if is_tar_header(&block) {
+        return Ok(ArchiveFormat::Tar(compression));
+    }
+    if compression == Compression::None && zipfile::is_zip(path) {
+        return Ok(ArchiveFormat::Zip);
+    }
+    Err(error("not a zip or tar archive".to_string()))
+}
+
+// First tar block (or less, for short files)
+fn read_header(reader: impl Read) -> Result<Vec<u8>, String> {
+    let mut header = Vec::with_capacity(TAR_BLOCK);
+    reader
+        .take(TAR_BLOCK as u64)
+        .read_to_end(&mut header)
+        .map_err(|e| e.to_string())?;
+    Ok(header)
+}
+
+// "ustar" marks POSIX and GNU headers; older tar files only have the checksum,
+// which counts the checksum field itself as eight spaces
+fn is_tar_header(block: &[u8]) -> bool {
+    if block.len() < TAR_BLOCK {
+        return false;
+    }
+    if &block[257..262] == b"ustar" {
+        return true;
+    }
+    let recorded = std::str::from_utf8(&block[148..156])
+        .ok()
+        .and_then(|field| u32::from_str_radix(field.trim_matches(|c: char| c == ' ' || c == '\0'), 8).ok());
+    let sum: u32 = block
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' as u32 } else { b as u32 })
+        .sum();
+    block[0] != 0 && recorded == Some(sum)
+}
+
+// Zip members are always checked against their stored CRC-32, so it is
+// computed even when CRC32 is not among the selected algorithms
+fn with_crc32(algorithms: &[Algorithm]) -> Vec<Algorithm> {
+    let mut algorithms = algorithms.to_vec();
+    if !algorithms.contains(&Algorithm::CRC32) {
+        algorithms.push(Algorithm::CRC32);
+    }
+    algorithms
+}
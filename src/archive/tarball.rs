@@ -0,0 +1,123 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::archive::compression::Compression;
+use crate::archive::{ArchiveMember, MemberHashes};
+use crate::hash::compute::hash_reader;
+use crate::hash::normalize::Normalization;
+use crate::hash::{Algorithm, HashOptions};
+
+// Compressed tar files cannot seek, so every pass decompresses from the start
+// and the entries are visited in order
+fn for_each_file(
+    path: &Path,
+    compression: Compression,
+    mut on_file: impl FnMut(usize, &mut tar::Entry<'_, Box<dyn Read>>) -> Result<(), String>,
+) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = tar::Archive::new(compression.decoder(BufReader::new(file))?);
+    let mut index = 0;
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        on_file(index, &mut entry)?;
+        index += 1;
+    }
+    Ok(())
+}
+
+fn entry_name(entry: &tar::Entry<'_, Box<dyn Read>>) -> String {
+    String::from_utf8_lossy(&entry.path_bytes()).into_owned()
+}
+
+pub fn list(path: &Path, compression: Compression) -> Result<Vec<ArchiveMember>, String> {
+    let mut members = Vec::new();
+    for_each_file(path, compression, |index, entry| {
+        members.push(ArchiveMember {
+            index,
+            name: entry_name(entry),
+            size: entry.size(),
+            stored_crc32: None,
+        });
+        Ok(())
+    })?;
+    Ok(members)
+}
+
+pub fn hash(
+    path: &Path,
+    compression: Compression,
+    wanted: &[&ArchiveMember],
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+) -> Result<Vec<MemberHashes>, String> {
+    let mut hashes = Vec::new();
+    for_each_file(path, compression, |index, entry| {
+        if let Some(member) = wanted.iter().find(|member| member.index == index) {
+            let size = entry.size();
+            hashes.push(MemberHashes {
+                member: (*member).clone(),
+                results: hash_reader(entry, algorithms, options, Some(size), Normalization::default()),
+                computed_crc32: None,
+            });
+        }
+        Ok(())
+    })?;
+    Ok(hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::compute::calculate_hash_from_data;
+    use crate::test_support::ScratchDir;
+    use std::io::Write;
+
+    fn build_tar(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_size(0);
+        header.set_mode(0o755);
+        builder.append_data(&mut header, "docs/", std::io::empty()).unwrap();
+        for (name, data) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn check(path: &Path, compression: Compression) {
+        let members = list(path, compression).unwrap();
+        let listed: Vec<(usize, &str, u64, Option<u32>)> = members
+            .iter()
+            .map(|member| (member.index, member.name.as_str(), member.size, member.stored_crc32))
+            .collect();
+        assert_eq!(listed, [(0, "docs/a.txt", 3, None), (1, "b.bin", 2000, None)]);
+
+        let options = HashOptions::default();
+        let algorithms = [Algorithm::SHA1, Algorithm::GITSHA1];
+        let hashes = hash(path, compression, &[&members[1]], &algorithms, &options).unwrap();
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(hashes[0].member.name, "b.bin");
+        let expected = algorithms.iter().map(|alg| (alg.clone(), calculate_hash_from_data(&[7; 2000], alg, &options))).collect();
+        assert_eq!(hashes[0].results, Ok(expected));
+        assert_eq!(hashes[0].crc_matches(), None);
+    }
+
+    #[test]
+    fn members_are_listed_and_hashed() {
+        let dir = ScratchDir::new("tarball-members");
+        let tar = build_tar(&[("docs/a.txt", b"abc"), ("b.bin", &[7; 2000])]);
+        check(&dir.write("test.tar", &tar), Compression::None);
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&tar).unwrap();
+        check(&dir.write("test.tar.gz", &encoder.finish().unwrap()), Compression::Gzip);
+    }
+}
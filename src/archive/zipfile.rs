@@ -0,0 +1,166 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::archive::compression::Compression;
+use crate::archive::{with_crc32, ArchiveMember, MemberHashes};
+use crate::hash::compute::hash_reader;
+use crate::hash::normalize::Normalization;
+use crate::hash::{Algorithm, HashOptions};
+
+fn open(path: &Path) -> Result<ZipArchive<BufReader<File>>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())
+}
+
+pub fn is_zip(path: &Path) -> bool {
+    open(path).is_ok()
+}
+
+// Members come from the central directory, which also holds each CRC-32
+pub fn list(path: &Path) -> Result<Vec<ArchiveMember>, String> {
+    let mut archive = open(path)?;
+    let mut members = Vec::new();
+    for number in 0..archive.len() {
+        let entry = archive.by_index_raw(number).map_err(|e| e.to_string())?;
+        if !entry.is_file() {
+            continue;
+        }
+        members.push(ArchiveMember {
+            index: members.len(),
+            name: entry.name().to_string(),
+            size: entry.size(),
+            stored_crc32: Some(entry.crc32()),
+        });
+    }
+    Ok(members)
+}
+
+// Each member's raw data is decompressed here rather than by the zip crate,
+// which would stop with an error on a CRC mismatch instead of reporting it
+pub fn hash(
+    path: &Path,
+    wanted: &[&ArchiveMember],
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+) -> Result<Vec<MemberHashes>, String> {
+    let mut archive = open(path)?;
+    let all = with_crc32(algorithms);
+    let mut hashes = Vec::new();
+    let mut listed = 0;
+
+    for number in 0..archive.len() {
+        let entry = archive.by_index_raw(number).map_err(|e| e.to_string())?;
+        if !entry.is_file() {
+            continue;
+        }
+        let index = listed;
+        listed += 1;
+        let member = match wanted.iter().find(|member| member.index == index) {
+            Some(member) => (*member).clone(),
+            None => continue,
+        };
+
+        let size = entry.size();
+        // The zip crate is built without its own decompressors; the methods
+        // RealOne can decode are handled here
+        let method = entry.compression();
+        let decoder = match method {
+            _ if entry.encrypted() => Err("encrypted members cannot be read".to_string()),
+            CompressionMethod::Stored => Compression::None.decoder(entry),
+            CompressionMethod::DEFLATE => Ok(Box::new(flate2::read::DeflateDecoder::new(entry)) as Box<dyn Read>),
+            CompressionMethod::BZIP2 => Compression::Bzip2.decoder(entry),
+            CompressionMethod::ZSTD => Compression::Zstd.decoder(entry),
+            CompressionMethod::XZ => Compression::Xz.decoder(entry),
+            other => Err(format!("unsupported compression method {}", other)),
+        };
+        let results = decoder.and_then(|mut decoder| hash_reader(&mut decoder, &all, options, Some(size), Normalization::default()));
+
+        let computed_crc32 = results.as_ref().ok().and_then(|results| {
+            results
+                .iter()
+                .find(|(alg, _)| *alg == Algorithm::CRC32)
+                .and_then(|(_, hex)| u32::from_str_radix(hex, 16).ok())
+        });
+        let results = results.map(|results| results.into_iter().filter(|(alg, _)| algorithms.contains(alg)).collect());
+        hashes.push(MemberHashes { member, results, computed_crc32 });
+    }
+    Ok(hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::compute::calculate_hash_from_data;
+    use crate::test_support::ScratchDir;
+    use std::io::{Cursor, Write};
+    use zip::write::SimpleFileOptions;
+
+    // Stored members only: the zip crate is built without its compressors
+    fn build_zip(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        writer.add_directory("docs/", options).unwrap();
+        for (name, data) in members {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn members_are_listed_and_hashed() {
+        let dir = ScratchDir::new("zipfile-members");
+        let path = dir.write("test.zip", &build_zip(&[("docs/a.txt", b"abc"), ("b.bin", b"")]));
+        assert!(is_zip(&path));
+        assert!(!is_zip(&dir.write("plain.txt", b"not a zip")));
+
+        // The directory is left out
+        let members = list(&path).unwrap();
+        let names: Vec<&str> = members.iter().map(|member| member.name.as_str()).collect();
+        assert_eq!(names, ["docs/a.txt", "b.bin"]);
+        assert_eq!((members[0].index, members[0].size, members[0].stored_crc32), (0, 3, Some(0x352441c2)));
+        assert_eq!((members[1].index, members[1].size, members[1].stored_crc32), (1, 0, Some(0)));
+
+        let options = HashOptions::default();
+        let algorithms = [Algorithm::SHA256];
+        let hashes = hash(&path, &[&members[1]], &algorithms, &options).unwrap();
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(hashes[0].member.name, "b.bin");
+        assert_eq!(
+            hashes[0].results,
+            Ok(vec![(Algorithm::SHA256, calculate_hash_from_data(b"", &Algorithm::SHA256, &options))])
+        );
+        assert_eq!(hashes[0].crc_matches(), Some(true));
+    }
+
+    #[test]
+    fn a_wrong_stored_crc_is_reported_not_an_error() {
+        let dir = ScratchDir::new("zipfile-bad-crc");
+        let mut data = build_zip(&[("a.txt", b"abc"), ("b.txt", b"def")]);
+        // CRC-32 of "abc", in the local header and the central directory
+        let crc = 0x352441c2u32.to_le_bytes();
+        let mut patched = 0;
+        for at in 0..data.len() - 4 {
+            if data[at..at + 4] == crc {
+                data[at..at + 4].copy_from_slice(&0xdeadbeefu32.to_le_bytes());
+                patched += 1;
+            }
+        }
+        assert_eq!(patched, 2);
+        let path = dir.write("bad.zip", &data);
+
+        let members = list(&path).unwrap();
+        assert_eq!(members[0].stored_crc32, Some(0xdeadbeef));
+        let options = HashOptions::default();
+        let hashes = hash(&path, &members.iter().collect::<Vec<_>>(), &[Algorithm::MD5], &options).unwrap();
+
+        assert_eq!(hashes[0].computed_crc32, Some(0x352441c2));
+        assert_eq!(hashes[0].crc_matches(), Some(false));
+        assert_eq!(hashes[0].results, Ok(vec![(Algorithm::MD5, "900150983cd24fb0d6963f7d28e17f72".to_string())]));
+        // The other member is unaffected
+        assert_eq!(hashes[1].crc_matches(), Some(true));
+    }
+}
@@ -0,0 +1,342 @@
+use crate::binary::{Bytes, SectionHash};
+use crate::hash::compute::calculate_hash_from_data;
+use crate::hash::{Algorithm, HashOptions};
+
+pub const MAGIC: &[u8] = b"\x7fELF";
+
+const SHT_NOTE: u32 = 7;
+const SHT_NOBITS: u32 = 8; // .bss and friends occupy no space in the file
+const PT_NOTE: u32 = 4;
+const NT_GNU_BUILD_ID: u32 = 3;
+const SHN_XINDEX: u16 = 0xffff;
+
+#[derive(Debug, Clone)]
+pub struct ElfReport {
+    pub class: &'static str, // "ELF32" or "ELF64"
+    pub machine: String,
+    pub build_id: Option<String>, // Hex, as `readelf -n` and `file` show it
+    pub sections: Vec<SectionHash>,
+}
+
+// Field offsets that differ between the 32- and 64-bit layouts
+struct Layout {
+    is_64: bool,
+}
+
+impl Layout {
+    fn word(&self, bytes: &Bytes, offset: u64) -> Result<u64, String> {
+        if self.is_64 {
+            bytes.u64(offset)
+        } else {
+            bytes.u32(offset).map(u64::from)
+        }
+    }
+}
+
+struct Section {
+    name_offset: u32,
+    kind: u32,
+    offset: u64,
+    size: u64,
+    align: u64,
+    link: u32,
+}
+
+pub fn analyze(data: &[u8]) -> Result<ElfReport, String> {
+    if data.len() < 16 {
+        return Err("truncated ELF header".to_string());
+    }
+    let is_64 = match data[4] {
+        1 => false,
+        2 => true,
+        other => return Err(format!("unknown ELF class {}", other)),
+    };
+    let little_endian = match data[5] {
+        1 => true,
+        2 => false,
+        other => return Err(format!("unknown ELF data encoding {}", other)),
+    };
+    let bytes = Bytes::new(data, little_endian);
+    let layout = Layout { is_64 };
+
+    let machine = machine_name(bytes.u16(0x12)?);
+    let sections = section_headers(&bytes, &layout)?;
+
+    // Section names come from the section named by e_shstrndx; with
+    // SHN_XINDEX the real index, which may not fit 16 bits, is in section 0
+    let mut names_index = bytes.u16(if is_64 { 0x3e } else { 0x32 })? as u32;
+    if names_index == SHN_XINDEX as u32 {
+        names_index = sections.first().map(|s| s.link).unwrap_or(0);
+    }
+    if names_index != 0 && names_index as usize >= sections.len() {
+        return Err(format!("section name table index {} is out of range", names_index));
+    }
+    let names = sections.get(names_index as usize).filter(|_| names_index != 0);
+    let section_name = |section: &Section| match names {
+        Some(names) => bytes
+            .c_string(names.offset.saturating_add(section.name_offset as u64))
+            .unwrap_or_default(),
+        None => String::new(),
+    };
+
+    // The build ID note normally has its own section; stripped files without
+    // section headers still have it in a PT_NOTE segment
+    let mut build_id = None;
+    for section in sections.iter().filter(|s| s.kind == SHT_NOTE) {
+        build_id = build_id.or(find_build_id(&bytes, section.offset, section.size, section.align)?);
+    }
+    if sections.is_empty() {
+        for (offset, size, align) in note_segments(&bytes, &layout)? {
+            build_id = build_id.or(find_build_id(&bytes, offset, size, align)?);
+        }
+    }
+
+    let hashes = sections
+        .iter()
+        .skip(1) // Index 0 is always the null section
+        .filter(|s| s.kind != SHT_NOBITS && s.size > 0)
+        .map(|section| {
+            let contents = bytes.slice(section.offset, section.size)?;
+            Ok(SectionHash {
+                name: section_name(section),
+                offset: section.offset,
+                size: section.size,
+                sha256: calculate_hash_from_data(contents, &Algorithm::SHA256, &HashOptions::default()),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(ElfReport {
+        class: if is_64 { "ELF64" } else { "ELF32" },
+        machine,
+        build_id,
+        sections: hashes,
+    })
+}
+
+fn section_headers(bytes: &Bytes, layout: &Layout) -> Result<Vec<Section>, String> {
+    let (shoff, entsize, count) = if layout.is_64 {
+        (bytes.u64(0x28)?, bytes.u16(0x3a)?, bytes.u16(0x3c)?)
+    } else {
+        (bytes.u32(0x20)? as u64, bytes.u16(0x2e)?, bytes.u16(0x30)?)
+    };
+    if shoff == 0 {
+        return Ok(Vec::new());
+    }
+
+    let read = |index: u64| -> Result<Section, String> {
+        let at = table_entry(bytes, shoff, index, entsize)?;
+        let w = if layout.is_64 { 8 } else { 4 };
+        Ok(Section {
+            name_offset: bytes.u32(at)?,
+            kind: bytes.u32(at + 4)?,
+            offset: layout.word(bytes, at + 8 + 2 * w)?,
+            size: layout.word(bytes, at + 8 + 3 * w)?,
+            link: bytes.u32(at + 8 + 4 * w)?,
+            align: layout.word(bytes, at + 16 + 4 * w)?,
+        })
+    };
+
+    // With 0xff00 or more sections the real count is kept in section 0
+    let first = read(0)?;
+    let count = if count == 0 { first.size } else { count as u64 };
+    if count > 1 << 20 {
+        return Err(format!("implausible section count {}", count));
+    }
+    let mut sections = vec![first];
+    for index in 1..count {
+        sections.push(read(index)?);
+    }
+    Ok(sections)
+}
+
+// Offset of entry `index` in a header table. The table offset and entry size
+// come from the file, so the arithmetic is checked, and the entry must lie in
+// the file, which keeps the field offsets added to it from overflowing
+fn table_entry(bytes: &Bytes, table: u64, index: u64, entsize: u16) -> Result<u64, String> {
+    let at = (entsize as u64)
+        .checked_mul(index)
+        .and_then(|offset| table.checked_add(offset))
+        .ok_or_else(|| format!("header table offset {} is out of range", table))?;
+    bytes.slice(at, entsize as u64)?;
+    Ok(at)
+}
+
+// (offset, size, alignment) of each PT_NOTE program header
+fn note_segments(bytes: &Bytes, layout: &Layout) -> Result<Vec<(u64, u64, u64)>, String> {
+    let (phoff, entsize, count) = if layout.is_64 {
+        (bytes.u64(0x20)?, bytes.u16(0x36)?, bytes.u16(0x38)?)
+    } else {
+        (bytes.u32(0x1c)? as u64, bytes.u16(0x2a)?, bytes.u16(0x2c)?)
+    };
+
+    let mut notes = Vec::new();
+    for index in 0..count as u64 {
+        let at = table_entry(bytes, phoff, index, entsize)?;
+        if bytes.u32(at)? != PT_NOTE {
+            continue;
+        }
+        let note = if layout.is_64 {
+            (bytes.u64(at + 8)?, bytes.u64(at + 32)?, bytes.u64(at + 48)?)
+        } else {
+            (bytes.u32(at + 4)? as u64, bytes.u32(at + 16)? as u64, bytes.u32(at + 28)? as u64)
+        };
+        notes.push(note);
+    }
+    Ok(notes)
+}
+
+// Walks the notes in one note section or segment. Each is a name size,
+// descriptor size and type, then the name and descriptor padded to the
+// alignment (4 bytes, or 8 for some 64-bit notes)
+fn find_build_id(bytes: &Bytes, offset: u64, size: u64, align: u64) -> Result<Option<String>, String> {
+    let notes = Bytes::new(bytes.slice(offset, size)?, bytes.little_endian);
+    let pad = |n: u64| if align == 8 { n.div_ceil(8) * 8 } else { n.div_ceil(4) * 4 };
+
+    let mut at = 0u64;
+    while at + 12 <= size {
+        let name_size = notes.u32(at)? as u64;
+        let desc_size = notes.u32(at + 4)? as u64;
+        let kind = notes.u32(at + 8)?;
+        let name = notes.slice(at + 12, name_size)?;
+        let desc_at = at + 12 + pad(name_size);
+
+        if kind == NT_GNU_BUILD_ID && name == b"GNU\0" {
+            return Ok(Some(hex::encode(notes.slice(desc_at, desc_size)?)));
+        }
+        at = desc_at + pad(desc_size);
+    }
+    Ok(None)
+}
+
+fn machine_name(machine: u16) -> String {
+    match machine {
+        0x03 => "x86".to_string(),
+        0x08 => "MIPS".to_string(),
+        0x14 => "PowerPC".to_string(),
+        0x15 => "PowerPC64".to_string(),
+        0x16 => "S/390".to_string(),
+        0x28 => "ARM".to_string(),
+        0x3e => "x86-64".to_string(),
+        0xb7 => "AArch64".to_string(),
+        0xf3 => "RISC-V".to_string(),
+        0x102 => "LoongArch".to_string(),
+        other => format!("machine 0x{:x}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ELF64 little-endian header with the given table offsets and one entry each
+    fn header(shoff: u64, phoff: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 64];
+        data[..4].copy_from_slice(MAGIC);
+        data[4] = 2;
+        data[5] = 1;
+        data[0x20..0x28].copy_from_slice(&phoff.to_le_bytes());
+        data[0x28..0x30].copy_from_slice(&shoff.to_le_bytes());
+        data[0x36..0x38].copy_from_slice(&56u16.to_le_bytes());
+        data[0x38..0x3a].copy_from_slice(&1u16.to_le_bytes());
+        data[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        data[0x3c..0x3e].copy_from_slice(&1u16.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn table_offsets_near_the_end_of_the_address_space_are_errors() {
+        assert!(analyze(&header(u64::MAX - 8, 0)).is_err());
+        assert!(analyze(&header(0, u64::MAX - 8)).is_err());
+    }
+
+    // Writes a 64-bit section header at index `index` of a table at offset 64
+    fn put_section(data: &mut [u8], index: usize, name: u32, kind: u32, offset: u64, size: u64, link: u32) {
+        let at = 64 + index * 64;
+        data[at..at + 4].copy_from_slice(&name.to_le_bytes());
+        data[at + 4..at + 8].copy_from_slice(&kind.to_le_bytes());
+        data[at + 24..at + 32].copy_from_slice(&offset.to_le_bytes());
+        data[at + 32..at + 40].copy_from_slice(&size.to_le_bytes());
+        data[at + 40..at + 44].copy_from_slice(&link.to_le_bytes());
+    }
+
+    // An ABI-tag note followed by a 20-byte GNU build ID note
+    fn notes() -> Vec<u8> {
+        let mut notes = Vec::new();
+        for (kind, desc) in [(1u32, vec![0u8; 16]), (NT_GNU_BUILD_ID, (1..=20).collect())] {
+            notes.extend_from_slice(&4u32.to_le_bytes());
+            notes.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+            notes.extend_from_slice(&kind.to_le_bytes());
+            notes.extend_from_slice(b"GNU\0");
+            notes.extend_from_slice(&desc);
+        }
+        notes
+    }
+
+    const BUILD_ID: &str = "0102030405060708090a0b0c0d0e0f1011121314";
+
+    #[test]
+    fn build_id_from_a_note_section() {
+        let notes = notes();
+        let mut data = header(64, 0);
+        data[0x3c..0x3e].copy_from_slice(&2u16.to_le_bytes());
+        data.resize(64 + 2 * 64, 0);
+        let notes_at = data.len() as u64;
+        put_section(&mut data, 1, 0, SHT_NOTE, notes_at, notes.len() as u64, 0);
+        data[64 + 64 + 48..64 + 64 + 56].copy_from_slice(&4u64.to_le_bytes());
+        data.extend_from_slice(&notes);
+
+        let report = analyze(&data).unwrap();
+        assert_eq!(report.class, "ELF64");
+        assert_eq!(report.build_id.as_deref(), Some(BUILD_ID));
+        assert_eq!(report.sections.len(), 1);
+    }
+
+    #[test]
+    fn build_id_from_a_note_segment_without_sections() {
+        let notes = notes();
+        let mut data = header(0, 64);
+        data.resize(64 + 56, 0);
+        let notes_at = data.len() as u64;
+        data[64..68].copy_from_slice(&PT_NOTE.to_le_bytes());
+        data[64 + 8..64 + 16].copy_from_slice(&notes_at.to_le_bytes());
+        data[64 + 32..64 + 40].copy_from_slice(&(notes.len() as u64).to_le_bytes());
+        data[64 + 48..64 + 56].copy_from_slice(&4u64.to_le_bytes());
+        data.extend_from_slice(&notes);
+
+        let report = analyze(&data).unwrap();
+        assert_eq!(report.build_id.as_deref(), Some(BUILD_ID));
+        assert!(report.sections.is_empty());
+    }
+
+    #[test]
+    fn extended_name_table_indexes_are_not_truncated() {
+        // 65,538 sections, so the name table at index 65,537 needs SHN_XINDEX
+        // and would land on section 1 if cut to 16 bits
+        let count = 65_538usize;
+        let names_at = 64 + count * 64;
+        let strings = b"\0.text\0.shstrtab\0";
+        let text_at = names_at + strings.len();
+
+        let mut data = header(64, 0);
+        data[0x20..0x28].copy_from_slice(&0u64.to_le_bytes());
+        data[0x38..0x3a].copy_from_slice(&0u16.to_le_bytes());
+        data[0x3c..0x3e].copy_from_slice(&0u16.to_le_bytes());
+        data[0x3e..0x40].copy_from_slice(&SHN_XINDEX.to_le_bytes());
+        data.resize(text_at + 4, 0);
+        data[names_at..text_at].copy_from_slice(strings);
+        data[text_at..].copy_from_slice(b"code");
+
+        put_section(&mut data, 0, 0, 0, 0, count as u64, (count - 1) as u32);
+        put_section(&mut data, 1, 1, 1, text_at as u64, 4, 0);
+        put_section(&mut data, count - 1, 7, 3, names_at as u64, strings.len() as u64, 0);
+
+        let report = analyze(&data).unwrap();
+        let names: Vec<&str> = report.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, [".text", ".shstrtab"]);
+
+        // An index past the last section is an error rather than a guess
+        put_section(&mut data, 0, 0, 0, 0, count as u64, count as u32);
+        assert!(analyze(&data).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+pub mod elf;
+pub mod pe;
+
+use std::path::Path;
+
+pub use elf::ElfReport;
+pub use pe::PeReport;
+
+#[derive(Debug, Clone)]
+pub enum BinaryReport {
+    Elf(ElfReport),
+    Pe(PeReport),
+}
+
+// Executables are parsed from memory; the whole file is needed anyway for
+// section hashes and the Authenticode hash
+pub fn analyze_binary(path: &Path) -> Result<BinaryReport, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let report = if data.starts_with(elf::MAGIC) {
+        elf::analyze(&data).map(BinaryReport::Elf)
+    } else if data.starts_with(pe::MZ_MAGIC) {
+        pe::analyze(&data).map(BinaryReport::Pe)
+    } else {
+        Err("not an ELF or PE file".to_string())
+    };
+    report.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Bounds-checked integer reads; headers come from untrusted files, so every
+// offset is checked rather than trusted
+#[derive(Clone, Copy)]
+pub(crate) struct Bytes<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Bytes<'a> {
+    pub(crate) fn new(data: &'a [u8], little_endian: bool) -> Self {
+        Self { data, little_endian }
+    }
+
+    pub(crate) fn slice(&self, offset: u64, length: u64) -> Result<&'a [u8], String> {
+        let end = offset.checked_add(length).filter(|&end| end <= self.data.len() as u64);
+        match end {
+            Some(end) => Ok(&self.data[offset as usize..end as usize]),
+            None => Err(format!("truncated file ({} bytes at offset {} are missing)", length, offset)),
+        }
+    }
+
+    fn array<const N: usize>(&self, offset: u64) -> Result<[u8; N], String> {
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(self.slice(offset, N as u64)?);
+        if !self.little_endian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    pub(crate) fn u16(&self, offset: u64) -> Result<u16, String> {
+        self.array(offset).map(u16::from_le_bytes)
+    }
+
+    pub(crate) fn u32(&self, offset: u64) -> Result<u32, String> {
+        self.array(offset).map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&self, offset: u64) -> Result<u64, String> {
+        self.array(offset).map(u64::from_le_bytes)
+    }
+
+    // NUL-terminated string starting at offset (lossy, for names)
+    pub(crate) fn c_string(&self, offset: u64) -> Result<String, String> {
+        let rest = self.slice(offset, (self.data.len() as u64).saturating_sub(offset))?;
+        let end = rest.iter().position(|&b| b == 0).ok_or("unterminated string")?;
+        Ok(String::from_utf8_lossy(&rest[..end]).into_owned())
+    }
+}
+
+// SHA-256 of one part of the file
+#[derive(Debug, Clone)]
+pub struct SectionHash {
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+    pub sha256: String,
+}
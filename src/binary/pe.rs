@@ -0,0 +1,802 @@
+use crate::binary::Bytes;
+use crate::hash::compute::{calculate_hash_from_data, AlgorithmHasher};
+use crate::hash::{Algorithm, HashOptions};
+
+pub const MZ_MAGIC: &[u8] = b"MZ";
+
+const PE32_MAGIC: u16 = 0x10b;
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+const IMPORT_DIRECTORY: u64 = 1;
+const SECURITY_DIRECTORY: u64 = 4; // The certificate table; its "address" is a file offset
+
+// Malformed files could otherwise make the import walk run for a long time
+const MAX_IMPORT_DLLS: u64 = 4096;
+const MAX_IMPORTS_PER_DLL: u64 = 65536;
+
+#[derive(Debug, Clone)]
+pub struct PeReport {
+    pub format: &'static str, // "PE32" or "PE32+"
+    pub machine: String,
+    pub authentihash: String, // SHA-256, as shown by signtool and VirusTotal
+    pub signed: bool, // Whether there is a certificate table; it is not validated
+    pub imphash: Option<String>, // None without an import table
+    pub imports: usize,
+}
+
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_size: u32,
+    raw_offset: u32,
+}
+
+struct Headers {
+    is_plus: bool,
+    checksum_at: u64,
+    directories_at: u64,
+    directory_count: u32,
+    size_of_headers: u32,
+    sections: Vec<Section>,
+}
+
+impl Headers {
+    fn directory(&self, bytes: &Bytes, index: u64) -> Result<Option<(u32, u32)>, String> {
+        if index >= self.directory_count as u64 {
+            return Ok(None);
+        }
+        let at = self.directories_at + index * 8;
+        let (address, size) = (bytes.u32(at)?, bytes.u32(at + 4)?);
+        Ok((address != 0 && size != 0).then_some((address, size)))
+    }
+
+    fn rva_to_offset(&self, rva: u32) -> Result<u64, String> {
+        if rva < self.size_of_headers {
+            return Ok(rva as u64);
+        }
+        self.sections
+            .iter()
+            .find(|s| rva >= s.virtual_address && rva - s.virtual_address < s.virtual_size.max(s.raw_size))
+            .map(|s| (rva - s.virtual_address) as u64 + s.raw_offset as u64)
+            .ok_or_else(|| format!("address 0x{:x} is outside every section", rva))
+    }
+}
+
+pub fn analyze(data: &[u8]) -> Result<PeReport, String> {
+    let bytes = Bytes::new(data, true);
+    let pe = bytes.u32(0x3c)? as u64;
+    if bytes.slice(pe, 4)? != b"PE\0\0" {
+        return Err("MZ executable without a PE header".to_string());
+    }
+    let machine = machine_name(bytes.u16(pe + 4)?);
+    let headers = read_headers(&bytes, pe)?;
+
+    let imports = imports(&bytes, &headers)?;
+    let imphash = (!imports.is_empty()).then(|| {
+        calculate_hash_from_data(imports.join(",").as_bytes(), &Algorithm::MD5, &HashOptions::default())
+    });
+
+    Ok(PeReport {
+        format: if headers.is_plus { "PE32+" } else { "PE32" },
+        machine,
+        authentihash: authentihash(&bytes, &headers)?,
+        signed: headers.directory(&bytes, SECURITY_DIRECTORY)?.is_some(),
+        imphash,
+        imports: imports.len(),
+    })
+}
+
+fn read_headers(bytes: &Bytes, pe: u64) -> Result<Headers, String> {
+    let section_count = bytes.u16(pe + 6)? as u64;
+    let optional_size = bytes.u16(pe + 20)? as u64;
+    let optional = pe + 24;
+
+    let is_plus = match bytes.u16(optional)? {
+        PE32_MAGIC => false,
+        PE32_PLUS_MAGIC => true,
+        other => return Err(format!("unknown optional header magic 0x{:x}", other)),
+    };
+    // PE32+ drops BaseOfData and widens the four stack/heap sizes, moving
+    // everything after them by 16 bytes
+    let (count_at, directories_at) = if is_plus { (optional + 108, optional + 112) } else { (optional + 92, optional + 96) };
+
+    let section_table = optional + optional_size;
+    let sections = (0..section_count)
+        .map(|index| {
+            let at = section_table + index * 40;
+            Ok(Section {
+                virtual_size: bytes.u32(at + 8)?,
+                virtual_address: bytes.u32(at + 12)?,
+                raw_size: bytes.u32(at + 16)?,
+                raw_offset: bytes.u32(at + 20)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(Headers {
+        is_plus,
+        checksum_at: optional + 64,
+        directories_at,
+        directory_count: bytes.u32(count_at)?,
+        size_of_headers: bytes.u32(optional + 60)?,
+        sections,
+    })
+}
+
+// Authenticode hash: the whole file except the header checksum, the
+// certificate table's directory entry and the certificate table itself, so
+// it stays the same when a file is signed or re-signed. Like osslsigncode,
+// the file is hashed in order rather than section by section, which is the
+// same for any file laid out the usual way
+fn authentihash(bytes: &Bytes, headers: &Headers) -> Result<String, String> {
+    let file_size = bytes.data.len() as u64;
+    let mut skipped = vec![(headers.checksum_at, 4)];
+    let mut end = file_size;
+    if (headers.directory_count as u64) > SECURITY_DIRECTORY {
+        skipped.push((headers.directories_at + SECURITY_DIRECTORY * 8, 8));
+        if let Some((offset, _)) = headers.directory(bytes, SECURITY_DIRECTORY)? {
+            if (offset as u64) > file_size {
+                return Err(format!("certificate table at offset {} is past the end of the file", offset));
+            }
+            end = offset as u64;
+        }
+    }
+
+    let mut hasher = AlgorithmHasher::new(&Algorithm::SHA256, &HashOptions::default())?;
+    let mut at = 0;
+    for (offset, length) in skipped {
+        hasher.update(bytes.slice(at, offset.saturating_sub(at))?);
+        at = offset + length;
+    }
+    hasher.update(bytes.slice(at, end.saturating_sub(at))?);
+    Ok(hasher.finalize())
+}
+
+// "dll.function" for every import, in table order, as pefile builds them
+// for the imphash: lowercase, without a .dll/.ocx/.sys extension, and with
+// known ordinals replaced by their function names
+fn imports(bytes: &Bytes, headers: &Headers) -> Result<Vec<String>, String> {
+    let (table_rva, _) = match headers.directory(bytes, IMPORT_DIRECTORY)? {
+        Some(directory) => directory,
+        None => return Ok(Vec::new()),
+    };
+    let table = headers.rva_to_offset(table_rva)?;
+    let thunk_size = if headers.is_plus { 8 } else { 4 };
+    let ordinal_flag = if headers.is_plus { 1u64 << 63 } else { 1u64 << 31 };
+
+    let mut imports = Vec::new();
+    for index in 0..MAX_IMPORT_DLLS {
+        let descriptor = table + index * 20;
+        let lookup_rva = bytes.u32(descriptor)?;
+        let name_rva = bytes.u32(descriptor + 12)?;
+        let address_rva = bytes.u32(descriptor + 16)?;
+        if name_rva == 0 && lookup_rva == 0 && address_rva == 0 {
+            break;
+        }
+
+        let dll = bytes.c_string(headers.rva_to_offset(name_rva)?)?.to_lowercase();
+        let library = match dll.rsplit_once('.') {
+            Some((stem, "dll" | "ocx" | "sys")) => stem.to_string(),
+            _ => dll.clone(),
+        };
+
+        // Bound imports may have overwritten FirstThunk, so the lookup table is preferred
+        let thunks = headers.rva_to_offset(if lookup_rva != 0 { lookup_rva } else { address_rva })?;
+        for slot in 0..MAX_IMPORTS_PER_DLL {
+            let at = thunks + slot * thunk_size;
+            let thunk = if headers.is_plus { bytes.u64(at)? } else { bytes.u32(at)? as u64 };
+            if thunk == 0 {
+                break;
+            }
+            let function = if thunk & ordinal_flag != 0 {
+                let ordinal = (thunk & 0xffff) as u16;
+                ordinal_name(&dll, ordinal).map(str::to_string).unwrap_or_else(|| format!("ord{}", ordinal))
+            } else {
+                // Skip the two-byte hint in front of the name
+                bytes.c_string(headers.rva_to_offset((thunk & 0x7fff_ffff) as u32)? + 2)?
+            };
+            imports.push(format!("{}.{}", library, function.to_lowercase()));
+        }
+    }
+    Ok(imports)
+}
+
+// The DLLs whose ordinals pefile's ordlookup names when computing imphashes;
+// any other ordinal becomes "ordN"
+fn ordinal_name(dll: &str, ordinal: u16) -> Option<&'static str> {
+    match dll {
+        "ws2_32.dll" | "wsock32.dll" => winsock_name(ordinal),
+        "oleaut32.dll" => oleaut32_name(ordinal),
+        _ => None,
+    }
+}
+
+// Winsock 1.1 ordinals, which ws2_32.dll and wsock32.dll still export under
+// the same numbers
+fn winsock_name(ordinal: u16) -> Option<&'static str> {
+    let name = match ordinal {
+        1 => "accept",
+        2 => "bind",
+        3 => "closesocket",
+        4 => "connect",
+        5 => "getpeername",
+        6 => "getsockname",
+        7 => "getsockopt",
+        8 => "htonl",
+        9 => "htons",
+        10 => "ioctlsocket",
+        11 => "inet_addr",
+        12 => "inet_ntoa",
+        13 => "listen",
+        14 => "ntohl",
+        15 => "ntohs",
+        16 => "recv",
+        17 => "recvfrom",
+        18 => "select",
+        19 => "send",
+        20 => "sendto",
+        21 => "setsockopt",
+        22 => "shutdown",
+        23 => "socket",
+        51 => "gethostbyaddr",
+        52 => "gethostbyname",
+        53 => "getprotobyname",
+        54 => "getprotobynumber",
+        55 => "getservbyname",
+        56 => "getservbyport",
+        57 => "gethostname",
+        101 => "WSAAsyncSelect",
+        102 => "WSAAsyncGetHostByAddr",
+        103 => "WSAAsyncGetHostByName",
+        104 => "WSAAsyncGetProtoByNumber",
+        105 => "WSAAsyncGetProtoByName",
+        106 => "WSAAsyncGetServByPort",
+        107 => "WSAAsyncGetServByName",
+        108 => "WSACancelAsyncRequest",
+        109 => "WSASetBlockingHook",
+        110 => "WSAUnhookBlockingHook",
+        111 => "WSAGetLastError",
+        112 => "WSASetLastError",
+        113 => "WSACancelBlockingCall",
+        114 => "WSAIsBlocking",
+        115 => "WSAStartup",
+        116 => "WSACleanup",
+        151 => "__WSAFDIsSet",
+        500 => "WEP",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// oleaut32.dll ordinals, which VB6 and Delphi programs import by number
+fn oleaut32_name(ordinal: u16) -> Option<&'static str> {
+    let name = match ordinal {
+        2 => "SysAllocString",
+        3 => "SysReAllocString",
+        4 => "SysAllocStringLen",
+        5 => "SysReAllocStringLen",
+        6 => "SysFreeString",
+        7 => "SysStringLen",
+        8 => "VariantInit",
+        9 => "VariantClear",
+        10 => "VariantCopy",
+        11 => "VariantCopyInd",
+        12 => "VariantChangeType",
+        13 => "VariantTimeToDosDateTime",
+        14 => "DosDateTimeToVariantTime",
+        15 => "SafeArrayCreate",
+        16 => "SafeArrayDestroy",
+        17 => "SafeArrayGetDim",
+        18 => "SafeArrayGetElemsize",
+        19 => "SafeArrayGetUBound",
+        20 => "SafeArrayGetLBound",
+        21 => "SafeArrayLock",
+        22 => "SafeArrayUnlock",
+        23 => "SafeArrayAccessData",
+        24 => "SafeArrayUnaccessData",
+        25 => "SafeArrayGetElement",
+        26 => "SafeArrayPutElement",
+        27 => "SafeArrayCopy",
+        28 => "DispGetParam",
+        29 => "DispGetIDsOfNames",
+        30 => "DispInvoke",
+        31 => "CreateDispTypeInfo",
+        32 => "CreateStdDispatch",
+        33 => "RegisterActiveObject",
+        34 => "RevokeActiveObject",
+        35 => "GetActiveObject",
+        36 => "SafeArrayAllocDescriptor",
+        37 => "SafeArrayAllocData",
+        38 => "SafeArrayDestroyDescriptor",
+        39 => "SafeArrayDestroyData",
+        40 => "SafeArrayRedim",
+        41 => "SafeArrayAllocDescriptorEx",
+        42 => "SafeArrayCreateEx",
+        43 => "SafeArrayCreateVectorEx",
+        44 => "SafeArraySetRecordInfo",
+        45 => "SafeArrayGetRecordInfo",
+        46 => "VarParseNumFromStr",
+        47 => "VarNumFromParseNum",
+        48 => "VarI2FromUI1",
+        49 => "VarI2FromI4",
+        50 => "VarI2FromR4",
+        51 => "VarI2FromR8",
+        52 => "VarI2FromCy",
+        53 => "VarI2FromDate",
+        54 => "VarI2FromStr",
+        55 => "VarI2FromDisp",
+        56 => "VarI2FromBool",
+        57 => "SafeArraySetIID",
+        58 => "VarI4FromUI1",
+        59 => "VarI4FromI2",
+        60 => "VarI4FromR4",
+        61 => "VarI4FromR8",
+        62 => "VarI4FromCy",
+        63 => "VarI4FromDate",
+        64 => "VarI4FromStr",
+        65 => "VarI4FromDisp",
+        66 => "VarI4FromBool",
+        67 => "SafeArrayGetIID",
+        68 => "VarR4FromUI1",
+        69 => "VarR4FromI2",
+        70 => "VarR4FromI4",
+        71 => "VarR4FromR8",
+        72 => "VarR4FromCy",
+        73 => "VarR4FromDate",
+        74 => "VarR4FromStr",
+        75 => "VarR4FromDisp",
+        76 => "VarR4FromBool",
+        77 => "SafeArrayGetVartype",
+        78 => "VarR8FromUI1",
+        79 => "VarR8FromI2",
+        80 => "VarR8FromI4",
+        81 => "VarR8FromR4",
+        82 => "VarR8FromCy",
+        83 => "VarR8FromDate",
+        84 => "VarR8FromStr",
+        85 => "VarR8FromDisp",
+        86 => "VarR8FromBool",
+        87 => "VarFormat",
+        88 => "VarDateFromUI1",
+        89 => "VarDateFromI2",
+        90 => "VarDateFromI4",
+        91 => "VarDateFromR4",
+        92 => "VarDateFromR8",
+        93 => "VarDateFromCy",
+        94 => "VarDateFromStr",
+        95 => "VarDateFromDisp",
+        96 => "VarDateFromBool",
+        97 => "VarFormatDateTime",
+        98 => "VarCyFromUI1",
+        99 => "VarCyFromI2",
+        100 => "VarCyFromI4",
+        101 => "VarCyFromR4",
+        102 => "VarCyFromR8",
+        103 => "VarCyFromDate",
+        104 => "VarCyFromStr",
+        105 => "VarCyFromDisp",
+        106 => "VarCyFromBool",
+        107 => "VarFormatNumber",
+        108 => "VarBstrFromUI1",
+        109 => "VarBstrFromI2",
+        110 => "VarBstrFromI4",
+        111 => "VarBstrFromR4",
+        112 => "VarBstrFromR8",
+        113 => "VarBstrFromCy",
+        114 => "VarBstrFromDate",
+        115 => "VarBstrFromDisp",
+        116 => "VarBstrFromBool",
+        117 => "VarFormatPercent",
+        118 => "VarBoolFromUI1",
+        119 => "VarBoolFromI2",
+        120 => "VarBoolFromI4",
+        121 => "VarBoolFromR4",
+        122 => "VarBoolFromR8",
+        123 => "VarBoolFromDate",
+        124 => "VarBoolFromCy",
+        125 => "VarBoolFromStr",
+        126 => "VarBoolFromDisp",
+        127 => "VarFormatCurrency",
+        128 => "VarWeekdayName",
+        129 => "VarMonthName",
+        130 => "VarUI1FromI2",
+        131 => "VarUI1FromI4",
+        132 => "VarUI1FromR4",
+        133 => "VarUI1FromR8",
+        134 => "VarUI1FromCy",
+        135 => "VarUI1FromDate",
+        136 => "VarUI1FromStr",
+        137 => "VarUI1FromDisp",
+        138 => "VarUI1FromBool",
+        139 => "VarFormatFromTokens",
+        140 => "VarTokenizeFormatString",
+        141 => "VarAdd",
+        142 => "VarAnd",
+        143 => "VarDiv",
+        144 => "DllCanUnloadNow",
+        145 => "DllGetClassObject",
+        146 => "DispCallFunc",
+        147 => "VariantChangeTypeEx",
+        148 => "SafeArrayPtrOfIndex",
+        149 => "SysStringByteLen",
+        150 => "SysAllocStringByteLen",
+        151 => "DllRegisterServer",
+        152 => "VarEqv",
+        153 => "VarIdiv",
+        154 => "VarImp",
+        155 => "VarMod",
+        156 => "VarMul",
+        157 => "VarOr",
+        158 => "VarPow",
+        159 => "VarSub",
+        160 => "CreateTypeLib",
+        161 => "LoadTypeLib",
+        162 => "LoadRegTypeLib",
+        163 => "RegisterTypeLib",
+        164 => "QueryPathOfRegTypeLib",
+        165 => "LHashValOfNameSys",
+        166 => "LHashValOfNameSysA",
+        167 => "VarXor",
+        168 => "VarAbs",
+        169 => "VarFix",
+        170 => "OaBuildVersion",
+        171 => "ClearCustData",
+        172 => "VarInt",
+        173 => "VarNeg",
+        174 => "VarNot",
+        175 => "VarRound",
+        176 => "VarCmp",
+        177 => "VarDecAdd",
+        178 => "VarDecDiv",
+        179 => "VarDecMul",
+        180 => "CreateTypeLib2",
+        181 => "VarDecSub",
+        182 => "VarDecAbs",
+        183 => "LoadTypeLibEx",
+        184 => "SystemTimeToVariantTime",
+        185 => "VariantTimeToSystemTime",
+        186 => "UnRegisterTypeLib",
+        187 => "VarDecFix",
+        188 => "VarDecInt",
+        189 => "VarDecNeg",
+        190 => "VarDecFromUI1",
+        191 => "VarDecFromI2",
+        192 => "VarDecFromI4",
+        193 => "VarDecFromR4",
+        194 => "VarDecFromR8",
+        195 => "VarDecFromDate",
+        196 => "VarDecFromCy",
+        197 => "VarDecFromStr",
+        198 => "VarDecFromDisp",
+        199 => "VarDecFromBool",
+        200 => "GetErrorInfo",
+        201 => "SetErrorInfo",
+        202 => "CreateErrorInfo",
+        203 => "VarDecRound",
+        204 => "VarDecCmp",
+        205 => "VarI2FromI1",
+        206 => "VarI2FromUI2",
+        207 => "VarI2FromUI4",
+        208 => "VarI2FromDec",
+        209 => "VarI4FromI1",
+        210 => "VarI4FromUI2",
+        211 => "VarI4FromUI4",
+        212 => "VarI4FromDec",
+        213 => "VarR4FromI1",
+        214 => "VarR4FromUI2",
+        215 => "VarR4FromUI4",
+        216 => "VarR4FromDec",
+        217 => "VarR8FromI1",
+        218 => "VarR8FromUI2",
+        219 => "VarR8FromUI4",
+        220 => "VarR8FromDec",
+        221 => "VarDateFromI1",
+        222 => "VarDateFromUI2",
+        223 => "VarDateFromUI4",
+        224 => "VarDateFromDec",
+        225 => "VarCyFromI1",
+        226 => "VarCyFromUI2",
+        227 => "VarCyFromUI4",
+        228 => "VarCyFromDec",
+        229 => "VarBstrFromI1",
+        230 => "VarBstrFromUI2",
+        231 => "VarBstrFromUI4",
+        232 => "VarBstrFromDec",
+        233 => "VarBoolFromI1",
+        234 => "VarBoolFromUI2",
+        235 => "VarBoolFromUI4",
+        236 => "VarBoolFromDec",
+        237 => "VarUI1FromI1",
+        238 => "VarUI1FromUI2",
+        239 => "VarUI1FromUI4",
+        240 => "VarUI1FromDec",
+        241 => "VarDecFromI1",
+        242 => "VarDecFromUI2",
+        243 => "VarDecFromUI4",
+        244 => "VarI1FromUI1",
+        245 => "VarI1FromI2",
+        246 => "VarI1FromI4",
+        247 => "VarI1FromR4",
+        248 => "VarI1FromR8",
+        249 => "VarI1FromDate",
+        250 => "VarI1FromCy",
+        251 => "VarI1FromStr",
+        252 => "VarI1FromDisp",
+        253 => "VarI1FromBool",
+        254 => "VarI1FromUI2",
+        255 => "VarI1FromUI4",
+        256 => "VarI1FromDec",
+        257 => "VarUI2FromUI1",
+        258 => "VarUI2FromI2",
+        259 => "VarUI2FromI4",
+        260 => "VarUI2FromR4",
+        261 => "VarUI2FromR8",
+        262 => "VarUI2FromDate",
+        263 => "VarUI2FromCy",
+        264 => "VarUI2FromStr",
+        265 => "VarUI2FromDisp",
+        266 => "VarUI2FromBool",
+        267 => "VarUI2FromI1",
+        268 => "VarUI2FromUI4",
+        269 => "VarUI2FromDec",
+        270 => "VarUI4FromUI1",
+        271 => "VarUI4FromI2",
+        272 => "VarUI4FromI4",
+        273 => "VarUI4FromR4",
+        274 => "VarUI4FromR8",
+        275 => "VarUI4FromDate",
+        276 => "VarUI4FromCy",
+        277 => "VarUI4FromStr",
+        278 => "VarUI4FromDisp",
+        279 => "VarUI4FromBool",
+        280 => "VarUI4FromI1",
+        281 => "VarUI4FromUI2",
+        282 => "VarUI4FromDec",
+        283 => "BSTR_UserSize",
+        284 => "BSTR_UserMarshal",
+        285 => "BSTR_UserUnmarshal",
+        286 => "BSTR_UserFree",
+        287 => "VARIANT_UserSize",
+        288 => "VARIANT_UserMarshal",
+        289 => "VARIANT_UserUnmarshal",
+        290 => "VARIANT_UserFree",
+        291 => "LPSAFEARRAY_UserSize",
+        292 => "LPSAFEARRAY_UserMarshal",
+        293 => "LPSAFEARRAY_UserUnmarshal",
+        294 => "LPSAFEARRAY_UserFree",
+        295 => "LPSAFEARRAY_Size",
+        296 => "LPSAFEARRAY_Marshal",
+        297 => "LPSAFEARRAY_Unmarshal",
+        298 => "VarDecCmpR8",
+        299 => "VarCyAdd",
+        300 => "DllUnregisterServer",
+        301 => "OACreateTypeLib2",
+        303 => "VarCyMul",
+        304 => "VarCyMulI4",
+        305 => "VarCySub",
+        306 => "VarCyAbs",
+        307 => "VarCyFix",
+        308 => "VarCyInt",
+        309 => "VarCyNeg",
+        310 => "VarCyRound",
+        311 => "VarCyCmp",
+        312 => "VarCyCmpR8",
+        313 => "VarBstrCat",
+        314 => "VarBstrCmp",
+        315 => "VarR8Pow",
+        316 => "VarR4CmpR8",
+        317 => "VarR8Round",
+        318 => "VarCat",
+        319 => "VarDateFromUdateEx",
+        322 => "GetRecordInfoFromGuids",
+        323 => "GetRecordInfoFromTypeInfo",
+        325 => "SetVarConversionLocaleSetting",
+        326 => "GetVarConversionLocaleSetting",
+        327 => "SetOaNoCache",
+        329 => "VarCyMulI8",
+        330 => "VarDateFromUdate",
+        331 => "VarUdateFromDate",
+        332 => "GetAltMonthNames",
+        333 => "VarI8FromUI1",
+        334 => "VarI8FromI2",
+        335 => "VarI8FromR4",
+        336 => "VarI8FromR8",
+        337 => "VarI8FromCy",
+        338 => "VarI8FromDate",
+        339 => "VarI8FromStr",
+        340 => "VarI8FromDisp",
+        341 => "VarI8FromBool",
+        342 => "VarI8FromI1",
+        343 => "VarI8FromUI2",
+        344 => "VarI8FromUI4",
+        345 => "VarI8FromDec",
+        346 => "VarI2FromI8",
+        347 => "VarI2FromUI8",
+        348 => "VarI4FromI8",
+        349 => "VarI4FromUI8",
+        360 => "VarR4FromI8",
+        361 => "VarR4FromUI8",
+        362 => "VarR8FromI8",
+        363 => "VarR8FromUI8",
+        364 => "VarDateFromI8",
+        365 => "VarDateFromUI8",
+        366 => "VarCyFromI8",
+        367 => "VarCyFromUI8",
+        368 => "VarBstrFromI8",
+        369 => "VarBstrFromUI8",
+        370 => "VarBoolFromI8",
+        371 => "VarBoolFromUI8",
+        372 => "VarUI1FromI8",
+        373 => "VarUI1FromUI8",
+        374 => "VarDecFromI8",
+        375 => "VarDecFromUI8",
+        376 => "VarI1FromI8",
+        377 => "VarI1FromUI8",
+        378 => "VarUI2FromI8",
+        379 => "VarUI2FromUI8",
+        401 => "OleLoadPictureEx",
+        402 => "OleLoadPictureFileEx",
+        411 => "SafeArrayCreateVector",
+        412 => "SafeArrayCopyData",
+        413 => "VectorFromBstr",
+        414 => "BstrFromVector",
+        415 => "OleIconToCursor",
+        416 => "OleCreatePropertyFrameIndirect",
+        417 => "OleCreatePropertyFrame",
+        418 => "OleLoadPicture",
+        419 => "OleCreatePictureIndirect",
+        420 => "OleCreateFontIndirect",
+        421 => "OleTranslateColor",
+        422 => "OleLoadPictureFile",
+        423 => "OleSavePictureFile",
+        424 => "OleLoadPicturePath",
+        425 => "VarUI4FromI8",
+        426 => "VarUI4FromUI8",
+        427 => "VarI8FromUI8",
+        428 => "VarUI8FromI8",
+        429 => "VarUI8FromUI1",
+        430 => "VarUI8FromI2",
+        431 => "VarUI8FromR4",
+        432 => "VarUI8FromR8",
+        433 => "VarUI8FromCy",
+        434 => "VarUI8FromDate",
+        435 => "VarUI8FromStr",
+        436 => "VarUI8FromDisp",
+        437 => "VarUI8FromBool",
+        438 => "VarUI8FromI1",
+        439 => "VarUI8FromUI2",
+        440 => "VarUI8FromUI4",
+        441 => "VarUI8FromDec",
+        442 => "RegisterTypeLibForUser",
+        443 => "UnRegisterTypeLibForUser",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn machine_name(machine: u16) -> String {
+    match machine {
+        0x14c => "x86".to_string(),
+        0x8664 => "x86-64".to_string(),
+        0x1c0 => "ARM".to_string(),
+        0x1c4 => "ARMv7 Thumb".to_string(),
+        0xaa64 => "ARM64".to_string(),
+        0x200 => "Itanium".to_string(),
+        other => format!("machine 0x{:x}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn put(data: &mut [u8], at: usize, bytes: &[u8]) {
+        data[at..at + bytes.len()].copy_from_slice(bytes);
+    }
+
+    // A PE32 or PE32+ file with one .idata section (RVA 0x1000, file offset
+    // 0x200) importing two kernel32 functions by name, ws2_32 ordinals 3 and
+    // 999 and oleaut32 ordinal 6, a non-zero checksum and a 16-byte
+    // certificate table at 0x400. Returns the file and the offsets of the
+    // checksum and the certificate table's directory entry
+    fn fixture(is_plus: bool) -> (Vec<u8>, usize, usize) {
+        let mut data = vec![0u8; 0x410];
+        put(&mut data, 0, MZ_MAGIC);
+        put(&mut data, 0x3c, &0x40u32.to_le_bytes());
+        put(&mut data, 0x40, b"PE\0\0");
+        let optional_size: u16 = if is_plus { 240 } else { 224 };
+        put(&mut data, 0x44, &(if is_plus { 0x8664u16 } else { 0x14c }).to_le_bytes());
+        put(&mut data, 0x46, &1u16.to_le_bytes());
+        put(&mut data, 0x54, &optional_size.to_le_bytes());
+
+        let optional = 0x58;
+        put(&mut data, optional, &(if is_plus { PE32_PLUS_MAGIC } else { PE32_MAGIC }).to_le_bytes());
+        put(&mut data, optional + 60, &0x200u32.to_le_bytes());
+        put(&mut data, optional + 64, &0xdead_beefu32.to_le_bytes());
+        let (count_at, directories) = if is_plus { (optional + 108, optional + 112) } else { (optional + 92, optional + 96) };
+        put(&mut data, count_at, &16u32.to_le_bytes());
+        put(&mut data, directories + 8, &[0x1000u32.to_le_bytes(), 80u32.to_le_bytes()].concat());
+        put(&mut data, directories + 32, &[0x400u32.to_le_bytes(), 16u32.to_le_bytes()].concat());
+
+        let section = optional + optional_size as usize;
+        put(&mut data, section, b".idata\0\0");
+        for (index, value) in [0x200u32, 0x1000, 0x200, 0x200].iter().enumerate() {
+            put(&mut data, section + 8 + index * 4, &value.to_le_bytes());
+        }
+
+        let at = |rva: u32| (rva - 0x1000 + 0x200) as usize;
+        let flag = if is_plus { 1u64 << 63 } else { 1u64 << 31 };
+        let dlls: [(&[u8], u32, u32, &[u64]); 3] = [
+            (b"KERNEL32.dll", 0x10c0, 0x1060, &[0x1100, 0x1120]),
+            (b"WS2_32.dll", 0x10d0, 0x1080, &[flag | 3, flag | 999]),
+            (b"OLEAUT32.DLL", 0x10e0, 0x10a0, &[flag | 6]),
+        ];
+        for (index, (name, name_rva, lookup, thunks)) in dlls.iter().enumerate() {
+            let descriptor = at(0x1000) + index * 20;
+            put(&mut data, descriptor, &lookup.to_le_bytes());
+            put(&mut data, descriptor + 12, &name_rva.to_le_bytes());
+            put(&mut data, descriptor + 16, &lookup.to_le_bytes());
+            put(&mut data, at(*name_rva), name);
+            for (slot, thunk) in thunks.iter().enumerate() {
+                if is_plus {
+                    put(&mut data, at(*lookup) + slot * 8, &thunk.to_le_bytes());
+                } else {
+                    put(&mut data, at(*lookup) + slot * 4, &(*thunk as u32).to_le_bytes());
+                }
+            }
+        }
+        put(&mut data, at(0x1100) + 2, b"GetProcAddress");
+        put(&mut data, at(0x1120) + 2, b"ExitProcess");
+        let certificate: Vec<u8> = (0..16).collect();
+        put(&mut data, 0x400, &certificate);
+
+        (data, optional + 64, directories + 32)
+    }
+
+    // Expected values follow osslsigncode's exclusions and pefile's
+    // get_imphash, of "kernel32.getprocaddress,kernel32.exitprocess,
+    // ws2_32.closesocket,ws2_32.ord999,oleaut32.sysfreestring"
+    #[test]
+    fn known_authentihash_and_imphash() {
+        let expected = [
+            (false, "PE32", "x86", "6cc0807f73a8d920b3e10f242ff6a4be7d7c77b1f57cd1fd22c534ac55eba6f6"),
+            (true, "PE32+", "x86-64", "a46370e4c942fdaafd292a1a976809381a41f28035caaeca9e1a6d0bc3a049d7"),
+        ];
+        for (is_plus, format, machine, authentihash) in expected {
+            let report = analyze(&fixture(is_plus).0).unwrap();
+            assert_eq!(report.format, format);
+            assert_eq!(report.machine, machine);
+            assert_eq!(report.authentihash, authentihash);
+            assert!(report.signed);
+            assert_eq!(report.imports, 5);
+            assert_eq!(report.imphash.as_deref(), Some("6439aad95b0b3e4dab0342df36e5cbe3"));
+        }
+    }
+
+    #[test]
+    fn authentihash_ignores_the_checksum_and_signature() {
+        for is_plus in [false, true] {
+            let (data, checksum, security) = fixture(is_plus);
+            let original = analyze(&data).unwrap().authentihash;
+
+            let mut resigned = data.clone();
+            put(&mut resigned, checksum, &0x1234_5678u32.to_le_bytes());
+            put(&mut resigned, security + 4, &24u32.to_le_bytes());
+            resigned.truncate(0x400);
+            resigned.extend_from_slice(&[0xaa; 24]);
+            assert_eq!(analyze(&resigned).unwrap().authentihash, original);
+
+            let mut patched = data.clone();
+            patched[0x3ff] ^= 1;
+            assert_ne!(analyze(&patched).unwrap().authentihash, original);
+        }
+    }
+
+    #[test]
+    fn ordinals_are_only_named_for_known_dlls() {
+        assert_eq!(ordinal_name("ws2_32.dll", 23), Some("socket"));
+        assert_eq!(ordinal_name("wsock32.dll", 115), Some("WSAStartup"));
+        assert_eq!(ordinal_name("oleaut32.dll", 2), Some("SysAllocString"));
+        assert_eq!(ordinal_name("ws2_32.dll", 999), None);
+        assert_eq!(ordinal_name("kernel32.dll", 1), None);
+    }
+}
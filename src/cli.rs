@@ -0,0 +1,742 @@
+use std::path::{Path, PathBuf};
+
+use crate::archive::compression::Compression;
+use crate::archive::{hash_members, list_archive};
+use crate::binary::{analyze_binary, BinaryReport};
+use crate::config::load_config;
+use crate::export::{ExportedHash, ExportedResults};
+use crate::hash::calculate_hashes_parallel_streaming;
+use crate::hash::checkpoint::hash_with_resume;
+use crate::hash::follow::{FollowEvent, Follower, POLL_INTERVAL};
+use crate::hash::jcs::canonical_json_of_file;
+use crate::hash::normalize::Normalization;
+use crate::hash::perceptual::is_image_file;
+use crate::hash::range::{parse_size, ByteRange};
+use crate::hash::split::{hash_split_set, SplitSet};
+use crate::hash::Algorithm;
+use crate::rdiff::{apply_delta, create_delta, Signature, SignatureFormat, DEFAULT_BLOCK_LENGTH};
+use crate::state::RealOne;
+
+const USAGE: &str = "\
+Usage: realone [OPTIONS] FILE...
+       realone --verify EXPORT.json
+       realone --signature BASIS SIGNATURE
+       realone --delta SIGNATURE NEWFILE DELTA
+       realone --patch BASIS DELTA NEWFILE
+       realone --binary FILE...
+       realone --archive ARCHIVE [MEMBER...]
+       realone --print-canonical FILE...
+       realone --follow FILE
+
+Hashes files with the algorithms selected in the GUI (or --algorithms).
+Settings such as the BLAKE2 length or CRC model come from the saved config;
+text normalization is only applied when requested with the options below.
+
+Options:
+  -a, --algorithms LIST   Comma-separated algorithms, e.g. md5,sha256,blake2b
+      --offset N          Start hashing at byte N (decimal or 0x hex)
+      --length N          Hash at most N bytes from the offset
+      --skip-head N       Skip N bytes at the start of the file
+      --skip-tail N       Skip N bytes at the end of the file
+      --normalize-eol     Convert CRLF and lone CR line endings to LF
+      --strip-bom         Drop a leading UTF-8 byte order mark
+      --strip-trailing-whitespace
+                          Drop spaces and tabs at the end of each line
+      --canonical-json    Hash JSON files in RFC 8785 (JCS) canonical form
+      --print-canonical   Print the canonical form of JSON files instead of hashing
+      --decompress        Hash the decompressed contents of gzip, bzip2, xz and
+                          zstd files (found by their magic bytes)
+      --join-parts        Hash a numbered split set (.001, .002... or .part01,
+                          .part02...) as one file, then each of its parts
+      --resume            Save checkpoints while hashing files over 1 GB and carry
+                          on from the last one after an interruption; algorithms
+                          whose state cannot be saved start over (also with --verify)
+  -o, --export FILE       Save the results as JSON (one input file only)
+      --verify FILE       Repeat the check recorded in an exported JSON file
+      --signature         Write an rdiff (librsync) signature of BASIS
+      --delta             Write the rdiff delta from the file behind SIGNATURE to NEWFILE
+      --patch             Rebuild NEWFILE from BASIS and DELTA, then hash it
+      --block-size N      Signature block length (default 2048)
+      --signature-format F
+                          blake2-rollsum (default), blake2-rabinkarp, md4-rollsum
+                          or md4-rabinkarp
+      --archive           Hash the files inside a zip or (compressed) tar archive,
+                          all of them or only the MEMBERs named
+      --binary            Show the build ID and section hashes of ELF files, or
+                          the Authentihash and imphash of PE files
+      --follow            Keep hashing a file that is still being written and
+                          print the digests whenever it grows, until interrupted
+  -h, --help              Show this help
+
+Exit status is 0 on success, 1 if a verification fails and 2 on errors.";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RdiffCommand {
+    Signature,
+    Delta,
+    Patch,
+}
+
+struct Options {
+    algorithms: Option<Vec<Algorithm>>,
+    offset: Option<u64>,
+    length: Option<u64>,
+    skip_head: Option<u64>,
+    skip_tail: Option<u64>,
+    normalization: Normalization,
+    decompress: bool,
+    join_parts: bool,
+    resume: bool,
+    export: Option<PathBuf>,
+    verify: Option<PathBuf>,
+    rdiff: Option<RdiffCommand>,
+    block_size: u32,
+    signature_format: SignatureFormat,
+    binary: bool,
+    archive: bool,
+    print_canonical: bool,
+    follow: bool,
+    files: Vec<PathBuf>,
+}
+
+impl Options {
+    fn range(&self) -> Result<Option<ByteRange>, String> {
+        let offset_length = self.offset.is_some() || self.length.is_some();
+        let skip = self.skip_head.is_some() || self.skip_tail.is_some();
+        match (offset_length, skip) {
+            (true, true) => Err("--offset/--length cannot be combined with --skip-head/--skip-tail".to_string()),
+            (true, false) => Ok(Some(ByteRange::OffsetLength {
+                offset: self.offset.unwrap_or(0),
+                length: self.length,
+            })),
+            (false, true) => Ok(Some(ByteRange::Skip {
+                head: self.skip_head.unwrap_or(0),
+                tail: self.skip_tail.unwrap_or(0),
+            })),
+            (false, false) => Ok(None),
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut options = Options {
+        algorithms: None,
+        offset: None,
+        length: None,
+        skip_head: None,
+        skip_tail: None,
+        normalization: Normalization::default(),
+        decompress: false,
+        join_parts: false,
+        resume: false,
+        export: None,
+        verify: None,
+        rdiff: None,
+        block_size: DEFAULT_BLOCK_LENGTH,
+        signature_format: SignatureFormat::Blake2Rollsum,
+        binary: false,
+        archive: false,
+        print_canonical: false,
+        follow: false,
+        files: Vec::new(),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "-a" | "--algorithms" => {
+                let list = value(&arg)?;
+                let algorithms = list
+                    .split(',')
+                    .filter(|name| !name.trim().is_empty())
+                    .map(|name| Algorithm::from_name(name.trim()).ok_or_else(|| format!("unknown algorithm \"{}\"", name)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                options.algorithms = Some(algorithms);
+            }
+            "--offset" => options.offset = Some(parse_size(&value(&arg)?)?),
+            "--length" => options.length = Some(parse_size(&value(&arg)?)?),
+            "--skip-head" => options.skip_head = Some(parse_size(&value(&arg)?)?),
+            "--skip-tail" => options.skip_tail = Some(parse_size(&value(&arg)?)?),
+            "--normalize-eol" => options.normalization.line_endings = true,
+            "--strip-bom" => options.normalization.strip_bom = true,
+            "--strip-trailing-whitespace" => options.normalization.strip_trailing_whitespace = true,
+            "--canonical-json" => options.normalization.canonical_json = true,
+            "--print-canonical" => options.print_canonical = true,
+            "--decompress" => options.decompress = true,
+            "--join-parts" => options.join_parts = true,
+            "--resume" => options.resume = true,
+            "-o" | "--export" => options.export = Some(PathBuf::from(value(&arg)?)),
+            "--verify" => options.verify = Some(PathBuf::from(value(&arg)?)),
+            "--signature" => options.rdiff = Some(RdiffCommand::Signature),
+            "--delta" => options.rdiff = Some(RdiffCommand::Delta),
+            "--patch" => options.rdiff = Some(RdiffCommand::Patch),
+            "--block-size" => {
+                let size = parse_size(&value(&arg)?)?;
+                options.block_size = u32::try_from(size)
+                    .ok()
+                    .filter(|size| *size > 0)
+                    .ok_or_else(|| format!("invalid block size {}", size))?;
+            }
+            "--signature-format" => {
+                let name = value(&arg)?;
+                options.signature_format = SignatureFormat::from_name(&name)
+                    .ok_or_else(|| format!("unknown signature format \"{}\"", name))?;
+            }
+            "--binary" => options.binary = true,
+            "--archive" => options.archive = true,
+            "--follow" => options.follow = true,
+            "--" => options.files.extend(args.by_ref().map(PathBuf::from)),
+            _ if arg.starts_with('-') && arg.len() > 1 => return Err(format!("unknown option \"{}\"", arg)),
+            _ => options.files.push(PathBuf::from(arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+// Entry point when RealOne is started with arguments; returns the exit code
+pub fn run(args: Vec<String>) -> i32 {
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return 0;
+    }
+
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("realone: {}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    let mut state = RealOne::default();
+    load_config(&mut state);
+
+    let result = match (&options.verify, options.rdiff) {
+        (Some(export), _) => verify(&state, export, options.resume),
+        (None, Some(command)) => rdiff(&state, command, options),
+        (None, None) if options.binary => inspect_binaries(&options),
+        (None, None) if options.archive => hash_archive(&state, &options),
+        (None, None) if options.print_canonical => print_canonical(&options),
+        (None, None) if options.follow => follow(&state, &options),
+        (None, None) => hash_files(&state, &options),
+    };
+
+    result.unwrap_or_else(|e| {
+        eprintln!("realone: {}", e);
+        2
+    })
+}
+
+fn hash_files(state: &RealOne, options: &Options) -> Result<i32, String> {
+    if options.files.is_empty() {
+        return Err("no input files (see --help)".to_string());
+    }
+    if options.export.is_some() && options.files.len() > 1 {
+        return Err("--export takes a single input file".to_string());
+    }
+
+    let algorithms = options.algorithms.clone().unwrap_or_else(|| state.selected_algorithms.clone());
+    if algorithms.is_empty() {
+        return Err("no algorithms selected".to_string());
+    }
+    let range = options.range()?;
+    let mut exit_code = 0;
+    let mut joined_sets: Vec<PathBuf> = Vec::new();
+
+    for file in &options.files {
+        let split_set = if options.join_parts { SplitSet::detect(file) } else { None };
+        if let Some(set) = &split_set {
+            // Naming several parts of one set (image.7z.*) hashes it once
+            if joined_sets.contains(&set.parts[0]) {
+                continue;
+            }
+            joined_sets.push(set.parts[0].clone());
+        }
+        // A joined set is reported under the name of the file it was cut from
+        let name = match &split_set {
+            Some(set) => file.with_file_name(&set.name),
+            None => file.clone(),
+        };
+        let size = match &split_set {
+            Some(set) => set.total_size(),
+            None => std::fs::metadata(file).map(|m| m.len()).unwrap_or(0),
+        };
+
+        if let Some(set) = &split_set {
+            println!("# {}: joined from {} parts ({} bytes)", name.display(), set.parts.len(), size);
+        }
+        if let Some(range) = &range {
+            let size = match &split_set {
+                Some(_) => size,
+                None => std::fs::metadata(file).map_err(|e| format!("{}: {}", file.display(), e))?.len(),
+            };
+            let span = range.resolve(size).map_err(|e| format!("{}: {}", name.display(), e))?;
+            println!("# {}: {} (bytes {}..{} of {})", name.display(), range, span.start, span.end, size);
+        }
+        let first_part = split_set.as_ref().map_or(file, |set| &set.parts[0]);
+        let compression = if options.decompress { Compression::detect_file(first_part) } else { Compression::None };
+        if compression != Compression::None {
+            println!("# {}: decompressed ({})", name.display(), compression);
+        }
+        if options.normalization.is_active() {
+            println!("# {}: normalized ({})", name.display(), options.normalization);
+        }
+
+        // Like the GUI, the saved selection only applies perceptual hashes to
+        // images; asked for explicitly, they report an error for other files
+        let algorithms: Vec<Algorithm> = if options.algorithms.is_none() && !is_image_file(file) {
+            algorithms.iter().filter(|alg| !alg.is_perceptual()).cloned().collect()
+        } else {
+            algorithms.clone()
+        };
+
+        let (results, parts) = match &split_set {
+            Some(set) => {
+                let hashes = hash_split_set(
+                    set,
+                    &algorithms,
+                    &state.hash_options,
+                    range.as_ref(),
+                    options.normalization,
+                    options.decompress,
+                );
+                (hashes.results, hashes.parts)
+            }
+            None => {
+                let results = hash_file(
+                    state,
+                    file,
+                    &algorithms,
+                    range.as_ref(),
+                    options.normalization,
+                    options.decompress,
+                    options.resume,
+                );
+                (results, Vec::new())
+            }
+        };
+
+        let (exported, mut complete) = print_hashes(state, &name, results);
+        // Each part's own digests follow those of the joined file
+        for part in parts {
+            let (_, part_complete) = print_hashes(state, &part.path, part.results);
+            complete &= part_complete;
+        }
+        if !complete {
+            exit_code = 2;
+        }
+
+        if let Some(target) = &options.export {
+            let export = ExportedResults {
+                file: std::fs::canonicalize(file).unwrap_or_else(|_| file.clone()),
+                file_size: size,
+                range,
+                normalization: options.normalization,
+                decompressed: compression != Compression::None,
+                split_set: split_set.is_some(),
+                results: exported,
+            };
+            export.save(target)?;
+        }
+    }
+
+    Ok(exit_code)
+}
+
+// With --resume, files large enough for checkpoints carry on from the last
+// one; a note on stdout says where, or why none could be kept
+fn hash_file(
+    state: &RealOne,
+    file: &PathBuf,
+    algorithms: &[Algorithm],
+    range: Option<&ByteRange>,
+    normalization: Normalization,
+    decompress: bool,
+    resume: bool,
+) -> Vec<(Algorithm, String)> {
+    if resume {
+        let hashes = hash_with_resume(file, algorithms, &state.hash_options, range, normalization, decompress);
+        if let Some(offset) = hashes.resumed_from {
+            println!("# {}: resumed from checkpoint at {} bytes", file.display(), offset);
+        }
+        if let Some(skipped) = &hashes.skipped {
+            println!("# {}: resume skipped, {}", file.display(), skipped);
+        }
+        return hashes.results;
+    }
+    calculate_hashes_parallel_streaming(file, algorithms, &state.hash_options, range, normalization, decompress)
+}
+
+// Prints one "ALG (name) = digest" line per result and errors on stderr;
+// returns the digests for an export and whether none of them failed
+fn print_hashes(state: &RealOne, name: &Path, results: Vec<(Algorithm, String)>) -> (Vec<ExportedHash>, bool) {
+    let mut exported = Vec::new();
+    let mut complete = true;
+
+    for (algorithm, hex) in results {
+        let label = algorithm.label(&state.hash_options);
+        if algorithm.is_fuzzy() && !hex.starts_with("Error") {
+            println!("{} ({}) = {}", label, name.display(), hex);
+            exported.push(ExportedHash { algorithm, label, hex });
+            continue;
+        }
+        match hex::decode(&hex) {
+            Ok(bytes) => {
+                let formatted = state.result_format(&algorithm).format_hash(&bytes);
+                println!("{} ({}) = {}", label, name.display(), formatted);
+                exported.push(ExportedHash { algorithm, label, hex });
+            }
+            Err(_) => {
+                eprintln!("{} ({}): {}", label, name.display(), hex);
+                complete = false;
+            }
+        }
+    }
+    (exported, complete)
+}
+
+// Writes each file's canonical JSON to stdout, so it can be diffed or piped
+// into other tools; the range and text options apply as when hashing
+fn print_canonical(options: &Options) -> Result<i32, String> {
+    if options.files.is_empty() {
+        return Err("no input files (see --help)".to_string());
+    }
+    let range = options.range()?;
+    let mut exit_code = 0;
+
+    for file in &options.files {
+        let compression = if options.decompress { Compression::detect_file(file) } else { Compression::None };
+        match canonical_json_of_file(file, range.as_ref(), options.normalization, compression) {
+            Ok(canonical) => println!("{}", canonical),
+            Err(e) => {
+                eprintln!("realone: {}: {}", file.display(), e);
+                exit_code = 2;
+            }
+        }
+    }
+
+    Ok(exit_code)
+}
+
+// Prints the length and digests each time the file grows, and a comment line
+// when it is truncated or replaced and hashing starts over. Read errors are
+// reported but polling goes on, since a rotated log can be missing briefly
+fn follow(state: &RealOne, options: &Options) -> Result<i32, String> {
+    let file = match options.files.as_slice() {
+        [file] => file,
+        _ => return Err("--follow takes a single file".to_string()),
+    };
+    let algorithms: Vec<Algorithm> = options
+        .algorithms
+        .clone()
+        .unwrap_or_else(|| state.selected_algorithms.clone());
+    if algorithms.is_empty() {
+        return Err("no algorithms selected".to_string());
+    }
+
+    let mut follower = Follower::new(file, &algorithms, &state.hash_options);
+    loop {
+        match follower.poll() {
+            Ok(update) => {
+                if let Some(results) = update.results {
+                    if matches!(update.event, FollowEvent::Truncated | FollowEvent::Rotated) {
+                        println!("# {}: {}, hashing again from the start", file.display(), update.event);
+                    }
+                    println!("# {}: {} bytes", file.display(), update.length);
+                    print_hashes(state, file, results);
+                }
+            }
+            Err(e) => eprintln!("realone: {}", e),
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// Members are named as "archive:member"; a zip member whose contents do not
+// match its stored CRC-32 fails like a verification would
+fn hash_archive(state: &RealOne, options: &Options) -> Result<i32, String> {
+    let (archive, names) = match options.files.split_first() {
+        Some(split) => split,
+        None => return Err("no archive given (see --help)".to_string()),
+    };
+    let algorithms: Vec<Algorithm> = options
+        .algorithms
+        .clone()
+        .unwrap_or_else(|| state.selected_algorithms.clone())
+        .into_iter()
+        .filter(|alg| options.algorithms.is_some() || !alg.is_perceptual())
+        .collect();
+    if algorithms.is_empty() {
+        return Err("no algorithms selected".to_string());
+    }
+
+    let listing = list_archive(archive)?;
+    let indexes = names
+        .iter()
+        .map(|name| {
+            let name = name.to_string_lossy();
+            listing
+                .members
+                .iter()
+                .find(|member| member.name == name)
+                .map(|member| member.index)
+                .ok_or_else(|| format!("{}: no file named \"{}\" in the archive", archive.display(), name))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    println!("# {}: {}, {} files", archive.display(), listing.format, listing.members.len());
+
+    let selection = (!names.is_empty()).then_some(indexes.as_slice());
+    let hashes = hash_members(archive, &listing, selection, &algorithms, &state.hash_options)?;
+    let mut exit_code = 0;
+
+    for member in &hashes {
+        let name = format!("{}:{}", archive.display(), member.member.name);
+        match &member.results {
+            Ok(results) => {
+                for (algorithm, hex) in results {
+                    let label = algorithm.label(&state.hash_options);
+                    match hex::decode(hex) {
+                        Ok(bytes) if !algorithm.is_fuzzy() => {
+                            println!("{} ({}) = {}", label, name, state.result_format(algorithm).format_hash(&bytes));
+                        }
+                        _ if hex.starts_with("Error") => {
+                            eprintln!("{} ({}): {}", label, name, hex);
+                            exit_code = 2;
+                        }
+                        _ => println!("{} ({}) = {}", label, name, hex),
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("realone: {}: {}", name, e);
+                exit_code = 2;
+            }
+        }
+        if member.crc_matches() == Some(false) {
+            eprintln!(
+                "{}: CRC-32 mismatch, stored {:08x} but the contents give {:08x}",
+                name,
+                member.member.stored_crc32.unwrap_or(0),
+                member.computed_crc32.unwrap_or(0)
+            );
+            exit_code = exit_code.max(1);
+        }
+    }
+
+    Ok(exit_code)
+}
+
+fn inspect_binaries(options: &Options) -> Result<i32, String> {
+    if options.files.is_empty() {
+        return Err("no input files (see --help)".to_string());
+    }
+    let mut exit_code = 0;
+
+    for file in &options.files {
+        match analyze_binary(file) {
+            Ok(BinaryReport::Elf(elf)) => {
+                println!("# {}: {} {}", file.display(), elf.class, elf.machine);
+                match &elf.build_id {
+                    Some(build_id) => println!("Build ID ({}) = {}", file.display(), build_id),
+                    None => println!("# {}: no GNU build ID note", file.display()),
+                }
+                for section in &elf.sections {
+                    println!("SHA256[{}] ({}) = {}", section.name, file.display(), section.sha256);
+                }
+            }
+            Ok(BinaryReport::Pe(pe)) => {
+                println!(
+                    "# {}: {} {}, {}, {} imports",
+                    file.display(),
+                    pe.format,
+                    pe.machine,
+                    if pe.signed { "signed" } else { "not signed" },
+                    pe.imports
+                );
+                println!("Authentihash ({}) = {}", file.display(), pe.authentihash);
+                if let Some(imphash) = &pe.imphash {
+                    println!("Imphash ({}) = {}", file.display(), imphash);
+                }
+            }
+            Err(e) => {
+                eprintln!("realone: {}", e);
+                exit_code = 2;
+            }
+        }
+    }
+
+    Ok(exit_code)
+}
+
+// The rdiff commands take their files in the same order as `rdiff` itself
+fn rdiff(state: &RealOne, command: RdiffCommand, mut options: Options) -> Result<i32, String> {
+    let expected = match command {
+        RdiffCommand::Signature => 2,
+        RdiffCommand::Delta | RdiffCommand::Patch => 3,
+    };
+    if options.files.len() != expected {
+        return Err(format!("expected {} file arguments (see --help)", expected));
+    }
+
+    match command {
+        RdiffCommand::Signature => {
+            let signature = Signature::compute(&options.files[0], options.signature_format, options.block_size)?;
+            signature.save(&options.files[1])?;
+            println!(
+                "# {}: {} signature, {} blocks of {} bytes",
+                options.files[1].display(),
+                signature.format,
+                signature.blocks.len(),
+                signature.block_length
+            );
+            Ok(0)
+        }
+        RdiffCommand::Delta => {
+            let signature = Signature::load(&options.files[0])?;
+            let stats = create_delta(&signature, &options.files[1], &options.files[2])?;
+            println!(
+                "# {}: {} bytes copied from the basis, {} bytes literal, delta is {} bytes",
+                options.files[2].display(),
+                stats.copied_bytes,
+                stats.literal_bytes,
+                stats.delta_size
+            );
+            Ok(0)
+        }
+        RdiffCommand::Patch => {
+            let size = apply_delta(&options.files[0], &options.files[1], &options.files[2])?;
+            println!("# {}: {} bytes written", options.files[2].display(), size);
+            // The rebuilt file is hashed like any other input
+            options.files = vec![options.files[2].clone()];
+            hash_files(state, &options)
+        }
+    }
+}
+
+// The recorded path is tried first, then a file of the same name next to
+// the export, so an export can travel together with the file it describes
+fn locate_file(export_path: &Path, recorded: &Path) -> PathBuf {
+    if recorded.exists() {
+        return recorded.to_path_buf();
+    }
+    match (export_path.parent(), recorded.file_name()) {
+        (Some(dir), Some(name)) if dir.join(name).exists() => dir.join(name),
+        _ => recorded.to_path_buf(),
+    }
+}
+
+fn verify(state: &RealOne, export_path: &Path, resume: bool) -> Result<i32, String> {
+    let export = ExportedResults::load(export_path)?;
+    let file = locate_file(export_path, &export.file);
+
+    // The parts of a split set are found again next to the recorded one
+    let split_set = if export.split_set {
+        let missing = || format!("{}: the other parts of the split set are missing", file.display());
+        Some(SplitSet::detect(&file).ok_or_else(missing)?)
+    } else {
+        None
+    };
+
+    if let Some(set) = &split_set {
+        println!("# {}: joined from {} parts", file.display(), set.parts.len());
+    }
+    if let Some(range) = &export.range {
+        println!("# {}: {}", file.display(), range);
+    }
+    if export.decompressed {
+        println!("# {}: decompressed", file.display());
+    }
+    if export.normalization.is_active() {
+        println!("# {}: normalized ({})", file.display(), export.normalization);
+    }
+
+    let algorithms: Vec<Algorithm> = export.results.iter().map(|r| r.algorithm.clone()).collect();
+    let results = match &split_set {
+        Some(set) => {
+            hash_split_set(
+                set,
+                &algorithms,
+                &state.hash_options,
+                export.range.as_ref(),
+                export.normalization,
+                export.decompressed,
+            )
+            .results
+        }
+        None => hash_file(
+            state,
+            &file,
+            &algorithms,
+            export.range.as_ref(),
+            export.normalization,
+            export.decompressed,
+            resume,
+        ),
+    };
+    let mut failed = false;
+
+    for (expected, (algorithm, actual)) in export.results.iter().zip(results) {
+        let label = algorithm.label(&state.hash_options);
+        if label != expected.label {
+            println!("{}: FAILED (recorded with {}, current settings give {})", expected.label, expected.label, label);
+            failed = true;
+        } else if actual == expected.hex {
+            println!("{}: OK", label);
+        } else if actual.starts_with("Error") {
+            println!("{}: FAILED ({})", label, actual);
+            failed = true;
+        } else {
+            println!("{}: FAILED", label);
+            failed = true;
+        }
+    }
+
+    Ok(if failed { 1 } else { 0 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    // An exported range is checked again by --verify, and only bytes inside
+    // the range can make it fail
+    #[test]
+    fn exported_ranges_verify() {
+        let dir = ScratchDir::new("cli-range");
+        let file = dir.join("firmware.bin");
+        let data: Vec<u8> = (0..1000).map(|i| (i * 13) as u8).collect();
+        std::fs::write(&file, &data).unwrap();
+        let export = dir.join("firmware.json");
+        let (file_arg, export_arg) = (file.to_str().unwrap(), export.to_str().unwrap());
+        let state = RealOne::default();
+
+        let ranges = [
+            (args(&["--offset", "0x40", "--length", "512"]), 0x40 + 100, 0x40 + 512),
+            (args(&["--skip-head", "16", "--skip-tail", "256"]), 16, 1000 - 256),
+        ];
+        for (range, inside, outside) in ranges {
+            let mut list = args(&["-a", "sha256,crc32", "-o", export_arg, file_arg]);
+            list.splice(0..0, range);
+            assert_eq!(hash_files(&state, &parse_args(list).unwrap()), Ok(0));
+            assert!(ExportedResults::load(&export).unwrap().range.is_some());
+            assert_eq!(verify(&state, &export, false), Ok(0));
+
+            let mut changed = data.clone();
+            changed[outside] ^= 0xff;
+            std::fs::write(&file, &changed).unwrap();
+            assert_eq!(verify(&state, &export, false), Ok(0));
+
+            changed[inside] ^= 0xff;
+            std::fs::write(&file, &changed).unwrap();
+            assert_eq!(verify(&state, &export, false), Ok(1));
+            std::fs::write(&file, &data).unwrap();
+        }
+    }
+}
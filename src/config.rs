@@ -1,7 +1,12 @@
 use std::fs;
 use std::path::PathBuf;
-use crate::hash::{Algorithm, GostVariant, TigerVariant};
-use crate::state::HashFormat;
+use crate::hash::{Algorithm, Blake2Params, Ed2kVariant, GostVariant, HavalVariant, TigerVariant};
+use crate::hash::crc::{CrcModel, CustomCrc};
+use crate::hash::cdc::CdcParams;
+use crate::rdiff::{SignatureFormat, DEFAULT_BLOCK_LENGTH};
+use crate::hash::normalize::Normalization;
+use crate::hash::piecewise::WindowSize;
+use crate::state::{HashFormat, RealOne};
 
 #[derive(serde::Serialize, serde::Deserialize)]
 struct Config {
@@ -9,6 +14,22 @@ struct Config {
     hash_format: Option<HashFormat>, // Optional for compatibility with old configs
     gost_variant: Option<GostVariant>, // Optional for compatibility with old configs
     tiger_variant: Option<TigerVariant>, // Optional for compatibility with old configs
+    blake2b: Option<Blake2Params>, // Optional for compatibility with old configs
+    blake2s: Option<Blake2Params>, // Optional for compatibility with old configs
+    crc_model: Option<CrcModel>, // Optional for compatibility with old configs
+    custom_crc: Option<CustomCrc>, // User-defined Rocksoft parameters for CrcModel::Custom
+    haval_variant: Option<HavalVariant>, // Optional for compatibility with old configs
+    ed2k_variant: Option<Ed2kVariant>, // Optional for compatibility with old configs
+    piecewise_algorithm: Option<Algorithm>, // Optional for compatibility with old configs
+    piecewise_window: Option<WindowSize>, // Optional for compatibility with old configs
+    normalization: Option<Normalization>, // Optional for compatibility with old configs
+    decompress: Option<bool>, // Optional for compatibility with old configs
+    resume: Option<bool>, // Optional for compatibility with old configs
+    cdc_params: Option<CdcParams>, // Optional for compatibility with old configs
+    cdc_algorithm: Option<Algorithm>, // Optional for compatibility with old configs
+    rdiff_format: Option<SignatureFormat>, // Optional for compatibility with old configs
+    rdiff_block_length: Option<u32>, // Optional for compatibility with old configs
+    image_algorithm: Option<Algorithm>, // Optional for compatibility with old configs
 }
 
 fn config_path() -> PathBuf {
@@ -18,49 +39,80 @@ fn config_path() -> PathBuf {
     path
 }
 
-pub fn load_config() -> (Vec<Algorithm>, HashFormat, GostVariant, TigerVariant) {
+// Applies the saved configuration to the state, keeping defaults for anything missing
+pub fn load_config(state: &mut RealOne) {
     let path = config_path();
-    
+
     if let Ok(content) = fs::read_to_string(&path) {
         if let Ok(config) = serde_json::from_str::<Config>(&content) {
-            let format = config.hash_format.unwrap_or(HashFormat::HexLower);
-            let gost_variant = config.gost_variant.unwrap_or(GostVariant::CryptoPro);
-            let tiger_variant = config.tiger_variant.unwrap_or(TigerVariant::Tiger);
-            return (config.selected_algorithms, format, gost_variant, tiger_variant);
+            state.selected_algorithms = config.selected_algorithms;
+            state.hash_format = config.hash_format.unwrap_or(HashFormat::HexLower);
+            state.hash_options.gost_variant = config.gost_variant.unwrap_or(GostVariant::CryptoPro);
+            state.hash_options.tiger_variant = config.tiger_variant.unwrap_or(TigerVariant::Tiger);
+            state.hash_options.blake2b = config.blake2b.unwrap_or_else(Blake2Params::blake2b_default);
+            state.hash_options.blake2s = config.blake2s.unwrap_or_else(Blake2Params::blake2s_default);
+            state.hash_options.crc_model = config.crc_model.unwrap_or(CrcModel::Crc32Iscsi);
+            state.hash_options.custom_crc = config.custom_crc.unwrap_or_default();
+            state.hash_options.haval_variant = config.haval_variant.unwrap_or(HavalVariant::Haval256_5);
+            state.hash_options.ed2k_variant = config.ed2k_variant.unwrap_or(Ed2kVariant::New);
+            state.piecewise_algorithm = config.piecewise_algorithm.unwrap_or(Algorithm::SHA256);
+            state.piecewise_window = config.piecewise_window.unwrap_or(WindowSize::Mib1);
+            state.normalization = config.normalization.unwrap_or_default();
+            state.decompress = config.decompress.unwrap_or(false);
+            state.resume = config.resume.unwrap_or(false);
+            let cdc_params = config.cdc_params.unwrap_or_default();
+            state.cdc_min = cdc_params.min_size.to_string();
+            state.cdc_avg = cdc_params.avg_size.to_string();
+            state.cdc_max = cdc_params.max_size.to_string();
+            state.cdc_algorithm = config.cdc_algorithm.unwrap_or(Algorithm::SHA256);
+            state.rdiff_format = config.rdiff_format.unwrap_or(SignatureFormat::Blake2Rollsum);
+            state.rdiff_block_length = config.rdiff_block_length.unwrap_or(DEFAULT_BLOCK_LENGTH);
+            state.image_algorithm = config.image_algorithm.unwrap_or(Algorithm::PHASH);
+            return;
         }
     }
-    
+
     // Default values if there's no configuration
-    (
-        vec![
-            Algorithm::MD5,
-            Algorithm::SHA256,
-            Algorithm::SHA512,
-        ],
-        HashFormat::HexLower,
-        GostVariant::CryptoPro,
-        TigerVariant::Tiger,
-    )
+    state.selected_algorithms = vec![
+        Algorithm::MD5,
+        Algorithm::SHA256,
+        Algorithm::SHA512,
+    ];
 }
 
-pub fn save_config(algorithms: &[Algorithm], format: HashFormat, gost_variant: GostVariant, tiger_variant: TigerVariant) -> Result<(), Box<dyn std::error::Error>> {
+pub fn save_config(state: &RealOne) -> Result<(), Box<dyn std::error::Error>> {
     let path = config_path();
-    
+
     // Create directory if it doesn't exist
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    
+
     let config = Config {
-        selected_algorithms: algorithms.to_vec(),
-        hash_format: Some(format),
-        gost_variant: Some(gost_variant),
-        tiger_variant: Some(tiger_variant),
+        selected_algorithms: state.selected_algorithms.clone(),
+        hash_format: Some(state.hash_format),
+        gost_variant: Some(state.hash_options.gost_variant),
+        tiger_variant: Some(state.hash_options.tiger_variant),
+        blake2b: Some(state.hash_options.blake2b.clone()),
+        blake2s: Some(state.hash_options.blake2s.clone()),
+        crc_model: Some(state.hash_options.crc_model),
+        custom_crc: Some(state.hash_options.custom_crc.clone()),
+        haval_variant: Some(state.hash_options.haval_variant),
+        ed2k_variant: Some(state.hash_options.ed2k_variant),
+        piecewise_algorithm: Some(state.piecewise_algorithm.clone()),
+        piecewise_window: Some(state.piecewise_window),
+        normalization: Some(state.normalization),
+        decompress: Some(state.decompress),
+        resume: Some(state.resume),
+        cdc_params: state.cdc_params().ok(), // Only valid sizes are saved
+        cdc_algorithm: Some(state.cdc_algorithm.clone()),
+        rdiff_format: Some(state.rdiff_format),
+        rdiff_block_length: Some(state.rdiff_block_length),
+        image_algorithm: Some(state.image_algorithm.clone()),
     };
-    
+
     let content = serde_json::to_string_pretty(&config)?;
     fs::write(&path, content)?;
-    
+
     Ok(())
 }
-
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::archive::compression::Compression;
+use crate::hash::normalize::Normalization;
+use crate::hash::range::ByteRange;
+use crate::hash::Algorithm;
+use crate::state::RealOne;
+
+// Results saved from the main window, with everything needed to repeat the
+// check later (`realone --verify file.json`)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ExportedResults {
+    pub file: PathBuf,
+    pub file_size: u64,
+    pub range: Option<ByteRange>,
+    #[serde(default)] // Missing in exports made before normalization existed
+    pub normalization: Normalization,
+    #[serde(default)] // Missing in exports made before decompression existed
+    pub decompressed: bool, // Hashed by the decompressed contents of a compressed file
+    #[serde(default)] // Missing in exports made before split sets were joined
+    pub split_set: bool, // `file` is one part of a split set, hashed joined with the others
+    pub results: Vec<ExportedHash>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ExportedHash {
+    pub algorithm: Algorithm,
+    pub label: String, // Name including settings, e.g. "BLAKE2b-256"
+    pub hex: String, // Lowercase hex regardless of the display format; ssdeep and TLSH as text
+}
+
+impl ExportedResults {
+    pub fn from_state(state: &RealOne) -> Result<ExportedResults, String> {
+        let file = state.file_path.clone().ok_or("no file selected")?;
+        let file_size = match &state.split_set {
+            Some(set) => set.total_size(),
+            None => fs::metadata(&file).map_err(|e| e.to_string())?.len(),
+        };
+
+        // Keep the selection order; errors and missing results are skipped
+        let results: Vec<ExportedHash> = state
+            .active_algorithms()
+            .iter()
+            .filter_map(|algorithm| {
+                let value = state.hash_results.get(algorithm)?;
+                if algorithm.is_fuzzy() {
+                    return (!value.starts_with("Error")).then(|| ExportedHash {
+                        algorithm: algorithm.clone(),
+                        label: algorithm.label(&state.hash_options),
+                        hex: value.clone(),
+                    });
+                }
+                let bytes = state.result_format(algorithm).parse_hash(value).ok()?;
+                Some(ExportedHash {
+                    algorithm: algorithm.clone(),
+                    label: algorithm.label(&state.hash_options),
+                    hex: hex::encode(bytes),
+                })
+            })
+            .collect();
+
+        if results.is_empty() {
+            return Err("there are no results to export".to_string());
+        }
+
+        Ok(ExportedResults {
+            file,
+            file_size,
+            range: state.results_range,
+            normalization: state.results_normalization,
+            decompressed: state.results_compression != Compression::None,
+            split_set: state.split_set.is_some(),
+            results,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<ExportedResults, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
@@ -1,5 +1,11 @@
 use std::fmt;
 
+use crate::hash::crc::{CrcModel, CustomCrc};
+use crate::hash::ed2k::Ed2kVariant;
+use crate::hash::git::GitObjectFormat;
+use crate::hash::haval::HavalVariant;
+use crate::hash::links;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum GostVariant {
     CryptoPro,
@@ -50,6 +56,96 @@ impl fmt::Display for TigerVariant {
     }
 }
 
+// BLAKE2 parameter block: digest length in bits plus optional key, salt and
+// personalization, all given as hex strings so they round-trip through the config
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Blake2Params {
+    pub digest_bits: usize,
+    #[serde(default)]
+    pub key: String,
+    #[serde(default)]
+    pub salt: String,
+    #[serde(default)]
+    pub personal: String,
+}
+
+impl Blake2Params {
+    pub fn blake2b_default() -> Self {
+        Blake2Params {
+            digest_bits: 512,
+            key: String::new(),
+            salt: String::new(),
+            personal: String::new(),
+        }
+    }
+
+    pub fn blake2s_default() -> Self {
+        Blake2Params {
+            digest_bits: 256,
+            ..Self::blake2b_default()
+        }
+    }
+
+    pub fn blake2b_lengths() -> Vec<usize> {
+        vec![128, 160, 224, 256, 384, 512]
+    }
+
+    pub fn blake2s_lengths() -> Vec<usize> {
+        vec![128, 160, 224, 256]
+    }
+
+    pub fn is_keyed(&self) -> bool {
+        !self.key.trim().is_empty()
+    }
+
+    // Key, salt and personalization for the result label, so exports record
+    // which ones were used. The key is secret and labels end up in exports,
+    // manifests and checkpoints, so a keyed hash is only marked as keyed
+    fn settings(&self) -> Vec<String> {
+        let compact = |value: &str| value.trim().replace(' ', "").to_lowercase();
+        let mut settings = Vec::new();
+        if self.is_keyed() {
+            settings.push("keyed".to_string());
+        }
+        for (name, value) in [("salt", &self.salt), ("personal", &self.personal)] {
+            let value = compact(value);
+            if !value.is_empty() {
+                settings.push(format!("{} {}", name, value));
+            }
+        }
+        settings
+    }
+}
+
+// Per-algorithm settings that change the digest produced for the same input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashOptions {
+    pub gost_variant: GostVariant,
+    pub tiger_variant: TigerVariant,
+    pub blake2b: Blake2Params,
+    pub blake2s: Blake2Params,
+    pub crc_model: CrcModel,
+    pub custom_crc: CustomCrc,
+    pub haval_variant: HavalVariant,
+    pub ed2k_variant: Ed2kVariant,
+}
+
+impl Default for HashOptions {
+    fn default() -> Self {
+        HashOptions {
+            gost_variant: GostVariant::CryptoPro,
+            tiger_variant: TigerVariant::Tiger,
+            blake2b: Blake2Params::blake2b_default(),
+            blake2s: Blake2Params::blake2s_default(),
+            crc_model: CrcModel::Crc32Iscsi,
+            custom_crc: CustomCrc::default(),
+            haval_variant: HavalVariant::Haval256_5,
+            ed2k_variant: Ed2kVariant::New,
+        }
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum Algorithm {
     MD4,
@@ -68,8 +164,35 @@ pub enum Algorithm {
     TIGER192,
     WHIRLPOOL,
     GOST,
+    STREEBOG256,
+    STREEBOG512,
+    SM3,
+    KUPYNA256,
+    KUPYNA384,
+    KUPYNA512,
     CRC32,
+    CRC,
     ADLER32,
+    XXH32,
+    XXH64,
+    XXH3_64,
+    XXH3_128,
+    ED2K,
+    TTH,
+    GITSHA1,
+    GITSHA256,
+    SSDEEP,
+    TLSH,
+    AHASH,
+    DHASH,
+    PHASH,
+    MD2,
+    RIPEMD128,
+    RIPEMD256,
+    RIPEMD320,
+    HAVAL,
+    SNEFRU128,
+    SNEFRU256,
 }
 
 impl Algorithm {
@@ -91,8 +214,35 @@ impl Algorithm {
             Algorithm::TIGER192,
             Algorithm::WHIRLPOOL,
             Algorithm::GOST,
+            Algorithm::STREEBOG256,
+            Algorithm::STREEBOG512,
+            Algorithm::SM3,
+            Algorithm::KUPYNA256,
+            Algorithm::KUPYNA384,
+            Algorithm::KUPYNA512,
             Algorithm::CRC32,
+            Algorithm::CRC,
             Algorithm::ADLER32,
+            Algorithm::XXH32,
+            Algorithm::XXH64,
+            Algorithm::XXH3_64,
+            Algorithm::XXH3_128,
+            Algorithm::ED2K,
+            Algorithm::TTH,
+            Algorithm::GITSHA1,
+            Algorithm::GITSHA256,
+            Algorithm::SSDEEP,
+            Algorithm::TLSH,
+            Algorithm::AHASH,
+            Algorithm::DHASH,
+            Algorithm::PHASH,
+            Algorithm::MD2,
+            Algorithm::RIPEMD128,
+            Algorithm::RIPEMD256,
+            Algorithm::RIPEMD320,
+            Algorithm::HAVAL,
+            Algorithm::SNEFRU128,
+            Algorithm::SNEFRU256,
         ]
     }
 
@@ -114,10 +264,129 @@ impl Algorithm {
             Algorithm::TIGER192 => "TIGER192",
             Algorithm::WHIRLPOOL => "WHIRLPOOL",
             Algorithm::GOST => "GOST",
+            Algorithm::STREEBOG256 => "STREEBOG-256",
+            Algorithm::STREEBOG512 => "STREEBOG-512",
+            Algorithm::SM3 => "SM3",
+            Algorithm::KUPYNA256 => "KUPYNA-256",
+            Algorithm::KUPYNA384 => "KUPYNA-384",
+            Algorithm::KUPYNA512 => "KUPYNA-512",
             Algorithm::CRC32 => "CRC32",
+            Algorithm::CRC => "CRC (Catalog)",
             Algorithm::ADLER32 => "ADLER32",
+            Algorithm::XXH32 => "XXH32",
+            Algorithm::XXH64 => "XXH64",
+            Algorithm::XXH3_64 => "XXH3-64",
+            Algorithm::XXH3_128 => "XXH3-128",
+            Algorithm::ED2K => "ED2K",
+            Algorithm::TTH => "TTH",
+            Algorithm::GITSHA1 => "GIT-SHA1",
+            Algorithm::GITSHA256 => "GIT-SHA256",
+            Algorithm::SSDEEP => "SSDEEP",
+            Algorithm::TLSH => "TLSH",
+            Algorithm::AHASH => "aHash",
+            Algorithm::DHASH => "dHash",
+            Algorithm::PHASH => "pHash",
+            Algorithm::MD2 => "MD2",
+            Algorithm::RIPEMD128 => "RIPEMD128",
+            Algorithm::RIPEMD256 => "RIPEMD256",
+            Algorithm::RIPEMD320 => "RIPEMD320",
+            Algorithm::HAVAL => "HAVAL",
+            Algorithm::SNEFRU128 => "SNEFRU128",
+            Algorithm::SNEFRU256 => "SNEFRU256",
         }
         .to_string()
     }
+
+    // Looks up an algorithm by name, ignoring case and separators, so
+    // "sha256", "SHA-256" and "SHA256" all match
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        let normalize = |s: &str| s.to_lowercase().replace(['-', '_', ' '], "");
+        let wanted = normalize(name);
+        Algorithm::all()
+            .into_iter()
+            .find(|alg| normalize(&alg.to_string()) == wanted)
+    }
+
+    // Broken or obsolete algorithms kept only to check old manifests;
+    // the settings window lists them in their own section
+    pub fn is_legacy(&self) -> bool {
+        matches!(
+            self,
+            Algorithm::MD2
+                | Algorithm::RIPEMD128
+                | Algorithm::RIPEMD256
+                | Algorithm::RIPEMD320
+                | Algorithm::HAVAL
+                | Algorithm::SNEFRU128
+                | Algorithm::SNEFRU256
+        )
+    }
+
+    // Similarity digests: text rather than hex, and compared by score rather
+    // than equality
+    pub fn is_fuzzy(&self) -> bool {
+        matches!(self, Algorithm::SSDEEP | Algorithm::TLSH)
+    }
+
+    // Image hashes computed from the decoded pixels; they are only offered
+    // for files that are images
+    pub fn is_perceptual(&self) -> bool {
+        matches!(self, Algorithm::AHASH | Algorithm::DHASH | Algorithm::PHASH)
+    }
+
+    // Builds the sharing link for algorithms that have one (ed2k://, magnet:)
+    pub fn link(&self, file_name: &str, file_size: u64, hash: &[u8]) -> Option<String> {
+        match self {
+            Algorithm::ED2K => Some(links::ed2k_link(file_name, file_size, hash)),
+            Algorithm::TTH => Some(links::tth_magnet_link(file_name, file_size, hash)),
+            _ => None,
+        }
+    }
+
+    // Git object IDs: blob IDs for files, tree IDs for folders
+    pub fn git_format(&self) -> Option<GitObjectFormat> {
+        match self {
+            Algorithm::GITSHA1 => Some(GitObjectFormat::Sha1),
+            Algorithm::GITSHA256 => Some(GitObjectFormat::Sha256),
+            _ => None,
+        }
+    }
+
+    pub fn has_link(&self) -> bool {
+        matches!(self, Algorithm::ED2K | Algorithm::TTH)
+    }
+
+    // Name shown next to a result, including parameters that affect the digest
+    pub fn label(&self, options: &HashOptions) -> String {
+        let blake2_label = |name: &str, params: &Blake2Params| {
+            let settings = params.settings();
+            if settings.is_empty() {
+                format!("{}-{}", name, params.digest_bits)
+            } else {
+                format!("{}-{} ({})", name, params.digest_bits, settings.join(", "))
+            }
+        };
+
+        match self {
+            Algorithm::BLAKE2b => blake2_label("BLAKE2b", &options.blake2b),
+            Algorithm::BLAKE2s => blake2_label("BLAKE2s", &options.blake2s),
+            Algorithm::HAVAL => options.haval_variant.to_string(),
+            // The conventions differ for exact multiples of the chunk size
+            Algorithm::ED2K => match options.ed2k_variant {
+                Ed2kVariant::New => "ED2K (new)".to_string(),
+                Ed2kVariant::Old => "ED2K (old)".to_string(),
+            },
+            Algorithm::CRC => match options.crc_model {
+                CrcModel::Custom => format!("CRC-{} (custom)", options.custom_crc.width),
+                model => model.to_string(),
+            },
+            _ => self.to_string(),
+        }
+    }
 }
 
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_string())
+    }
+}
@@ -0,0 +1,329 @@
+use digest::generic_array::GenericArray;
+
+use crate::hash::algo::Algorithm;
+
+const MD5_SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+const MD5_K: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee,
+    0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be,
+    0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa,
+    0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed,
+    0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c,
+    0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05,
+    0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039,
+    0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1,
+    0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+const MD5_IV: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+const SHA1_IV: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+const SHA256_IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+const SHA384_IV: [u64; 8] = [
+    0xcbbb9d5dc1059ed8, 0x629a292a367cd507, 0x9159015a3070dd17, 0x152fecd8f70e5939,
+    0x67332667ffc00b31, 0x8eb44a8768581511, 0xdb0c2e0d64f98fa7, 0x47b5481dbefa4fa4,
+];
+const SHA512_IV: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+fn md5_compress(state: &mut [u32; 4], block: &[u8]) {
+    let mut m = [0u32; 16];
+    for (word, bytes) in m.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+
+    let [mut a, mut b, mut c, mut d] = *state;
+    for i in 0..64 {
+        let (f, g) = match i / 16 {
+            0 => ((b & c) | (!b & d), i),
+            1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2 => (b ^ c ^ d, (3 * i + 5) % 16),
+            _ => (c ^ (b | !d), (7 * i) % 16),
+        };
+        let f = f.wrapping_add(a).wrapping_add(MD5_K[i]).wrapping_add(m[g]);
+        a = d;
+        d = c;
+        c = b;
+        b = b.wrapping_add(f.rotate_left(MD5_SHIFTS[(i / 16) * 4 + i % 4]));
+    }
+
+    for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+        *s = s.wrapping_add(v);
+    }
+}
+
+// Chaining value of a Merkle-Damgard hash between blocks
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum Chain {
+    Md5([u32; 4]),
+    Sha1([u32; 5]),
+    Sha256([u32; 8]),
+    Sha512([u64; 8]), // Also SHA-384, which starts from other values
+}
+
+impl Chain {
+    fn block_size(&self) -> usize {
+        match self {
+            Chain::Sha512(_) => 128,
+            _ => 64,
+        }
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        match self {
+            Chain::Md5(state) => md5_compress(state, block),
+            Chain::Sha1(state) => sha1::compress(state, &[*GenericArray::from_slice(block)]),
+            Chain::Sha256(state) => sha2::compress256(state, &[*GenericArray::from_slice(block)]),
+            Chain::Sha512(state) => sha2::compress512(state, &[*GenericArray::from_slice(block)]),
+        }
+    }
+}
+
+// MD5, SHA-1 and SHA-2 driven block by block through their compression
+// functions. Unlike the md5 and sha2 hashers, whose state is private, all of
+// this one's state is plain data that can be saved and picked up later, so
+// these are what checkpointed hashing uses
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BlockHasher {
+    algorithm: Algorithm,
+    chain: Chain,
+    pending: Vec<u8>, // Start of a block that is not complete yet
+    length: u64,
+}
+
+impl BlockHasher {
+    pub fn new(algorithm: &Algorithm) -> Option<Self> {
+        let chain = match algorithm {
+            Algorithm::MD5 => Chain::Md5(MD5_IV),
+            Algorithm::SHA1 => Chain::Sha1(SHA1_IV),
+            Algorithm::SHA256 => Chain::Sha256(SHA256_IV),
+            Algorithm::SHA384 => Chain::Sha512(SHA384_IV),
+            Algorithm::SHA512 => Chain::Sha512(SHA512_IV),
+            _ => return None,
+        };
+        Some(BlockHasher {
+            algorithm: algorithm.clone(),
+            chain,
+            pending: Vec::new(),
+            length: 0,
+        })
+    }
+
+    pub fn algorithm(&self) -> &Algorithm {
+        &self.algorithm
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        let block_size = self.chain.block_size();
+        self.length += data.len() as u64;
+
+        if !self.pending.is_empty() {
+            let take = (block_size - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.pending.len() < block_size {
+                return;
+            }
+            let block = std::mem::take(&mut self.pending);
+            self.chain.compress(&block);
+        }
+
+        let mut blocks = data.chunks_exact(block_size);
+        for block in &mut blocks {
+            self.chain.compress(block);
+        }
+        self.pending.extend_from_slice(blocks.remainder());
+    }
+
+    // Pads with 0x80, zeros and the length in bits: little-endian for MD5,
+    // big-endian for SHA, which also takes 16 length bytes for SHA-384/512
+    pub fn finalize(mut self) -> Vec<u8> {
+        let block_size = self.chain.block_size();
+        let length_size = block_size / 8;
+        let bits = (self.length as u128) * 8;
+
+        let mut tail = std::mem::take(&mut self.pending);
+        tail.push(0x80);
+        while tail.len() % block_size != block_size - length_size {
+            tail.push(0);
+        }
+        match self.chain {
+            Chain::Md5(_) => tail.extend_from_slice(&(bits as u64).to_le_bytes()),
+            Chain::Sha512(_) => tail.extend_from_slice(&bits.to_be_bytes()),
+            _ => tail.extend_from_slice(&(bits as u64).to_be_bytes()),
+        }
+        for block in tail.chunks_exact(block_size) {
+            self.chain.compress(block);
+        }
+
+        let digest: Vec<u8> = match &self.chain {
+            Chain::Md5(state) => state.iter().flat_map(|w| w.to_le_bytes()).collect(),
+            Chain::Sha1(state) => state.iter().flat_map(|w| w.to_be_bytes()).collect(),
+            Chain::Sha256(state) => state.iter().flat_map(|w| w.to_be_bytes()).collect(),
+            Chain::Sha512(state) => state.iter().flat_map(|w| w.to_be_bytes()).collect(),
+        };
+        let digest_size = if self.algorithm == Algorithm::SHA384 { 48 } else { digest.len() };
+        digest[..digest_size].to_vec()
+    }
+}
+
+// SHA-3 driven through the Keccak-f[1600] permutation, for the same reason:
+// the sponge's lanes and the unabsorbed bytes are all of its state
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SpongeHasher {
+    algorithm: Algorithm,
+    lanes: [u64; 25],
+    pending: Vec<u8>,
+}
+
+impl SpongeHasher {
+    pub fn new(algorithm: &Algorithm) -> Option<Self> {
+        match algorithm {
+            Algorithm::SHA3_224 | Algorithm::SHA3_256 | Algorithm::SHA3_384 | Algorithm::SHA3_512 => Some(SpongeHasher {
+                algorithm: algorithm.clone(),
+                lanes: [0; 25],
+                pending: Vec::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn algorithm(&self) -> &Algorithm {
+        &self.algorithm
+    }
+
+    fn digest_size(&self) -> usize {
+        match self.algorithm {
+            Algorithm::SHA3_224 => 28,
+            Algorithm::SHA3_256 => 32,
+            Algorithm::SHA3_384 => 48,
+            _ => 64,
+        }
+    }
+
+    // Bytes absorbed per permutation; the capacity is twice the digest size
+    fn rate(&self) -> usize {
+        200 - 2 * self.digest_size()
+    }
+
+    fn absorb(&mut self, block: &[u8]) {
+        for (lane, bytes) in self.lanes.iter_mut().zip(block.chunks_exact(8)) {
+            *lane ^= u64::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]]);
+        }
+        keccak::f1600(&mut self.lanes);
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        let rate = self.rate();
+
+        if !self.pending.is_empty() {
+            let take = (rate - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.pending.len() < rate {
+                return;
+            }
+            let block = std::mem::take(&mut self.pending);
+            self.absorb(&block);
+        }
+
+        let mut blocks = data.chunks_exact(rate);
+        for block in &mut blocks {
+            self.absorb(block);
+        }
+        self.pending.extend_from_slice(blocks.remainder());
+    }
+
+    // SHA-3 domain bits 01, then pad10*1 up to the rate
+    pub fn finalize(mut self) -> Vec<u8> {
+        let rate = self.rate();
+        let mut block = std::mem::take(&mut self.pending);
+        block.push(0x06);
+        block.resize(rate, 0);
+        block[rate - 1] |= 0x80;
+        self.absorb(&block);
+
+        let digest_size = self.digest_size();
+        self.lanes.iter().flat_map(|lane| lane.to_le_bytes()).take(digest_size).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::algo::HashOptions;
+    use crate::hash::compute::AlgorithmHasher;
+
+    // Around the padding boundaries: the length field fits after 55 bytes of
+    // a 64-byte block and after 111 of a 128-byte one, but not after 56 or 112
+    const LENGTHS: [usize; 9] = [0, 55, 56, 63, 64, 111, 112, 127, 128];
+
+    fn expected(algorithm: &Algorithm, data: &[u8]) -> String {
+        let mut hasher = AlgorithmHasher::new(algorithm, &HashOptions::default()).unwrap();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    fn data(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i * 7 + 3) as u8).collect()
+    }
+
+    #[test]
+    fn block_hasher_matches_crate_hashers() {
+        for algorithm in [Algorithm::MD5, Algorithm::SHA1, Algorithm::SHA256, Algorithm::SHA384, Algorithm::SHA512] {
+            for length in LENGTHS {
+                let data = data(length);
+                for split in [1, 13, 64] {
+                    let mut hasher = BlockHasher::new(&algorithm).unwrap();
+                    for chunk in data.chunks(split) {
+                        hasher.update(chunk);
+                    }
+                    assert_eq!(hex::encode(hasher.finalize()), expected(&algorithm, &data), "{} of {} bytes", algorithm, length);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sponge_hasher_matches_crate_hashers() {
+        for algorithm in [Algorithm::SHA3_224, Algorithm::SHA3_256, Algorithm::SHA3_384, Algorithm::SHA3_512] {
+            let rate = SpongeHasher::new(&algorithm).unwrap().rate();
+            for length in LENGTHS.into_iter().chain([rate - 1, rate, rate + 1, 2 * rate]) {
+                let data = data(length);
+                for split in [1, 13, 64] {
+                    let mut hasher = SpongeHasher::new(&algorithm).unwrap();
+                    for chunk in data.chunks(split) {
+                        hasher.update(chunk);
+                    }
+                    assert_eq!(hex::encode(hasher.finalize()), expected(&algorithm, &data), "{} of {} bytes", algorithm, length);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn saved_state_carries_on() {
+        let data = data(300);
+        for algorithm in [Algorithm::MD5, Algorithm::SHA512, Algorithm::SHA3_256] {
+            let mut hasher = AlgorithmHasher::resumable(&algorithm, &HashOptions::default(), 300).unwrap();
+            hasher.update(&data[..150]);
+            let saved = serde_json::to_string(&hasher.save_state().unwrap()).unwrap();
+
+            let mut resumed = AlgorithmHasher::resumable(&algorithm, &HashOptions::default(), 300).unwrap();
+            assert!(resumed.restore_state(&serde_json::from_str(&saved).unwrap()));
+            resumed.update(&data[150..]);
+            assert_eq!(resumed.finalize(), expected(&algorithm, &data), "{}", algorithm);
+        }
+    }
+}
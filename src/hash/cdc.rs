@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use fastcdc::v2020;
+
+use crate::hash::algo::{Algorithm, HashOptions};
+use crate::hash::compute::AlgorithmHasher;
+use crate::hash::io::read_chunks;
+
+// FastCDC chunk size limits in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CdcParams {
+    pub min_size: usize,
+    pub avg_size: usize,
+    pub max_size: usize,
+}
+
+impl Default for CdcParams {
+    fn default() -> Self {
+        CdcParams {
+            min_size: 16 * 1024,
+            avg_size: 64 * 1024,
+            max_size: 256 * 1024,
+        }
+    }
+}
+
+impl CdcParams {
+    // Digests offered for chunks; collisions would hide differences, so only
+    // cryptographic hashes are used
+    pub fn algorithms() -> Vec<Algorithm> {
+        vec![Algorithm::SHA256, Algorithm::BLAKE2b, Algorithm::BLAKE2s]
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let check = |name: &str, value: usize, low: usize, high: usize| {
+            if value < low || value > high {
+                Err(format!("{} chunk size must be between {} and {} bytes", name, low, high))
+            } else {
+                Ok(())
+            }
+        };
+        check("Minimum", self.min_size, v2020::MINIMUM_MIN, v2020::MINIMUM_MAX)?;
+        check("Average", self.avg_size, v2020::AVERAGE_MIN, v2020::AVERAGE_MAX)?;
+        check("Maximum", self.max_size, v2020::MAXIMUM_MIN, v2020::MAXIMUM_MAX)?;
+        if self.min_size > self.avg_size || self.avg_size > self.max_size {
+            return Err("chunk sizes must satisfy minimum <= average <= maximum".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CdcFileReport {
+    pub path: PathBuf,
+    pub size: u64,
+    pub chunks: usize,
+    pub shared_chunks: usize, // Chunks that also occur in another file
+}
+
+#[derive(Debug, Clone)]
+pub struct CdcReport {
+    pub label: String, // Chunk digest, e.g. "SHA256"
+    pub files: Vec<CdcFileReport>,
+    pub total_chunks: usize,
+    pub total_bytes: u64,
+    pub unique_chunks: usize, // Distinct chunks, i.e. what a deduplicating store keeps
+    pub unique_bytes: u64,
+    pub repeated_chunks: usize, // Distinct chunks that occur more than once
+    pub smallest_chunk: usize,
+    pub largest_chunk: usize,
+}
+
+impl CdcReport {
+    // Bytes read divided by bytes a deduplicating store would keep
+    pub fn dedup_ratio(&self) -> f64 {
+        if self.unique_bytes == 0 {
+            1.0
+        } else {
+            self.total_bytes as f64 / self.unique_bytes as f64
+        }
+    }
+}
+
+struct ChunkInfo {
+    size: usize,
+    occurrences: Vec<(usize, usize)>, // (file index, count)
+}
+
+// Splits each file into content-defined chunks with FastCDC (2020 variant,
+// normalization level 1) and counts which chunks repeat within and across files
+pub fn analyze_files(
+    paths: &[PathBuf],
+    params: CdcParams,
+    algorithm: &Algorithm,
+    options: &HashOptions,
+) -> Result<CdcReport, String> {
+    params.validate()?;
+    if paths.is_empty() {
+        return Err("no files selected".to_string());
+    }
+
+    let (mask_s, mask_l) = v2020::select_masks(params.avg_size, v2020::Normalization::Level1);
+    let cut = |data: &[u8]| {
+        let (_, length) = v2020::cut(
+            data,
+            params.min_size,
+            params.avg_size,
+            params.max_size,
+            mask_s,
+            mask_l,
+            mask_s << 1,
+            mask_l << 1,
+        );
+        length
+    };
+
+    // Built once so bad settings (a BLAKE2 key, say) are reported instead of
+    // every chunk getting the same empty digest; each chunk hashes a copy
+    let hasher = AlgorithmHasher::new(algorithm, options)?;
+    let digest_of = |chunk: &[u8]| {
+        let mut copy = hasher.snapshot()?;
+        copy.update(chunk);
+        copy.finalize_bytes()
+    };
+    if digest_of(&[]).is_none() {
+        return Err(format!("{} cannot be used for chunks", algorithm.label(options)));
+    }
+
+    let mut chunks: HashMap<Vec<u8>, ChunkInfo> = HashMap::new();
+    let mut files = Vec::new();
+    let mut smallest_chunk = usize::MAX;
+    let mut largest_chunk = 0;
+
+    for (index, path) in paths.iter().enumerate() {
+        let mut file = CdcFileReport { path: path.clone(), size: 0, chunks: 0, shared_chunks: 0 };
+        let mut record = |chunk: &[u8]| {
+            let digest = digest_of(chunk).unwrap_or_default();
+            let info = chunks.entry(digest).or_insert_with(|| ChunkInfo { size: chunk.len(), occurrences: Vec::new() });
+            match info.occurrences.last_mut() {
+                Some((file_index, count)) if *file_index == index => *count += 1,
+                _ => info.occurrences.push((index, 1)),
+            }
+            file.chunks += 1;
+            file.size += chunk.len() as u64;
+            smallest_chunk = smallest_chunk.min(chunk.len());
+            largest_chunk = largest_chunk.max(chunk.len());
+        };
+
+        // A cut can only be placed once max_size bytes are available (or the
+        // file has ended), so data is buffered across reads
+        let mut buffer: Vec<u8> = Vec::new();
+        read_chunks(path, |data| {
+            buffer.extend_from_slice(data);
+            let mut start = 0;
+            while buffer.len() - start >= params.max_size {
+                let length = cut(&buffer[start..]);
+                record(&buffer[start..start + length]);
+                start += length;
+            }
+            buffer.drain(..start);
+        })
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let mut start = 0;
+        while start < buffer.len() {
+            let length = cut(&buffer[start..]);
+            record(&buffer[start..start + length]);
+            start += length;
+        }
+
+        files.push(file);
+    }
+
+    let mut total_chunks = 0;
+    let mut total_bytes = 0u64;
+    let mut unique_bytes = 0u64;
+    let mut repeated_chunks = 0;
+    for info in chunks.values() {
+        let count: usize = info.occurrences.iter().map(|(_, count)| count).sum();
+        total_chunks += count;
+        total_bytes += (info.size * count) as u64;
+        unique_bytes += info.size as u64;
+        if count > 1 {
+            repeated_chunks += 1;
+        }
+        if info.occurrences.len() > 1 {
+            for (file_index, count) in &info.occurrences {
+                files[*file_index].shared_chunks += count;
+            }
+        }
+    }
+
+    Ok(CdcReport {
+        label: algorithm.label(options),
+        files,
+        total_chunks,
+        total_bytes,
+        unique_chunks: chunks.len(),
+        unique_bytes,
+        repeated_chunks,
+        smallest_chunk: if total_chunks == 0 { 0 } else { smallest_chunk },
+        largest_chunk,
+    })
+}
@@ -0,0 +1,470 @@
+use std::fmt;
+use std::fs::{self, File, Metadata};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use digest::Digest;
+
+use crate::archive::compression::Compression;
+use crate::hash::algo::{Algorithm, HashOptions};
+use crate::hash::compute::{calculate_hashes_parallel_streaming, AlgorithmHasher, HasherState};
+use crate::hash::io::read_range;
+use crate::hash::range::ByteRange;
+use crate::hash::normalize::Normalization;
+
+// A checkpoint is written each time this much more of the file has been
+// hashed; smaller files are quicker to hash again than to checkpoint
+pub const CHECKPOINT_INTERVAL: u64 = 1024 * 1024 * 1024; // 1GB
+
+// Why a file large enough for checkpoints was hashed without them, in whole
+// or for some of the algorithms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResumeSkipped {
+    Transformed,
+    Unsupported(Vec<String>), // Labels of the algorithms whose state cannot be saved
+}
+
+impl fmt::Display for ResumeSkipped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResumeSkipped::Transformed => write!(f, "byte ranges, normalized text and decompressed data are not checkpointed"),
+            ResumeSkipped::Unsupported(labels) => {
+                write!(f, "not checkpointed, so hashed from the start on every run: {}", labels.join(", "))
+            }
+        }
+    }
+}
+
+// What must be unchanged for a checkpoint to still describe the file
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct FileIdentity {
+    size: u64,
+    modified: Option<u64>,     // Nanoseconds since the Unix epoch
+    inode: Option<(u64, u64)>, // Device and inode number
+}
+
+impl FileIdentity {
+    fn of(metadata: &Metadata) -> Self {
+        FileIdentity {
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|since| since.as_nanos() as u64),
+            inode: inode_of(metadata),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn inode_of(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn inode_of(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Checkpoint {
+    file: PathBuf,
+    identity: FileIdentity,
+    offset: u64, // Bytes hashed into the saved states
+    algorithms: Vec<Algorithm>,
+    labels: Vec<String>, // Algorithms with their settings, e.g. the CRC model
+    states: Vec<Option<HasherState>>, // None for algorithms that start over
+}
+
+#[derive(Debug, Clone)]
+pub struct ResumableHashes {
+    pub results: Vec<(Algorithm, String)>,
+    pub resumed_from: Option<u64>, // Offset of the checkpoint that was picked up
+    pub skipped: Option<ResumeSkipped>,
+}
+
+// One file per hashed file, named after its full path
+fn checkpoint_path(file: &Path) -> PathBuf {
+    let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("RealOne");
+    path.push("checkpoints");
+    let digest = sha2::Sha256::digest(file.to_string_lossy().as_bytes());
+    path.push(format!("{}.json", hex::encode(digest)));
+    path
+}
+
+fn load_checkpoint(path: &Path) -> Option<Checkpoint> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// Written next to the old checkpoint and renamed over it, so an interruption
+// while saving leaves the previous checkpoint intact
+fn save_checkpoint(path: &Path, checkpoint: &Checkpoint) -> Result<(), String> {
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string(checkpoint).map_err(|e| e.to_string())?;
+    let partial = path.with_extension("json.partial");
+    fs::write(&partial, content).map_err(|e| e.to_string())?;
+    fs::rename(&partial, path).map_err(|e| e.to_string())
+}
+
+// Hashes a file, saving the hashers' state every CHECKPOINT_INTERVAL bytes
+// and starting from the last checkpoint when the file is unchanged since it
+// was written. Files too small to need checkpoints are hashed the usual way,
+// and so are those checkpoints cannot cover, with `skipped` saying why. It
+// also names the algorithms whose state cannot be saved; those are hashed
+// from the start of the file alongside the ones that resume
+pub fn hash_with_resume(
+    path: &PathBuf,
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+    range: Option<&ByteRange>,
+    normalization: Normalization,
+    decompress: bool,
+) -> ResumableHashes {
+    let usual = |skipped| ResumableHashes {
+        results: calculate_hashes_parallel_streaming(path, algorithms, options, range, normalization, decompress),
+        resumed_from: None,
+        skipped,
+    };
+
+    let Ok(file) = fs::canonicalize(path) else {
+        return usual(None);
+    };
+    let Ok(metadata) = fs::metadata(&file) else {
+        return usual(None);
+    };
+    if !metadata.is_file() || metadata.len() <= CHECKPOINT_INTERVAL {
+        return usual(None);
+    }
+    let compressed = decompress && Compression::detect_file(&file) != Compression::None;
+    if range.is_some() || normalization.is_active() || compressed {
+        return usual(Some(ResumeSkipped::Transformed));
+    }
+
+    let checkpoint_file = checkpoint_path(&file);
+    match hash_checkpointed(&file, &metadata, algorithms, options, &checkpoint_file, CHECKPOINT_INTERVAL) {
+        Ok(hashes) => hashes,
+        Err(skipped) => usual(Some(skipped)),
+    }
+}
+
+fn hash_checkpointed(
+    file: &PathBuf,
+    metadata: &Metadata,
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+    checkpoint_file: &Path,
+    interval: u64,
+) -> Result<ResumableHashes, ResumeSkipped> {
+    let identity = FileIdentity::of(metadata);
+    let labels: Vec<String> = algorithms.iter().map(|alg| alg.label(options)).collect();
+
+    // Settings that fail to build a hasher are left for the usual path to report
+    let mut hashers = Vec::new();
+    for (alg, label) in algorithms.iter().zip(&labels) {
+        match AlgorithmHasher::resumable(alg, options, identity.size) {
+            Ok(hasher) => hashers.push(hasher),
+            Err(_) => return Err(ResumeSkipped::Unsupported(vec![label.clone()])),
+        }
+    }
+    let saveable: Vec<bool> = hashers.iter().map(|h| h.save_state().is_some()).collect();
+    let unsupported: Vec<String> = labels.iter().zip(&saveable).filter(|(_, &s)| !s).map(|(l, _)| l.clone()).collect();
+    if unsupported.len() == hashers.len() {
+        return Err(ResumeSkipped::Unsupported(unsupported));
+    }
+
+    // Where each hasher picks up: the checkpoint's offset for the ones it
+    // saved, the start of the file for the rest
+    let mut starts = vec![0u64; hashers.len()];
+    let mut offset = 0;
+    if let Some(checkpoint) = load_checkpoint(checkpoint_file) {
+        let mut restored: Vec<AlgorithmHasher> = hashers.iter().filter_map(|h| h.snapshot()).collect();
+        let matches = checkpoint.file == *file
+            && checkpoint.identity == identity
+            && checkpoint.algorithms == algorithms
+            && checkpoint.labels == labels
+            && checkpoint.offset <= identity.size
+            && restored.len() == hashers.len()
+            && checkpoint.states.len() == restored.len()
+            && restored.iter_mut().zip(&checkpoint.states).zip(&saveable).all(|((h, state), &saveable)| match state {
+                Some(state) => saveable && h.restore_state(state),
+                None => !saveable,
+            });
+        if matches {
+            hashers = restored;
+            offset = checkpoint.offset;
+            for (start, &saveable) in starts.iter_mut().zip(&saveable) {
+                if saveable {
+                    *start = offset;
+                }
+            }
+        }
+    }
+    let first = starts.iter().copied().min().unwrap_or(0);
+
+    // A checkpoint that cannot be saved only means the next run starts over.
+    // Until the hashers that started over have caught up with the resumed
+    // ones there is nothing new to save
+    let save = |position: u64, hashers: &[AlgorithmHasher]| {
+        if position <= offset {
+            return;
+        }
+        let checkpoint = Checkpoint {
+            file: file.clone(),
+            identity: identity.clone(),
+            offset: position,
+            algorithms: algorithms.to_vec(),
+            labels: labels.clone(),
+            states: hashers.iter().map(|h| h.save_state()).collect(),
+        };
+        let _ = save_checkpoint(checkpoint_file, &checkpoint);
+    };
+
+    // The part of data read at `position` that a hasher starting at `start` still needs
+    let needed = |data: &[u8], position: u64, start: u64| {
+        start.saturating_sub(position).min(data.len() as u64) as usize
+    };
+
+    let mmap = File::open(file).and_then(|handle| unsafe { memmap2::MmapOptions::new().map(&handle) });
+    let read_result = match mmap {
+        Ok(mmap) => {
+            // Each interval is hashed by one thread per algorithm, as the
+            // usual path does, and checkpointed once all of them are through
+            let mut position = first;
+            while position < identity.size {
+                let end = (position + interval).min(identity.size);
+                let window = &mmap[position as usize..end as usize];
+                std::thread::scope(|scope| {
+                    for (hasher, &start) in hashers.iter_mut().zip(&starts) {
+                        let part = &window[needed(window, position, start)..];
+                        if !part.is_empty() {
+                            scope.spawn(move || hasher.update(part));
+                        }
+                    }
+                });
+                position = end;
+                if position < identity.size {
+                    save(position, &hashers);
+                }
+            }
+            Ok(())
+        }
+        Err(_) => {
+            let mut position = first;
+            let mut next_checkpoint = first + interval;
+            read_range(file, first..identity.size, |chunk| {
+                for (hasher, &start) in hashers.iter_mut().zip(&starts) {
+                    hasher.update(&chunk[needed(chunk, position, start)..]);
+                }
+                position += chunk.len() as u64;
+                if position >= next_checkpoint {
+                    save(position, &hashers);
+                    next_checkpoint = position + interval;
+                }
+            })
+        }
+    };
+
+    let results = match read_result {
+        Ok(()) => {
+            let _ = fs::remove_file(checkpoint_file);
+            algorithms.iter().cloned().zip(hashers.into_iter().map(|h| h.finalize())).collect()
+        }
+        Err(e) => algorithms.iter().map(|alg| (alg.clone(), e.clone())).collect(),
+    };
+    Ok(ResumableHashes {
+        results,
+        resumed_from: (offset > 0).then_some(offset),
+        skipped: (!unsupported.is_empty()).then_some(ResumeSkipped::Unsupported(unsupported)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+    use crate::hash::compute::calculate_hash_from_data;
+
+    fn data(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i * 31 + i / 97) as u8).collect()
+    }
+
+    // Names the variant, so a new kind of state fails to compile here until
+    // it is covered below
+    fn kind(state: &HasherState) -> &'static str {
+        match state {
+            HasherState::Block(_) => "block",
+            HasherState::Sponge(_) => "sponge",
+            HasherState::Crc32 { .. } => "crc32",
+            HasherState::Crc { .. } => "crc",
+            HasherState::Adler32 { .. } => "adler32",
+            HasherState::Haval(_) => "haval",
+            HasherState::Snefru(_) => "snefru",
+            HasherState::Tth(_) => "tth",
+            HasherState::GitBlob(_) => "git",
+        }
+    }
+
+    #[test]
+    fn every_saved_state_finishes_like_a_one_shot_hash() {
+        let options = HashOptions::default();
+        let data = data(5000);
+        let algorithms = [
+            (Algorithm::MD5, "block"),
+            (Algorithm::SHA3_256, "sponge"),
+            (Algorithm::CRC32, "crc32"),
+            (Algorithm::CRC, "crc"),
+            (Algorithm::ADLER32, "adler32"),
+            (Algorithm::HAVAL, "haval"),
+            (Algorithm::SNEFRU256, "snefru"),
+            (Algorithm::TTH, "tth"),
+            (Algorithm::GITSHA1, "git"),
+        ];
+        for (algorithm, expected_kind) in algorithms {
+            let expected = calculate_hash_from_data(&data, &algorithm, &options);
+            for split in [0, 1, 1024, 2501, 5000] {
+                let mut hasher = AlgorithmHasher::resumable(&algorithm, &options, data.len() as u64).unwrap();
+                hasher.update(&data[..split]);
+                let state = hasher.save_state().unwrap();
+                assert_eq!(kind(&state), expected_kind);
+                let saved = serde_json::to_string(&state).unwrap();
+
+                let mut resumed = AlgorithmHasher::resumable(&algorithm, &options, data.len() as u64).unwrap();
+                assert!(resumed.restore_state(&serde_json::from_str(&saved).unwrap()));
+                resumed.update(&data[split..]);
+                assert_eq!(resumed.finalize(), expected, "{} split at {}", algorithm, split);
+            }
+        }
+    }
+
+    #[test]
+    fn states_are_not_restored_into_other_algorithms() {
+        let options = HashOptions::default();
+        let state = AlgorithmHasher::resumable(&Algorithm::SHA256, &options, 0).unwrap().save_state().unwrap();
+        let mut other = AlgorithmHasher::resumable(&Algorithm::SHA512, &options, 0).unwrap();
+        assert!(!other.restore_state(&state));
+    }
+
+    const INTERVAL: u64 = 4096;
+
+    struct Fixture {
+        _dir: ScratchDir,
+        file: PathBuf,
+        checkpoint: PathBuf,
+        data: Vec<u8>,
+        algorithms: Vec<Algorithm>,
+        options: HashOptions,
+    }
+
+    impl Fixture {
+        // SHA-256 can be checkpointed, BLAKE2b cannot
+        fn new(name: &str) -> Self {
+            let dir = ScratchDir::new(name);
+            let data = data(10_000);
+            let file = dir.join("data.bin");
+            fs::write(&file, &data).unwrap();
+            Fixture {
+                file,
+                checkpoint: dir.join("checkpoint.json"),
+                _dir: dir,
+                data,
+                algorithms: vec![Algorithm::SHA256, Algorithm::BLAKE2b],
+                options: HashOptions::default(),
+            }
+        }
+
+        fn identity(&self) -> FileIdentity {
+            FileIdentity::of(&fs::metadata(&self.file).unwrap())
+        }
+
+        // A checkpoint at `offset` whose SHA-256 state has seen `seen`, which
+        // a test can make differ from the file to tell if it was trusted
+        fn plant(&self, identity: FileIdentity, offset: u64, seen: &[u8]) {
+            let mut sha256 = AlgorithmHasher::resumable(&Algorithm::SHA256, &self.options, identity.size).unwrap();
+            sha256.update(seen);
+            let checkpoint = Checkpoint {
+                file: self.file.clone(),
+                identity,
+                offset,
+                algorithms: self.algorithms.clone(),
+                labels: self.algorithms.iter().map(|alg| alg.label(&self.options)).collect(),
+                states: vec![sha256.save_state(), None],
+            };
+            save_checkpoint(&self.checkpoint, &checkpoint).unwrap();
+        }
+
+        fn hash(&self) -> ResumableHashes {
+            let metadata = fs::metadata(&self.file).unwrap();
+            hash_checkpointed(&self.file, &metadata, &self.algorithms, &self.options, &self.checkpoint, INTERVAL).unwrap()
+        }
+
+        fn one_shot(&self) -> Vec<(Algorithm, String)> {
+            self.algorithms
+                .iter()
+                .map(|alg| (alg.clone(), calculate_hash_from_data(&self.data, alg, &self.options)))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn matching_checkpoint_resumes_and_the_rest_start_over() {
+        let fixture = Fixture::new("checkpoint-resume");
+        fixture.plant(fixture.identity(), INTERVAL, &fixture.data[..INTERVAL as usize]);
+        let hashes = fixture.hash();
+        assert_eq!(hashes.results, fixture.one_shot());
+        assert_eq!(hashes.resumed_from, Some(INTERVAL));
+        let blake2b = Algorithm::BLAKE2b.label(&fixture.options);
+        assert_eq!(hashes.skipped, Some(ResumeSkipped::Unsupported(vec![blake2b])));
+        assert!(!fixture.checkpoint.exists());
+
+        // A state that saw other data shows the checkpoint is what SHA-256
+        // carried on from, while BLAKE2b read the file from the start
+        fixture.plant(fixture.identity(), INTERVAL, &[0; INTERVAL as usize]);
+        let hashes = fixture.hash();
+        assert_ne!(hashes.results[0], fixture.one_shot()[0]);
+        assert_eq!(hashes.results[1], fixture.one_shot()[1]);
+    }
+
+    #[test]
+    fn changed_file_identity_ignores_the_checkpoint() {
+        let fixture = Fixture::new("checkpoint-identity");
+        let identity = fixture.identity();
+        let changed = [
+            FileIdentity { size: identity.size + 1, ..identity.clone() },
+            FileIdentity { modified: identity.modified.map(|t| t + 1).or(Some(1)), ..identity.clone() },
+            FileIdentity { inode: identity.inode.map(|(dev, ino)| (dev, ino + 1)).or(Some((0, 1))), ..identity.clone() },
+        ];
+        for identity in changed {
+            fixture.plant(identity, INTERVAL, &[0; INTERVAL as usize]);
+            let hashes = fixture.hash();
+            assert_eq!(hashes.results, fixture.one_shot());
+            assert_eq!(hashes.resumed_from, None);
+        }
+    }
+
+    #[test]
+    fn corrupt_checkpoints_are_ignored() {
+        let fixture = Fixture::new("checkpoint-corrupt");
+        fixture.plant(fixture.identity(), INTERVAL, &[0; INTERVAL as usize]);
+        let valid = fs::read(&fixture.checkpoint).unwrap();
+
+        let truncated = valid[..valid.len() / 2].to_vec();
+        let states_dropped = String::from_utf8(valid.clone()).unwrap().replace("\"states\":[", "\"states\":[null,");
+        let beyond_the_end = String::from_utf8(valid).unwrap().replace(
+            &format!("\"offset\":{}", INTERVAL),
+            &format!("\"offset\":{}", fixture.data.len() + 1),
+        );
+        assert!(states_dropped.contains("[null,") && beyond_the_end.contains(&(fixture.data.len() + 1).to_string()));
+        for content in [truncated, b"not json".to_vec(), states_dropped.into_bytes(), beyond_the_end.into_bytes()] {
+            fs::write(&fixture.checkpoint, content).unwrap();
+            let hashes = fixture.hash();
+            assert_eq!(hashes.results, fixture.one_shot());
+            assert_eq!(hashes.resumed_from, None);
+        }
+    }
+}
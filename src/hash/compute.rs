@@ -1,139 +1,418 @@
 use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
 
 use digest::Digest;
 use whirlpool::Whirlpool;
 
-use crate::hash::algo::{Algorithm, GostVariant, TigerVariant};
-use crate::hash::io::{BUFFER_SIZE, LARGE_BUFFER_SIZE, LARGE_FILE_THRESHOLD, MMAP_THRESHOLD};
-use crate::hash::wrappers::{GostHasher, TigerHasher};
+use crate::archive::compression::Compression;
+use crate::hash::algo::{Algorithm, HashOptions};
+use crate::hash::block::{BlockHasher, SpongeHasher};
+use crate::hash::crc::{CrcHasher, CrcParams};
+use crate::hash::ed2k::Ed2kHasher;
+use crate::hash::fuzzy::{SsdeepHasher, TlshHasher};
+use crate::hash::git::{git_tree_id, GitBlobHasher};
+use crate::hash::haval::{Haval, HavalState};
+use crate::hash::snefru::{Snefru, SnefruState};
+use crate::hash::tth::TthHasher;
+use crate::hash::io::{read_range, BUFFER_SIZE, MMAP_THRESHOLD};
+use crate::hash::normalize::{Normalization, Normalizer};
+use crate::hash::perceptual::PerceptualHasher;
+use crate::hash::range::ByteRange;
+use crate::hash::wrappers::{Blake2Hasher, GostHasher, TigerHasher};
 
-pub fn calculate_hash_from_data(
-    data: &[u8],
-    algorithm: &Algorithm,
-    gost_variant: GostVariant,
-    tiger_variant: TigerVariant,
-) -> String {
-    match algorithm {
-        Algorithm::MD5 => {
-            let mut context = md5::Context::new();
-            context.consume(data);
-            let digest = context.finalize();
-            hex::encode(digest.as_slice())
-        }
-        Algorithm::SHA1 => {
-            use sha1::{Sha1, Digest};
-            let mut hasher = Sha1::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
-        }
-        Algorithm::SHA256 => {
-            use sha2::{Sha256, Digest};
-            let mut hasher = Sha256::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
-        }
-        Algorithm::SHA384 => {
-            use sha2::{Sha384, Digest};
-            let mut hasher = Sha384::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
-        }
-        Algorithm::SHA512 => {
-            use sha2::{Sha512, Digest};
-            let mut hasher = Sha512::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
-        }
-        Algorithm::SHA3_224 => {
-            use sha3::{Sha3_224, Digest};
-            let mut hasher = Sha3_224::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
-        }
-        Algorithm::SHA3_256 => {
-            use sha3::{Sha3_256, Digest};
-            let mut hasher = Sha3_256::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
-        }
-        Algorithm::SHA3_384 => {
-            use sha3::{Sha3_384, Digest};
-            let mut hasher = Sha3_384::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
-        }
-        Algorithm::SHA3_512 => {
-            use sha3::{Sha3_512, Digest};
-            let mut hasher = Sha3_512::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
-        }
-        Algorithm::BLAKE2b => {
-            use blake2::{Blake2b512, Digest};
-            let mut hasher = Blake2b512::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
+// One running hasher for any supported algorithm, so the single-pass file loop
+// and the in-memory path share the same construction and finalization code
+pub enum AlgorithmHasher {
+    Md4(md4::Md4),
+    Md5(md5::Context),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+    Sha384(sha2::Sha384),
+    Sha512(sha2::Sha512),
+    Sha3_224(sha3::Sha3_224),
+    Sha3_256(sha3::Sha3_256),
+    Sha3_384(sha3::Sha3_384),
+    Sha3_512(sha3::Sha3_512),
+    Blake2(Blake2Hasher),
+    Ripemd160(ripemd::Ripemd160),
+    Tiger(TigerHasher),
+    Whirlpool(Whirlpool),
+    Gost(GostHasher),
+    Streebog256(streebog::Streebog256),
+    Streebog512(streebog::Streebog512),
+    Sm3(sm3::Sm3),
+    Kupyna256(kupyna::Kupyna256),
+    Kupyna384(kupyna::Kupyna384),
+    Kupyna512(kupyna::Kupyna512),
+    Crc32(crc32fast::Hasher),
+    Crc(CrcHasher),
+    Adler32(adler32::RollingAdler32),
+    Xxh32(xxhash_rust::xxh32::Xxh32),
+    Xxh64(xxhash_rust::xxh64::Xxh64),
+    Xxh3_64(xxhash_rust::xxh3::Xxh3Default),
+    Xxh3_128(xxhash_rust::xxh3::Xxh3Default),
+    Ed2k(Ed2kHasher),
+    Tth(TthHasher),
+    Md2(md2::Md2),
+    Ripemd128(ripemd::Ripemd128),
+    Ripemd256(ripemd::Ripemd256),
+    Ripemd320(ripemd::Ripemd320),
+    Haval(Haval),
+    Snefru(Snefru),
+    GitBlob(GitBlobHasher),
+    Ssdeep(SsdeepHasher),
+    Tlsh(Box<TlshHasher>), // The bucket counts make it much larger than the others
+    Perceptual(PerceptualHasher),
+    Block(BlockHasher),   // MD5, SHA-1 or SHA-2 with state a checkpoint can save
+    Sponge(SpongeHasher), // SHA-3 with state a checkpoint can save
+}
+
+// Running state of a hasher as written to a checkpoint
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum HasherState {
+    Block(BlockHasher),
+    Sponge(SpongeHasher),
+    Crc32 { crc: u32 },
+    Crc { params: CrcParams, register: u64 },
+    Adler32 { value: u32 },
+    Haval(HavalState),
+    Snefru(SnefruState),
+    Tth(TthHasher),
+    GitBlob(BlockHasher),
+}
+
+impl AlgorithmHasher {
+    pub fn new(algorithm: &Algorithm, options: &HashOptions) -> Result<Self, String> {
+        let hasher = match algorithm {
+            Algorithm::MD4 => AlgorithmHasher::Md4(md4::Md4::new()),
+            Algorithm::MD5 => AlgorithmHasher::Md5(md5::Context::new()),
+            Algorithm::SHA1 => AlgorithmHasher::Sha1(sha1::Sha1::new()),
+            Algorithm::SHA256 => AlgorithmHasher::Sha256(sha2::Sha256::new()),
+            Algorithm::SHA384 => AlgorithmHasher::Sha384(sha2::Sha384::new()),
+            Algorithm::SHA512 => AlgorithmHasher::Sha512(sha2::Sha512::new()),
+            Algorithm::SHA3_224 => AlgorithmHasher::Sha3_224(sha3::Sha3_224::new()),
+            Algorithm::SHA3_256 => AlgorithmHasher::Sha3_256(sha3::Sha3_256::new()),
+            Algorithm::SHA3_384 => AlgorithmHasher::Sha3_384(sha3::Sha3_384::new()),
+            Algorithm::SHA3_512 => AlgorithmHasher::Sha3_512(sha3::Sha3_512::new()),
+            Algorithm::BLAKE2b => AlgorithmHasher::Blake2(Blake2Hasher::blake2b(&options.blake2b)?),
+            Algorithm::BLAKE2s => AlgorithmHasher::Blake2(Blake2Hasher::blake2s(&options.blake2s)?),
+            Algorithm::RIPEMD160 => AlgorithmHasher::Ripemd160(ripemd::Ripemd160::new()),
+            Algorithm::TIGER192 => AlgorithmHasher::Tiger(TigerHasher::new(options.tiger_variant)),
+            Algorithm::WHIRLPOOL => AlgorithmHasher::Whirlpool(Whirlpool::new()),
+            Algorithm::GOST => AlgorithmHasher::Gost(GostHasher::new(options.gost_variant)),
+            Algorithm::STREEBOG256 => AlgorithmHasher::Streebog256(streebog::Streebog256::new()),
+            Algorithm::STREEBOG512 => AlgorithmHasher::Streebog512(streebog::Streebog512::new()),
+            Algorithm::SM3 => AlgorithmHasher::Sm3(sm3::Sm3::new()),
+            // kupyna is built on digest 0.11, so it goes through its own re-exported trait
+            Algorithm::KUPYNA256 => AlgorithmHasher::Kupyna256(kupyna::Digest::new()),
+            Algorithm::KUPYNA384 => AlgorithmHasher::Kupyna384(kupyna::Digest::new()),
+            Algorithm::KUPYNA512 => AlgorithmHasher::Kupyna512(kupyna::Digest::new()),
+            Algorithm::CRC32 => AlgorithmHasher::Crc32(crc32fast::Hasher::new()),
+            Algorithm::CRC => {
+                let params = match options.crc_model.params() {
+                    Some(params) => params,
+                    None => options.custom_crc.resolve()?,
+                };
+                AlgorithmHasher::Crc(CrcHasher::new(params))
+            }
+            Algorithm::ADLER32 => AlgorithmHasher::Adler32(adler32::RollingAdler32::new()),
+            Algorithm::XXH32 => AlgorithmHasher::Xxh32(xxhash_rust::xxh32::Xxh32::new(0)),
+            Algorithm::XXH64 => AlgorithmHasher::Xxh64(xxhash_rust::xxh64::Xxh64::new(0)),
+            Algorithm::XXH3_64 => AlgorithmHasher::Xxh3_64(xxhash_rust::xxh3::Xxh3Default::new()),
+            Algorithm::XXH3_128 => AlgorithmHasher::Xxh3_128(xxhash_rust::xxh3::Xxh3Default::new()),
+            Algorithm::ED2K => AlgorithmHasher::Ed2k(Ed2kHasher::new(options.ed2k_variant)),
+            Algorithm::TTH => AlgorithmHasher::Tth(TthHasher::default()),
+            Algorithm::MD2 => AlgorithmHasher::Md2(md2::Md2::new()),
+            Algorithm::RIPEMD128 => AlgorithmHasher::Ripemd128(ripemd::Ripemd128::new()),
+            Algorithm::RIPEMD256 => AlgorithmHasher::Ripemd256(ripemd::Ripemd256::new()),
+            Algorithm::RIPEMD320 => AlgorithmHasher::Ripemd320(ripemd::Ripemd320::new()),
+            Algorithm::HAVAL => AlgorithmHasher::Haval(Haval::new(options.haval_variant)),
+            Algorithm::SNEFRU128 => AlgorithmHasher::Snefru(Snefru::snefru128()),
+            Algorithm::SNEFRU256 => AlgorithmHasher::Snefru(Snefru::snefru256()),
+            Algorithm::GITSHA1 | Algorithm::GITSHA256 => {
+                AlgorithmHasher::GitBlob(GitBlobHasher::new(algorithm.git_format().unwrap(), None))
+            }
+            Algorithm::SSDEEP => AlgorithmHasher::Ssdeep(SsdeepHasher::new()),
+            Algorithm::TLSH => AlgorithmHasher::Tlsh(Box::new(TlshHasher::new())),
+            Algorithm::AHASH | Algorithm::DHASH | Algorithm::PHASH => {
+                AlgorithmHasher::Perceptual(PerceptualHasher::new(algorithm))
+            }
+        };
+        Ok(hasher)
+    }
+
+    // Same as new, for when the amount of data is known in advance; git blob
+    // IDs start with the length and otherwise keep the data until the end
+    pub fn with_length(algorithm: &Algorithm, options: &HashOptions, length: u64) -> Result<Self, String> {
+        match algorithm.git_format() {
+            Some(format) => Ok(AlgorithmHasher::GitBlob(GitBlobHasher::new(format, Some(length)))),
+            None => AlgorithmHasher::new(algorithm, options),
         }
-        Algorithm::BLAKE2s => {
-            use blake2::{Blake2s256, Digest};
-            let mut hasher = Blake2s256::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
+    }
+
+    // Like with_length, but MD5, SHA-1, SHA-2, SHA-3 and git blob IDs come
+    // from block.rs, whose running state save_state can write out
+    pub fn resumable(algorithm: &Algorithm, options: &HashOptions, length: u64) -> Result<Self, String> {
+        if let Some(hasher) = BlockHasher::new(algorithm) {
+            return Ok(AlgorithmHasher::Block(hasher));
         }
-        Algorithm::RIPEMD160 => {
-            use ripemd::{Ripemd160, Digest};
-            let mut hasher = Ripemd160::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
+        if let Some(hasher) = SpongeHasher::new(algorithm) {
+            return Ok(AlgorithmHasher::Sponge(hasher));
         }
-        Algorithm::CRC32 => {
-            let mut hasher = crc32fast::Hasher::new();
-            hasher.update(data);
-            format!("{:08x}", hasher.finalize())
+        match algorithm.git_format() {
+            Some(format) => Ok(AlgorithmHasher::GitBlob(GitBlobHasher::resumable(format, length))),
+            None => AlgorithmHasher::new(algorithm, options),
         }
-        Algorithm::ADLER32 => {
-            let mut adler = adler32::RollingAdler32::new();
-            for &byte in data {
-                adler.update(byte);
+    }
+
+    // None for hashers that keep their state inside their crates (the other
+    // RustCrypto ones, BLAKE2, xxHash, ed2k, the similarity and image hashes)
+    // and for the crate-backed MD5, SHA and git hashers that new() builds
+    pub fn save_state(&self) -> Option<HasherState> {
+        let state = match self {
+            AlgorithmHasher::Block(h) => HasherState::Block(h.clone()),
+            AlgorithmHasher::Sponge(h) => HasherState::Sponge(h.clone()),
+            AlgorithmHasher::Crc32(h) => HasherState::Crc32 { crc: h.clone().finalize() },
+            AlgorithmHasher::Crc(h) => {
+                let (params, register) = h.state();
+                HasherState::Crc { params, register }
             }
-            format!("{:08x}", adler.hash())
-        }
-        Algorithm::MD4 => {
-            let mut hasher = md4::Md4::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
+            AlgorithmHasher::Adler32(h) => HasherState::Adler32 { value: h.hash() },
+            AlgorithmHasher::Haval(h) => HasherState::Haval(h.save()),
+            AlgorithmHasher::Snefru(h) => HasherState::Snefru(h.save()),
+            AlgorithmHasher::Tth(h) => HasherState::Tth(h.clone()),
+            AlgorithmHasher::GitBlob(h) => HasherState::GitBlob(h.save()?),
+            _ => return None,
+        };
+        Some(state)
+    }
+
+    // False when the state was saved by another algorithm or CRC model
+    pub fn restore_state(&mut self, state: &HasherState) -> bool {
+        match (self, state) {
+            (AlgorithmHasher::Block(h), HasherState::Block(saved)) if saved.algorithm() == h.algorithm() => {
+                *h = saved.clone();
+            }
+            (AlgorithmHasher::Sponge(h), HasherState::Sponge(saved)) if saved.algorithm() == h.algorithm() => {
+                *h = saved.clone();
+            }
+            (AlgorithmHasher::Crc32(h), HasherState::Crc32 { crc }) => {
+                *h = crc32fast::Hasher::new_with_initial(*crc);
+            }
+            (AlgorithmHasher::Crc(h), HasherState::Crc { params, register }) if h.state().0 == *params => {
+                h.set_register(*register);
+            }
+            (AlgorithmHasher::Adler32(h), HasherState::Adler32 { value }) => {
+                *h = adler32::RollingAdler32::from_value(*value);
+            }
+            (AlgorithmHasher::Haval(h), HasherState::Haval(saved)) => return h.restore(saved),
+            (AlgorithmHasher::Snefru(h), HasherState::Snefru(saved)) => return h.restore(saved),
+            (AlgorithmHasher::Tth(h), HasherState::Tth(saved)) => {
+                *h = saved.clone();
+            }
+            (AlgorithmHasher::GitBlob(h), HasherState::GitBlob(saved)) => return h.restore(saved),
+            _ => return false,
         }
-        Algorithm::TIGER192 => {
-            let mut hasher = TigerHasher::new(tiger_variant);
-            hasher.update(data);
-            hex::encode(hasher.finalize())
+        true
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            AlgorithmHasher::Md4(h) => h.update(data),
+            AlgorithmHasher::Md5(ctx) => ctx.consume(data),
+            AlgorithmHasher::Sha1(h) => h.update(data),
+            AlgorithmHasher::Sha256(h) => h.update(data),
+            AlgorithmHasher::Sha384(h) => h.update(data),
+            AlgorithmHasher::Sha512(h) => h.update(data),
+            AlgorithmHasher::Sha3_224(h) => h.update(data),
+            AlgorithmHasher::Sha3_256(h) => h.update(data),
+            AlgorithmHasher::Sha3_384(h) => h.update(data),
+            AlgorithmHasher::Sha3_512(h) => h.update(data),
+            AlgorithmHasher::Blake2(h) => h.update(data),
+            AlgorithmHasher::Ripemd160(h) => h.update(data),
+            AlgorithmHasher::Tiger(h) => h.update(data),
+            AlgorithmHasher::Whirlpool(h) => h.update(data),
+            AlgorithmHasher::Gost(h) => h.update(data),
+            AlgorithmHasher::Streebog256(h) => h.update(data),
+            AlgorithmHasher::Streebog512(h) => h.update(data),
+            AlgorithmHasher::Sm3(h) => h.update(data),
+            AlgorithmHasher::Kupyna256(h) => kupyna::Digest::update(h, data),
+            AlgorithmHasher::Kupyna384(h) => kupyna::Digest::update(h, data),
+            AlgorithmHasher::Kupyna512(h) => kupyna::Digest::update(h, data),
+            AlgorithmHasher::Crc32(h) => h.update(data),
+            AlgorithmHasher::Crc(h) => h.update(data),
+            AlgorithmHasher::Adler32(h) => h.update_buffer(data),
+            AlgorithmHasher::Xxh32(h) => h.update(data),
+            AlgorithmHasher::Xxh64(h) => h.update(data),
+            AlgorithmHasher::Xxh3_64(h) => h.update(data),
+            AlgorithmHasher::Xxh3_128(h) => h.update(data),
+            AlgorithmHasher::Ed2k(h) => h.update(data),
+            AlgorithmHasher::Tth(h) => h.update(data),
+            AlgorithmHasher::Md2(h) => h.update(data),
+            AlgorithmHasher::Ripemd128(h) => h.update(data),
+            AlgorithmHasher::Ripemd256(h) => h.update(data),
+            AlgorithmHasher::Ripemd320(h) => h.update(data),
+            AlgorithmHasher::Haval(h) => h.update(data),
+            AlgorithmHasher::Snefru(h) => h.update(data),
+            AlgorithmHasher::GitBlob(h) => h.update(data),
+            AlgorithmHasher::Ssdeep(h) => h.update(data),
+            AlgorithmHasher::Tlsh(h) => h.update(data),
+            AlgorithmHasher::Perceptual(h) => h.update(data),
+            AlgorithmHasher::Block(h) => h.update(data),
+            AlgorithmHasher::Sponge(h) => h.update(data),
         }
-        Algorithm::WHIRLPOOL => {
-            let mut hasher = Whirlpool::new();
-            hasher.update(data);
-            hex::encode(hasher.finalize())
+    }
+
+    // Copy of the running state, so a digest of the data so far can be taken
+    // while hashing goes on; ssdeep's state cannot be copied
+    pub fn snapshot(&self) -> Option<Self> {
+        let copy = match self {
+            AlgorithmHasher::Md4(h) => AlgorithmHasher::Md4(h.clone()),
+            AlgorithmHasher::Md5(h) => AlgorithmHasher::Md5(h.clone()),
+            AlgorithmHasher::Sha1(h) => AlgorithmHasher::Sha1(h.clone()),
+            AlgorithmHasher::Sha256(h) => AlgorithmHasher::Sha256(h.clone()),
+            AlgorithmHasher::Sha384(h) => AlgorithmHasher::Sha384(h.clone()),
+            AlgorithmHasher::Sha512(h) => AlgorithmHasher::Sha512(h.clone()),
+            AlgorithmHasher::Sha3_224(h) => AlgorithmHasher::Sha3_224(h.clone()),
+            AlgorithmHasher::Sha3_256(h) => AlgorithmHasher::Sha3_256(h.clone()),
+            AlgorithmHasher::Sha3_384(h) => AlgorithmHasher::Sha3_384(h.clone()),
+            AlgorithmHasher::Sha3_512(h) => AlgorithmHasher::Sha3_512(h.clone()),
+            AlgorithmHasher::Blake2(h) => AlgorithmHasher::Blake2(h.clone()),
+            AlgorithmHasher::Ripemd160(h) => AlgorithmHasher::Ripemd160(h.clone()),
+            AlgorithmHasher::Tiger(h) => AlgorithmHasher::Tiger(h.clone()),
+            AlgorithmHasher::Whirlpool(h) => AlgorithmHasher::Whirlpool(h.clone()),
+            AlgorithmHasher::Gost(h) => AlgorithmHasher::Gost(h.clone()),
+            AlgorithmHasher::Streebog256(h) => AlgorithmHasher::Streebog256(h.clone()),
+            AlgorithmHasher::Streebog512(h) => AlgorithmHasher::Streebog512(h.clone()),
+            AlgorithmHasher::Sm3(h) => AlgorithmHasher::Sm3(h.clone()),
+            AlgorithmHasher::Kupyna256(h) => AlgorithmHasher::Kupyna256(h.clone()),
+            AlgorithmHasher::Kupyna384(h) => AlgorithmHasher::Kupyna384(h.clone()),
+            AlgorithmHasher::Kupyna512(h) => AlgorithmHasher::Kupyna512(h.clone()),
+            AlgorithmHasher::Crc32(h) => AlgorithmHasher::Crc32(h.clone()),
+            AlgorithmHasher::Crc(h) => AlgorithmHasher::Crc(h.clone()),
+            AlgorithmHasher::Adler32(h) => AlgorithmHasher::Adler32(h.clone()),
+            AlgorithmHasher::Xxh32(h) => AlgorithmHasher::Xxh32(h.clone()),
+            AlgorithmHasher::Xxh64(h) => AlgorithmHasher::Xxh64(h.clone()),
+            AlgorithmHasher::Xxh3_64(h) => AlgorithmHasher::Xxh3_64(h.clone()),
+            AlgorithmHasher::Xxh3_128(h) => AlgorithmHasher::Xxh3_128(h.clone()),
+            AlgorithmHasher::Ed2k(h) => AlgorithmHasher::Ed2k(h.clone()),
+            AlgorithmHasher::Tth(h) => AlgorithmHasher::Tth(h.clone()),
+            AlgorithmHasher::Md2(h) => AlgorithmHasher::Md2(h.clone()),
+            AlgorithmHasher::Ripemd128(h) => AlgorithmHasher::Ripemd128(h.clone()),
+            AlgorithmHasher::Ripemd256(h) => AlgorithmHasher::Ripemd256(h.clone()),
+            AlgorithmHasher::Ripemd320(h) => AlgorithmHasher::Ripemd320(h.clone()),
+            AlgorithmHasher::Haval(h) => AlgorithmHasher::Haval(h.clone()),
+            AlgorithmHasher::Snefru(h) => AlgorithmHasher::Snefru(h.clone()),
+            AlgorithmHasher::GitBlob(h) => AlgorithmHasher::GitBlob(h.clone()),
+            AlgorithmHasher::Ssdeep(_) => return None,
+            AlgorithmHasher::Tlsh(h) => AlgorithmHasher::Tlsh(h.clone()),
+            AlgorithmHasher::Perceptual(h) => AlgorithmHasher::Perceptual(h.clone()),
+            AlgorithmHasher::Block(h) => AlgorithmHasher::Block(h.clone()),
+            AlgorithmHasher::Sponge(h) => AlgorithmHasher::Sponge(h.clone()),
+        };
+        Some(copy)
+    }
+
+    // Raw digest; checksums and xxHash values are big-endian. None for the
+    // CRC catalogue, whose widths are not always whole bytes, and for the
+    // ssdeep, TLSH and image hashes, which are text
+    pub fn finalize_bytes(self) -> Option<Vec<u8>> {
+        let digest = match self {
+            AlgorithmHasher::Md4(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Md5(ctx) => ctx.finalize().to_vec(),
+            AlgorithmHasher::Sha1(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Sha256(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Sha384(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Sha512(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Sha3_224(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Sha3_256(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Sha3_384(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Sha3_512(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Blake2(h) => h.finalize(),
+            AlgorithmHasher::Ripemd160(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Tiger(h) => h.finalize(),
+            AlgorithmHasher::Whirlpool(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Gost(h) => h.finalize(),
+            AlgorithmHasher::Streebog256(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Streebog512(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Sm3(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Kupyna256(h) => kupyna::Digest::finalize(h).to_vec(),
+            AlgorithmHasher::Kupyna384(h) => kupyna::Digest::finalize(h).to_vec(),
+            AlgorithmHasher::Kupyna512(h) => kupyna::Digest::finalize(h).to_vec(),
+            AlgorithmHasher::Crc32(h) => h.finalize().to_be_bytes().to_vec(),
+            AlgorithmHasher::Adler32(h) => h.hash().to_be_bytes().to_vec(),
+            AlgorithmHasher::Xxh32(h) => h.digest().to_be_bytes().to_vec(),
+            AlgorithmHasher::Xxh64(h) => h.digest().to_be_bytes().to_vec(),
+            AlgorithmHasher::Xxh3_64(h) => h.digest().to_be_bytes().to_vec(),
+            AlgorithmHasher::Xxh3_128(h) => h.digest128().to_be_bytes().to_vec(),
+            AlgorithmHasher::Ed2k(h) => h.finalize(),
+            AlgorithmHasher::Tth(h) => h.finalize(),
+            AlgorithmHasher::Md2(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Ripemd128(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Ripemd256(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Ripemd320(h) => h.finalize().to_vec(),
+            AlgorithmHasher::Haval(h) => h.finalize(),
+            AlgorithmHasher::Snefru(h) => h.finalize(),
+            AlgorithmHasher::GitBlob(h) => h.finalize(),
+            AlgorithmHasher::Block(h) => h.finalize(),
+            AlgorithmHasher::Sponge(h) => h.finalize(),
+            AlgorithmHasher::Crc(_)
+            | AlgorithmHasher::Ssdeep(_)
+            | AlgorithmHasher::Tlsh(_)
+            | AlgorithmHasher::Perceptual(_) => return None,
+        };
+        Some(digest)
+    }
+
+    // Returns the digest as lowercase hex, the same way crc32/xxhsum print
+    // checksums and xxHash values. ssdeep and TLSH digests are returned in
+    // their usual text form instead
+    pub fn finalize(self) -> String {
+        match self {
+            AlgorithmHasher::Crc(h) => h.finalize(),
+            AlgorithmHasher::Ssdeep(h) => h.finalize(),
+            AlgorithmHasher::Tlsh(h) => h.finalize(),
+            AlgorithmHasher::Perceptual(h) => h.finalize(),
+            other => hex::encode(other.finalize_bytes().unwrap_or_default()),
         }
-        Algorithm::GOST => {
-            let mut hasher = GostHasher::new(gost_variant);
+    }
+}
+
+pub fn calculate_hash_from_data(
+    data: &[u8],
+    algorithm: &Algorithm,
+    options: &HashOptions,
+) -> String {
+    match AlgorithmHasher::with_length(algorithm, options, data.len() as u64) {
+        Ok(mut hasher) => {
             hasher.update(data);
-            hex::encode(hasher.finalize())
+            hasher.finalize()
         }
+        Err(e) => format!("Error: {}", e),
     }
 }
 
+// Selected part of the file contents, clamped in case the file changed size
+// after its metadata was read
+fn span_of(data: &[u8], start: usize, end: usize) -> &[u8] {
+    let end = end.min(data.len());
+    &data[start.min(end)..end]
+}
+
 // Optimized function that processes multiple algorithms in a single file pass
 // Reads the file once using streaming and updates all hashers
+// An optional byte range limits hashing to that part of the file; text
+// normalization is applied to the bytes of that range. With decompress set,
+// gzip, bzip2, xz and zstd files are hashed by their decompressed contents
 pub fn calculate_hashes_parallel_streaming(
     path: &PathBuf,
     algorithms: &[Algorithm],
-    gost_variant: GostVariant,
-    tiger_variant: TigerVariant,
+    options: &HashOptions,
+    range: Option<&ByteRange>,
+    normalization: Normalization,
+    decompress: bool,
 ) -> Vec<(Algorithm, String)> {
-    use std::io::Read;
-    use sha1::Digest;
-    
     // Clone algorithms at the beginning to avoid ownership issues
     let algorithms_clone = algorithms.to_vec();
     
@@ -143,19 +422,46 @@ pub fn calculate_hashes_parallel_streaming(
     };
     let file_size = metadata.len();
     
-    // OPTIMIZED STRATEGY: Try mmap first (more efficient)
-    // If mmap fails or is not available, load into memory and process in parallel
-    // This is faster than sequential streaming
-    let file = match File::open(path) {
-        Ok(f) => f,
+    if metadata.is_dir() {
+        return hash_directory(path, &algorithms_clone, range, normalization);
+    }
+    
+    if decompress {
+        let compression = Compression::detect_file(path);
+        if compression != Compression::None {
+            return hash_decompressed(path, &algorithms_clone, options, compression, range, normalization);
+        }
+    }
+    
+    let span = match range.map(|r| r.resolve(file_size)).transpose() {
+        Ok(span) => span.unwrap_or(0..file_size),
         Err(e) => return algorithms_clone.iter().map(|alg| (alg.clone(), format!("Error: {}", e))).collect(),
     };
+    let (start, end) = (span.start as usize, span.end as usize);
     
-    // Try to use mmap (works for large files too)
-    let mmap_result = unsafe {
-        memmap2::MmapOptions::new().map(&file)
+    if normalization.canonical_json {
+        return hash_canonical_json(path, &algorithms_clone, options, span, normalization);
+    }
+    
+    // Normalized data no longer matches the file byte for byte, so it always
+    // goes through the streaming loop below
+    let mmap_result = if normalization.is_active() {
+        Err(std::io::Error::other("normalization requires streaming"))
+    } else {
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(e) => return algorithms_clone.iter().map(|alg| (alg.clone(), format!("Error: {}", e))).collect(),
+        };
+        
+        // Try to use mmap (works for large files too)
+        unsafe {
+            memmap2::MmapOptions::new().map(&file)
+        }
     };
     
+    // OPTIMIZED STRATEGY: Try mmap first (more efficient)
+    // If mmap fails or is not available, load into memory and process in parallel
+    // This is faster than sequential streaming
     match mmap_result {
         Ok(mmap) => {
             // Use mmap directly - the OS handles memory efficiently
@@ -168,11 +474,10 @@ pub fn calculate_hashes_parallel_streaming(
                 let alg = algorithm.clone();
                 let mmap_clone = mmap_arc.clone();
                 let alg_clone = alg.clone();
-                let gost_variant_clone = gost_variant;
-                let tiger_variant_clone = tiger_variant;
+                let options_clone = options.clone();
                 let handle = std::thread::spawn(move || {
-                    let data: &[u8] = &*mmap_clone;
-                    (alg_clone.clone(), calculate_hash_from_data(data, &alg_clone, gost_variant_clone, tiger_variant_clone))
+                    let data = span_of(&mmap_clone, start, end);
+                    (alg_clone.clone(), calculate_hash_from_data(data, &alg_clone, &options_clone))
                 });
                 handles.push(handle);
             }
@@ -189,7 +494,7 @@ pub fn calculate_hashes_parallel_streaming(
         Err(_) => {
             // If mmap fails, load the entire file into memory and process in parallel
             // This is faster than sequential streaming for reasonable files
-            if file_size <= MMAP_THRESHOLD {
+            if file_size <= MMAP_THRESHOLD && !normalization.is_active() {
                 let file2 = match File::open(path) {
                     Ok(f) => f,
                     Err(e) => return algorithms_clone.iter().map(|alg| (alg.clone(), format!("Error: {}", e))).collect(),
@@ -204,10 +509,9 @@ pub fn calculate_hashes_parallel_streaming(
                         let alg = algorithm.clone();
                         let data_clone = data_arc.clone();
                         let alg_clone = alg.clone();
-                        let gost_variant_clone = gost_variant;
-                        let tiger_variant_clone = tiger_variant;
+                        let options_clone = options.clone();
                         let handle = std::thread::spawn(move || {
-                            (alg_clone.clone(), calculate_hash_from_data(&data_clone, &alg_clone, gost_variant_clone, tiger_variant_clone))
+                            (alg_clone.clone(), calculate_hash_from_data(span_of(&data_clone, start, end), &alg_clone, &options_clone))
                         });
                         handles.push(handle);
                     }
@@ -228,227 +532,302 @@ pub fn calculate_hashes_parallel_streaming(
     
     // For large files, use streaming: read once and update all hashers
     // This avoids loading everything into memory
-    let file = match File::open(path) {
-        Ok(f) => f,
-        Err(e) => return algorithms_clone.iter().map(|alg| (alg.clone(), format!("Error: {}", e))).collect(),
-    };
     
-    let buffer_size = if file_size > LARGE_FILE_THRESHOLD {
-        LARGE_BUFFER_SIZE
+    // Git blob IDs need the length up front; normalization changes it, so the
+    // normalized length is counted in a first pass rather than buffering the data
+    let length = if normalization.is_active() && algorithms_clone.iter().any(|alg| alg.git_format().is_some()) {
+        match normalized_length(path, span.clone(), normalization) {
+            Ok(length) => length,
+            Err(e) => return algorithms_clone.iter().map(|alg| (alg.clone(), e.clone())).collect(),
+        }
     } else {
-        BUFFER_SIZE
+        span.end - span.start
     };
     
-    let mut reader = BufReader::with_capacity(buffer_size, file);
-    let mut buffer = vec![0u8; buffer_size];
-    
-    // Initialize all hashers (only those needed)
-    let has_md4 = algorithms_clone.contains(&Algorithm::MD4);
-    let has_md5 = algorithms_clone.contains(&Algorithm::MD5);
-    let has_sha1 = algorithms_clone.contains(&Algorithm::SHA1);
-    let has_sha256 = algorithms_clone.contains(&Algorithm::SHA256);
-    let has_sha384 = algorithms_clone.contains(&Algorithm::SHA384);
-    let has_sha512 = algorithms_clone.contains(&Algorithm::SHA512);
-    let has_sha3_224 = algorithms_clone.contains(&Algorithm::SHA3_224);
-    let has_sha3_256 = algorithms_clone.contains(&Algorithm::SHA3_256);
-    let has_sha3_384 = algorithms_clone.contains(&Algorithm::SHA3_384);
-    let has_sha3_512 = algorithms_clone.contains(&Algorithm::SHA3_512);
-    let has_blake2b = algorithms_clone.contains(&Algorithm::BLAKE2b);
-    let has_blake2s = algorithms_clone.contains(&Algorithm::BLAKE2s);
-    let has_ripemd160 = algorithms_clone.contains(&Algorithm::RIPEMD160);
-    let has_tiger192 = algorithms_clone.contains(&Algorithm::TIGER192);
-    let has_whirlpool = algorithms_clone.contains(&Algorithm::WHIRLPOOL);
-    let has_gost = algorithms_clone.contains(&Algorithm::GOST);
-    let has_crc32 = algorithms_clone.contains(&Algorithm::CRC32);
-    let has_adler32 = algorithms_clone.contains(&Algorithm::ADLER32);
+    // Initialize one hasher per selected algorithm; a hasher that cannot be
+    // built (e.g. bad BLAKE2 parameters) keeps its error for the results
+    let mut hashers: Vec<(Algorithm, Result<AlgorithmHasher, String>)> = algorithms_clone
+        .iter()
+        .map(|alg| (alg.clone(), AlgorithmHasher::with_length(alg, options, length)))
+        .collect();
     
-    let mut md4_hasher = if has_md4 { Some(md4::Md4::new()) } else { None };
-    let mut md5_ctx = if has_md5 { Some(md5::Context::new()) } else { None };
-    let mut sha1_hasher = if has_sha1 { Some(sha1::Sha1::new()) } else { None };
-    let mut sha256_hasher = if has_sha256 { Some(sha2::Sha256::new()) } else { None };
-    let mut sha384_hasher = if has_sha384 { Some(sha2::Sha384::new()) } else { None };
-    let mut sha512_hasher = if has_sha512 { Some(sha2::Sha512::new()) } else { None };
-    let mut sha3_224_hasher = if has_sha3_224 { Some(sha3::Sha3_224::new()) } else { None };
-    let mut sha3_256_hasher = if has_sha3_256 { Some(sha3::Sha3_256::new()) } else { None };
-    let mut sha3_384_hasher = if has_sha3_384 { Some(sha3::Sha3_384::new()) } else { None };
-    let mut sha3_512_hasher = if has_sha3_512 { Some(sha3::Sha3_512::new()) } else { None };
-    let mut blake2b_hasher = if has_blake2b { Some(blake2::Blake2b512::new()) } else { None };
-    let mut blake2s_hasher = if has_blake2s { Some(blake2::Blake2s256::new()) } else { None };
-    let mut ripemd160_hasher = if has_ripemd160 { Some(ripemd::Ripemd160::new()) } else { None };
-    let mut tiger192_hasher = if has_tiger192 { Some(TigerHasher::new(tiger_variant)) } else { None };
-    let mut whirlpool_hasher = if has_whirlpool { Some(Whirlpool::new()) } else { None };
-    let mut gost_hasher = if has_gost { Some(GostHasher::new(gost_variant)) } else { None };
-    let mut crc32_hasher = if has_crc32 { Some(crc32fast::Hasher::new()) } else { None };
-    let mut adler32_hasher = if has_adler32 { Some(adler32::RollingAdler32::new()) } else { None };
+    let mut update_all = |data: &[u8]| {
+        for (_, hasher) in hashers.iter_mut() {
+            if let Ok(h) = hasher {
+                h.update(data);
+            }
+        }
+    };
     
     // Read the file once and update all active hashers
-    loop {
-        match reader.read(&mut buffer) {
-            Ok(0) => break,
-            Ok(n) => {
-                let chunk = &buffer[..n];
-                if let Some(ref mut h) = md4_hasher { h.update(chunk); }
-                if let Some(ref mut ctx) = md5_ctx { ctx.consume(chunk); }
-                if let Some(ref mut h) = sha1_hasher { h.update(chunk); }
-                if let Some(ref mut h) = sha256_hasher { h.update(chunk); }
-                if let Some(ref mut h) = sha384_hasher { h.update(chunk); }
-                if let Some(ref mut h) = sha512_hasher { h.update(chunk); }
-                if let Some(ref mut h) = sha3_224_hasher { h.update(chunk); }
-                if let Some(ref mut h) = sha3_256_hasher { h.update(chunk); }
-                if let Some(ref mut h) = sha3_384_hasher { h.update(chunk); }
-                if let Some(ref mut h) = sha3_512_hasher { h.update(chunk); }
-                if let Some(ref mut h) = blake2b_hasher { h.update(chunk); }
-                if let Some(ref mut h) = blake2s_hasher { h.update(chunk); }
-                if let Some(ref mut h) = ripemd160_hasher { h.update(chunk); }
-                if let Some(ref mut h) = tiger192_hasher { h.update(chunk); }
-                if let Some(ref mut h) = whirlpool_hasher { h.update(chunk); }
-                if let Some(ref mut h) = gost_hasher { h.update(chunk); }
-                if let Some(ref mut h) = crc32_hasher { h.update(chunk); }
-                if let Some(ref mut h) = adler32_hasher {
-                    for &byte in chunk {
-                        h.update(byte);
-                    }
-                }
-            }
-            Err(e) => return algorithms_clone.iter().map(|alg| (alg.clone(), format!("Error reading file: {}", e))).collect(),
+    let mut normalizer = Normalizer::new(normalization);
+    let mut normalized = Vec::new();
+    let read_result = read_range(path, span, |chunk| {
+        if normalization.is_active() {
+            normalized.clear();
+            normalizer.push(chunk, &mut normalized);
+            update_all(&normalized);
+        } else {
+            update_all(chunk);
         }
+    });
+    if let Err(e) = read_result {
+        return algorithms_clone.iter().map(|alg| (alg.clone(), e.clone())).collect();
     }
+    normalized.clear();
+    normalizer.finish(&mut normalized);
+    update_all(&normalized);
     
     // Finalize all hashes
-    let mut results = Vec::new();
-    for alg in &algorithms_clone {
-        let hash = match alg {
-            Algorithm::MD4 => {
-                if let Some(h) = md4_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::MD5 => {
-                if let Some(ctx) = md5_ctx.take() {
-                    let digest = ctx.finalize();
-                    hex::encode(digest.as_slice())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::SHA1 => {
-                if let Some(h) = sha1_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::SHA256 => {
-                if let Some(h) = sha256_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::SHA384 => {
-                if let Some(h) = sha384_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::SHA512 => {
-                if let Some(h) = sha512_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::SHA3_224 => {
-                if let Some(h) = sha3_224_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::SHA3_256 => {
-                if let Some(h) = sha3_256_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::SHA3_384 => {
-                if let Some(h) = sha3_384_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::SHA3_512 => {
-                if let Some(h) = sha3_512_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::BLAKE2b => {
-                if let Some(h) = blake2b_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::BLAKE2s => {
-                if let Some(h) = blake2s_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::RIPEMD160 => {
-                if let Some(h) = ripemd160_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::TIGER192 => {
-                if let Some(h) = tiger192_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::WHIRLPOOL => {
-                if let Some(h) = whirlpool_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::GOST => {
-                if let Some(h) = gost_hasher.take() {
-                    hex::encode(h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::CRC32 => {
-                if let Some(h) = crc32_hasher.take() {
-                    format!("{:08x}", h.finalize())
-                } else {
-                    continue;
-                }
-            }
-            Algorithm::ADLER32 => {
-                if let Some(h) = adler32_hasher.take() {
-                    format!("{:08x}", h.hash())
-                } else {
-                    continue;
-                }
+    hashers
+        .into_iter()
+        .map(|(alg, hasher)| match hasher {
+            Ok(h) => (alg, h.finalize()),
+            Err(e) => (alg, format!("Error: {}", e)),
+        })
+        .collect()
+}
+
+// Length of a range once normalized, found by streaming it through the
+// normalizer without keeping the output
+fn normalized_length(path: &PathBuf, span: std::ops::Range<u64>, normalization: Normalization) -> Result<u64, String> {
+    let mut normalizer = Normalizer::new(normalization);
+    let mut normalized = Vec::new();
+    let mut length = 0;
+    read_range(path, span, |chunk| {
+        normalized.clear();
+        normalizer.push(chunk, &mut normalized);
+        length += normalized.len() as u64;
+    })?;
+    normalized.clear();
+    normalizer.finish(&mut normalized);
+    Ok(length + normalized.len() as u64)
+}
+
+// Single pass over data that only exists as a stream, such as an archive
+// member or a decompressed file. A known length lets git blob IDs start right
+// away, and the data must then turn out to be exactly that long. Text
+// normalization is applied to the data as it streams past
+pub fn hash_reader(
+    reader: &mut dyn Read,
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+    length: Option<u64>,
+    normalization: Normalization,
+) -> Result<Vec<(Algorithm, String)>, String> {
+    if normalization.canonical_json {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).map_err(|e| e.to_string())?;
+        let canonical = normalization.prepare(&data)?;
+        return Ok(algorithms
+            .iter()
+            .map(|alg| (alg.clone(), calculate_hash_from_data(&canonical, alg, options)))
+            .collect());
+    }
+
+    let mut hashers: Vec<(Algorithm, Result<AlgorithmHasher, String>)> = algorithms
+        .iter()
+        .map(|alg| {
+            let hasher = match length {
+                Some(length) if !normalization.is_active() => AlgorithmHasher::with_length(alg, options, length),
+                _ => AlgorithmHasher::new(alg, options),
+            };
+            (alg.clone(), hasher)
+        })
+        .collect();
+    let mut update_all = |data: &[u8]| {
+        for (_, hasher) in hashers.iter_mut() {
+            if let Ok(h) = hasher {
+                h.update(data);
             }
+        }
+    };
+
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut normalizer = Normalizer::new(normalization);
+    let mut normalized = Vec::new();
+    let mut total = 0u64;
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.to_string()),
         };
-        results.push((alg.clone(), hash));
+        total += n as u64;
+        if normalization.is_active() {
+            normalized.clear();
+            normalizer.push(&buffer[..n], &mut normalized);
+            update_all(&normalized);
+        } else {
+            update_all(&buffer[..n]);
+        }
+    }
+    if let Some(length) = length.filter(|&length| length != total) {
+        return Err(format!("expected {} bytes but read {}", length, total));
+    }
+    normalized.clear();
+    normalizer.finish(&mut normalized);
+    update_all(&normalized);
+
+    Ok(hashers
+        .into_iter()
+        .map(|(alg, hasher)| match hasher {
+            Ok(h) => (alg, h.finalize()),
+            Err(e) => (alg, format!("Error: {}", e)),
+        })
+        .collect())
+}
+
+// A compressed file is hashed by what it decompresses to, with the decoder
+// feeding the hashers directly so nothing is written to disk. Offsets in a
+// byte range would be ambiguous, so ranges are refused
+fn hash_decompressed(
+    path: &PathBuf,
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+    compression: Compression,
+    range: Option<&ByteRange>,
+    normalization: Normalization,
+) -> Vec<(Algorithm, String)> {
+    if range.is_some() {
+        let e = "a byte range cannot be used with decompressed contents";
+        return algorithms.iter().map(|alg| (alg.clone(), format!("Error: {}", e))).collect();
+    }
+    let open = || File::open(path).map(BufReader::new).map_err(|e| e.to_string());
+    hash_decoded(open, open, algorithms, options, compression, normalization)
+}
+
+// Hashes the decompressed form of the stream `open` gives. Git blob IDs start
+// with the length, which a first decoding pass over `open_for_length` counts;
+// with normalization that length is unknown, so like the image hashes they
+// would need all of the decompressed data in memory and are refused
+pub(crate) fn hash_decoded<C: Read, R: Read>(
+    open_for_length: impl FnOnce() -> Result<C, String>,
+    open: impl FnOnce() -> Result<R, String>,
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+    compression: Compression,
+    normalization: Normalization,
+) -> Vec<(Algorithm, String)> {
+    let refused = |alg: &Algorithm| alg.is_perceptual() || (alg.git_format().is_some() && normalization.is_active());
+    let accepted: Vec<Algorithm> = algorithms.iter().filter(|alg| !refused(alg)).cloned().collect();
+
+    let length = if !normalization.is_active() && accepted.iter().any(|alg| alg.git_format().is_some()) {
+        open_for_length()
+            .and_then(|reader| compression.decoder(reader))
+            .and_then(|mut decoder| std::io::copy(&mut decoder, &mut std::io::sink()).map_err(|e| e.to_string()))
+            .map(Some)
+    } else {
+        Ok(None)
+    };
+    let results = length
+        .and_then(|length| {
+            open()
+                .and_then(|reader| compression.decoder(reader))
+                .and_then(|mut decoder| hash_reader(&mut decoder, &accepted, options, length, normalization))
+        })
+        .map_err(|e| format!("{} data: {}", compression, e));
+
+    match results {
+        Ok(results) => algorithms
+            .iter()
+            .map(|alg| match results.iter().find(|(hashed, _)| hashed == alg) {
+                Some(result) => result.clone(),
+                None => (alg.clone(), "Error: not available for decompressed data, as it keeps all of it in memory".to_string()),
+            })
+            .collect(),
+        Err(e) => algorithms.iter().map(|alg| (alg.clone(), format!("Error: {}", e))).collect(),
+    }
+}
+
+// Canonical JSON is produced from the whole document at once, so the range is
+// read into memory, rewritten and then hashed; a document that does not parse
+// gives the same error for every algorithm
+fn hash_canonical_json(
+    path: &PathBuf,
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+    span: std::ops::Range<u64>,
+    normalization: Normalization,
+) -> Vec<(Algorithm, String)> {
+    let mut data = Vec::new();
+    let canonical = read_range(path, span, |chunk| data.extend_from_slice(chunk))
+        .and_then(|_| normalization.prepare(&data).map_err(|e| format!("Error: {}", e)));
+    match canonical {
+        Ok(canonical) => algorithms
+            .iter()
+            .map(|alg| (alg.clone(), calculate_hash_from_data(&canonical, alg, options)))
+            .collect(),
+        Err(e) => algorithms.iter().map(|alg| (alg.clone(), e.clone())).collect(),
     }
-    
-    results
 }
 
+// A folder can only be hashed as a git tree; the other algorithms, byte
+// ranges and text normalization have no meaning for it
+fn hash_directory(
+    path: &Path,
+    algorithms: &[Algorithm],
+    range: Option<&ByteRange>,
+    normalization: Normalization,
+) -> Vec<(Algorithm, String)> {
+    algorithms
+        .iter()
+        .map(|alg| {
+            let result = match alg.git_format() {
+                _ if range.is_some() => "Error: a byte range cannot be used with a folder".to_string(),
+                _ if normalization.is_active() => "Error: text normalization cannot be used with a folder".to_string(),
+                Some(format) => match git_tree_id(path, format) {
+                    Ok(id) => hex::encode(id),
+                    Err(e) => format!("Error: {}", e),
+                },
+                None => "Error: folders can only be hashed as a git tree".to_string(),
+            };
+            (alg.clone(), result)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+
+    // xxhsum -H0, -H1, -H2 and -H3 print the canonical big-endian form
+    #[test]
+    fn xxhash_known_answers() {
+        let options = HashOptions::default();
+        let cases: [(Algorithm, &str, &str); 4] = [
+            (Algorithm::XXH32, "02cc5d05", "32d153ff"),
+            (Algorithm::XXH64, "ef46db3751d8e999", "44bc2cf5ad770999"),
+            (Algorithm::XXH3_64, "2d06800538d394c2", "78af5f94892f3950"),
+            (Algorithm::XXH3_128, "99aa06d3014798d86001c324468d497f", "06b05ab6733a618578af5f94892f3950"),
+        ];
+        for (algorithm, empty, abc) in cases {
+            assert_eq!(calculate_hash_from_data(b"", &algorithm, &options), empty, "{}", algorithm);
+            assert_eq!(calculate_hash_from_data(b"abc", &algorithm, &options), abc, "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn normalized_git_blob_ids_stream_with_the_normalized_length() {
+        let dir = ScratchDir::new("compute-normalized-git");
+        let path = dir.write("crlf.txt", b"\xef\xbb\xbfline one  \r\nline two\rline three\t\r\n");
+        let normalization = Normalization {
+            line_endings: true,
+            strip_bom: true,
+            strip_trailing_whitespace: true,
+            canonical_json: false,
+        };
+        let options = HashOptions::default();
+        let algorithms = [Algorithm::GITSHA1, Algorithm::GITSHA256, Algorithm::SHA1];
+
+        let results = calculate_hashes_parallel_streaming(&path, &algorithms, &options, None, normalization, false);
+        // `git hash-object` of the normalized text
+        assert_eq!(results[0].1, "0c2aa38e0600e0d2df09c2f84664d8a14f899879");
+        for (algorithm, result) in results {
+            let expected = calculate_hash_from_data(b"line one\nline two\nline three\n", &algorithm, &options);
+            assert_eq!(result, expected, "{}", algorithm);
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use std::fmt;
+
+// Rocksoft-model CRC parameters (width, poly, init, refin, refout, xorout)
+// together with the published check value for the ASCII string "123456789"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CrcParams {
+    pub width: u8,
+    pub poly: u64,
+    pub init: u64,
+    pub refin: bool,
+    pub refout: bool,
+    pub xorout: u64,
+    pub check: u64,
+}
+
+const CHECK_INPUT: &[u8] = b"123456789";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum CrcModel {
+    Crc8Smbus,
+    Crc8MaximDow,
+    Crc8Autosar,
+    Crc16Arc,
+    Crc16Ibm3740,
+    Crc16Kermit,
+    Crc16Xmodem,
+    Crc16Modbus,
+    Crc16IbmSdlc,
+    Crc16Usb,
+    Crc24OpenPgp,
+    Crc32IsoHdlc,
+    Crc32Iscsi,
+    Crc32Bzip2,
+    Crc32Mpeg2,
+    Crc32Cksum,
+    Crc32Autosar,
+    Crc32Jamcrc,
+    Crc32Aixm,
+    Crc64Xz,
+    Crc64Ecma182,
+    Crc64GoIso,
+    Crc64We,
+    Crc64Nvme,
+    Custom,
+}
+
+impl CrcModel {
+    pub fn all() -> Vec<CrcModel> {
+        vec![
+            CrcModel::Crc8Smbus,
+            CrcModel::Crc8MaximDow,
+            CrcModel::Crc8Autosar,
+            CrcModel::Crc16Arc,
+            CrcModel::Crc16Ibm3740,
+            CrcModel::Crc16Kermit,
+            CrcModel::Crc16Xmodem,
+            CrcModel::Crc16Modbus,
+            CrcModel::Crc16IbmSdlc,
+            CrcModel::Crc16Usb,
+            CrcModel::Crc24OpenPgp,
+            CrcModel::Crc32IsoHdlc,
+            CrcModel::Crc32Iscsi,
+            CrcModel::Crc32Bzip2,
+            CrcModel::Crc32Mpeg2,
+            CrcModel::Crc32Cksum,
+            CrcModel::Crc32Autosar,
+            CrcModel::Crc32Jamcrc,
+            CrcModel::Crc32Aixm,
+            CrcModel::Crc64Xz,
+            CrcModel::Crc64Ecma182,
+            CrcModel::Crc64GoIso,
+            CrcModel::Crc64We,
+            CrcModel::Crc64Nvme,
+            CrcModel::Custom,
+        ]
+    }
+
+    // Catalog parameters; `Custom` is resolved from the user's `CustomCrc` instead
+    pub fn params(&self) -> Option<CrcParams> {
+        let p = |width, poly, init, refin, refout, xorout, check| {
+            Some(CrcParams { width, poly, init, refin, refout, xorout, check })
+        };
+
+        match self {
+            CrcModel::Crc8Smbus => p(8, 0x07, 0x00, false, false, 0x00, 0xf4),
+            CrcModel::Crc8MaximDow => p(8, 0x31, 0x00, true, true, 0x00, 0xa1),
+            CrcModel::Crc8Autosar => p(8, 0x2f, 0xff, false, false, 0xff, 0xdf),
+            CrcModel::Crc16Arc => p(16, 0x8005, 0x0000, true, true, 0x0000, 0xbb3d),
+            CrcModel::Crc16Ibm3740 => p(16, 0x1021, 0xffff, false, false, 0x0000, 0x29b1),
+            CrcModel::Crc16Kermit => p(16, 0x1021, 0x0000, true, true, 0x0000, 0x2189),
+            CrcModel::Crc16Xmodem => p(16, 0x1021, 0x0000, false, false, 0x0000, 0x31c3),
+            CrcModel::Crc16Modbus => p(16, 0x8005, 0xffff, true, true, 0x0000, 0x4b37),
+            CrcModel::Crc16IbmSdlc => p(16, 0x1021, 0xffff, true, true, 0xffff, 0x906e),
+            CrcModel::Crc16Usb => p(16, 0x8005, 0xffff, true, true, 0xffff, 0xb4c8),
+            CrcModel::Crc24OpenPgp => p(24, 0x864cfb, 0xb704ce, false, false, 0x000000, 0x21cf02),
+            CrcModel::Crc32IsoHdlc => p(32, 0x04c11db7, 0xffffffff, true, true, 0xffffffff, 0xcbf43926),
+            CrcModel::Crc32Iscsi => p(32, 0x1edc6f41, 0xffffffff, true, true, 0xffffffff, 0xe3069283),
+            CrcModel::Crc32Bzip2 => p(32, 0x04c11db7, 0xffffffff, false, false, 0xffffffff, 0xfc891918),
+            CrcModel::Crc32Mpeg2 => p(32, 0x04c11db7, 0xffffffff, false, false, 0x00000000, 0x0376e6e7),
+            CrcModel::Crc32Cksum => p(32, 0x04c11db7, 0x00000000, false, false, 0xffffffff, 0x765e7680),
+            CrcModel::Crc32Autosar => p(32, 0xf4acfb13, 0xffffffff, true, true, 0xffffffff, 0x1697d06a),
+            CrcModel::Crc32Jamcrc => p(32, 0x04c11db7, 0xffffffff, true, true, 0x00000000, 0x340bc6d9),
+            CrcModel::Crc32Aixm => p(32, 0x814141ab, 0x00000000, false, false, 0x00000000, 0x3010bf7f),
+            CrcModel::Crc64Xz => p(64, 0x42f0e1eba9ea3693, u64::MAX, true, true, u64::MAX, 0x995dc9bbdf1939fa),
+            CrcModel::Crc64Ecma182 => p(64, 0x42f0e1eba9ea3693, 0, false, false, 0, 0x6c40df5f0b497347),
+            CrcModel::Crc64GoIso => p(64, 0x000000000000001b, u64::MAX, true, true, u64::MAX, 0xb90956c775a41001),
+            CrcModel::Crc64We => p(64, 0x42f0e1eba9ea3693, u64::MAX, false, false, u64::MAX, 0x62ec59e3f1a4f00a),
+            CrcModel::Crc64Nvme => p(64, 0xad93d23594c93659, u64::MAX, true, true, u64::MAX, 0xae8b14860a799888),
+            CrcModel::Custom => None,
+        }
+    }
+}
+
+impl fmt::Display for CrcModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CrcModel::Crc8Smbus => "CRC-8/SMBUS",
+            CrcModel::Crc8MaximDow => "CRC-8/MAXIM-DOW",
+            CrcModel::Crc8Autosar => "CRC-8/AUTOSAR",
+            CrcModel::Crc16Arc => "CRC-16/ARC",
+            CrcModel::Crc16Ibm3740 => "CRC-16/IBM-3740 (CCITT-FALSE)",
+            CrcModel::Crc16Kermit => "CRC-16/KERMIT (CCITT)",
+            CrcModel::Crc16Xmodem => "CRC-16/XMODEM",
+            CrcModel::Crc16Modbus => "CRC-16/MODBUS",
+            CrcModel::Crc16IbmSdlc => "CRC-16/IBM-SDLC (X-25)",
+            CrcModel::Crc16Usb => "CRC-16/USB",
+            CrcModel::Crc24OpenPgp => "CRC-24/OPENPGP",
+            CrcModel::Crc32IsoHdlc => "CRC-32/ISO-HDLC",
+            CrcModel::Crc32Iscsi => "CRC-32/ISCSI (CRC-32C)",
+            CrcModel::Crc32Bzip2 => "CRC-32/BZIP2",
+            CrcModel::Crc32Mpeg2 => "CRC-32/MPEG-2",
+            CrcModel::Crc32Cksum => "CRC-32/CKSUM",
+            CrcModel::Crc32Autosar => "CRC-32/AUTOSAR",
+            CrcModel::Crc32Jamcrc => "CRC-32/JAMCRC",
+            CrcModel::Crc32Aixm => "CRC-32/AIXM",
+            CrcModel::Crc64Xz => "CRC-64/XZ",
+            CrcModel::Crc64Ecma182 => "CRC-64/ECMA-182",
+            CrcModel::Crc64GoIso => "CRC-64/GO-ISO",
+            CrcModel::Crc64We => "CRC-64/WE",
+            CrcModel::Crc64Nvme => "CRC-64/NVME",
+            CrcModel::Custom => "Custom (from config)",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// User-defined CRC as stored in the config; numeric fields are hex strings
+// ("0x1edc6f41") so they can be copied straight from a CRC catalogue
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CustomCrc {
+    pub width: u8,
+    pub poly: String,
+    pub init: String,
+    pub refin: bool,
+    pub refout: bool,
+    pub xorout: String,
+    pub check: String,
+}
+
+impl Default for CustomCrc {
+    fn default() -> Self {
+        CustomCrc {
+            width: 32,
+            poly: "0x04c11db7".to_string(),
+            init: "0xffffffff".to_string(),
+            refin: true,
+            refout: true,
+            xorout: "0xffffffff".to_string(),
+            check: "0xcbf43926".to_string(),
+        }
+    }
+}
+
+fn parse_crc_value(name: &str, value: &str, width: u8) -> Result<u64, String> {
+    let trimmed = value.trim();
+    let digits = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+    let parsed = u64::from_str_radix(digits, 16)
+        .map_err(|e| format!("invalid custom CRC {}: {}", name, e))?;
+    if parsed & !width_mask(width) != 0 {
+        return Err(format!("custom CRC {} does not fit in {} bits", name, width));
+    }
+    Ok(parsed)
+}
+
+impl CustomCrc {
+    // Parses the parameters and checks them against the declared check value
+    pub fn resolve(&self) -> Result<CrcParams, String> {
+        if self.width == 0 || self.width > 64 {
+            return Err("custom CRC width must be between 1 and 64".to_string());
+        }
+
+        let params = CrcParams {
+            width: self.width,
+            poly: parse_crc_value("poly", &self.poly, self.width)?,
+            init: parse_crc_value("init", &self.init, self.width)?,
+            refin: self.refin,
+            refout: self.refout,
+            xorout: parse_crc_value("xorout", &self.xorout, self.width)?,
+            check: parse_crc_value("check", &self.check, self.width)?,
+        };
+
+        let mut hasher = CrcHasher::new(params);
+        hasher.update(CHECK_INPUT);
+        let computed = hasher.value();
+        if computed != params.check {
+            return Err(format!(
+                "custom CRC check value mismatch (expected 0x{}, computed 0x{})",
+                params.format_value(params.check),
+                params.format_value(computed)
+            ));
+        }
+
+        Ok(params)
+    }
+}
+
+fn width_mask(width: u8) -> u64 {
+    if width >= 64 {
+        u64::MAX
+    } else {
+        (1u64 << width) - 1
+    }
+}
+
+fn reflect(value: u64, width: u8) -> u64 {
+    value.reverse_bits() >> (64 - width as u32)
+}
+
+impl CrcParams {
+    // Zero-padded to whole bytes so the hex string can be reformatted like any other digest
+    pub fn format_value(&self, value: u64) -> String {
+        let digits = (self.width as usize).div_ceil(8) * 2;
+        format!("{:0width$x}", value, width = digits)
+    }
+}
+
+// Table-driven CRC for any width from 1 to 64 bits. Reflected models keep the
+// register reflected; the others keep it left-aligned in the top bits of a u64
+#[derive(Clone)]
+pub struct CrcHasher {
+    params: CrcParams,
+    table: Box<[u64; 256]>,
+    register: u64,
+}
+
+impl CrcHasher {
+    pub fn new(params: CrcParams) -> Self {
+        let mut table = Box::new([0u64; 256]);
+        let shift = 64 - params.width as u32;
+
+        if params.refin {
+            let poly = reflect(params.poly, params.width);
+            for (i, entry) in table.iter_mut().enumerate() {
+                let mut c = i as u64;
+                for _ in 0..8 {
+                    c = if c & 1 != 0 { (c >> 1) ^ poly } else { c >> 1 };
+                }
+                *entry = c;
+            }
+        } else {
+            let poly = params.poly << shift;
+            for (i, entry) in table.iter_mut().enumerate() {
+                let mut c = (i as u64) << 56;
+                for _ in 0..8 {
+                    c = if c & (1 << 63) != 0 { (c << 1) ^ poly } else { c << 1 };
+                }
+                *entry = c;
+            }
+        }
+
+        let register = if params.refin {
+            reflect(params.init, params.width)
+        } else {
+            params.init << shift
+        };
+
+        CrcHasher { params, table, register }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        if self.params.refin {
+            for &byte in data {
+                self.register = self.table[((self.register ^ byte as u64) & 0xff) as usize] ^ (self.register >> 8);
+            }
+        } else {
+            for &byte in data {
+                self.register = self.table[(((self.register >> 56) ^ byte as u64) & 0xff) as usize] ^ (self.register << 8);
+            }
+        }
+    }
+
+    // Parameters and register, which together are the whole running state;
+    // the table follows from the parameters
+    pub fn state(&self) -> (CrcParams, u64) {
+        (self.params, self.register)
+    }
+
+    pub fn set_register(&mut self, register: u64) {
+        self.register = register;
+    }
+
+    pub fn value(&self) -> u64 {
+        let mut crc = if self.params.refin {
+            self.register
+        } else {
+            self.register >> (64 - self.params.width as u32)
+        };
+        if self.params.refin != self.params.refout {
+            crc = reflect(crc, self.params.width);
+        }
+        (crc ^ self.params.xorout) & width_mask(self.params.width)
+    }
+
+    pub fn finalize(self) -> String {
+        self.params.format_value(self.value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catalogue_models_match_their_check_values() {
+        for model in CrcModel::all() {
+            let Some(params) = model.params() else { continue };
+            let mut hasher = CrcHasher::new(params);
+            hasher.update(CHECK_INPUT);
+            assert_eq!(
+                hasher.value(),
+                params.check,
+                "{}: expected 0x{}",
+                model,
+                params.format_value(params.check)
+            );
+        }
+    }
+
+    #[test]
+    fn split_updates_match_a_single_update() {
+        for model in CrcModel::all() {
+            let Some(params) = model.params() else { continue };
+            let mut hasher = CrcHasher::new(params);
+            for chunk in CHECK_INPUT.chunks(2) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.value(), params.check, "{}", model);
+        }
+    }
+
+    fn custom(width: u8, poly: &str, init: &str, refin: bool, xorout: &str, check: &str) -> CustomCrc {
+        CustomCrc {
+            width,
+            poly: poly.to_string(),
+            init: init.to_string(),
+            refin,
+            refout: refin,
+            xorout: xorout.to_string(),
+            check: check.to_string(),
+        }
+    }
+
+    #[test]
+    fn custom_parameters_give_the_catalogue_check_values() {
+        use crate::hash::algo::{Algorithm, HashOptions};
+        use crate::hash::compute::calculate_hash_from_data;
+
+        // CRC-16/ARC written out by hand rather than picked from the list
+        let arc = custom(16, "0x8005", "0x0000", true, "0x0000", "0xbb3d");
+        assert_eq!(arc.resolve(), Ok(CrcModel::Crc16Arc.params().unwrap()));
+        let options = HashOptions { crc_model: CrcModel::Custom, custom_crc: arc, ..HashOptions::default() };
+        assert_eq!(calculate_hash_from_data(CHECK_INPUT, &Algorithm::CRC, &options), "bb3d");
+
+        // Widths that are not whole bytes, and the full 64 bits
+        assert!(custom(5, "05", "1f", true, "1f", "19").resolve().is_ok());
+        assert!(custom(64, "42f0e1eba9ea3693", "ffffffffffffffff", true, "ffffffffffffffff", "995dc9bbdf1939fa")
+            .resolve()
+            .is_ok());
+    }
+
+    #[test]
+    fn custom_widths_outside_1_to_64_are_errors() {
+        for width in [0, 65, 255] {
+            let error = custom(width, "0x1", "0x0", false, "0x0", "0x0").resolve().unwrap_err();
+            assert_eq!(error, "custom CRC width must be between 1 and 64");
+        }
+    }
+
+    #[test]
+    fn custom_values_wider_than_the_crc_are_errors() {
+        let cases = [
+            (custom(16, "0x18005", "0x0000", true, "0x0000", "0xbb3d"), "poly"),
+            (custom(16, "0x8005", "0x10000", true, "0x0000", "0xbb3d"), "init"),
+            (custom(16, "0x8005", "0x0000", true, "0xfffff", "0xbb3d"), "xorout"),
+            (custom(5, "0x25", "0x1f", true, "0x1f", "0x19"), "poly"),
+        ];
+        for (crc, field) in cases {
+            assert_eq!(crc.resolve().unwrap_err(), format!("custom CRC {} does not fit in {} bits", field, crc.width));
+        }
+    }
+
+    #[test]
+    fn malformed_custom_values_are_errors() {
+        for poly in ["", "0x", "0xzz", "80 05", "-8005", "0x0x8005"] {
+            let error = custom(16, poly, "0x0000", true, "0x0000", "0xbb3d").resolve().unwrap_err();
+            assert!(error.starts_with("invalid custom CRC poly"), "{:?}: {}", poly, error);
+        }
+        let error = custom(16, "0x8005", "0x0000", true, "0x0000", "0xbb3e").resolve().unwrap_err();
+        assert_eq!(error, "custom CRC check value mismatch (expected 0xbb3e, computed 0xbb3d)");
+    }
+}
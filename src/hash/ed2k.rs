@@ -0,0 +1,139 @@
+use std::fmt;
+
+use digest::Digest;
+use md4::Md4;
+
+// Size of one eDonkey chunk; each chunk is hashed with MD4 on its own
+const CHUNK_SIZE: u64 = 9_728_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Ed2kVariant {
+    New,
+    Old,
+}
+
+impl Ed2kVariant {
+    pub fn all() -> Vec<Ed2kVariant> {
+        vec![Ed2kVariant::New, Ed2kVariant::Old]
+    }
+}
+
+impl fmt::Display for Ed2kVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Ed2kVariant::New => "New (no extra chunk, eMule 0.50+)",
+            Ed2kVariant::Old => "Old (empty chunk appended, eDonkey)",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// The two conventions only differ for files whose size is an exact multiple
+// of the chunk size: the old one hashes an extra empty chunk at the end
+#[derive(Clone)]
+pub struct Ed2kHasher {
+    variant: Ed2kVariant,
+    chunk: Md4,
+    chunk_len: u64,
+    total_len: u64,
+    chunk_digests: Vec<u8>,
+}
+
+impl Ed2kHasher {
+    pub fn new(variant: Ed2kVariant) -> Self {
+        Ed2kHasher {
+            variant,
+            chunk: Md4::new(),
+            chunk_len: 0,
+            total_len: 0,
+            chunk_digests: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // A full chunk is only closed once more data arrives, so the
+            // last chunk is always still open in finalize()
+            if self.chunk_len == CHUNK_SIZE {
+                let digest = std::mem::replace(&mut self.chunk, Md4::new()).finalize();
+                self.chunk_digests.extend_from_slice(&digest);
+                self.chunk_len = 0;
+            }
+
+            let take = ((CHUNK_SIZE - self.chunk_len) as usize).min(data.len());
+            self.chunk.update(&data[..take]);
+            self.chunk_len += take as u64;
+            self.total_len += take as u64;
+            data = &data[take..];
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        let mut digests = self.chunk_digests;
+        digests.extend_from_slice(&self.chunk.finalize());
+
+        if self.variant == Ed2kVariant::Old
+            && self.total_len > 0
+            && self.total_len.is_multiple_of(CHUNK_SIZE)
+        {
+            digests.extend_from_slice(&Md4::new().finalize());
+        }
+
+        // A single chunk is its own ed2k hash
+        if digests.len() == 16 {
+            digests
+        } else {
+            Md4::digest(&digests).to_vec()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ed2k_of_zeros(variant: Ed2kVariant, length: u64) -> String {
+        let zeros = vec![0u8; 1 << 20];
+        let mut hasher = Ed2kHasher::new(variant);
+        let mut remaining = length;
+        while remaining > 0 {
+            let take = remaining.min(zeros.len() as u64) as usize;
+            hasher.update(&zeros[..take]);
+            remaining -= take as u64;
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    #[test]
+    fn single_chunk_is_its_md4() {
+        for variant in Ed2kVariant::all() {
+            let mut hasher = Ed2kHasher::new(variant);
+            hasher.update(b"abc");
+            assert_eq!(hex::encode(hasher.finalize()), "a448017aaf21d8525fc10ae87aa6729d");
+            assert_eq!(ed2k_of_zeros(variant, 0), "31d6cfe0d16ae931b73c59d7e0c089c0");
+        }
+    }
+
+    #[test]
+    fn exact_multiple_of_the_chunk_size() {
+        assert_eq!(ed2k_of_zeros(Ed2kVariant::New, CHUNK_SIZE), "d7def262a127cd79096a108e7a9fc138");
+        assert_eq!(ed2k_of_zeros(Ed2kVariant::Old, CHUNK_SIZE), "fc21d9af828f92a8df64beac3357425d");
+        assert_eq!(ed2k_of_zeros(Ed2kVariant::New, 2 * CHUNK_SIZE), "194ee9e4fa79b2ee9f8829284c466051");
+        assert_eq!(ed2k_of_zeros(Ed2kVariant::Old, 2 * CHUNK_SIZE), "114b21c63a74b6ca922291a11177dd5c");
+    }
+
+    #[test]
+    fn other_sizes_agree_between_variants() {
+        for variant in Ed2kVariant::all() {
+            assert_eq!(ed2k_of_zeros(variant, CHUNK_SIZE + 1), "06329e9dba1373512c06386fe29e3c65");
+        }
+    }
+
+    #[test]
+    fn label_names_the_convention() {
+        use crate::hash::algo::{Algorithm, HashOptions};
+        let options = |ed2k_variant| HashOptions { ed2k_variant, ..HashOptions::default() };
+        assert_eq!(Algorithm::ED2K.label(&options(Ed2kVariant::New)), "ED2K (new)");
+        assert_eq!(Algorithm::ED2K.label(&options(Ed2kVariant::Old)), "ED2K (old)");
+    }
+}
@@ -0,0 +1,322 @@
+use std::fmt;
+use std::fs::{File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::hash::algo::{Algorithm, HashOptions};
+use crate::hash::compute::AlgorithmHasher;
+use crate::hash::io::BUFFER_SIZE;
+
+// Polling works the same on every platform and for files on network shares,
+// where change notifications are unreliable
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Bytes just before the current position that must still be there on the next
+// poll; a file truncated and refilled past that point would otherwise go unseen
+const TAIL_CHECK: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowEvent {
+    Unchanged,
+    Appended(u64),
+    Truncated, // Shorter than what was hashed, or rewritten in place
+    Rotated,   // The name now points to another file
+}
+
+impl fmt::Display for FollowEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FollowEvent::Unchanged => write!(f, "no new data"),
+            FollowEvent::Appended(bytes) => write!(f, "{} bytes appended", bytes),
+            FollowEvent::Truncated => write!(f, "truncated"),
+            FollowEvent::Rotated => write!(f, "replaced by a new file"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FollowUpdate {
+    pub event: FollowEvent,
+    pub length: u64, // Bytes hashed so far
+    pub restarts: u32,
+    pub last_restart: Option<FollowEvent>,
+    pub results: Option<Vec<(Algorithm, String)>>, // Digests at `length`; None when nothing changed
+}
+
+// Hashes a file that is still being written. The hashers keep their state
+// between polls, so each poll only reads what was appended since the last one
+pub struct Follower {
+    path: PathBuf,
+    algorithms: Vec<Algorithm>,
+    options: HashOptions,
+    hashers: Vec<(Algorithm, Result<AlgorithmHasher, String>)>,
+    position: u64,
+    identity: Option<(u64, u64)>, // Device and inode of the file being followed
+    tail: Vec<u8>,
+    restarts: u32,
+    last_restart: Option<FollowEvent>,
+}
+
+impl Follower {
+    pub fn new(path: &Path, algorithms: &[Algorithm], options: &HashOptions) -> Self {
+        let mut follower = Follower {
+            path: path.to_path_buf(),
+            algorithms: algorithms.to_vec(),
+            options: options.clone(),
+            hashers: Vec::new(),
+            position: 0,
+            identity: None,
+            tail: Vec::new(),
+            restarts: 0,
+            last_restart: None,
+        };
+        follower.reset();
+        follower
+    }
+
+    // Git blob IDs (whose length is not known while the file grows) and the
+    // image hashes would keep the whole file in memory and copy it on every
+    // poll, so they are refused
+    fn reset(&mut self) {
+        self.hashers = self
+            .algorithms
+            .iter()
+            .map(|alg| {
+                let hasher = if alg.git_format().is_some() || alg.is_perceptual() {
+                    Err("not available while following, as it keeps the whole file in memory; hash the file once it is complete".to_string())
+                } else {
+                    AlgorithmHasher::new(alg, &self.options)
+                };
+                (alg.clone(), hasher)
+            })
+            .collect();
+        self.position = 0;
+        self.tail.clear();
+    }
+
+    // Reads whatever was appended since the last poll, after checking that
+    // the data already hashed is still the start of the file
+    pub fn poll(&mut self) -> Result<FollowUpdate, String> {
+        let path = self.path.clone();
+        let error = |e: std::io::Error| format!("{}: {}", path.display(), e);
+        let mut file = File::open(&self.path).map_err(error)?;
+        let metadata = file.metadata().map_err(error)?;
+        let identity = file_identity(&metadata);
+
+        let restart = if self.position == 0 && self.identity.is_none() {
+            None
+        } else if identity != self.identity {
+            Some(FollowEvent::Rotated)
+        } else if metadata.len() < self.position || !self.tail_unchanged(&mut file).map_err(error)? {
+            Some(FollowEvent::Truncated)
+        } else {
+            None
+        };
+        if restart.is_some() {
+            self.reset();
+            self.restarts += 1;
+            self.last_restart = restart;
+        }
+        self.identity = identity;
+
+        let appended = self.read_appended(&mut file).map_err(error)?;
+        let event = match restart {
+            Some(event) => event,
+            None if appended > 0 => FollowEvent::Appended(appended),
+            None => FollowEvent::Unchanged,
+        };
+        Ok(FollowUpdate {
+            event,
+            length: self.position,
+            restarts: self.restarts,
+            last_restart: self.last_restart,
+            results: (event != FollowEvent::Unchanged).then(|| self.digests()),
+        })
+    }
+
+    fn tail_unchanged(&self, file: &mut File) -> std::io::Result<bool> {
+        let mut tail = vec![0u8; self.tail.len()];
+        file.seek(SeekFrom::Start(self.position - self.tail.len() as u64))?;
+        file.read_exact(&mut tail)?;
+        Ok(tail == self.tail)
+    }
+
+    fn read_appended(&mut self, file: &mut File) -> std::io::Result<u64> {
+        file.seek(SeekFrom::Start(self.position))?;
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+        let mut appended = 0u64;
+        loop {
+            let n = match file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let chunk = &buffer[..n];
+            for (_, hasher) in self.hashers.iter_mut() {
+                if let Ok(h) = hasher {
+                    h.update(chunk);
+                }
+            }
+            self.tail.extend_from_slice(chunk);
+            if self.tail.len() > TAIL_CHECK {
+                self.tail.drain(..self.tail.len() - TAIL_CHECK);
+            }
+            appended += n as u64;
+            self.position += n as u64;
+        }
+        Ok(appended)
+    }
+
+    // Finalizes copies of the hashers, which carry on with the next poll
+    fn digests(&self) -> Vec<(Algorithm, String)> {
+        self.hashers
+            .iter()
+            .map(|(alg, hasher)| {
+                let result = match hasher {
+                    Ok(h) => match h.snapshot() {
+                        Some(copy) => copy.finalize(),
+                        None => "Error: no running digest; hash the file once it is complete".to_string(),
+                    },
+                    Err(e) => format!("Error: {}", e),
+                };
+                (alg.clone(), result)
+            })
+            .collect()
+    }
+}
+
+#[cfg(unix)]
+fn file_identity(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+// Without inode numbers a rotation is still noticed when the new file is
+// shorter or starts differently
+#[cfg(not(unix))]
+fn file_identity(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::compute::calculate_hash_from_data;
+    use crate::test_support::ScratchDir;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    const ALGORITHMS: [Algorithm; 3] = [Algorithm::MD5, Algorithm::SHA256, Algorithm::CRC32];
+
+    fn one_shot(data: &[u8]) -> Vec<(Algorithm, String)> {
+        let options = HashOptions::default();
+        ALGORITHMS.iter().map(|alg| (alg.clone(), calculate_hash_from_data(data, alg, &options))).collect()
+    }
+
+    fn append(path: &Path, data: &[u8]) {
+        OpenOptions::new().append(true).open(path).unwrap().write_all(data).unwrap();
+    }
+
+    // Lines long enough that the tail check covers only part of the data
+    fn lines(from: usize, to: usize) -> Vec<u8> {
+        (from..to).flat_map(|n| format!("log line {:04}\n", n).into_bytes()).collect()
+    }
+
+    #[test]
+    fn growing_file_matches_a_one_shot_hash_at_each_length() {
+        let dir = ScratchDir::new("follow-grow");
+        let mut contents = lines(0, 10);
+        let path = dir.write("app.log", &contents);
+        let mut follower = Follower::new(&path, &ALGORITHMS, &HashOptions::default());
+
+        let update = follower.poll().unwrap();
+        assert_eq!(update.event, FollowEvent::Appended(contents.len() as u64));
+        assert_eq!(update.length, contents.len() as u64);
+        assert_eq!(update.results, Some(one_shot(&contents)));
+
+        for (from, to) in [(10, 11), (11, 500)] {
+            let more = lines(from, to);
+            append(&path, &more);
+            contents.extend_from_slice(&more);
+            let update = follower.poll().unwrap();
+            assert_eq!(update.event, FollowEvent::Appended(more.len() as u64));
+            assert_eq!(update.length, contents.len() as u64);
+            assert_eq!(update.results, Some(one_shot(&contents)));
+        }
+
+        let update = follower.poll().unwrap();
+        assert_eq!(update.event, FollowEvent::Unchanged);
+        assert_eq!(update.length, contents.len() as u64);
+        assert!(update.results.is_none());
+        assert_eq!((update.restarts, update.last_restart), (0, None));
+    }
+
+    #[test]
+    fn truncation_and_rewrites_restart_from_the_beginning() {
+        let dir = ScratchDir::new("follow-truncate");
+        let path = dir.write("app.log", &lines(0, 100));
+        let mut follower = Follower::new(&path, &ALGORITHMS, &HashOptions::default());
+        follower.poll().unwrap();
+
+        // Shorter than what was hashed
+        let contents = lines(0, 20);
+        OpenOptions::new().write(true).open(&path).unwrap().set_len(contents.len() as u64).unwrap();
+        let update = follower.poll().unwrap();
+        assert_eq!(update.event, FollowEvent::Truncated);
+        assert_eq!(update.length, contents.len() as u64);
+        assert_eq!((update.restarts, update.last_restart), (1, Some(FollowEvent::Truncated)));
+        assert_eq!(update.results, Some(one_shot(&contents)));
+
+        // Same length, but the last 64 bytes rewritten in place
+        let mut contents = contents;
+        let end = contents.len();
+        contents[end - 64..].fill(b'x');
+        let mut file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.seek(SeekFrom::Start(end as u64 - 64)).unwrap();
+        file.write_all(&contents[end - 64..]).unwrap();
+        drop(file);
+        let update = follower.poll().unwrap();
+        assert_eq!(update.event, FollowEvent::Truncated);
+        assert_eq!((update.restarts, update.last_restart), (2, Some(FollowEvent::Truncated)));
+        assert_eq!(update.results, Some(one_shot(&contents)));
+
+        // Growing again is an ordinary append, and the restarts are kept
+        append(&path, b"after\n");
+        contents.extend_from_slice(b"after\n");
+        let update = follower.poll().unwrap();
+        assert_eq!(update.event, FollowEvent::Appended(6));
+        assert_eq!((update.restarts, update.last_restart), (2, Some(FollowEvent::Truncated)));
+        assert_eq!(update.results, Some(one_shot(&contents)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_replaced_file_is_a_rotation() {
+        let dir = ScratchDir::new("follow-rotate");
+        let path = dir.write("app.log", &lines(0, 100));
+        let mut follower = Follower::new(&path, &ALGORITHMS, &HashOptions::default());
+        follower.poll().unwrap();
+
+        // Longer and starting the same way, so only the file identity tells
+        let contents = lines(0, 200);
+        let fresh = dir.write("app.log.new", &contents);
+        std::fs::rename(&fresh, &path).unwrap();
+        let update = follower.poll().unwrap();
+        assert_eq!(update.event, FollowEvent::Rotated);
+        assert_eq!(update.length, contents.len() as u64);
+        assert_eq!((update.restarts, update.last_restart), (1, Some(FollowEvent::Rotated)));
+        assert_eq!(update.results, Some(one_shot(&contents)));
+    }
+
+    #[test]
+    fn whole_file_algorithms_are_refused() {
+        let dir = ScratchDir::new("follow-refused");
+        let path = dir.write("app.log", b"abc");
+        let mut follower = Follower::new(&path, &[Algorithm::GITSHA1, Algorithm::MD5], &HashOptions::default());
+        let results = follower.poll().unwrap().results.unwrap();
+        assert!(results[0].1.starts_with("Error: not available while following"));
+        assert_eq!(results[1].1, "900150983cd24fb0d6963f7d28e17f72");
+    }
+}
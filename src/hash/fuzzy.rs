@@ -0,0 +1,257 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use fuzzyhash::FuzzyHash;
+
+use crate::hash::algo::{Algorithm, HashOptions};
+use crate::hash::compute::calculate_hashes_parallel_streaming;
+use crate::hash::normalize::Normalization;
+
+// fuzzyhash keeps the input length in 32 bits
+const SSDEEP_MAX_INPUT: u64 = u32::MAX as u64;
+
+// ssdeep (context-triggered piecewise hashing), e.g. "96:U57GjXnLt9co6pZwvLhJluvrs:Hj3BeoEcNJ0Ts"
+pub struct SsdeepHasher {
+    inner: FuzzyHash,
+    length: u64,
+}
+
+impl SsdeepHasher {
+    pub fn new() -> Self {
+        Self { inner: FuzzyHash::default(), length: 0 }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        if self.length <= SSDEEP_MAX_INPUT {
+            self.inner.update(data);
+        }
+    }
+
+    pub fn finalize(mut self) -> String {
+        if self.length > SSDEEP_MAX_INPUT {
+            return "Error: ssdeep is limited to 4 GiB of input".to_string();
+        }
+        self.inner.finalize();
+        self.inner.to_string()
+    }
+}
+
+// TLSH with the standard 128 buckets and 1-byte checksum, written with the
+// "T1" version prefix like `tlsh -f`
+#[derive(Clone)]
+pub struct TlshHasher {
+    builder: tlsh2::TlshDefaultBuilder,
+}
+
+impl TlshHasher {
+    pub fn new() -> Self {
+        Self { builder: tlsh2::TlshDefaultBuilder::new() }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.builder.update(data);
+    }
+
+    pub fn finalize(self) -> String {
+        match self.builder.build() {
+            Some(tlsh) => String::from_utf8_lossy(&tlsh.hash()).into_owned(),
+            None => "Error: TLSH needs at least 50 bytes with some variety".to_string(),
+        }
+    }
+}
+
+// ssdeep and TLSH of one side of a comparison; None when that side is a
+// pasted hash of the other kind. Failed hashes hold their "Error: ..." text
+#[derive(Debug, Clone, Default)]
+pub struct FuzzyDigests {
+    pub ssdeep: Option<String>,
+    pub tlsh: Option<String>,
+}
+
+impl FuzzyDigests {
+    pub fn of_file(path: &PathBuf, options: &HashOptions) -> FuzzyDigests {
+        let results = calculate_hashes_parallel_streaming(
+            path,
+            &[Algorithm::SSDEEP, Algorithm::TLSH],
+            options,
+            None,
+            Normalization::default(),
+            false,
+        );
+        let mut digests = FuzzyDigests::default();
+        for (algorithm, digest) in results {
+            match algorithm {
+                Algorithm::SSDEEP => digests.ssdeep = Some(digest),
+                Algorithm::TLSH => digests.tlsh = Some(digest),
+                _ => {}
+            }
+        }
+        digests
+    }
+
+    // A pasted hash is told apart by its shape: ssdeep hashes have two colons,
+    // TLSH hashes are 70 hex digits, usually after a "T1" prefix
+    pub fn parse(text: &str) -> Result<FuzzyDigests, String> {
+        let text = text.trim();
+        if text.matches(':').count() == 2 {
+            return Ok(FuzzyDigests { ssdeep: Some(text.to_string()), tlsh: None });
+        }
+
+        let upper = text.to_uppercase();
+        let tlsh = if upper.len() == 70 { format!("T1{}", upper) } else { upper };
+        if tlsh2::TlshDefault::from_str(&tlsh).is_ok() {
+            return Ok(FuzzyDigests { ssdeep: None, tlsh: Some(tlsh) });
+        }
+
+        Err("not an ssdeep or TLSH hash".to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimilarityReport {
+    pub first: FuzzyDigests,
+    pub second: FuzzyDigests,
+    pub ssdeep_score: Option<Result<u32, String>>, // 0 to 100, None unless both sides have an ssdeep hash
+    pub tlsh_distance: Option<Result<i32, String>>, // 0 for identical, grows without bound
+}
+
+impl SimilarityReport {
+    pub fn compare(first: FuzzyDigests, second: FuzzyDigests) -> SimilarityReport {
+        let ssdeep_score = match (&first.ssdeep, &second.ssdeep) {
+            (Some(a), Some(b)) => Some(ssdeep_score(a, b)),
+            _ => None,
+        };
+        let tlsh_distance = match (&first.tlsh, &second.tlsh) {
+            (Some(a), Some(b)) => Some(tlsh_distance(a, b)),
+            _ => None,
+        };
+        SimilarityReport { first, second, ssdeep_score, tlsh_distance }
+    }
+
+    // TLSH distances on the same 0 to 100 scale as ssdeep: distance 0 is 100,
+    // and anything at or beyond 100 (clearly unrelated) is 0
+    pub fn tlsh_score(distance: i32) -> u32 {
+        (100 - distance.clamp(0, 100)) as u32
+    }
+}
+
+fn failed(digest: &str) -> Option<String> {
+    digest.strip_prefix("Error: ").map(|e| e.to_string())
+}
+
+fn ssdeep_score(first: &str, second: &str) -> Result<u32, String> {
+    if let Some(e) = failed(first).or_else(|| failed(second)) {
+        return Err(e);
+    }
+    match FuzzyHash::compare(first, second) {
+        Ok(score) => Ok(score),
+        // Hashes taken at block sizes too far apart, or without a common
+        // substring, are simply not similar; ssdeep reports 0 for both
+        Err(fuzzyhash::error::Error::IncompatibleBlockSizes) | Err(fuzzyhash::error::Error::NoCommonSubstrings) => Ok(0),
+        Err(_) => Err("malformed ssdeep hash".to_string()),
+    }
+}
+
+fn tlsh_distance(first: &str, second: &str) -> Result<i32, String> {
+    if let Some(e) = failed(first).or_else(|| failed(second)) {
+        return Err(e);
+    }
+    let parse = |digest: &str| tlsh2::TlshDefault::from_str(digest).map_err(|_| format!("malformed TLSH hash \"{}\"", digest));
+    // The length difference is part of the score, as in `tlsh -c`
+    Ok(parse(first)?.diff(&parse(second)?, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // small.txt from the TLSH test data, with the hash `tlsh -r` gives for it
+    // in the reference implementation's expected output
+    const SMALL: &[u8] = b"bfghjdbkfdj376t4en, i7e2w 8o\tq22we ,9wq12q ,32qTE$#!#$%^I&*\n";
+    const SMALL_TLSH: &str = "T1F8A0220C0F8C0023CB880800CA33E88B8F0C022AB302C2008A030300300E8A00C83AAC";
+    // small2.txt, 221 away from small.txt by `tlsh -xref`
+    const SMALL2_TLSH: &str = "T1C6A022A2E0008CC320C083A3E20AA888022A00000A0AB0088828022A0008A00022F22A";
+
+    fn tlsh(data: &[u8]) -> String {
+        let mut hasher = TlshHasher::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    fn ssdeep(data: &[u8]) -> String {
+        let mut hasher = SsdeepHasher::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    #[test]
+    fn tlsh_known_answer() {
+        assert_eq!(tlsh(SMALL), SMALL_TLSH);
+        let report = SimilarityReport::compare(
+            FuzzyDigests::parse(SMALL_TLSH).unwrap(),
+            FuzzyDigests::parse(SMALL2_TLSH).unwrap(),
+        );
+        assert_eq!(report.tlsh_distance, Some(Ok(221)));
+        assert!(report.ssdeep_score.is_none());
+    }
+
+    // Worked out from ssdeep's spamsum algorithm; block size 96 with a second
+    // part at 192
+    #[test]
+    fn ssdeep_known_answer() {
+        let verses: String = (1..=100).rev().map(|n| format!("{} bottles of beer on the wall\n", n)).collect();
+        assert_eq!(
+            ssdeep(verses.as_bytes()),
+            "96:un+x/IzqtUvG8V6DYxW/k91cjaR4f2dE62a8VO3QJybby9k/WxYD6gZmP0wIKToP:9Bl5O9"
+        );
+    }
+
+    #[test]
+    fn tlsh_needs_enough_input() {
+        assert_eq!(tlsh(b"too short"), "Error: TLSH needs at least 50 bytes with some variety");
+        assert_eq!(tlsh(&[b'a'; 200]), "Error: TLSH needs at least 50 bytes with some variety");
+    }
+
+    #[test]
+    fn pasted_hashes_are_told_apart_by_shape() {
+        let ssdeep = FuzzyDigests::parse(" 3:YKKGhR0tn:YRGRmn\n").unwrap();
+        assert_eq!(ssdeep.ssdeep.as_deref(), Some("3:YKKGhR0tn:YRGRmn"));
+        assert!(ssdeep.tlsh.is_none());
+
+        let prefixed = FuzzyDigests::parse(SMALL_TLSH).unwrap();
+        assert_eq!(prefixed.tlsh.as_deref(), Some(SMALL_TLSH));
+        assert!(prefixed.ssdeep.is_none());
+        // Without the version prefix, and in lowercase
+        let bare = FuzzyDigests::parse(&SMALL_TLSH[2..].to_lowercase()).unwrap();
+        assert_eq!(bare.tlsh.as_deref(), Some(SMALL_TLSH));
+
+        assert!(FuzzyDigests::parse("").is_err());
+        assert!(FuzzyDigests::parse("d41d8cd98f00b204e9800998ecf8427e").is_err());
+        assert!(FuzzyDigests::parse(&SMALL_TLSH[..68]).is_err());
+        assert!(FuzzyDigests::parse(&SMALL_TLSH.replace('F', "G")).is_err());
+    }
+
+    #[test]
+    fn tlsh_scores_are_clamped() {
+        assert_eq!(SimilarityReport::tlsh_score(0), 100);
+        assert_eq!(SimilarityReport::tlsh_score(30), 70);
+        assert_eq!(SimilarityReport::tlsh_score(100), 0);
+        assert_eq!(SimilarityReport::tlsh_score(221), 0);
+        assert_eq!(SimilarityReport::tlsh_score(-1), 100);
+    }
+
+    #[test]
+    fn unrelated_ssdeep_hashes_score_zero() {
+        // Block sizes 3 and 48 are too far apart to compare
+        let (first, second) = ("3:YKKGhR0tn:YRGRmn", "48:abcdefghijklmnop:qrstuvwx");
+        assert!(matches!(FuzzyHash::compare(first, second), Err(fuzzyhash::error::Error::IncompatibleBlockSizes)));
+        assert_eq!(ssdeep_score(first, second), Ok(0));
+        // Neighbouring block sizes, no 7-character substring in common
+        let (first, second) = ("3:abcdefghijklmnop:abcdefgh", "6:ponmlkjihgfedcba:hgfedcba");
+        assert!(matches!(FuzzyHash::compare(first, second), Err(fuzzyhash::error::Error::NoCommonSubstrings)));
+        assert_eq!(ssdeep_score(first, second), Ok(0));
+        assert_eq!(ssdeep_score("3:YKKGhR0tn:YRGRmn", "3:YKKGhR0tn:YRGRmn"), Ok(100));
+        assert!(ssdeep_score("Error: ssdeep is limited to 4 GiB of input", "3:YKKGhR0tn:YRGRmn").is_err());
+    }
+}
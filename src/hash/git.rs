@@ -0,0 +1,355 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use digest::Digest;
+
+use crate::hash::algo::Algorithm;
+use crate::hash::block::BlockHasher;
+use crate::hash::io::read_chunks;
+
+// Hash function behind the object IDs of a repository; SHA-256 repositories
+// are created with `git init --object-format=sha256`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitObjectFormat {
+    Sha1,
+    Sha256,
+}
+
+#[derive(Clone)]
+enum ObjectDigest {
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+    Block(BlockHasher), // Either of them, in a form a checkpoint can save
+}
+
+impl ObjectDigest {
+    // Starts an object of the given type, e.g. "blob 12\0" for a 12-byte file
+    fn start(format: GitObjectFormat, kind: &str, length: u64) -> Self {
+        let mut digest = match format {
+            GitObjectFormat::Sha1 => ObjectDigest::Sha1(sha1::Sha1::new()),
+            GitObjectFormat::Sha256 => ObjectDigest::Sha256(sha2::Sha256::new()),
+        };
+        digest.update(format!("{} {}\0", kind, length).as_bytes());
+        digest
+    }
+
+    fn start_block(format: GitObjectFormat, kind: &str, length: u64) -> Option<Self> {
+        let algorithm = match format {
+            GitObjectFormat::Sha1 => Algorithm::SHA1,
+            GitObjectFormat::Sha256 => Algorithm::SHA256,
+        };
+        let mut digest = ObjectDigest::Block(BlockHasher::new(&algorithm)?);
+        digest.update(format!("{} {}\0", kind, length).as_bytes());
+        Some(digest)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            ObjectDigest::Sha1(h) => h.update(data),
+            ObjectDigest::Sha256(h) => h.update(data),
+            ObjectDigest::Block(h) => h.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            ObjectDigest::Sha1(h) => h.finalize().to_vec(),
+            ObjectDigest::Sha256(h) => h.finalize().to_vec(),
+            ObjectDigest::Block(h) => h.finalize(),
+        }
+    }
+}
+
+// `git hash-object` for file contents. The header carries the length, so
+// when it is not known up front (normalized text, for instance) the data is
+// kept in memory until the end
+#[derive(Clone)]
+pub struct GitBlobHasher {
+    format: GitObjectFormat,
+    digest: Option<ObjectDigest>,
+    buffer: Vec<u8>,
+}
+
+impl GitBlobHasher {
+    pub fn new(format: GitObjectFormat, length: Option<u64>) -> Self {
+        Self {
+            format,
+            digest: length.map(|length| ObjectDigest::start(format, "blob", length)),
+            buffer: Vec::new(),
+        }
+    }
+
+    // Same ID for a file of known length, from a hasher whose state can be
+    // checkpointed
+    pub fn resumable(format: GitObjectFormat, length: u64) -> Self {
+        Self {
+            format,
+            digest: ObjectDigest::start_block(format, "blob", length),
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn save(&self) -> Option<BlockHasher> {
+        match &self.digest {
+            Some(ObjectDigest::Block(h)) => Some(h.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn restore(&mut self, saved: &BlockHasher) -> bool {
+        match &mut self.digest {
+            Some(ObjectDigest::Block(h)) if h.algorithm() == saved.algorithm() => {
+                *h = saved.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.digest {
+            Some(digest) => digest.update(data),
+            None => self.buffer.extend_from_slice(data),
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self.digest {
+            Some(digest) => digest.finalize(),
+            None => {
+                let mut digest = ObjectDigest::start(self.format, "blob", self.buffer.len() as u64);
+                digest.update(&self.buffer);
+                digest.finalize()
+            }
+        }
+    }
+}
+
+struct TreeEntry {
+    mode: &'static str,
+    name: Vec<u8>,
+    id: Vec<u8>,
+}
+
+impl TreeEntry {
+    fn is_tree(&self) -> bool {
+        self.mode == "40000"
+    }
+}
+
+// Git orders tree entries by name as if directory names ended in '/'
+fn compare_entries(a: &TreeEntry, b: &TreeEntry) -> Ordering {
+    let key = |entry: &TreeEntry| {
+        let mut key = entry.name.clone();
+        if entry.is_tree() {
+            key.push(b'/');
+        }
+        key
+    };
+    key(a).cmp(&key(b))
+}
+
+#[cfg(unix)]
+fn name_bytes(name: &std::ffi::OsStr) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    name.as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn name_bytes(name: &std::ffi::OsStr) -> Vec<u8> {
+    name.to_string_lossy().into_owned().into_bytes()
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o100 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+// The length goes into the header first, so a file that changes size while
+// it is read is an error rather than a wrong ID
+fn blob_id(path: &PathBuf, length: u64, format: GitObjectFormat) -> Result<Vec<u8>, String> {
+    let mut hasher = GitBlobHasher::new(format, Some(length));
+    let mut read = 0u64;
+    read_chunks(path, |chunk| {
+        read += chunk.len() as u64;
+        hasher.update(chunk);
+    })
+    .map_err(|e| format!("{}: {}", path.display(), e.trim_start_matches("Error: ")))?;
+    if read != length {
+        return Err(format!(
+            "{}: changed while it was hashed ({} bytes expected, {} read)",
+            path.display(),
+            length,
+            read
+        ));
+    }
+    Ok(hasher.finalize())
+}
+
+// ID of the tree object git would write for this directory (what
+// `git rev-parse HEAD^{tree}` prints for a clean checkout). Everything on disk
+// is included except `.git`, so ignored and untracked files must be removed
+// first for the IDs to match
+pub fn git_tree_id(dir: &Path, format: GitObjectFormat) -> Result<Vec<u8>, String> {
+    match tree_id(dir, format)? {
+        Some(id) => Ok(id),
+        None => {
+            let digest = ObjectDigest::start(format, "tree", 0);
+            Ok(digest.finalize())
+        }
+    }
+}
+
+// None for a directory with nothing git could store; such directories are
+// left out of their parent
+fn tree_id(dir: &Path, format: GitObjectFormat) -> Result<Option<Vec<u8>>, String> {
+    let read_error = |e: std::io::Error| format!("{}: {}", dir.display(), e);
+    let mut entries = Vec::new();
+
+    for item in fs::read_dir(dir).map_err(read_error)? {
+        let item = item.map_err(read_error)?;
+        let name = item.file_name();
+        if name == ".git" {
+            continue;
+        }
+        let path = item.path();
+        let metadata = fs::symlink_metadata(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let entry = if metadata.file_type().is_symlink() {
+            // A symlink is stored as a blob holding its target
+            let target = fs::read_link(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let target = name_bytes(target.as_os_str());
+            let mut hasher = GitBlobHasher::new(format, Some(target.len() as u64));
+            hasher.update(&target);
+            TreeEntry { mode: "120000", name: name_bytes(&name), id: hasher.finalize() }
+        } else if metadata.is_dir() {
+            if path.join(".git").exists() {
+                return Err(format!(
+                    "{}: nested repositories and submodules are not supported",
+                    path.display()
+                ));
+            }
+            match tree_id(&path, format)? {
+                Some(id) => TreeEntry { mode: "40000", name: name_bytes(&name), id },
+                None => continue,
+            }
+        } else if metadata.is_file() {
+            let mode = if is_executable(&metadata) { "100755" } else { "100644" };
+            TreeEntry { mode, name: name_bytes(&name), id: blob_id(&path, metadata.len(), format)? }
+        } else {
+            // Reading a FIFO would block, and git refuses these as well
+            return Err(format!(
+                "{}: only regular files, symbolic links and directories can be stored in git",
+                path.display()
+            ));
+        };
+        entries.push(entry);
+    }
+
+    if entries.is_empty() {
+        return Ok(None);
+    }
+    entries.sort_by(compare_entries);
+
+    // "<mode> <name>\0<binary id>" for each entry
+    let mut content = Vec::new();
+    for entry in &entries {
+        content.extend_from_slice(entry.mode.as_bytes());
+        content.push(b' ');
+        content.extend_from_slice(&entry.name);
+        content.push(0);
+        content.extend_from_slice(&entry.id);
+    }
+
+    let mut digest = ObjectDigest::start(format, "tree", content.len() as u64);
+    digest.update(&content);
+    Ok(Some(digest.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+
+    fn blob(format: GitObjectFormat, data: &[u8], length: Option<u64>) -> String {
+        let mut hasher = GitBlobHasher::new(format, length);
+        data.chunks(4).for_each(|chunk| hasher.update(chunk));
+        hex::encode(hasher.finalize())
+    }
+
+    // `git hash-object`
+    #[test]
+    fn blob_ids() {
+        for length in [Some(6), None] {
+            assert_eq!(blob(GitObjectFormat::Sha1, b"hello\n", length), "ce013625030ba8dba906f756967f9e9ca394464a");
+            assert_eq!(
+                blob(GitObjectFormat::Sha256, b"hello\n", length),
+                "2cf8d83d9ee29543b34a87727421fdecb7e3f3a183d337639025de576db9ebb4"
+            );
+        }
+        assert_eq!(blob(GitObjectFormat::Sha1, b"", Some(0)), "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391");
+    }
+
+    // `git add -A && git write-tree` (the same as `git mktree` over the
+    // listed entries) for this directory, in both object formats
+    #[cfg(unix)]
+    #[test]
+    fn tree_ids_match_git() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = ScratchDir::new("git-tree");
+        let root = dir.path();
+        fs::write(root.join("hello.txt"), b"hello\n").unwrap();
+        fs::write(root.join("run.sh"), b"#!/bin/sh\n").unwrap();
+        fs::set_permissions(root.join("run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        // "sub.txt" sorts before the directory "sub", which git compares as "sub/"
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("sub/a"), b"a\n").unwrap();
+        fs::write(root.join("sub.txt"), b"x").unwrap();
+        std::os::unix::fs::symlink("hello.txt", root.join("link")).unwrap();
+        // Left out, as git has nothing to store for it
+        fs::create_dir(root.join("empty")).unwrap();
+
+        assert_eq!(
+            hex::encode(git_tree_id(root, GitObjectFormat::Sha1).unwrap()),
+            "388499db7de6e5646c77bda8790cbaac51b74913"
+        );
+        assert_eq!(
+            hex::encode(git_tree_id(root, GitObjectFormat::Sha256).unwrap()),
+            "34c5c9be864996ead7a7c684e04c32212b8f206d8160b8a0d0745f288de6cdd1"
+        );
+    }
+
+    #[test]
+    fn empty_directory_is_the_empty_tree() {
+        let dir = ScratchDir::new("git-empty-tree");
+        assert_eq!(
+            hex::encode(git_tree_id(dir.path(), GitObjectFormat::Sha1).unwrap()),
+            "4b825dc642cb6eb9a060e54bf8d69288fbee4904"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn special_files_are_errors() {
+        let dir = ScratchDir::new("git-socket");
+        let _socket = std::os::unix::net::UnixListener::bind(dir.join("socket")).unwrap();
+        assert!(git_tree_id(dir.path(), GitObjectFormat::Sha1).is_err());
+    }
+
+    #[test]
+    fn blob_length_must_match_what_is_read() {
+        let dir = ScratchDir::new("git-blob-length");
+        let path = dir.join("file");
+        fs::write(&path, b"hello\n").unwrap();
+        assert!(blob_id(&path, 6, GitObjectFormat::Sha1).is_ok());
+        assert!(blob_id(&path, 7, GitObjectFormat::Sha1).is_err());
+    }
+}
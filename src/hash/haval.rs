@@ -0,0 +1,410 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum HavalVariant {
+    Haval128_3,
+    Haval160_3,
+    Haval192_3,
+    Haval224_3,
+    Haval256_3,
+    Haval128_4,
+    Haval160_4,
+    Haval192_4,
+    Haval224_4,
+    Haval256_4,
+    Haval128_5,
+    Haval160_5,
+    Haval192_5,
+    Haval224_5,
+    Haval256_5,
+}
+
+impl HavalVariant {
+    pub fn all() -> Vec<HavalVariant> {
+        vec![
+            HavalVariant::Haval128_3,
+            HavalVariant::Haval160_3,
+            HavalVariant::Haval192_3,
+            HavalVariant::Haval224_3,
+            HavalVariant::Haval256_3,
+            HavalVariant::Haval128_4,
+            HavalVariant::Haval160_4,
+            HavalVariant::Haval192_4,
+            HavalVariant::Haval224_4,
+            HavalVariant::Haval256_4,
+            HavalVariant::Haval128_5,
+            HavalVariant::Haval160_5,
+            HavalVariant::Haval192_5,
+            HavalVariant::Haval224_5,
+            HavalVariant::Haval256_5,
+        ]
+    }
+
+    // (passes, digest length in bits)
+    fn parameters(&self) -> (usize, u32) {
+        match self {
+            HavalVariant::Haval128_3 => (3, 128),
+            HavalVariant::Haval160_3 => (3, 160),
+            HavalVariant::Haval192_3 => (3, 192),
+            HavalVariant::Haval224_3 => (3, 224),
+            HavalVariant::Haval256_3 => (3, 256),
+            HavalVariant::Haval128_4 => (4, 128),
+            HavalVariant::Haval160_4 => (4, 160),
+            HavalVariant::Haval192_4 => (4, 192),
+            HavalVariant::Haval224_4 => (4, 224),
+            HavalVariant::Haval256_4 => (4, 256),
+            HavalVariant::Haval128_5 => (5, 128),
+            HavalVariant::Haval160_5 => (5, 160),
+            HavalVariant::Haval192_5 => (5, 192),
+            HavalVariant::Haval224_5 => (5, 224),
+            HavalVariant::Haval256_5 => (5, 256),
+        }
+    }
+}
+
+impl fmt::Display for HavalVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HavalVariant::Haval128_3 => "HAVAL-128/3",
+            HavalVariant::Haval160_3 => "HAVAL-160/3",
+            HavalVariant::Haval192_3 => "HAVAL-192/3",
+            HavalVariant::Haval224_3 => "HAVAL-224/3",
+            HavalVariant::Haval256_3 => "HAVAL-256/3",
+            HavalVariant::Haval128_4 => "HAVAL-128/4",
+            HavalVariant::Haval160_4 => "HAVAL-160/4",
+            HavalVariant::Haval192_4 => "HAVAL-192/4",
+            HavalVariant::Haval224_4 => "HAVAL-224/4",
+            HavalVariant::Haval256_4 => "HAVAL-256/4",
+            HavalVariant::Haval128_5 => "HAVAL-128/5",
+            HavalVariant::Haval160_5 => "HAVAL-160/5",
+            HavalVariant::Haval192_5 => "HAVAL-192/5",
+            HavalVariant::Haval224_5 => "HAVAL-224/5",
+            HavalVariant::Haval256_5 => "HAVAL-256/5",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// HAVAL (Zheng, Pieprzyk, Seberry, 1992), version 1 as used by mhash, PHP and
+// most old manifests. Digests are written as little-endian words.
+
+const VERSION: u8 = 1;
+const BLOCK_SIZE: usize = 128;
+
+const IV: [u32; 8] = [
+    0x243f6a88, 0x85a308d3, 0x13198a2e, 0x03707344, 0xa4093822, 0x299f31d0, 0x082efa98, 0xec4e6c89,
+];
+
+// Round constants for passes 2-5, taken from the fraction part of pi after the IV
+const K: [[u32; 32]; 4] = [
+    [
+        0x452821e6, 0x38d01377, 0xbe5466cf, 0x34e90c6c, 0xc0ac29b7, 0xc97c50dd, 0x3f84d5b5, 0xb5470917,
+        0x9216d5d9, 0x8979fb1b, 0xd1310ba6, 0x98dfb5ac, 0x2ffd72db, 0xd01adfb7, 0xb8e1afed, 0x6a267e96,
+        0xba7c9045, 0xf12c7f99, 0x24a19947, 0xb3916cf7, 0x0801f2e2, 0x858efc16, 0x636920d8, 0x71574e69,
+        0xa458fea3, 0xf4933d7e, 0x0d95748f, 0x728eb658, 0x718bcd58, 0x82154aee, 0x7b54a41d, 0xc25a59b5,
+    ],
+    [
+        0x9c30d539, 0x2af26013, 0xc5d1b023, 0x286085f0, 0xca417918, 0xb8db38ef, 0x8e79dcb0, 0x603a180e,
+        0x6c9e0e8b, 0xb01e8a3e, 0xd71577c1, 0xbd314b27, 0x78af2fda, 0x55605c60, 0xe65525f3, 0xaa55ab94,
+        0x57489862, 0x63e81440, 0x55ca396a, 0x2aab10b6, 0xb4cc5c34, 0x1141e8ce, 0xa15486af, 0x7c72e993,
+        0xb3ee1411, 0x636fbc2a, 0x2ba9c55d, 0x741831f6, 0xce5c3e16, 0x9b87931e, 0xafd6ba33, 0x6c24cf5c,
+    ],
+    [
+        0x7a325381, 0x28958677, 0x3b8f4898, 0x6b4bb9af, 0xc4bfe81b, 0x66282193, 0x61d809cc, 0xfb21a991,
+        0x487cac60, 0x5dec8032, 0xef845d5d, 0xe98575b1, 0xdc262302, 0xeb651b88, 0x23893e81, 0xd396acc5,
+        0x0f6d6ff3, 0x83f44239, 0x2e0b4482, 0xa4842004, 0x69c8f04a, 0x9e1f9b5e, 0x21c66842, 0xf6e96c9a,
+        0x670c9c61, 0xabd388f0, 0x6a51a0d2, 0xd8542f68, 0x960fa728, 0xab5133a3, 0x6eef0b6c, 0x137a3be4,
+    ],
+    [
+        0xba3bf050, 0x7efb2a98, 0xa1f1651d, 0x39af0176, 0x66ca593e, 0x82430e88, 0x8cee8619, 0x456f9fb4,
+        0x7d84a5c3, 0x3b8b5ebe, 0xe06f75d8, 0x85c12073, 0x401a449f, 0x56c16aa6, 0x4ed3aa62, 0x363f7706,
+        0x1bfedf72, 0x429b023d, 0x37d0d724, 0xd00a1248, 0xdb0fead3, 0x49f1c09b, 0x075372c9, 0x80991b7b,
+        0x25d479d8, 0xf6e8def7, 0xe3fe501a, 0xb6794c3b, 0x976ce0bd, 0x04c006ba, 0xc1a94fb6, 0x409f60c4,
+    ],
+];
+
+// Message word order for passes 2-5 (pass 1 uses the words in order)
+const WORD_ORDER: [[usize; 32]; 4] = [
+    [5, 14, 26, 18, 11, 28, 7, 16, 0, 23, 20, 22, 1, 10, 4, 8, 30, 3, 21, 9, 17, 24, 29, 6, 19, 12, 15, 13, 2, 25, 31, 27],
+    [19, 9, 4, 20, 28, 17, 8, 22, 29, 14, 25, 12, 24, 30, 16, 26, 31, 15, 7, 3, 1, 0, 18, 27, 13, 6, 21, 10, 23, 11, 5, 2],
+    [24, 4, 0, 14, 2, 7, 28, 23, 26, 6, 30, 20, 18, 25, 19, 3, 22, 11, 31, 21, 8, 27, 12, 9, 1, 29, 5, 15, 17, 10, 16, 13],
+    [27, 3, 21, 26, 17, 11, 20, 29, 19, 0, 12, 7, 13, 8, 31, 10, 5, 9, 14, 30, 18, 6, 28, 24, 2, 23, 16, 22, 4, 1, 25, 15],
+];
+
+// Input permutations phi_{passes,pass}: which of x6..x0 feeds each argument
+// position of the boolean function, listed for positions 6..0
+const PHI_3: [[usize; 7]; 3] = [
+    [1, 0, 3, 5, 6, 2, 4],
+    [4, 2, 1, 0, 5, 3, 6],
+    [6, 1, 2, 3, 4, 5, 0],
+];
+const PHI_4: [[usize; 7]; 4] = [
+    [2, 6, 1, 4, 5, 3, 0],
+    [3, 5, 2, 0, 1, 6, 4],
+    [1, 4, 3, 6, 0, 2, 5],
+    [6, 4, 0, 5, 2, 1, 3],
+];
+const PHI_5: [[usize; 7]; 5] = [
+    [3, 4, 1, 0, 5, 2, 6],
+    [6, 2, 1, 0, 3, 4, 5],
+    [2, 6, 0, 4, 3, 1, 5],
+    [1, 5, 3, 2, 0, 4, 6],
+    [2, 5, 0, 6, 4, 3, 1],
+];
+
+// Boolean functions F1-F5, with the arguments given as [x6, x5, x4, x3, x2, x1, x0]
+fn boolean(pass: usize, args: [u32; 7]) -> u32 {
+    let [x6, x5, x4, x3, x2, x1, x0] = args;
+    match pass {
+        0 => x1 & (x0 ^ x4) ^ x2 & x5 ^ x3 & x6 ^ x0,
+        1 => x2 & (x1 & !x3 ^ x4 & x5 ^ x6 ^ x0) ^ x4 & (x1 ^ x5) ^ x3 & x5 ^ x0,
+        2 => x3 & (x1 & x2 ^ x6 ^ x0) ^ x1 & x4 ^ x2 & x5 ^ x0,
+        3 => x4 & (x5 & !x2 ^ x3 & !x6 ^ x1 ^ x6 ^ x0) ^ x3 & (x1 & x2 ^ x5 ^ x6) ^ x2 & x6 ^ x0,
+        _ => x0 & (x1 & x2 & x3 ^ !x5) ^ x1 & x4 ^ x2 & x5 ^ x3 & x6,
+    }
+}
+
+// Running state in a form a checkpoint can hold; the number of passes and
+// the output length come from the settings
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct HavalState {
+    state: [u32; 8],
+    pending: Vec<u8>,
+    length: u64,
+}
+
+#[derive(Clone)]
+pub struct Haval {
+    passes: usize,
+    bits: u32,
+    state: [u32; 8],
+    buffer: [u8; BLOCK_SIZE],
+    buffer_len: usize,
+    length: u64,
+}
+
+impl Haval {
+    pub fn new(variant: HavalVariant) -> Self {
+        let (passes, bits) = variant.parameters();
+        Haval {
+            passes,
+            bits,
+            state: IV,
+            buffer: [0; BLOCK_SIZE],
+            buffer_len: 0,
+            length: 0,
+        }
+    }
+
+    pub fn save(&self) -> HavalState {
+        HavalState {
+            state: self.state,
+            pending: self.buffer[..self.buffer_len].to_vec(),
+            length: self.length,
+        }
+    }
+
+    pub fn restore(&mut self, saved: &HavalState) -> bool {
+        if saved.pending.len() >= BLOCK_SIZE {
+            return false;
+        }
+        self.state = saved.state;
+        self.buffer[..saved.pending.len()].copy_from_slice(&saved.pending);
+        self.buffer_len = saved.pending.len();
+        self.length = saved.length;
+        true
+    }
+
+    fn compress(&mut self, block: &[u8]) {
+        let mut w = [0u32; 32];
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        let phi: &[[usize; 7]] = match self.passes {
+            3 => &PHI_3,
+            4 => &PHI_4,
+            _ => &PHI_5,
+        };
+
+        let mut t = self.state;
+        for (pass, perm) in phi.iter().enumerate() {
+            for i in 0..32 {
+                // x_j is t[(j - i) mod 8]; x7 is the word being replaced
+                let x = |j: usize| t[(j + 8 - i % 8) % 8];
+                let f = boolean(pass, perm.map(x));
+
+                let (word, constant) = if pass == 0 {
+                    (w[i], 0)
+                } else {
+                    (w[WORD_ORDER[pass - 1][i]], K[pass - 1][i])
+                };
+
+                let target = (7 + 8 - i % 8) % 8;
+                t[target] = f
+                    .rotate_right(7)
+                    .wrapping_add(t[target].rotate_right(11))
+                    .wrapping_add(word)
+                    .wrapping_add(constant);
+            }
+        }
+
+        for (s, v) in self.state.iter_mut().zip(t.iter()) {
+            *s = s.wrapping_add(*v);
+        }
+    }
+
+    fn absorb(&mut self, mut data: &[u8]) {
+        if self.buffer_len > 0 {
+            let take = (BLOCK_SIZE - self.buffer_len).min(data.len());
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&data[..take]);
+            self.buffer_len += take;
+            data = &data[take..];
+            if self.buffer_len < BLOCK_SIZE {
+                return;
+            }
+            let block = self.buffer;
+            self.compress(&block);
+            self.buffer_len = 0;
+        }
+
+        let mut blocks = data.chunks_exact(BLOCK_SIZE);
+        for block in &mut blocks {
+            self.compress(block);
+        }
+        let rest = blocks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_len = rest.len();
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.length = self.length.wrapping_add(data.len() as u64);
+        self.absorb(data);
+    }
+
+    // Folds the 256-bit state down to the requested fingerprint length
+    fn tailor(&mut self) {
+        let s = &mut self.state;
+        match self.bits {
+            128 => {
+                let t = (s[7] & 0x000000ff) | (s[6] & 0xff000000) | (s[5] & 0x00ff0000) | (s[4] & 0x0000ff00);
+                s[0] = s[0].wrapping_add(t.rotate_right(8));
+                let t = (s[7] & 0x0000ff00) | (s[6] & 0x000000ff) | (s[5] & 0xff000000) | (s[4] & 0x00ff0000);
+                s[1] = s[1].wrapping_add(t.rotate_right(16));
+                let t = (s[7] & 0x00ff0000) | (s[6] & 0x0000ff00) | (s[5] & 0x000000ff) | (s[4] & 0xff000000);
+                s[2] = s[2].wrapping_add(t.rotate_right(24));
+                let t = (s[7] & 0xff000000) | (s[6] & 0x00ff0000) | (s[5] & 0x0000ff00) | (s[4] & 0x000000ff);
+                s[3] = s[3].wrapping_add(t);
+            }
+            160 => {
+                let t = (s[7] & 0x3f) | (s[6] & (0x7f << 25)) | (s[5] & (0x3f << 19));
+                s[0] = s[0].wrapping_add(t.rotate_right(19));
+                let t = (s[7] & (0x3f << 6)) | (s[6] & 0x3f) | (s[5] & (0x7f << 25));
+                s[1] = s[1].wrapping_add(t.rotate_right(25));
+                let t = (s[7] & (0x7f << 12)) | (s[6] & (0x3f << 6)) | (s[5] & 0x3f);
+                s[2] = s[2].wrapping_add(t);
+                let t = (s[7] & (0x3f << 19)) | (s[6] & (0x7f << 12)) | (s[5] & (0x3f << 6));
+                s[3] = s[3].wrapping_add(t >> 6);
+                let t = (s[7] & (0x7f << 25)) | (s[6] & (0x3f << 19)) | (s[5] & (0x7f << 12));
+                s[4] = s[4].wrapping_add(t >> 12);
+            }
+            192 => {
+                let t = (s[7] & 0x1f) | (s[6] & (0x3f << 26));
+                s[0] = s[0].wrapping_add(t.rotate_right(26));
+                let t = (s[7] & (0x1f << 5)) | (s[6] & 0x1f);
+                s[1] = s[1].wrapping_add(t);
+                let t = (s[7] & (0x3f << 10)) | (s[6] & (0x1f << 5));
+                s[2] = s[2].wrapping_add(t >> 5);
+                let t = (s[7] & (0x1f << 16)) | (s[6] & (0x3f << 10));
+                s[3] = s[3].wrapping_add(t >> 10);
+                let t = (s[7] & (0x1f << 21)) | (s[6] & (0x1f << 16));
+                s[4] = s[4].wrapping_add(t >> 16);
+                let t = (s[7] & (0x3f << 26)) | (s[6] & (0x1f << 21));
+                s[5] = s[5].wrapping_add(t >> 21);
+            }
+            224 => {
+                s[0] = s[0].wrapping_add((s[7] >> 27) & 0x1f);
+                s[1] = s[1].wrapping_add((s[7] >> 22) & 0x1f);
+                s[2] = s[2].wrapping_add((s[7] >> 18) & 0x0f);
+                s[3] = s[3].wrapping_add((s[7] >> 13) & 0x1f);
+                s[4] = s[4].wrapping_add((s[7] >> 9) & 0x0f);
+                s[5] = s[5].wrapping_add((s[7] >> 4) & 0x1f);
+                s[6] = s[6].wrapping_add(s[7] & 0x0f);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn finalize(mut self) -> Vec<u8> {
+        let bit_length = self.length.wrapping_mul(8);
+
+        let mut tail = [0u8; 10];
+        tail[0] = (((self.bits & 0x3) as u8) << 6) | (((self.passes & 0x7) as u8) << 3) | (VERSION & 0x7);
+        tail[1] = ((self.bits >> 2) & 0xff) as u8;
+        tail[2..].copy_from_slice(&bit_length.to_le_bytes());
+
+        // Pad with a single 1 bit (0x01) and zeros up to 118 mod 128, then the tail
+        let pad_len = if self.buffer_len < 118 {
+            118 - self.buffer_len
+        } else {
+            246 - self.buffer_len
+        };
+        let mut padding = vec![0u8; pad_len];
+        padding[0] = 0x01;
+        self.absorb(&padding);
+        self.absorb(&tail);
+
+        self.tailor();
+
+        self.state[..(self.bits / 32) as usize]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn haval(variant: HavalVariant, data: &[u8]) -> String {
+        let mut hasher = Haval::new(variant);
+        hasher.update(data);
+        hex::encode(hasher.finalize())
+    }
+
+    // Digests of the empty string from the HAVAL reference implementation
+    #[test]
+    fn empty_string_vectors() {
+        let expected = [
+            (HavalVariant::Haval128_3, "c68f39913f901f3ddf44c707357a7d70"),
+            (HavalVariant::Haval160_3, "d353c3ae22a25401d257643836d7231a9a95f953"),
+            (HavalVariant::Haval192_3, "e9c48d7903eaf2a91c5b350151efcb175c0fc82de2289a4e"),
+            (HavalVariant::Haval224_3, "c5aae9d47bffcaaf84a8c6e7ccacd60a0dd1932be7b1a192b9214b6d"),
+            (HavalVariant::Haval256_3, "4f6938531f0bc8991f62da7bbd6f7de3fad44562b8c6f4ebf146d5b4e46f7c17"),
+            (HavalVariant::Haval128_4, "ee6bbf4d6a46a679b3a856c88538bb98"),
+            (HavalVariant::Haval160_4, "1d33aae1be4146dbaaca0b6e70d7a11f10801525"),
+            (HavalVariant::Haval192_4, "4a8372945afa55c7dead800311272523ca19d42ea47b72da"),
+            (HavalVariant::Haval224_4, "3e56243275b3b81561750550e36fcd676ad2f5dd9e15f2e89e6ed78e"),
+            (HavalVariant::Haval256_4, "c92b2e23091e80e375dadce26982482d197b1a2521be82da819f8ca2c579b99b"),
+            (HavalVariant::Haval128_5, "184b8482a0c050dca54b59c7f05bf5dd"),
+            (HavalVariant::Haval160_5, "255158cfc1eed1a7be7c55ddd64d9790415b933b"),
+            (HavalVariant::Haval192_5, "4839d0626f95935e17ee2fc4509387bbe2cc46cb382ffe85"),
+            (HavalVariant::Haval224_5, "4a0513c032754f5582a758d35917ac9adf3854219b39e3ac77d1837e"),
+            (HavalVariant::Haval256_5, "be417bb4dd5cfb76c7126f4f8eeb1553a449039307b1a3cd451dbfdc0fbbe330"),
+        ];
+        for (variant, digest) in expected {
+            assert_eq!(haval(variant, b""), digest, "{}", variant);
+        }
+    }
+
+    #[test]
+    fn pangram_vectors() {
+        let data = b"The quick brown fox jumps over the lazy dog";
+        assert_eq!(haval(HavalVariant::Haval128_3, data), "713502673d67e5fa557629a71d331945");
+        assert_eq!(
+            haval(HavalVariant::Haval256_5, data),
+            "b89c551cdfe2e06dbd4cea2be1bc7d557416c58ebb4d07cbc94e49f710c55be4"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
 use std::path::PathBuf;
 
 pub const BUFFER_SIZE: usize = 2 * 1024 * 1024; // 2MB
@@ -7,6 +8,41 @@ pub const MMAP_THRESHOLD: u64 = 4 * 1024 * 1024 * 1024; // 4GB
 pub const LARGE_FILE_THRESHOLD: u64 = 100 * 1024 * 1024; // 100MB
 pub const LARGE_BUFFER_SIZE: usize = 4 * 1024 * 1024; // 4MB
 
+// Reads the file once in buffer-sized chunks, passing each one to `on_chunk`;
+// this is the streaming loop shared by the single-pass and piecewise hashing
+pub fn read_chunks(path: &PathBuf, on_chunk: impl FnMut(&[u8])) -> Result<(), String> {
+    read_range(path, 0..u64::MAX, on_chunk)
+}
+
+// Same as read_chunks, limited to a byte range of the file
+pub fn read_range(path: &PathBuf, range: Range<u64>, mut on_chunk: impl FnMut(&[u8])) -> Result<(), String> {
+    let mut file = File::open(path).map_err(|e| format!("Error: {}", e))?;
+    let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if range.start > 0 {
+        file.seek(SeekFrom::Start(range.start)).map_err(|e| format!("Error: {}", e))?;
+    }
+    let file = file.take(range.end.saturating_sub(range.start));
+
+    let buffer_size = if file_size > LARGE_FILE_THRESHOLD {
+        LARGE_BUFFER_SIZE
+    } else {
+        BUFFER_SIZE
+    };
+
+    let mut reader = BufReader::with_capacity(buffer_size, file);
+    let mut buffer = vec![0u8; buffer_size];
+
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => break, // EOF
+            Ok(n) => on_chunk(&buffer[..n]),
+            Err(e) => return Err(format!("Error reading file: {}", e)),
+        }
+    }
+
+    Ok(())
+}
+
 // Helper function to read file in chunks and update a hasher that implements Digest
 // Optimized for better performance with large buffers and efficient reading
 // Uses memory-mapped I/O for small files and streaming for large files
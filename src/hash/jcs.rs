@@ -0,0 +1,500 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+use crate::archive::compression::Compression;
+use crate::hash::normalize::Normalization;
+use crate::hash::range::ByteRange;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+// Deeper documents are rejected rather than risking the stack
+const MAX_DEPTH: usize = 512;
+
+// Rewrites a JSON document in the RFC 8785 (JCS) canonical form: no
+// insignificant whitespace, object members sorted by the UTF-16 code units
+// of their names, numbers as ECMAScript prints them and strings with only the
+// required escapes. Documents that differ only in layout, key order or the
+// spelling of numbers and strings come out byte for byte the same.
+// Duplicate member names, lone surrogates and numbers outside the range of a
+// double are errors, as the RFC requires
+pub fn canonicalize(data: &[u8]) -> Result<Vec<u8>, String> {
+    // RFC 8259 lets parsers ignore a byte order mark
+    let data = data.strip_prefix(UTF8_BOM).unwrap_or(data);
+    let text = std::str::from_utf8(data).map_err(|e| {
+        let (line, column) = line_and_column(data, e.valid_up_to());
+        format!("invalid JSON: not UTF-8 at line {}, column {}", line, column)
+    })?;
+
+    let mut parser = Parser { text, at: 0, depth: 0 };
+    let mut out = String::with_capacity(text.len());
+    parser.skip_whitespace();
+    let result = parser.value(&mut out).and_then(|_| {
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(()),
+            Some(_) => Err("unexpected data after the document".to_string()),
+        }
+    });
+    match result {
+        Ok(()) => Ok(out.into_bytes()),
+        Err(e) => {
+            let (line, column) = line_and_column(data, parser.at);
+            Err(format!("invalid JSON at line {}, column {}: {}", line, column, e))
+        }
+    }
+}
+
+// Canonical form of a file (or the selected range of it), after the text
+// normalization steps; used to show what the hashes were computed over
+pub fn canonical_json_of_file(
+    path: &PathBuf,
+    range: Option<&ByteRange>,
+    normalization: Normalization,
+    compression: Compression,
+) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut data = Vec::new();
+    if compression != Compression::None {
+        if range.is_some() {
+            return Err("a byte range cannot be used with decompressed contents".to_string());
+        }
+        compression
+            .decoder(BufReader::new(file))?
+            .read_to_end(&mut data)
+            .map_err(|e| format!("{} data: {}", compression, e))?;
+    } else {
+        let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+        let span = match range {
+            Some(range) => range.resolve(file_size)?,
+            None => 0..file_size,
+        };
+        file.seek(SeekFrom::Start(span.start))
+            .and_then(|_| file.take(span.end - span.start).read_to_end(&mut data))
+            .map_err(|e| e.to_string())?;
+    }
+    let canonical = Normalization { canonical_json: true, ..normalization }.prepare(&data)?;
+    // The canonical form is always valid UTF-8
+    Ok(String::from_utf8_lossy(&canonical).into_owned())
+}
+
+fn line_and_column(data: &[u8], offset: usize) -> (usize, usize) {
+    let before = &data[..offset.min(data.len())];
+    let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+    let line_start = before.iter().rposition(|&b| b == b'\n').map(|p| p + 1).unwrap_or(0);
+    (line, String::from_utf8_lossy(&before[line_start..]).chars().count() + 1)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    at: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.at).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), String> {
+        if self.text[self.at..].starts_with(literal) {
+            self.at += literal.len();
+            Ok(())
+        } else {
+            Err(format!("expected \"{}\"", literal))
+        }
+    }
+
+    fn value(&mut self, out: &mut String) -> Result<(), String> {
+        match self.peek() {
+            Some(b'{') => self.nested(|parser| parser.object(out)),
+            Some(b'[') => self.nested(|parser| parser.array(out)),
+            Some(b'"') => {
+                let string = self.string()?;
+                write_string(&string, out);
+                Ok(())
+            }
+            Some(b't') => self.expect("true").map(|_| out.push_str("true")),
+            Some(b'f') => self.expect("false").map(|_| out.push_str("false")),
+            Some(b'n') => self.expect("null").map(|_| out.push_str("null")),
+            Some(b'-' | b'0'..=b'9') => {
+                let number = self.number()?;
+                out.push_str(&format_number(number));
+                Ok(())
+            }
+            Some(_) => Err("expected a value".to_string()),
+            None => Err("unexpected end of the document".to_string()),
+        }
+    }
+
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> Result<(), String>) -> Result<(), String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(format!("nested more than {} levels deep", MAX_DEPTH));
+        }
+        self.depth += 1;
+        parse(self)?;
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn object(&mut self, out: &mut String) -> Result<(), String> {
+        self.at += 1; // '{'
+        let mut members: Vec<(Vec<u16>, String, String)> = Vec::new();
+        let mut names = HashSet::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.at += 1;
+            out.push_str("{}");
+            return Ok(());
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err("expected a member name".to_string());
+            }
+            let name_at = self.at;
+            let name = self.string()?;
+            self.skip_whitespace();
+            self.expect(":")?;
+            self.skip_whitespace();
+            let mut value = String::new();
+            self.value(&mut value)?;
+
+            let key: Vec<u16> = name.encode_utf16().collect();
+            if !names.insert(key.clone()) {
+                self.at = name_at;
+                return Err(format!("duplicate member name \"{}\"", name));
+            }
+            members.push((key, name, value));
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b'}') => {
+                    self.at += 1;
+                    break;
+                }
+                _ => return Err("expected ',' or '}'".to_string()),
+            }
+        }
+
+        members.sort_by(|a, b| a.0.cmp(&b.0));
+        out.push('{');
+        for (index, (_, name, value)) in members.iter().enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            write_string(name, out);
+            out.push(':');
+            out.push_str(value);
+        }
+        out.push('}');
+        Ok(())
+    }
+
+    fn array(&mut self, out: &mut String) -> Result<(), String> {
+        self.at += 1; // '['
+        out.push('[');
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.at += 1;
+            out.push(']');
+            return Ok(());
+        }
+        loop {
+            self.skip_whitespace();
+            self.value(out)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => {
+                    self.at += 1;
+                    out.push(',');
+                }
+                Some(b']') => {
+                    self.at += 1;
+                    out.push(']');
+                    return Ok(());
+                }
+                _ => return Err("expected ',' or ']'".to_string()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.at += 1; // '"'
+        let mut string = String::new();
+        loop {
+            let rest = &self.text[self.at..];
+            let c = match rest.chars().next() {
+                Some(c) => c,
+                None => return Err("unterminated string".to_string()),
+            };
+            match c {
+                '"' => {
+                    self.at += 1;
+                    return Ok(string);
+                }
+                '\\' => {
+                    self.at += 1;
+                    string.push(self.escape()?);
+                }
+                '\u{0}'..='\u{1f}' => return Err("unescaped control character in a string".to_string()),
+                _ => {
+                    self.at += c.len_utf8();
+                    string.push(c);
+                }
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let c = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.at += 1;
+                let unit = self.hex4()?;
+                return match unit {
+                    0xD800..=0xDBFF => {
+                        if !self.text[self.at..].starts_with("\\u") {
+                            return Err("lone surrogate in a \\u escape".to_string());
+                        }
+                        self.at += 2;
+                        let low = self.hex4()?;
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err("lone surrogate in a \\u escape".to_string());
+                        }
+                        let code = 0x10000 + ((unit as u32 - 0xD800) << 10) + (low as u32 - 0xDC00);
+                        char::from_u32(code).ok_or_else(|| "invalid \\u escape".to_string())
+                    }
+                    0xDC00..=0xDFFF => Err("lone surrogate in a \\u escape".to_string()),
+                    _ => char::from_u32(unit as u32).ok_or_else(|| "invalid \\u escape".to_string()),
+                };
+            }
+            _ => return Err("invalid escape".to_string()),
+        };
+        self.at += 1;
+        Ok(c)
+    }
+
+    fn hex4(&mut self) -> Result<u16, String> {
+        let digits = self.text.get(self.at..self.at + 4).filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()));
+        match digits {
+            Some(digits) => {
+                self.at += 4;
+                u16::from_str_radix(digits, 16).map_err(|_| "invalid \\u escape".to_string())
+            }
+            None => Err("invalid \\u escape".to_string()),
+        }
+    }
+
+    // RFC 8259 number grammar; the value is rounded to the nearest double
+    fn number(&mut self) -> Result<f64, String> {
+        let start = self.at;
+        let bytes = self.text.as_bytes();
+        let digits = |at: &mut usize| {
+            let from = *at;
+            while bytes.get(*at).is_some_and(u8::is_ascii_digit) {
+                *at += 1;
+            }
+            *at - from
+        };
+
+        let mut at = self.at;
+        if bytes.get(at) == Some(&b'-') {
+            at += 1;
+        }
+        let integer_at = at;
+        match digits(&mut at) {
+            0 => return Err("invalid number".to_string()),
+            n if n > 1 && bytes[integer_at] == b'0' => return Err("leading zero in a number".to_string()),
+            _ => {}
+        }
+        if bytes.get(at) == Some(&b'.') {
+            at += 1;
+            if digits(&mut at) == 0 {
+                return Err("invalid number".to_string());
+            }
+        }
+        if let Some(b'e' | b'E') = bytes.get(at) {
+            at += 1;
+            if let Some(b'+' | b'-') = bytes.get(at) {
+                at += 1;
+            }
+            if digits(&mut at) == 0 {
+                return Err("invalid number".to_string());
+            }
+        }
+
+        let value: f64 = self.text[start..at].parse().map_err(|_| "invalid number".to_string())?;
+        if !value.is_finite() {
+            return Err("number is too large for a double".to_string());
+        }
+        self.at = at;
+        Ok(value)
+    }
+}
+
+// Only '"', '\' and control characters are escaped, with the short forms where
+// JSON has them and lowercase \u00xx otherwise
+fn write_string(string: &str, out: &mut String) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            '\u{0}'..='\u{1f}' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// ECMAScript Number::toString: the shortest digits that round-trip, written
+// out in full between 1e-7 and 1e21 and in exponent form outside that range
+fn format_number(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string(); // Also for -0
+    }
+    // Rust's {:e} gives the same shortest digits, as "d.ddde-x"
+    let scientific = format!("{:e}", value.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let mut digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent = exponent.parse::<i32>().unwrap_or(0);
+    if let Some(even) = even_tie(value.abs(), &digits, exponent) {
+        digits = even;
+    }
+    let k = digits.len() as i32;
+    let n = exponent + 1; // value = 0.digits * 10^n
+
+    let sign = if value < 0.0 { "-" } else { "" };
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat((-n) as usize), digits)
+    } else {
+        let exponent = n - 1;
+        let exponent_sign = if exponent < 0 { "-" } else { "+" };
+        match digits.split_at(1) {
+            (first, "") => format!("{}e{}{}", first, exponent_sign, exponent.abs()),
+            (first, rest) => format!("{}.{}e{}{}", first, rest, exponent_sign, exponent.abs()),
+        }
+    };
+    format!("{}{}", sign, body)
+}
+
+// When the value lies exactly halfway between two shortest candidates,
+// ECMAScript takes the one with the even last digit where Rust rounds up
+fn even_tie(value: f64, digits: &str, exponent: i32) -> Option<String> {
+    let (prefix, last) = digits.split_at(digits.len() - 1);
+    let last = last.parse::<u8>().ok().filter(|d| d % 2 == 1)?;
+    [last - 1, last + 1].into_iter().filter(|d| *d <= 9).find_map(|even| {
+        let candidate = format!("{}{}", prefix, even);
+        let midpoint = format!("{}{}5", prefix, even.min(last));
+        let parses_to_value = |digits: &str| format!("0.{}e{}", digits, exponent + 1).parse::<f64>() == Ok(value);
+        if !parses_to_value(&candidate) || !parses_to_value(&midpoint) {
+            return None;
+        }
+        // A double has at most 767 significant digits, so this is exact
+        let exact = format!("{:.800e}", value);
+        let (mantissa, exact_exponent) = exact.split_once('e')?;
+        let exact_digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+        (exact_exponent.parse::<i32>() == Ok(exponent) && exact_digits.trim_end_matches('0') == midpoint)
+            .then_some(candidate)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 8785 Appendix B, as IEEE 754 bit patterns and their expected text
+    const NUMBER_SAMPLES: &[(u64, &str)] = &[
+        (0x0000000000000000, "0"),
+        (0x8000000000000000, "0"),
+        (0x0000000000000001, "5e-324"),
+        (0x8000000000000001, "-5e-324"),
+        (0x7fefffffffffffff, "1.7976931348623157e+308"),
+        (0xffefffffffffffff, "-1.7976931348623157e+308"),
+        (0x4340000000000000, "9007199254740992"),
+        (0xc340000000000000, "-9007199254740992"),
+        (0x4430000000000000, "295147905179352830000"),
+        (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+        (0x44b52d02c7e14af6, "1e+23"),
+        (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+        (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+        (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+        (0x444b1ae4d6e2ef50, "1e+21"),
+        (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+        (0x3eb0c6f7a0b5ed8d, "0.000001"),
+        (0x41b3de4355555553, "333333333.3333332"),
+        (0x41b3de4355555554, "333333333.33333325"),
+        (0x41b3de4355555555, "333333333.3333333"),
+        (0x41b3de4355555556, "333333333.3333334"),
+        (0x41b3de4355555557, "333333333.33333343"),
+        (0xbecbf647612f3696, "-0.0000033333333333333333"),
+        (0x43143ff3c1cb0959, "1424953923781206.2"),
+    ];
+
+    #[test]
+    fn numbers_are_formatted_as_ecmascript_does() {
+        for &(bits, expected) in NUMBER_SAMPLES {
+            assert_eq!(format_number(f64::from_bits(bits)), expected, "{:016x}", bits);
+        }
+    }
+
+    #[test]
+    fn formatted_numbers_are_canonical_when_parsed_again() {
+        for &(_, expected) in NUMBER_SAMPLES {
+            let canonical = canonicalize(expected.as_bytes()).unwrap();
+            assert_eq!(canonical, expected.as_bytes());
+        }
+    }
+
+    // RFC 8785 section 3.2.3
+    #[test]
+    fn members_are_sorted_by_utf16_code_units() {
+        let input = r#"{
+  "\u20ac": "Euro Sign",
+  "\r": "Carriage Return",
+  "\ufb33": "Hebrew Letter Dalet With Dagesh",
+  "1": "One",
+  "\ud83d\ude00": "Emoji: Grinning Face",
+  "\u0080": "Control",
+  "\u00f6": "Latin Small Letter O With Diaeresis"
+}"#;
+        let expected = "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\
+            \"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",\
+            \"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}";
+        let canonical = canonicalize(input.as_bytes()).unwrap();
+        assert_eq!(String::from_utf8(canonical).unwrap(), expected);
+    }
+
+    #[test]
+    fn duplicate_names_and_lone_surrogates_are_errors() {
+        assert!(canonicalize(br#"{"a":1,"a":2}"#).is_err());
+        assert!(canonicalize(br#"{"a":1,"\u0061":2}"#).is_err());
+        assert!(canonicalize(br#""\ud83d""#).is_err());
+        assert!(canonicalize(b"1e400").is_err());
+    }
+}
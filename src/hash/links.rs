@@ -0,0 +1,33 @@
+// Percent-encodes everything except RFC 3986 unreserved characters, so
+// separators, spaces and non-ASCII names survive inside a link
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+// ed2k://|file|name|size|hash|/ with the MD4-based ed2k hash in hex
+pub fn ed2k_link(file_name: &str, file_size: u64, hash: &[u8]) -> String {
+    format!(
+        "ed2k://|file|{}|{}|{}|/",
+        percent_encode(file_name),
+        file_size,
+        hex::encode_upper(hash)
+    )
+}
+
+// magnet:?xt=urn:tree:tiger:... with the TTH root in base32
+pub fn tth_magnet_link(file_name: &str, file_size: u64, hash: &[u8]) -> String {
+    format!(
+        "magnet:?xt=urn:tree:tiger:{}&xl={}&dn={}",
+        base32::encode(base32::Alphabet::Rfc4648 { padding: false }, hash),
+        file_size,
+        percent_encode(file_name)
+    )
+}
@@ -1,8 +1,30 @@
 pub mod algo;
+pub mod block;
+pub mod cdc;
+pub mod checkpoint;
 pub mod io;
 pub mod wrappers;
 pub mod compute;
+pub mod crc;
+pub mod haval;
+pub mod snefru;
+pub mod ed2k;
+pub mod follow;
+pub mod fuzzy;
+pub mod git;
+pub mod jcs;
+pub mod tth;
+pub mod links;
+pub mod piecewise;
+pub mod perceptual;
+pub mod normalize;
+pub mod range;
+pub mod split;
+pub mod text;
 
-pub use algo::{Algorithm, GostVariant, TigerVariant};
-pub use compute::calculate_hashes_parallel_streaming;
+pub use algo::{Algorithm, Blake2Params, GostVariant, HashOptions, TigerVariant};
+pub use compute::{calculate_hash_from_data, calculate_hashes_parallel_streaming};
+pub use crc::CrcModel;
+pub use ed2k::Ed2kVariant;
+pub use haval::HavalVariant;
 
@@ -0,0 +1,234 @@
+use std::fmt;
+
+use crate::hash::jcs::canonicalize;
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+// Optional clean-up of text files before hashing, so the same script or CSV
+// gives the same digest whether it was saved on Windows or on Linux
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Normalization {
+    pub line_endings: bool, // CRLF and lone CR become LF
+    pub strip_bom: bool, // A leading UTF-8 byte order mark is dropped
+    pub strip_trailing_whitespace: bool, // Spaces and tabs before a line break or the end of the data are dropped
+    #[serde(default)] // Missing in configs and exports made before canonical JSON existed
+    pub canonical_json: bool, // The data is parsed as JSON and rewritten in RFC 8785 canonical form
+}
+
+impl Normalization {
+    pub fn is_active(&self) -> bool {
+        self.line_endings || self.strip_bom || self.strip_trailing_whitespace || self.canonical_json
+    }
+
+    // Canonical JSON needs the whole document, so unlike the text steps it
+    // cannot be streamed, and it fails on data that is not JSON
+    pub fn prepare(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let text = self.apply(data);
+        if self.canonical_json {
+            canonicalize(&text)
+        } else {
+            Ok(text)
+        }
+    }
+
+    pub fn apply(&self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len());
+        let mut normalizer = Normalizer::new(*self);
+        normalizer.push(data, &mut out);
+        normalizer.finish(&mut out);
+        out
+    }
+}
+
+impl fmt::Display for Normalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut steps = Vec::new();
+        if self.line_endings {
+            steps.push("line endings to LF");
+        }
+        if self.strip_bom {
+            steps.push("BOM stripped");
+        }
+        if self.strip_trailing_whitespace {
+            steps.push("trailing whitespace stripped");
+        }
+        if self.canonical_json {
+            steps.push("canonical JSON");
+        }
+        if steps.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", steps.join(", "))
+        }
+    }
+}
+
+// Streaming form of Normalization::apply. A CR, a partial BOM or a run of
+// spaces at the end of one chunk is held back until the next chunk shows
+// what follows it
+pub struct Normalizer {
+    settings: Normalization,
+    bom_pending: Option<Vec<u8>>, // Leading bytes that may still turn out to be a BOM
+    pending_cr: bool,
+    pending_whitespace: Vec<u8>,
+}
+
+impl Normalizer {
+    pub fn new(settings: Normalization) -> Self {
+        Self {
+            settings,
+            bom_pending: settings.strip_bom.then(Vec::new),
+            pending_cr: false,
+            pending_whitespace: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, mut chunk: &[u8], out: &mut Vec<u8>) {
+        if let Some(mut pending) = self.bom_pending.take() {
+            while pending.len() < UTF8_BOM.len() && !chunk.is_empty() {
+                pending.push(chunk[0]);
+                chunk = &chunk[1..];
+                if !UTF8_BOM.starts_with(&pending) {
+                    break;
+                }
+            }
+            if pending.len() < UTF8_BOM.len() && UTF8_BOM.starts_with(&pending) {
+                // Still undecided; wait for more data
+                self.bom_pending = Some(pending);
+                return;
+            }
+            if pending != UTF8_BOM {
+                for byte in pending {
+                    self.push_byte(byte, out);
+                }
+            }
+        }
+
+        for &byte in chunk {
+            self.push_byte(byte, out);
+        }
+    }
+
+    fn push_byte(&mut self, byte: u8, out: &mut Vec<u8>) {
+        if self.settings.line_endings {
+            if self.pending_cr {
+                self.pending_cr = false;
+                self.end_line(out);
+                if byte == b'\n' {
+                    return;
+                }
+            }
+            if byte == b'\r' {
+                self.pending_cr = true;
+                return;
+            }
+        }
+
+        if self.settings.strip_trailing_whitespace {
+            match byte {
+                b' ' | b'\t' => {
+                    self.pending_whitespace.push(byte);
+                    return;
+                }
+                b'\r' | b'\n' => self.pending_whitespace.clear(),
+                _ => out.append(&mut self.pending_whitespace),
+            }
+        }
+
+        out.push(byte);
+    }
+
+    fn end_line(&mut self, out: &mut Vec<u8>) {
+        self.pending_whitespace.clear();
+        out.push(b'\n');
+    }
+
+    // Flushes what was held back at the end of the data
+    pub fn finish(&mut self, out: &mut Vec<u8>) {
+        if let Some(pending) = self.bom_pending.take() {
+            for byte in pending {
+                self.push_byte(byte, out);
+            }
+        }
+        if self.pending_cr {
+            self.pending_cr = false;
+            self.end_line(out);
+        }
+        self.pending_whitespace.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: Normalization = Normalization {
+        line_endings: true,
+        strip_bom: true,
+        strip_trailing_whitespace: true,
+        canonical_json: false,
+    };
+
+    const INPUTS: &[&[u8]] = &[
+        b"",
+        b"a \r\nb\r",
+        b"\xEF\xBB\xBFline one\t \r\rline three  ",
+        b"\xEF\xBB",
+        b"\xEF\xBBx\r\n",
+        b"x\xEF\xBB\xBF",
+        b" \t\r \n\r\n\n\r",
+    ];
+
+    fn settings() -> Vec<Normalization> {
+        (0..8)
+            .map(|bits| Normalization {
+                line_endings: bits & 1 != 0,
+                strip_bom: bits & 2 != 0,
+                strip_trailing_whitespace: bits & 4 != 0,
+                canonical_json: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn expected_outputs() {
+        assert_eq!(ALL.apply(b"a \r\nb\r"), b"a\nb\n");
+        assert_eq!(ALL.apply(b"\xEF\xBB\xBFline one\t \r\rline three  "), b"line one\n\nline three");
+        assert_eq!(ALL.apply(b"\xEF\xBBx\r\n"), b"\xEF\xBBx\n");
+        assert_eq!(ALL.apply(b"x\xEF\xBB\xBF"), b"x\xEF\xBB\xBF");
+
+        let whitespace_only = Normalization { strip_trailing_whitespace: true, ..Normalization::default() };
+        assert_eq!(whitespace_only.apply(b"a \r\nb \n c "), b"a\r\nb\n c");
+        let bom_only = Normalization { strip_bom: true, ..Normalization::default() };
+        assert_eq!(bom_only.apply(b"\xEF\xBB\xBFa \r\n"), b"a \r\n");
+        assert_eq!(Normalization::default().apply(b"\xEF\xBB\xBFa \r\n"), b"\xEF\xBB\xBFa \r\n");
+    }
+
+    // The held-back CR, partial BOM and trailing whitespace must come out the
+    // same wherever the chunks happen to end
+    #[test]
+    fn chunks_split_anywhere_match_apply() {
+        for settings in settings() {
+            for &input in INPUTS {
+                let expected = settings.apply(input);
+                for first in 0..=input.len() {
+                    for second in first..=input.len() {
+                        let mut normalizer = Normalizer::new(settings);
+                        let mut out = Vec::new();
+                        normalizer.push(&input[..first], &mut out);
+                        normalizer.push(&input[first..second], &mut out);
+                        normalizer.push(&input[second..], &mut out);
+                        normalizer.finish(&mut out);
+                        assert_eq!(out, expected, "{:?} split at {} and {} with {}", input, first, second, settings);
+                    }
+                }
+
+                let mut normalizer = Normalizer::new(settings);
+                let mut out = Vec::new();
+                input.iter().for_each(|byte| normalizer.push(&[*byte], &mut out));
+                normalizer.finish(&mut out);
+                assert_eq!(out, expected, "{:?} byte by byte with {}", input, settings);
+            }
+        }
+    }
+}
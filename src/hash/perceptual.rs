@@ -0,0 +1,241 @@
+use std::io::Read;
+use std::path::Path;
+
+use image::imageops::FilterType;
+use image::GrayImage;
+
+use crate::hash::algo::Algorithm;
+
+pub const HASH_BITS: u32 = 64;
+pub const NEAR_DUPLICATE_BITS: u32 = 10; // Usual cut-off for "same picture, re-encoded or resized"
+
+const DCT_SIZE: usize = 32; // pHash transforms a 32x32 thumbnail and keeps the lowest 8x8 frequencies
+
+// Perceptual hashes need the whole image, so the data is collected and only
+// decoded once it is complete
+#[derive(Clone)]
+pub struct PerceptualHasher {
+    algorithm: Algorithm,
+    data: Vec<u8>,
+}
+
+impl PerceptualHasher {
+    pub fn new(algorithm: &Algorithm) -> Self {
+        Self { algorithm: algorithm.clone(), data: Vec::new() }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data);
+    }
+
+    pub fn finalize(self) -> String {
+        match perceptual_hash(&self.data, &self.algorithm) {
+            Ok(hash) => format!("{:016x}", hash),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+}
+
+// Whether the file starts like an image format that can be decoded
+pub fn is_image_file(path: &Path) -> bool {
+    let mut header = Vec::with_capacity(64);
+    let read = std::fs::File::open(path).and_then(|file| file.take(64).read_to_end(&mut header));
+    match (read, image::guess_format(&header)) {
+        (Ok(_), Ok(format)) => format.reading_enabled(),
+        _ => false,
+    }
+}
+
+// One perceptual hash of each image and the number of bits that differ
+#[derive(Debug, Clone)]
+pub struct HashDistance {
+    pub algorithm: Algorithm,
+    pub first: u64,
+    pub second: u64,
+    pub distance: u32, // 0 to HASH_BITS
+}
+
+impl HashDistance {
+    pub fn new(algorithm: Algorithm, first: u64, second: u64) -> Self {
+        let distance = (first ^ second).count_ones();
+        HashDistance { algorithm, first, second, distance }
+    }
+}
+
+pub fn algorithms() -> Vec<Algorithm> {
+    vec![Algorithm::AHASH, Algorithm::DHASH, Algorithm::PHASH]
+}
+
+// aHash, dHash and pHash as computed by the Python imagehash package: the
+// image is reduced to a small grayscale thumbnail and each bit says whether a
+// pixel (or frequency) is brighter than its reference. Resampling differs a
+// little between libraries, so hashes from other tools may be a few bits off
+pub fn perceptual_hash(data: &[u8], algorithm: &Algorithm) -> Result<u64, String> {
+    hash_gray(&decode(data)?, algorithm)
+}
+
+// All three hashes of an image file, decoding it only once
+pub fn image_hashes(path: &Path) -> Result<Vec<(Algorithm, u64)>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let gray = decode(&data).map_err(|e| format!("{}: {}", path.display(), e))?;
+    algorithms()
+        .into_iter()
+        .map(|algorithm| hash_gray(&gray, &algorithm).map(|hash| (algorithm, hash)))
+        .collect()
+}
+
+// 16 hex digits, as shown in the results
+pub fn parse_perceptual_hash(text: &str) -> Result<u64, String> {
+    let digits = text.trim().replace(' ', "");
+    if digits.len() != 16 {
+        return Err(format!("\"{}\" is not a 64-bit hash (16 hex digits)", text.trim()));
+    }
+    // from_str_radix would also take a leading "+"
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("\"{}\" is not a hex hash", text.trim()));
+    }
+    u64::from_str_radix(&digits, 16).map_err(|_| format!("\"{}\" is not a hex hash", text.trim()))
+}
+
+fn decode(data: &[u8]) -> Result<GrayImage, String> {
+    let image = image::load_from_memory(data).map_err(|e| format!("not a supported image ({})", e))?;
+    Ok(luma(&image))
+}
+
+fn hash_gray(gray: &GrayImage, algorithm: &Algorithm) -> Result<u64, String> {
+    match algorithm {
+        Algorithm::AHASH => Ok(average_hash(gray)),
+        Algorithm::DHASH => Ok(difference_hash(gray)),
+        Algorithm::PHASH => Ok(dct_hash(gray)),
+        other => Err(format!("{} is not a perceptual hash", other)),
+    }
+}
+
+// Grayscale with the ITU-R 601 weights that PIL's "L" mode uses; alpha is ignored
+fn luma(image: &image::DynamicImage) -> GrayImage {
+    let rgb = image.to_rgb8();
+    GrayImage::from_fn(rgb.width(), rgb.height(), |x, y| {
+        let [r, g, b] = rgb.get_pixel(x, y).0;
+        let value = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114 + 500) / 1000;
+        image::Luma([value as u8])
+    })
+}
+
+fn thumbnail(gray: &GrayImage, width: u32, height: u32) -> Vec<f64> {
+    image::imageops::resize(gray, width, height, FilterType::Lanczos3)
+        .pixels()
+        .map(|p| p.0[0] as f64)
+        .collect()
+}
+
+// Row by row, first value in the most significant bit
+fn pack_bits(bits: impl Iterator<Item = bool>) -> u64 {
+    bits.fold(0u64, |hash, bit| (hash << 1) | bit as u64)
+}
+
+fn average_hash(gray: &GrayImage) -> u64 {
+    let pixels = thumbnail(gray, 8, 8);
+    let mean = pixels.iter().sum::<f64>() / pixels.len() as f64;
+    pack_bits(pixels.iter().map(|&p| p > mean))
+}
+
+// Compares each pixel with its left neighbour in a 9x8 thumbnail
+fn difference_hash(gray: &GrayImage) -> u64 {
+    let pixels = thumbnail(gray, 9, 8);
+    pack_bits(pixels.chunks(9).flat_map(|row| row.windows(2).map(|pair| pair[1] > pair[0])))
+}
+
+fn dct_hash(gray: &GrayImage) -> u64 {
+    let pixels = thumbnail(gray, DCT_SIZE as u32, DCT_SIZE as u32);
+
+    // Separable 2-D DCT-II: transform the rows, then the first 8 columns,
+    // keeping only the lowest 8x8 frequencies in row order
+    let mut rows = vec![0.0; DCT_SIZE * DCT_SIZE];
+    for (row, out) in pixels.chunks(DCT_SIZE).zip(rows.chunks_mut(DCT_SIZE)) {
+        dct(row, out);
+    }
+    let mut low = vec![0.0; 64];
+    let mut column = [0.0; DCT_SIZE];
+    let mut transformed = [0.0; DCT_SIZE];
+    for x in 0..8 {
+        for (y, value) in column.iter_mut().enumerate() {
+            *value = rows[y * DCT_SIZE + x];
+        }
+        dct(&column, &mut transformed);
+        for (y, &value) in transformed[..8].iter().enumerate() {
+            low[y * 8 + x] = value;
+        }
+    }
+
+    // The median is taken over all 64 coefficients, including the DC term
+    let mut sorted = low.clone();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = (sorted[31] + sorted[32]) / 2.0;
+    pack_bits(low.iter().map(|&c| c > median))
+}
+
+fn dct(input: &[f64], output: &mut [f64]) {
+    let n = input.len() as f64;
+    for (k, out) in output.iter_mut().enumerate() {
+        *out = input
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| x * (std::f64::consts::PI * (2 * i + 1) as f64 * k as f64 / (2.0 * n)).cos())
+            .sum();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A grayscale image without flat areas, the same on every run
+    fn pattern(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| image::Luma([((x * 29 + y * 47 + (x * y * 11) % 37) % 256) as u8]))
+    }
+
+    #[test]
+    fn hashes_are_16_hex_digits() {
+        assert_eq!(parse_perceptual_hash("e548bb207edc9172"), Ok(0xe548bb207edc9172));
+        assert_eq!(parse_perceptual_hash(" E548 BB20 7EDC 9172\n"), Ok(0xe548bb207edc9172));
+        assert_eq!(parse_perceptual_hash("0000000000000000"), Ok(0));
+        assert!(parse_perceptual_hash("e548bb207edc917").is_err());
+        assert!(parse_perceptual_hash("e548bb207edc91720").is_err());
+        assert!(parse_perceptual_hash("").is_err());
+        assert!(parse_perceptual_hash("g548bb207edc9172").is_err());
+        assert!(parse_perceptual_hash("+548bb207edc9172").is_err());
+    }
+
+    #[test]
+    fn distance_counts_differing_bits() {
+        assert_eq!(HashDistance::new(Algorithm::AHASH, 0x1234, 0x1234).distance, 0);
+        assert_eq!(HashDistance::new(Algorithm::DHASH, 0b1011, 0b0001).distance, 2);
+        let opposite = HashDistance::new(Algorithm::PHASH, 0, u64::MAX);
+        assert_eq!(opposite.distance, HASH_BITS);
+        assert_eq!((opposite.first, opposite.second), (0, u64::MAX));
+    }
+
+    #[test]
+    fn first_pixel_is_the_most_significant_bit() {
+        assert_eq!(pack_bits([true].into_iter().chain([false; 63])), 1 << 63);
+        assert_eq!(pack_bits([false; 63].into_iter().chain([true])), 1);
+
+        // A 9x8 image is its own dHash thumbnail. Only the top row brightens
+        // from left to right, so only the first byte is set
+        let top_row = GrayImage::from_fn(9, 8, |x, y| image::Luma([if y == 0 { x as u8 * 20 } else { 200 - x as u8 * 20 }]));
+        assert_eq!(difference_hash(&top_row), 0xff00_0000_0000_0000);
+        // Only the first pair of each row brightens
+        let left_column = GrayImage::from_fn(9, 8, |x, _| image::Luma([if x == 0 { 0 } else { 200 - x as u8 * 20 }]));
+        assert_eq!(difference_hash(&left_column), 0x8080_8080_8080_8080);
+    }
+
+    // Images already at thumbnail size are not resampled, so these match the
+    // steps of Python imagehash's average_hash, dhash and phash exactly
+    #[test]
+    fn known_answers() {
+        assert_eq!(hash_gray(&pattern(8, 8), &Algorithm::AHASH), Ok(0x071f78f0c18f0f3c));
+        assert_eq!(hash_gray(&pattern(9, 8), &Algorithm::DHASH), Ok(0xfffef7efbb7ffefb));
+        assert_eq!(hash_gray(&pattern(32, 32), &Algorithm::PHASH), Ok(0xe548bb207edc9172));
+        assert!(hash_gray(&pattern(8, 8), &Algorithm::MD5).is_err());
+    }
+}
@@ -0,0 +1,413 @@
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::hash::algo::{Algorithm, HashOptions};
+use crate::hash::compute::AlgorithmHasher;
+use crate::hash::io::read_chunks;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum WindowSize {
+    Kib64,
+    Mib1,
+    Mib16,
+    Mib64,
+    Mib256,
+    Gib1,
+}
+
+impl WindowSize {
+    pub fn all() -> Vec<WindowSize> {
+        vec![
+            WindowSize::Kib64,
+            WindowSize::Mib1,
+            WindowSize::Mib16,
+            WindowSize::Mib64,
+            WindowSize::Mib256,
+            WindowSize::Gib1,
+        ]
+    }
+
+    pub fn bytes(&self) -> u64 {
+        match self {
+            WindowSize::Kib64 => 64 * 1024,
+            WindowSize::Mib1 => 1024 * 1024,
+            WindowSize::Mib16 => 16 * 1024 * 1024,
+            WindowSize::Mib64 => 64 * 1024 * 1024,
+            WindowSize::Mib256 => 256 * 1024 * 1024,
+            WindowSize::Gib1 => 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl fmt::Display for WindowSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            WindowSize::Kib64 => "64 KiB",
+            WindowSize::Mib1 => "1 MiB",
+            WindowSize::Mib16 => "16 MiB",
+            WindowSize::Mib64 => "64 MiB",
+            WindowSize::Mib256 => "256 MiB",
+            WindowSize::Gib1 => "1 GiB",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// One digest per fixed-size window plus the whole-file digest, in the
+// spirit of dcfldd's hashwindow log
+#[derive(Debug, Clone)]
+pub struct PiecewiseManifest {
+    pub algorithm: Algorithm,
+    pub label: Option<String>, // Algorithm label including its settings, when known
+    pub window: u64,
+    pub file_size: u64,
+    pub windows: Vec<String>,
+    pub total: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PiecewiseComparison {
+    pub window: u64,
+    pub windows_compared: usize,
+    pub differing: Vec<Range<u64>>, // Byte ranges whose window digests differ
+    pub sizes: (u64, u64), // (expected, actual)
+    pub total_match: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum PiecewiseOutcome {
+    Created {
+        path: PathBuf,
+        windows: usize,
+        total: String,
+    },
+    Compared(PiecewiseComparison),
+}
+
+impl PiecewiseManifest {
+    pub fn compute(
+        path: &PathBuf,
+        algorithm: &Algorithm,
+        options: &HashOptions,
+        window: u64,
+    ) -> Result<PiecewiseManifest, String> {
+        if window == 0 {
+            return Err("window size must be greater than zero".to_string());
+        }
+        if algorithm.is_fuzzy() || algorithm.is_perceptual() {
+            return Err(format!("{} digests cannot be compared window by window", algorithm));
+        }
+
+        // Window lengths are known from the size, which git blob IDs need up front
+        let expected_size = std::fs::metadata(path).map_err(|e| format!("Error: {}", e))?.len();
+        let window_length = |start: u64| window.min(expected_size.saturating_sub(start));
+
+        let mut total = AlgorithmHasher::with_length(algorithm, options, expected_size)?;
+        let mut current = AlgorithmHasher::with_length(algorithm, options, window_length(0))?;
+        let mut in_window = 0u64;
+        let mut file_size = 0u64;
+        let mut windows = Vec::new();
+        let mut failed = None;
+
+        read_chunks(path, |mut chunk| {
+            if failed.is_some() {
+                return;
+            }
+            total.update(chunk);
+            file_size += chunk.len() as u64;
+
+            while !chunk.is_empty() {
+                let take = (window - in_window).min(chunk.len() as u64) as usize;
+                current.update(&chunk[..take]);
+                in_window += take as u64;
+                chunk = &chunk[take..];
+
+                if in_window == window {
+                    let next_start = (windows.len() as u64 + 1) * window;
+                    match AlgorithmHasher::with_length(algorithm, options, window_length(next_start)) {
+                        Ok(next) => windows.push(std::mem::replace(&mut current, next).finalize()),
+                        Err(e) => {
+                            failed = Some(e);
+                            return;
+                        }
+                    }
+                    in_window = 0;
+                }
+            }
+        })?;
+        if let Some(e) = failed {
+            return Err(e);
+        }
+
+        if in_window > 0 {
+            windows.push(current.finalize());
+        }
+
+        Ok(PiecewiseManifest {
+            algorithm: algorithm.clone(),
+            label: Some(algorithm.label(options)),
+            window,
+            file_size,
+            windows,
+            total: total.finalize(),
+        })
+    }
+
+    // dcfldd-style "start - end: digest" lines behind a commented header
+    pub fn to_text(&self, file_name: &str) -> String {
+        let mut text = String::from("# RealOne piecewise hashes\n");
+        text.push_str(&format!("# file: {}\n", file_name));
+        text.push_str(&format!("# algorithm: {}\n", self.algorithm));
+        if let Some(label) = &self.label {
+            text.push_str(&format!("# label: {}\n", label));
+        }
+        text.push_str(&format!("# window: {}\n", self.window));
+        text.push_str(&format!("# size: {}\n", self.file_size));
+
+        for (index, digest) in self.windows.iter().enumerate() {
+            let start = index as u64 * self.window;
+            let end = (start + self.window).min(self.file_size);
+            text.push_str(&format!("{} - {}: {}\n", start, end, digest));
+        }
+        text.push_str(&format!("Total ({}): {}\n", self.algorithm, self.total));
+        text
+    }
+
+    // Reads our own format as well as a plain dcfldd hashwindow log, which has
+    // no header: the window then comes from the first of several lines and the
+    // algorithm from the "Total (...)" line. A log with a single line only
+    // shows that the window is at least the file size, which is what it records
+    pub fn parse(text: &str) -> Result<PiecewiseManifest, String> {
+        let mut algorithm = None;
+        let mut label = None;
+        let mut window = None;
+        let mut file_size = None;
+        let mut windows = Vec::new();
+        let mut total = None;
+        let mut end_of_data = 0u64;
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let bad_line = || format!("line {}: cannot parse \"{}\"", number + 1, line);
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('#') {
+                if let Some((key, value)) = header.split_once(':') {
+                    let value = value.trim();
+                    match key.trim() {
+                        "algorithm" => algorithm = Some(Algorithm::from_name(value).ok_or_else(bad_line)?),
+                        "label" => label = Some(value.to_string()),
+                        "window" => window = Some(value.parse::<u64>().map_err(|_| bad_line())?),
+                        "size" => file_size = Some(value.parse::<u64>().map_err(|_| bad_line())?),
+                        _ => {}
+                    }
+                }
+                continue;
+            }
+
+            let (range, digest) = line.rsplit_once(':').ok_or_else(bad_line)?;
+            let digest = digest.trim().to_lowercase();
+
+            if let Some(name) = range.trim().strip_prefix("Total") {
+                let name = name.trim();
+                let name = name
+                    .strip_prefix('(')
+                    .and_then(|n| n.strip_suffix(')'))
+                    .unwrap_or(name);
+                if algorithm.is_none() {
+                    algorithm = Some(Algorithm::from_name(name).ok_or_else(bad_line)?);
+                }
+                total = Some(digest);
+                continue;
+            }
+
+            let (start, end) = range.split_once('-').ok_or_else(bad_line)?;
+            let start: u64 = start.trim().parse().map_err(|_| bad_line())?;
+            let end: u64 = end.trim().parse().map_err(|_| bad_line())?;
+            if end <= start {
+                return Err(bad_line());
+            }
+
+            if start != end_of_data || window.is_some_and(|window| start != windows.len() as u64 * window) {
+                return Err(format!("line {}: windows are not contiguous", number + 1));
+            }
+            // A second line shows that the first one was a whole window
+            if windows.len() == 1 && window.is_none() {
+                window = Some(end_of_data);
+            }
+            windows.push(digest);
+            end_of_data = end;
+        }
+        if windows.len() == 1 && window.is_none() {
+            window = Some(end_of_data);
+        }
+
+        Ok(PiecewiseManifest {
+            algorithm: algorithm.ok_or("manifest does not name its algorithm")?,
+            label,
+            window: window.ok_or("manifest has no window size")?,
+            file_size: file_size.unwrap_or(end_of_data),
+            windows,
+            total: total.ok_or("manifest has no Total line")?,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<PiecewiseManifest, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        PiecewiseManifest::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path, file_name: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_text(file_name)).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+// Lists the byte ranges where two manifests disagree; adjacent differing
+// windows are merged into one range
+pub fn compare_manifests(
+    expected: &PiecewiseManifest,
+    actual: &PiecewiseManifest,
+) -> Result<PiecewiseComparison, String> {
+    if expected.algorithm != actual.algorithm {
+        return Err(format!(
+            "manifests use different algorithms ({} and {})",
+            expected.algorithm, actual.algorithm
+        ));
+    }
+    if let (Some(a), Some(b)) = (&expected.label, &actual.label) {
+        if a != b {
+            return Err(format!("manifests use different settings ({} and {})", a, b));
+        }
+    }
+
+    // A manifest whose single window covers the whole file, such as a dcfldd
+    // log of a file shorter than its hashwindow, fits any window at least as long
+    let fits = |single: &PiecewiseManifest, other: &PiecewiseManifest| {
+        single.windows.len() <= 1 && single.window >= single.file_size && other.window >= single.file_size
+    };
+    let window = if expected.window == actual.window || fits(actual, expected) {
+        expected.window
+    } else if fits(expected, actual) {
+        actual.window
+    } else {
+        return Err(format!(
+            "manifests use different window sizes ({} and {} bytes)",
+            expected.window, actual.window
+        ));
+    };
+
+    let end_of_data = expected.file_size.max(actual.file_size);
+    let count = expected.windows.len().max(actual.windows.len());
+    let mut differing: Vec<Range<u64>> = Vec::new();
+
+    for index in 0..count {
+        if expected.windows.get(index) == actual.windows.get(index) {
+            continue;
+        }
+        let start = index as u64 * window;
+        let end = (start + window).min(end_of_data);
+        match differing.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => differing.push(start..end),
+        }
+    }
+
+    Ok(PiecewiseComparison {
+        window,
+        windows_compared: count,
+        differing,
+        sizes: (expected.file_size, actual.file_size),
+        total_match: expected.total == actual.total && expected.file_size == actual.file_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::compute::calculate_hash_from_data;
+    use crate::test_support::ScratchDir;
+
+    fn manifest(window: u64, file_size: u64, windows: &[&str]) -> PiecewiseManifest {
+        PiecewiseManifest {
+            algorithm: Algorithm::MD5,
+            label: None,
+            window,
+            file_size,
+            windows: windows.iter().map(|digest| digest.to_string()).collect(),
+            total: "total".to_string(),
+        }
+    }
+
+    #[test]
+    fn computed_manifest_round_trips_through_text() {
+        let dir = ScratchDir::new("piecewise");
+        let path = dir.write("fox.txt", b"The quick brown fox jumps over the lazy dog");
+        let computed = PiecewiseManifest::compute(&path, &Algorithm::MD5, &HashOptions::default(), 16).unwrap();
+
+        assert_eq!(computed.file_size, 43);
+        assert_eq!(computed.windows.len(), 3);
+        assert_eq!(computed.total, "9e107d9d372bb6826bd81d3542a419d6");
+        // The last window is the short "he lazy dog"
+        let options = HashOptions::default();
+        assert_eq!(computed.windows[2], calculate_hash_from_data(b"he lazy dog", &Algorithm::MD5, &options));
+
+        let parsed = PiecewiseManifest::parse(&computed.to_text("fox.txt")).unwrap();
+        assert_eq!(parsed.algorithm, computed.algorithm);
+        assert_eq!(parsed.label, computed.label);
+        assert_eq!((parsed.window, parsed.file_size), (16, 43));
+        assert_eq!(parsed.windows, computed.windows);
+        assert_eq!(parsed.total, computed.total);
+    }
+
+    #[test]
+    fn dcfldd_log_without_header() {
+        let log = "0 - 1048576: 3B5D3C7D207E37DCEEEDD301E35E2E58\n\
+                   1048576 - 2097152: 0A3B5D3C7D207E37DCEEEDD301E35E2E\n\
+                   2097152 - 2500000: 58E35E2E0A3B5D3C7D207E37DCEEEDD3\n\
+                   Total (md5): 9E107D9D372BB6826BD81D3542A419D6\n";
+        let parsed = PiecewiseManifest::parse(log).unwrap();
+        assert_eq!(parsed.algorithm, Algorithm::MD5);
+        assert_eq!((parsed.window, parsed.file_size), (1048576, 2500000));
+        assert_eq!(parsed.windows[0], "3b5d3c7d207e37dceeedd301e35e2e58");
+        assert_eq!(parsed.total, "9e107d9d372bb6826bd81d3542a419d6");
+
+        let gap = "0 - 10: aa\n20 - 30: bb\nTotal (md5): cc\n";
+        assert!(PiecewiseManifest::parse(gap).is_err());
+        let short_middle = "0 - 10: aa\n10 - 15: bb\n15 - 25: cc\nTotal (md5): dd\n";
+        assert!(PiecewiseManifest::parse(short_middle).is_err());
+    }
+
+    #[test]
+    fn single_line_dcfldd_log_compares_with_any_longer_window() {
+        let log = PiecewiseManifest::parse("0 - 4000: aa\nTotal (md5): total\n").unwrap();
+        assert_eq!((log.window, log.file_size), (4000, 4000));
+
+        let ours = manifest(1048576, 4000, &["aa"]);
+        let comparison = compare_manifests(&ours, &log).unwrap();
+        assert_eq!(comparison.window, 1048576);
+        assert!(comparison.differing.is_empty() && comparison.total_match);
+        assert!(compare_manifests(&log, &ours).is_ok());
+
+        // Windows shorter than the file really are different
+        assert!(compare_manifests(&manifest(1000, 4000, &["a", "b", "c", "d"]), &log).is_err());
+    }
+
+    #[test]
+    fn adjacent_differing_windows_are_merged() {
+        let expected = manifest(10, 55, &["a", "b", "c", "d", "e", "f"]);
+        let actual = manifest(10, 55, &["a", "x", "x", "d", "x", "x"]);
+        let comparison = compare_manifests(&expected, &actual).unwrap();
+        assert_eq!(comparison.differing, vec![10..30, 40..55]);
+        assert_eq!(comparison.windows_compared, 6);
+
+        // Windows only one side has count as differing, up to the longer file
+        let longer = manifest(10, 72, &["a", "b", "c", "d", "e", "f", "g", "h"]);
+        let comparison = compare_manifests(&expected, &longer).unwrap();
+        assert_eq!(comparison.differing, vec![60..72]);
+        assert!(!comparison.total_match);
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+use std::ops::Range;
+
+// Part of a file to hash, either as offset/length or as bytes to skip at
+// both ends (e.g. a firmware header and signature trailer)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ByteRange {
+    OffsetLength { offset: u64, length: Option<u64> },
+    Skip { head: u64, tail: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeMode {
+    WholeFile,
+    OffsetLength,
+    SkipHeadTail,
+}
+
+impl RangeMode {
+    pub fn all() -> Vec<RangeMode> {
+        vec![RangeMode::WholeFile, RangeMode::OffsetLength, RangeMode::SkipHeadTail]
+    }
+}
+
+impl fmt::Display for RangeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RangeMode::WholeFile => "Whole file",
+            RangeMode::OffsetLength => "Offset / length",
+            RangeMode::SkipHeadTail => "Skip header / trailer",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Accepts decimal or 0x-prefixed hexadecimal, as offsets are often given in hex
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim().replace('_', "");
+    let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse::<u64>(),
+    };
+    parsed.map_err(|_| format!("\"{}\" is not a valid byte count", value))
+}
+
+impl ByteRange {
+    // Builds a range from the two GUI/CLI fields; an empty length means "to the end"
+    pub fn from_fields(mode: RangeMode, first: &str, second: &str) -> Result<Option<ByteRange>, String> {
+        let optional = |value: &str| -> Result<Option<u64>, String> {
+            if value.trim().is_empty() {
+                Ok(None)
+            } else {
+                parse_size(value).map(Some)
+            }
+        };
+
+        match mode {
+            RangeMode::WholeFile => Ok(None),
+            RangeMode::OffsetLength => Ok(Some(ByteRange::OffsetLength {
+                offset: optional(first)?.unwrap_or(0),
+                length: optional(second)?,
+            })),
+            RangeMode::SkipHeadTail => Ok(Some(ByteRange::Skip {
+                head: optional(first)?.unwrap_or(0),
+                tail: optional(second)?.unwrap_or(0),
+            })),
+        }
+    }
+
+    // Absolute byte range within a file of the given size
+    pub fn resolve(&self, file_size: u64) -> Result<Range<u64>, String> {
+        let range = match *self {
+            ByteRange::OffsetLength { offset, length } => {
+                let end = match length {
+                    Some(length) => offset.checked_add(length).ok_or("range is too large")?,
+                    None => file_size,
+                };
+                offset..end
+            }
+            ByteRange::Skip { head, tail } => {
+                let end = file_size.checked_sub(tail).ok_or("trailer is larger than the file")?;
+                head..end
+            }
+        };
+
+        if range.start > range.end || range.end > file_size {
+            return Err(format!(
+                "range {}..{} does not fit in the file ({} bytes)",
+                range.start, range.end, file_size
+            ));
+        }
+        Ok(range)
+    }
+}
+
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ByteRange::OffsetLength { offset, length: Some(length) } => {
+                write!(f, "offset {} length {}", offset, length)
+            }
+            ByteRange::OffsetLength { offset, length: None } => write!(f, "offset {} to end", offset),
+            ByteRange::Skip { head, tail } => write!(f, "skip {} head, {} tail", head, tail),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offset(offset: u64, length: Option<u64>) -> ByteRange {
+        ByteRange::OffsetLength { offset, length }
+    }
+
+    fn skip(head: u64, tail: u64) -> ByteRange {
+        ByteRange::Skip { head, tail }
+    }
+
+    #[test]
+    fn offset_at_the_end_of_the_file_is_empty() {
+        assert_eq!(offset(100, None).resolve(100), Ok(100..100));
+        assert_eq!(offset(100, Some(0)).resolve(100), Ok(100..100));
+        assert!(offset(100, Some(1)).resolve(100).is_err());
+        assert!(offset(101, None).resolve(100).is_err());
+    }
+
+    #[test]
+    fn ranges_past_the_end_are_errors() {
+        assert_eq!(offset(40, Some(60)).resolve(100), Ok(40..100));
+        assert!(offset(40, Some(61)).resolve(100).is_err());
+        assert!(offset(0, Some(1)).resolve(0).is_err());
+    }
+
+    #[test]
+    fn skips_larger_than_the_file_are_errors() {
+        assert_eq!(skip(30, 70).resolve(100), Ok(30..30));
+        assert!(skip(31, 70).resolve(100).is_err());
+        assert!(skip(101, 0).resolve(100).is_err());
+        assert_eq!(skip(0, 101).resolve(100), Err("trailer is larger than the file".to_string()));
+    }
+
+    #[test]
+    fn zero_length_is_an_empty_range() {
+        assert_eq!(offset(10, Some(0)).resolve(100), Ok(10..10));
+        assert_eq!(offset(0, Some(0)).resolve(0), Ok(0..0));
+        assert_eq!(skip(0, 0).resolve(0), Ok(0..0));
+    }
+
+    #[test]
+    fn overflowing_ranges_are_errors() {
+        assert_eq!(offset(u64::MAX, Some(1)).resolve(u64::MAX), Err("range is too large".to_string()));
+        assert_eq!(offset(1, Some(u64::MAX)).resolve(u64::MAX), Err("range is too large".to_string()));
+        assert!(skip(u64::MAX, u64::MAX).resolve(u64::MAX).is_err());
+        assert_eq!(offset(u64::MAX, Some(0)).resolve(u64::MAX), Ok(u64::MAX..u64::MAX));
+    }
+
+    #[test]
+    fn sizes_are_decimal_or_hex() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size(" 0x1_000 "), Ok(4096));
+        assert_eq!(parse_size("0X10"), Ok(16));
+        assert!(parse_size("-1").is_err());
+        assert!(parse_size("0x").is_err());
+        assert!(parse_size("18446744073709551616").is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::archive::compression::Compression;
+use crate::hash::algo::{Algorithm, HashOptions};
+use crate::hash::compute::{hash_decoded, hash_reader, AlgorithmHasher};
+use crate::hash::io::BUFFER_SIZE;
+use crate::hash::normalize::Normalization;
+use crate::hash::range::ByteRange;
+
+// Numbered pieces of one file, such as image.7z.001, .002... from 7-Zip or
+// HJSplit, or x.part01.rar, x.part02.rar... from WinRAR
+#[derive(Debug, Clone)]
+pub struct SplitSet {
+    pub name: String, // Name of the joined file, e.g. "image.7z"
+    pub parts: Vec<PathBuf>,
+    pub sizes: Vec<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PartDigests {
+    pub path: PathBuf,
+    pub size: u64,
+    pub results: Vec<(Algorithm, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitHashes {
+    pub results: Vec<(Algorithm, String)>, // Digests of the parts joined in order
+    pub parts: Vec<PartDigests>,
+}
+
+// A file name taken apart around its part number
+struct PartName {
+    prefix: String,
+    number: u64,
+    width: usize, // Digits in the name; shorter numbers are padded with zeros
+    suffix: String,
+    joined: String,
+}
+
+impl PartName {
+    // A numeric extension needs three or more digits (".001"), so names like
+    // "notes.1" are not mistaken for parts; ".partN" may be followed by one
+    // extension (".part01.rar")
+    fn parse(file_name: &str) -> Option<PartName> {
+        let dot = file_name.rfind('.')?;
+        let extension = &file_name[dot + 1..];
+        if extension.len() >= 3 && extension.bytes().all(|b| b.is_ascii_digit()) {
+            return Some(PartName {
+                prefix: file_name[..=dot].to_string(),
+                number: extension.parse().ok()?,
+                width: extension.len(),
+                suffix: String::new(),
+                joined: file_name[..dot].to_string(),
+            });
+        }
+
+        let marker = file_name.to_ascii_lowercase().rfind(".part")?;
+        let start = marker + ".part".len();
+        let digits = file_name[start..].bytes().take_while(|b| b.is_ascii_digit()).count();
+        let suffix = &file_name[start + digits..];
+        let single_extension = suffix.is_empty() || (suffix.starts_with('.') && !suffix[1..].contains('.'));
+        if digits == 0 || !single_extension {
+            return None;
+        }
+        Some(PartName {
+            prefix: file_name[..start].to_string(),
+            number: file_name[start..start + digits].parse().ok()?,
+            width: digits,
+            suffix: suffix.to_string(),
+            joined: format!("{}{}", &file_name[..marker], suffix),
+        })
+    }
+
+    fn with_number(&self, number: u64) -> String {
+        format!("{}{:0width$}{}", self.prefix, number, self.suffix, width = self.width)
+    }
+}
+
+impl SplitSet {
+    // The set a selected part belongs to, numbered from 0 or 1 up to the first
+    // missing number. A part on its own is just a file
+    pub fn detect(path: &Path) -> Option<SplitSet> {
+        let part = PartName::parse(path.file_name()?.to_str()?)?;
+        let folder = path.parent()?;
+        let first = if folder.join(part.with_number(0)).is_file() { 0 } else { 1 };
+
+        let mut parts = Vec::new();
+        let mut sizes = Vec::new();
+        for number in first.. {
+            let candidate = folder.join(part.with_number(number));
+            match fs::metadata(&candidate) {
+                Ok(metadata) if metadata.is_file() => {
+                    parts.push(candidate);
+                    sizes.push(metadata.len());
+                }
+                _ => break,
+            }
+        }
+
+        let numbers = first..first + parts.len() as u64;
+        if parts.len() < 2 || !numbers.contains(&part.number) {
+            return None;
+        }
+        Some(SplitSet { name: part.joined, parts, sizes })
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.sizes.iter().sum()
+    }
+}
+
+// Reads the parts back to back as one stream, passing every byte to the
+// hashers of the part it came from
+struct PartsReader<'a> {
+    set: &'a SplitSet,
+    current: usize,
+    file: Option<BufReader<File>>,
+    hashers: Vec<Vec<(Algorithm, Result<AlgorithmHasher, String>)>>,
+    read: Vec<u64>,
+}
+
+impl<'a> PartsReader<'a> {
+    fn new(set: &'a SplitSet, algorithms: &[Algorithm], options: &HashOptions) -> Self {
+        let hashers = set
+            .sizes
+            .iter()
+            .map(|&size| {
+                algorithms
+                    .iter()
+                    .map(|alg| (alg.clone(), AlgorithmHasher::with_length(alg, options, size)))
+                    .collect()
+            })
+            .collect();
+        PartsReader {
+            set,
+            current: 0,
+            file: None,
+            hashers,
+            read: vec![0; set.parts.len()],
+        }
+    }
+
+    // Parts that could not be read to the end get the error instead of digests
+    fn finish(self, error: Option<String>) -> Vec<PartDigests> {
+        let current = self.current;
+        self.hashers
+            .into_iter()
+            .enumerate()
+            .map(|(index, hashers)| {
+                let problem = match &error {
+                    Some(e) if index >= current => Some(e.clone()),
+                    _ if self.read[index] != self.set.sizes[index] => {
+                        Some("the part changed size while being read".to_string())
+                    }
+                    _ => None,
+                };
+                let results = hashers
+                    .into_iter()
+                    .map(|(alg, hasher)| {
+                        let result = match (&problem, hasher) {
+                            (Some(e), _) => format!("Error: {}", e),
+                            (None, Ok(h)) => h.finalize(),
+                            (None, Err(e)) => format!("Error: {}", e),
+                        };
+                        (alg, result)
+                    })
+                    .collect();
+                PartDigests {
+                    path: self.set.parts[index].clone(),
+                    size: self.set.sizes[index],
+                    results,
+                }
+            })
+            .collect()
+    }
+}
+
+impl Read for PartsReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.current < self.set.parts.len() {
+            let file = match &mut self.file {
+                Some(file) => file,
+                None => {
+                    let path = &self.set.parts[self.current];
+                    let file = File::open(path)
+                        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+                    self.file.insert(BufReader::with_capacity(BUFFER_SIZE, file))
+                }
+            };
+            let n = file.read(buf)?;
+            if n > 0 {
+                self.read[self.current] += n as u64;
+                for (_, hasher) in self.hashers[self.current].iter_mut() {
+                    if let Ok(h) = hasher {
+                        h.update(&buf[..n]);
+                    }
+                }
+                return Ok(n);
+            }
+            self.file = None;
+            self.current += 1;
+        }
+        Ok(0)
+    }
+}
+
+// Hashes the joined stream and every part in a single pass over the parts.
+// The range, decompression and text normalization apply to the joined
+// stream, since that is the file the parts were cut from
+pub fn hash_split_set(
+    set: &SplitSet,
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+    range: Option<&ByteRange>,
+    normalization: Normalization,
+    decompress: bool,
+) -> SplitHashes {
+    let mut reader = PartsReader::new(set, algorithms, options);
+    let results = match hash_joined(&mut reader, set, algorithms, options, range, normalization, decompress) {
+        Ok(results) => results,
+        Err(e) => algorithms.iter().map(|alg| (alg.clone(), format!("Error: {}", e))).collect(),
+    };
+
+    // Whatever the joined digests did not need is still read for the part digests
+    let error = io::copy(&mut reader, &mut io::sink()).err().map(|e| e.to_string());
+    SplitHashes {
+        results,
+        parts: reader.finish(error),
+    }
+}
+
+fn hash_joined(
+    reader: &mut PartsReader,
+    set: &SplitSet,
+    algorithms: &[Algorithm],
+    options: &HashOptions,
+    range: Option<&ByteRange>,
+    normalization: Normalization,
+    decompress: bool,
+) -> Result<Vec<(Algorithm, String)>, String> {
+    let compression = if decompress { Compression::detect_file(&set.parts[0]) } else { Compression::None };
+    if compression != Compression::None {
+        if range.is_some() {
+            return Err("a byte range cannot be used with decompressed contents".to_string());
+        }
+        // The length pass reads the parts again without touching their digests
+        let open_for_length = || Ok(PartsReader::new(set, &[], options));
+        let open = move || Ok(reader);
+        return Ok(hash_decoded(open_for_length, open, algorithms, options, compression, normalization));
+    }
+
+    let total = set.total_size();
+    let span = match range {
+        Some(range) => range.resolve(total)?,
+        None => 0..total,
+    };
+    io::copy(&mut (&mut *reader).take(span.start), &mut io::sink()).map_err(|e| e.to_string())?;
+    let length = span.end - span.start;
+    hash_reader(&mut (&mut *reader).take(length), algorithms, options, Some(length), normalization)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+    use crate::hash::compute::calculate_hashes_parallel_streaming;
+    use std::io::Write;
+
+    // A gzip file on its own and cut into three parts must hash the same,
+    // including the algorithms that are refused for decompressed data
+    #[test]
+    fn compressed_set_hashes_like_the_joined_file() {
+        let dir = ScratchDir::new("split-gzip");
+        let text: Vec<u8> = (0..20_000).flat_map(|i| format!("line {}\r\n", i).into_bytes()).collect();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&text).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let joined = dir.write("data.gz", &compressed);
+        let third = compressed.len() / 3;
+        for (number, part) in compressed.chunks(third + 1).enumerate() {
+            dir.write(format!("data.gz.{:03}", number + 1), part);
+        }
+        let set = SplitSet::detect(&dir.join("data.gz.002")).unwrap();
+        assert_eq!(set.name, "data.gz");
+        assert_eq!(set.parts.len(), 3);
+
+        let algorithms = [Algorithm::SHA256, Algorithm::CRC32, Algorithm::GITSHA1, Algorithm::AHASH];
+        let options = HashOptions::default();
+        let line_endings = Normalization { line_endings: true, ..Normalization::default() };
+        for normalization in [Normalization::default(), line_endings] {
+            let single = calculate_hashes_parallel_streaming(&joined, &algorithms, &options, None, normalization, true);
+            let split = hash_split_set(&set, &algorithms, &options, None, normalization, true);
+            assert_eq!(split.results, single);
+            assert!(split.results[0].1.len() == 64 && !split.results[0].1.starts_with("Error"));
+            assert!(split.results[3].1.starts_with("Error"));
+            assert_eq!(split.results[2].1.starts_with("Error"), normalization.is_active());
+        }
+
+        let plain = dir.write("data", &text);
+        let expected = calculate_hashes_parallel_streaming(&plain, &algorithms[..3], &options, None, Normalization::default(), false);
+        let split = hash_split_set(&set, &algorithms[..3], &options, None, Normalization::default(), true);
+        assert_eq!(split.results, expected);
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+
+use crate::hash::normalize::Normalization;
+
+// How the text typed or pasted in the main window is turned into bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Hex,
+    Base64,
+}
+
+impl TextEncoding {
+    pub fn all() -> Vec<TextEncoding> {
+        vec![
+            TextEncoding::Utf8,
+            TextEncoding::Utf16Le,
+            TextEncoding::Hex,
+            TextEncoding::Base64,
+        ]
+    }
+
+    // Hex and base64 describe raw bytes, so line breaks in them are just formatting
+    pub fn is_text(&self) -> bool {
+        matches!(self, TextEncoding::Utf8 | TextEncoding::Utf16Le)
+    }
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Hex => "Hex",
+            TextEncoding::Base64 => "Base64",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// `echo` adds a newline and `echo -n` does not; digests of "the same string"
+// differ for exactly this reason, so it is chosen explicitly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingNewline {
+    AsEntered,
+    Strip,
+    EnsureLf,
+    EnsureCrLf,
+}
+
+impl TrailingNewline {
+    pub fn all() -> Vec<TrailingNewline> {
+        vec![
+            TrailingNewline::AsEntered,
+            TrailingNewline::Strip,
+            TrailingNewline::EnsureLf,
+            TrailingNewline::EnsureCrLf,
+        ]
+    }
+
+    pub fn apply<'a>(&self, text: &'a str) -> std::borrow::Cow<'a, str> {
+        let stripped = text.trim_end_matches(['\r', '\n']);
+        match self {
+            TrailingNewline::AsEntered => text.into(),
+            TrailingNewline::Strip => stripped.into(),
+            TrailingNewline::EnsureLf => format!("{}\n", stripped).into(),
+            TrailingNewline::EnsureCrLf => format!("{}\r\n", stripped).into(),
+        }
+    }
+}
+
+impl fmt::Display for TrailingNewline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TrailingNewline::AsEntered => "Newline as entered",
+            TrailingNewline::Strip => "No trailing newline",
+            TrailingNewline::EnsureLf => "End with LF",
+            TrailingNewline::EnsureCrLf => "End with CRLF",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Bytes to hash for the entered text; whitespace is ignored in hex and base64.
+// Normalization runs on the text before it is encoded, so that UTF-16 line
+// endings are normalized too, and on the decoded bytes otherwise
+pub fn decode_text_input(
+    text: &str,
+    encoding: TextEncoding,
+    newline: TrailingNewline,
+    normalization: Normalization,
+) -> Result<Vec<u8>, String> {
+    let compact = || text.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    let normalize_text = || normalization.prepare(newline.apply(text).as_bytes());
+
+    match encoding {
+        TextEncoding::Utf8 => normalize_text(),
+        TextEncoding::Utf16Le => Ok(String::from_utf8_lossy(&normalize_text()?)
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()),
+        TextEncoding::Hex => {
+            let compact = compact();
+            let digits = compact
+                .strip_prefix("0x")
+                .or_else(|| compact.strip_prefix("0X"))
+                .unwrap_or(&compact);
+            hex::decode(digits)
+                .map_err(|e| format!("invalid hex: {}", e))
+                .and_then(|data| normalization.prepare(&data))
+        }
+        TextEncoding::Base64 => {
+            use base64::Engine;
+            let compact = compact();
+            // URL-safe input ('-' and '_') and missing padding are both common in tokens
+            let standard = compact.replace('-', "+").replace('_', "/");
+            base64::engine::general_purpose::STANDARD_NO_PAD
+                .decode(standard.trim_end_matches('='))
+                .map_err(|e| format!("invalid base64: {}", e))
+                .and_then(|data| normalization.prepare(&data))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(text: &str, encoding: TextEncoding) -> Result<Vec<u8>, String> {
+        decode_text_input(text, encoding, TrailingNewline::AsEntered, Normalization::default())
+    }
+
+    #[test]
+    fn hex_input() {
+        assert_eq!(decode("48656c6c6f", TextEncoding::Hex), Ok(b"Hello".to_vec()));
+        assert_eq!(decode("48656C6C6F", TextEncoding::Hex), Ok(b"Hello".to_vec()));
+        assert_eq!(decode(" 48 65\n6c 6c\t6f\r\n", TextEncoding::Hex), Ok(b"Hello".to_vec()));
+        assert_eq!(decode("0x4865", TextEncoding::Hex), Ok(b"He".to_vec()));
+        assert_eq!(decode("0X 48 65", TextEncoding::Hex), Ok(b"He".to_vec()));
+        assert_eq!(decode("", TextEncoding::Hex), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn invalid_hex_is_an_error() {
+        for input in ["486", "0x486", "48zz", "48 0x65", "0x0x48"] {
+            let error = decode(input, TextEncoding::Hex).unwrap_err();
+            assert!(error.starts_with("invalid hex"), "{}: {}", input, error);
+        }
+    }
+
+    #[test]
+    fn base64_input() {
+        assert_eq!(decode("SGVsbG8=", TextEncoding::Base64), Ok(b"Hello".to_vec()));
+        assert_eq!(decode("SGVsbG8", TextEncoding::Base64), Ok(b"Hello".to_vec()));
+        assert_eq!(decode("SGVs\nbG8=\n", TextEncoding::Base64), Ok(b"Hello".to_vec()));
+        assert_eq!(decode("SGk=", TextEncoding::Base64), Ok(b"Hi".to_vec()));
+        assert_eq!(decode("SGk", TextEncoding::Base64), Ok(b"Hi".to_vec()));
+        // 0xfb 0xff is "+/8=" in the standard alphabet and "-_8" URL-safe
+        assert_eq!(decode("+/8=", TextEncoding::Base64), Ok(vec![0xfb, 0xff]));
+        assert_eq!(decode("-_8", TextEncoding::Base64), Ok(vec![0xfb, 0xff]));
+    }
+
+    #[test]
+    fn invalid_base64_is_an_error() {
+        for input in ["SGV$bG8=", "S", "SG=k", "SGVsbG8h*"] {
+            let error = decode(input, TextEncoding::Base64).unwrap_err();
+            assert!(error.starts_with("invalid base64"), "{}: {}", input, error);
+        }
+    }
+
+    #[test]
+    fn text_is_encoded_after_the_newline_and_normalization() {
+        let line_endings = Normalization { line_endings: true, ..Normalization::default() };
+        let utf16 = decode_text_input("a\r\nb\r\n", TextEncoding::Utf16Le, TrailingNewline::Strip, line_endings);
+        assert_eq!(utf16, Ok(vec![b'a', 0, b'\n', 0, b'b', 0]));
+        let utf8 = decode_text_input("abc", TextEncoding::Utf8, TrailingNewline::EnsureCrLf, Normalization::default());
+        assert_eq!(utf8, Ok(b"abc\r\n".to_vec()));
+    }
+}
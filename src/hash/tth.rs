@@ -0,0 +1,125 @@
+use digest::Digest;
+use tiger::Tiger;
+
+// THEX leaf size; leaves and internal nodes get distinct one-byte prefixes
+const LEAF_SIZE: usize = 1024;
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+// Tiger Tree Hash computed incrementally: finished subtrees are kept on a
+// stack with their height, and two subtrees of the same height are merged
+// as soon as both exist, so memory stays logarithmic in the file size. All of
+// it is plain data, which is also what a checkpoint saves
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TthHasher {
+    leaf: Vec<u8>,
+    leaves: u64,
+    stack: Vec<(u32, Vec<u8>)>,
+}
+
+fn leaf_hash(data: &[u8]) -> Vec<u8> {
+    let mut hasher = Tiger::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(data);
+    hasher.finalize().to_vec()
+}
+
+fn node_hash(left: &[u8], right: &[u8]) -> Vec<u8> {
+    let mut hasher = Tiger::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().to_vec()
+}
+
+impl TthHasher {
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // A full leaf is only hashed once more data arrives, so the
+            // last leaf (possibly full) is always handled by finalize()
+            if self.leaf.len() == LEAF_SIZE {
+                self.push_leaf();
+            }
+
+            let take = (LEAF_SIZE - self.leaf.len()).min(data.len());
+            self.leaf.extend_from_slice(&data[..take]);
+            data = &data[take..];
+        }
+    }
+
+    fn push_leaf(&mut self) {
+        let mut height = 0;
+        let mut hash = leaf_hash(&self.leaf);
+        self.leaf.clear();
+        self.leaves += 1;
+
+        while let Some((top_height, _)) = self.stack.last() {
+            if *top_height != height {
+                break;
+            }
+            let (_, left) = self.stack.pop().unwrap();
+            hash = node_hash(&left, &hash);
+            height += 1;
+        }
+        self.stack.push((height, hash));
+    }
+
+    pub fn finalize(mut self) -> Vec<u8> {
+        // An empty file still has one (empty) leaf
+        if !self.leaf.is_empty() || self.leaves == 0 {
+            self.push_leaf();
+        }
+
+        // Remaining subtrees have decreasing heights; an unpaired right
+        // subtree is promoted unchanged, which folding right to left gives
+        let mut root = self.stack.pop().map(|(_, hash)| hash).unwrap_or_default();
+        while let Some((_, left)) = self.stack.pop() {
+            root = node_hash(&left, &root);
+        }
+        root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tth(data: &[u8], split: usize) -> String {
+        let mut hasher = TthHasher::default();
+        data.chunks(split).for_each(|chunk| hasher.update(chunk));
+        base32::encode(base32::Alphabet::Rfc4648 { padding: false }, &hasher.finalize())
+    }
+
+    // THEX test vectors
+    #[test]
+    fn thex_vectors() {
+        assert_eq!(tth(b"", 1), "LWPNACQDBZRYXW3VHJVCJ64QBZNGHOHHHZWCLNQ");
+        assert_eq!(tth(b"\0", 1), "VK54ZIEEVTWNAUI5D5RDFIL37LX2IQNSTAXFKSA");
+        for split in [1, 100, 1024, 4096] {
+            assert_eq!(tth(&[b'A'; 1024], split), "L66Q4YVNAFWVS23X2HJIRA5ZJ7WXR3F26RSASFA");
+            assert_eq!(tth(&[b'A'; 1025], split), "PZMRYHGY6LTBEH63ZWAHDORHSYTLO4LEFUIKHWY");
+        }
+    }
+
+    // Trees whose leaf count is not a power of two promote the unpaired
+    // right subtree; the incremental stack must agree with building levels
+    #[test]
+    fn stack_matches_level_by_level_tree() {
+        for leaves in [2usize, 3, 4, 5, 7, 8, 9] {
+            let data: Vec<u8> = (0..leaves * LEAF_SIZE - 10).map(|i| i as u8).collect();
+            let mut level: Vec<Vec<u8>> = data.chunks(LEAF_SIZE).map(leaf_hash).collect();
+            while level.len() > 1 {
+                level = level
+                    .chunks(2)
+                    .map(|pair| match pair {
+                        [left, right] => node_hash(left, right),
+                        [single] => single.clone(),
+                        _ => unreachable!(),
+                    })
+                    .collect();
+            }
+            let expected = base32::encode(base32::Alphabet::Rfc4648 { padding: false }, &level[0]);
+            assert_eq!(tth(&data, 777), expected, "{} leaves", leaves);
+        }
+    }
+}
@@ -2,18 +2,26 @@ use digest::Digest;
 use gost94::{Gost94CryptoPro, Gost94Test};
 use tiger::{Tiger, Tiger2};
 
-use crate::hash::algo::{GostVariant, TigerVariant};
+use crate::hash::algo::{Blake2Params, GostVariant, TigerVariant};
 
+#[derive(Clone)]
 pub enum TigerHasher {
     Tiger(Tiger),
     Tiger2(Tiger2),
 }
 
+#[derive(Clone)]
 pub enum GostHasher {
     Crypto(Gost94CryptoPro),
     Test(Gost94Test),
 }
 
+#[derive(Clone)]
+pub enum Blake2Hasher {
+    B(blake2b_simd::State),
+    S(blake2s_simd::State),
+}
+
 fn reverse_words8(bytes: &mut [u8]) {
     for chunk in bytes.chunks_mut(8) {
         chunk.reverse();
@@ -69,3 +77,173 @@ impl GostHasher {
     }
 }
 
+
+// Decodes an optional hex parameter and checks it against the BLAKE2 size limit
+fn decode_blake2_param(name: &str, value: &str, max_len: usize) -> Result<Vec<u8>, String> {
+    let bytes = hex::decode(value.trim().replace(' ', ""))
+        .map_err(|e| format!("invalid {} hex: {}", name, e))?;
+    if bytes.len() > max_len {
+        return Err(format!("{} must be at most {} bytes", name, max_len));
+    }
+    Ok(bytes)
+}
+
+fn check_blake2_length(bits: usize, max_bytes: usize) -> Result<usize, String> {
+    if bits == 0 || !bits.is_multiple_of(8) || bits / 8 > max_bytes {
+        return Err(format!("digest length must be a multiple of 8 up to {} bits", max_bytes * 8));
+    }
+    Ok(bits / 8)
+}
+
+impl Blake2Hasher {
+    pub fn blake2b(params: &Blake2Params) -> Result<Self, String> {
+        let len = check_blake2_length(params.digest_bits, blake2b_simd::OUTBYTES)?;
+        let key = decode_blake2_param("key", &params.key, blake2b_simd::KEYBYTES)?;
+        let salt = decode_blake2_param("salt", &params.salt, blake2b_simd::SALTBYTES)?;
+        let personal = decode_blake2_param("personalization", &params.personal, blake2b_simd::PERSONALBYTES)?;
+
+        let state = blake2b_simd::Params::new()
+            .hash_length(len)
+            .key(&key)
+            .salt(&salt)
+            .personal(&personal)
+            .to_state();
+        Ok(Blake2Hasher::B(state))
+    }
+
+    pub fn blake2s(params: &Blake2Params) -> Result<Self, String> {
+        let len = check_blake2_length(params.digest_bits, blake2s_simd::OUTBYTES)?;
+        let key = decode_blake2_param("key", &params.key, blake2s_simd::KEYBYTES)?;
+        let salt = decode_blake2_param("salt", &params.salt, blake2s_simd::SALTBYTES)?;
+        let personal = decode_blake2_param("personalization", &params.personal, blake2s_simd::PERSONALBYTES)?;
+
+        let state = blake2s_simd::Params::new()
+            .hash_length(len)
+            .key(&key)
+            .salt(&salt)
+            .personal(&personal)
+            .to_state();
+        Ok(Blake2Hasher::S(state))
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Blake2Hasher::B(h) => {
+                h.update(data);
+            }
+            Blake2Hasher::S(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Blake2Hasher::B(h) => h.finalize().as_bytes().to_vec(),
+            Blake2Hasher::S(h) => h.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hash::algo::{Algorithm, Blake2Params, HashOptions};
+    use crate::hash::compute::calculate_hash_from_data;
+
+    fn params(digest_bits: usize, key: &str, salt: &str, personal: &str) -> Blake2Params {
+        Blake2Params {
+            digest_bits,
+            key: key.to_string(),
+            salt: salt.to_string(),
+            personal: personal.to_string(),
+        }
+    }
+
+    fn blake2b(data: &[u8], params: Blake2Params) -> String {
+        let options = HashOptions { blake2b: params, ..HashOptions::default() };
+        calculate_hash_from_data(data, &Algorithm::BLAKE2b, &options)
+    }
+
+    fn blake2s(data: &[u8], params: Blake2Params) -> String {
+        let options = HashOptions { blake2s: params, ..HashOptions::default() };
+        calculate_hash_from_data(data, &Algorithm::BLAKE2s, &options)
+    }
+
+    // 0x00, 0x01... as the reference implementation's keyed test vectors use
+    fn counting(length: u8) -> String {
+        hex::encode((0..length).collect::<Vec<u8>>())
+    }
+
+    // RFC 7693 appendices A and B
+    #[test]
+    fn rfc_7693_abc() {
+        assert_eq!(
+            blake2b(b"abc", Blake2Params::blake2b_default()),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(
+            blake2s(b"abc", Blake2Params::blake2s_default()),
+            "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982"
+        );
+    }
+
+    // blake2b-kat.txt and blake2s-kat.txt from the reference implementation
+    #[test]
+    fn keyed_reference_vectors() {
+        assert_eq!(
+            blake2b(b"", params(512, &counting(64), "", "")),
+            "10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568"
+        );
+        let message: Vec<u8> = (0..255).collect();
+        assert_eq!(
+            blake2b(&message, params(512, &counting(64), "", "")),
+            "142709d62e28fcccd0af97fad0f8465b971e82201dc51070faa0372aa43e92484be1c1e73ba10906d5d1853db6a4106e0a7bf9800d373d6dee2d46d62ef2a461"
+        );
+        assert_eq!(
+            blake2s(b"", params(256, &counting(32), "", "")),
+            "48a8997da407876b3d79c0d92325ad3b89cbb754d86ab71aee047ad345fd2c49"
+        );
+    }
+
+    // Checked against Python's hashlib.blake2b/blake2s, which wrap the
+    // reference implementation
+    #[test]
+    fn shorter_digests_salt_and_personalization() {
+        assert_eq!(
+            blake2b(b"abc", params(256, "", "", "")),
+            "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"
+        );
+        assert_eq!(
+            blake2b(b"abc", params(160, "", &counting(16), "706572736f6e616c697a6174696f6e21")),
+            "6f85dac17aa922b02f4eb0be654228a631c7da8c"
+        );
+        assert_eq!(
+            blake2s(b"abc", params(256, "", "0001020304050607", "5265616c4f6e6521")),
+            "ac04daf48cd7b68474c692c172f416ef50fe3f0dad45f90af1ca0b51a68626df"
+        );
+        assert_eq!(
+            blake2s(b"abc", params(256, "736563726574", "00 01 02 03 04 05 06 07", "5265616C4F6E6521")),
+            "7731797b321fbda01e7ba383ce5e7f52fa356863afcae07c064d392fcfdd1855"
+        );
+    }
+
+    #[test]
+    fn invalid_parameters_are_errors() {
+        assert!(blake2b(b"", params(520, "", "", "")).starts_with("Error"));
+        assert!(blake2b(b"", params(250, "", "", "")).starts_with("Error"));
+        assert!(blake2s(b"", params(256, &counting(33), "", "")).starts_with("Error"));
+        assert!(blake2s(b"", params(256, "", "0001", "zz")).starts_with("Error"));
+    }
+
+    #[test]
+    fn label_records_the_settings() {
+        let label = |params| Algorithm::BLAKE2s.label(&HashOptions { blake2s: params, ..HashOptions::default() });
+        assert_eq!(label(Blake2Params::blake2s_default()), "BLAKE2s-256");
+        assert_eq!(label(params(128, "736563726574", "", "")), "BLAKE2s-128 (keyed)");
+        assert_eq!(label(params(128, "73 65 63 72 65 74", "", "")), "BLAKE2s-128 (keyed)");
+        assert_eq!(
+            label(params(256, &counting(32), "0001020304050607", "5265616C4F6E6521")),
+            "BLAKE2s-256 (keyed, salt 0001020304050607, personal 5265616c4f6e6521)"
+        );
+    }
+}
@@ -1,8 +1,17 @@
 mod state;
 mod app;
 mod config;
+mod cli;
+mod export;
 mod ui;
 mod hash;
+mod torrent;
+mod oci;
+mod rdiff;
+mod binary;
+mod archive;
+#[cfg(test)]
+mod test_support;
 
 use state::RealOne;
 use state::Message;
@@ -11,14 +20,15 @@ use app::{update, view, title, subscription};
 use config::load_config;
 
 fn main() -> iced::Result {
-    // Load saved configuration
-    let (saved_algorithms, saved_format, saved_gost_variant, saved_tiger_variant) = load_config();
+    // Any arguments select the command-line mode instead of the GUI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(args));
+    }
     
+    // Load saved configuration
     let mut state = RealOne::default();
-    state.selected_algorithms = saved_algorithms;
-    state.hash_format = saved_format;
-    state.gost_variant = saved_gost_variant;
-    state.tiger_variant = saved_tiger_variant;
+    load_config(&mut state);
     
     // Open main window at startup using WindowManager
     let (_main_window_id, main_window_task) = state.window_manager.open(Box::new(MainWindow));
@@ -0,0 +1,124 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// Media types whose blobs list further descriptors; Docker's v2 schema 2
+// types are still common in layouts written by older tools
+pub const INDEX_TYPES: [&str; 2] = [
+    "application/vnd.oci.image.index.v1+json",
+    "application/vnd.docker.distribution.manifest.list.v2+json",
+];
+pub const MANIFEST_TYPES: [&str; 2] = [
+    "application/vnd.oci.image.manifest.v1+json",
+    "application/vnd.docker.distribution.manifest.v2+json",
+];
+
+// Reference to a blob by digest and size, as found in index.json and manifests
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Descriptor {
+    #[serde(default)]
+    pub media_type: String,
+    pub digest: String,
+    pub size: u64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageIndex {
+    #[serde(default)]
+    pub manifests: Vec<Descriptor>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageManifest {
+    pub config: Option<Descriptor>,
+    #[serde(default)]
+    pub layers: Vec<Descriptor>,
+    pub subject: Option<Descriptor>,
+}
+
+impl ImageManifest {
+    pub fn descriptors(&self) -> Vec<Descriptor> {
+        self.config.iter().chain(&self.layers).chain(&self.subject).cloned().collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    Sha256,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    fn hex_length(&self) -> usize {
+        match self {
+            DigestAlgorithm::Sha256 => 64,
+            DigestAlgorithm::Sha512 => 128,
+        }
+    }
+}
+
+impl fmt::Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DigestAlgorithm::Sha256 => "sha256",
+            DigestAlgorithm::Sha512 => "sha512",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// A parsed "algorithm:hex" digest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobDigest {
+    pub algorithm: DigestAlgorithm,
+    pub hex: String,
+}
+
+impl BlobDigest {
+    // The hex part becomes a file name, so anything but lowercase hex of the
+    // right length is rejected
+    pub fn parse(digest: &str) -> Result<BlobDigest, String> {
+        let (algorithm, hex) = digest
+            .split_once(':')
+            .ok_or_else(|| format!("\"{}\" is not an algorithm:hex digest", digest))?;
+        let algorithm = match algorithm {
+            "sha256" => DigestAlgorithm::Sha256,
+            "sha512" => DigestAlgorithm::Sha512,
+            other => return Err(format!("unsupported digest algorithm \"{}\"", other)),
+        };
+        let valid = hex.len() == algorithm.hex_length()
+            && hex.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+        if !valid {
+            return Err(format!("\"{}\" is not a valid {} digest", digest, algorithm));
+        }
+        Ok(BlobDigest { algorithm, hex: hex.to_string() })
+    }
+
+    // Content-addressed location: blobs/<algorithm>/<hex>
+    pub fn path_in(&self, layout: &Path) -> PathBuf {
+        layout.join("blobs").join(self.algorithm.to_string()).join(&self.hex)
+    }
+}
+
+impl fmt::Display for BlobDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.hex)
+    }
+}
+
+// The oci-layout marker file names the layout version
+pub fn check_marker(layout: &Path) -> Result<String, String> {
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Marker {
+        image_layout_version: String,
+    }
+
+    let path = layout.join("oci-layout");
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("{} is not an OCI image layout ({}: {})", layout.display(), path.display(), e))?;
+    let marker: Marker = serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(marker.image_layout_version)
+}
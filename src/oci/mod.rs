@@ -0,0 +1,4 @@
+pub mod layout;
+pub mod verify;
+
+pub use verify::{verify_layout, BlobStatus, LayoutReport};
@@ -0,0 +1,325 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::Path;
+
+use crate::hash::compute::{calculate_hash_from_data, AlgorithmHasher};
+use crate::hash::io::read_chunks;
+use crate::hash::{Algorithm, HashOptions};
+use crate::oci::layout::{
+    check_marker, BlobDigest, Descriptor, DigestAlgorithm, ImageIndex, ImageManifest, INDEX_TYPES, MANIFEST_TYPES,
+};
+
+// Registries refuse manifests over 4 MiB, so a blob of unknown type that is
+// larger is not read whole to see whether it is one
+const MAX_MANIFEST_SIZE: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlobStatus {
+    Ok,
+    Missing,
+    WrongSize(u64), // Size found on disk
+    Corrupt(String), // Digest of the data found on disk
+    Invalid(String), // Bad digest in the descriptor, or a manifest that cannot be read
+}
+
+#[derive(Debug, Clone)]
+pub struct BlobReport {
+    pub digest: String,
+    pub media_type: String,
+    pub size: u64,
+    pub status: BlobStatus,
+}
+
+#[derive(Debug, Clone)]
+pub struct LayoutReport {
+    pub layout_version: String,
+    pub manifests: usize, // Indexes and manifests that were walked
+    pub blobs: Vec<BlobReport>,
+    pub unreferenced: Vec<String>, // Blobs on disk that nothing points to
+}
+
+impl LayoutReport {
+    pub fn is_ok(&self) -> bool {
+        self.blobs.iter().all(|blob| blob.status == BlobStatus::Ok)
+    }
+}
+
+// Walks index.json and every index and manifest it leads to, checking each
+// referenced blob against its size and digest; blobs shared by several
+// images are checked once
+pub fn verify_layout(layout: &Path) -> Result<LayoutReport, String> {
+    let layout_version = check_marker(layout)?;
+
+    let index_path = layout.join("index.json");
+    let index = std::fs::read(&index_path).map_err(|e| format!("{}: {}", index_path.display(), e))?;
+    let index: ImageIndex = serde_json::from_slice(&index).map_err(|e| format!("{}: {}", index_path.display(), e))?;
+
+    let mut queue: VecDeque<Descriptor> = index.manifests.into();
+    let mut seen = HashSet::new();
+    let mut blobs = Vec::new();
+    let mut manifests = 0;
+
+    while let Some(descriptor) = queue.pop_front() {
+        if !seen.insert(descriptor.digest.clone()) {
+            continue;
+        }
+
+        let (status, children) = check_blob(layout, &descriptor);
+        if let Some(children) = children {
+            manifests += 1;
+            queue.extend(children);
+        }
+        blobs.push(BlobReport {
+            digest: descriptor.digest,
+            media_type: descriptor.media_type,
+            size: descriptor.size,
+            status,
+        });
+    }
+
+    Ok(LayoutReport {
+        layout_version,
+        manifests,
+        blobs,
+        unreferenced: unreferenced_blobs(layout, &seen),
+    })
+}
+
+fn hash_algorithm(algorithm: DigestAlgorithm) -> Algorithm {
+    match algorithm {
+        DigestAlgorithm::Sha256 => Algorithm::SHA256,
+        DigestAlgorithm::Sha512 => Algorithm::SHA512,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlobKind {
+    Index,
+    Manifest,
+    Other, // Layers, configs and anything else that lists no descriptors
+}
+
+impl BlobKind {
+    fn of(media_type: &str) -> Self {
+        if INDEX_TYPES.contains(&media_type) {
+            BlobKind::Index
+        } else if MANIFEST_TYPES.contains(&media_type) {
+            BlobKind::Manifest
+        } else {
+            BlobKind::Other
+        }
+    }
+
+    // For a descriptor without a mediaType: the document's own mediaType, or
+    // failing that the fields an index or manifest must have
+    fn of_document(data: &[u8]) -> Self {
+        let Ok(serde_json::Value::Object(document)) = serde_json::from_slice(data) else {
+            return BlobKind::Other;
+        };
+        if let Some(media_type) = document.get("mediaType").and_then(|t| t.as_str()) {
+            return BlobKind::of(media_type);
+        }
+        let schema_2 = document.get("schemaVersion").and_then(|v| v.as_u64()) == Some(2);
+        if schema_2 && document.get("manifests").is_some_and(|m| m.is_array()) {
+            BlobKind::Index
+        } else if schema_2 && document.get("layers").is_some_and(|l| l.is_array()) {
+            BlobKind::Manifest
+        } else {
+            BlobKind::Other
+        }
+    }
+}
+
+// Status of one blob plus, for an intact index or manifest, the descriptors it lists
+fn check_blob(layout: &Path, descriptor: &Descriptor) -> (BlobStatus, Option<Vec<Descriptor>>) {
+    let digest = match BlobDigest::parse(&descriptor.digest) {
+        Ok(digest) => digest,
+        Err(e) => return (BlobStatus::Invalid(e), None),
+    };
+    let path = digest.path_in(layout);
+    let actual_size = match std::fs::metadata(&path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return (BlobStatus::Missing, None),
+    };
+    if actual_size != descriptor.size {
+        return (BlobStatus::WrongSize(actual_size), None);
+    }
+
+    let algorithm = hash_algorithm(digest.algorithm);
+    let options = HashOptions::default();
+    let untyped = descriptor.media_type.is_empty() && descriptor.size <= MAX_MANIFEST_SIZE;
+    let kind = BlobKind::of(&descriptor.media_type);
+
+    // Layers are streamed; indexes and manifests are small and read whole
+    // so they can be parsed once their digest checks out. So are small blobs
+    // of no stated type, which may turn out to be either
+    if kind == BlobKind::Other && !untyped {
+        let mut hasher = match AlgorithmHasher::new(&algorithm, &options) {
+            Ok(hasher) => hasher,
+            Err(e) => return (BlobStatus::Invalid(e), None),
+        };
+        if let Err(e) = read_chunks(&path, |chunk| hasher.update(chunk)) {
+            return (BlobStatus::Invalid(e), None);
+        }
+        let actual = hasher.finalize();
+        let status = if actual == digest.hex { BlobStatus::Ok } else { BlobStatus::Corrupt(actual) };
+        return (status, None);
+    }
+
+    let data = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(e) => return (BlobStatus::Invalid(e.to_string()), None),
+    };
+    let actual = calculate_hash_from_data(&data, &algorithm, &options);
+    if actual != digest.hex {
+        return (BlobStatus::Corrupt(actual), None);
+    }
+
+    let kind = if untyped { BlobKind::of_document(&data) } else { kind };
+    let children = match kind {
+        BlobKind::Index => serde_json::from_slice::<ImageIndex>(&data).map(|index| index.manifests),
+        BlobKind::Manifest => serde_json::from_slice::<ImageManifest>(&data).map(|manifest| manifest.descriptors()),
+        BlobKind::Other => return (BlobStatus::Ok, None),
+    };
+    let type_name = if untyped { "untyped blob" } else { descriptor.media_type.as_str() };
+    match children {
+        Ok(children) => (BlobStatus::Ok, Some(children)),
+        Err(e) => (BlobStatus::Invalid(format!("cannot parse {}: {}", type_name, e)), None),
+    }
+}
+
+// Everything under blobs/<algorithm>/ that the walk did not reach
+fn unreferenced_blobs(layout: &Path, seen: &HashSet<String>) -> Vec<String> {
+    let mut unreferenced = Vec::new();
+    let algorithms = match std::fs::read_dir(layout.join("blobs")) {
+        Ok(algorithms) => algorithms,
+        Err(_) => return unreferenced,
+    };
+
+    for algorithm_dir in algorithms.flatten() {
+        let entries = match std::fs::read_dir(algorithm_dir.path()) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        let algorithm = algorithm_dir.file_name().to_string_lossy().into_owned();
+        for entry in entries.flatten() {
+            let digest = format!("{}:{}", algorithm, entry.file_name().to_string_lossy());
+            if !seen.contains(&digest) {
+                unreferenced.push(digest);
+            }
+        }
+    }
+
+    unreferenced.sort();
+    unreferenced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+    use digest::Digest;
+    use serde_json::json;
+
+    // Stores data as a sha256 blob and returns its digest
+    fn blob(dir: &ScratchDir, data: &[u8]) -> String {
+        let hex = hex::encode(sha2::Sha256::digest(data));
+        dir.write(Path::new("blobs").join("sha256").join(&hex), data);
+        format!("sha256:{}", hex)
+    }
+
+    fn descriptor(media_type: &str, digest: &str, size: usize) -> serde_json::Value {
+        json!({ "mediaType": media_type, "digest": digest, "size": size })
+    }
+
+    fn status_of<'a>(report: &'a LayoutReport, digest: &str) -> &'a BlobStatus {
+        &report.blobs.iter().find(|blob| blob.digest == digest).unwrap().status
+    }
+
+    // index.json -> nested index -> manifest -> config and four layers: one
+    // intact, one missing, one whose bytes changed and one cut short. A
+    // second manifest is listed without a mediaType, and one blob on disk
+    // is referenced by nothing
+    #[test]
+    fn layout_walk_reports_every_kind_of_blob() {
+        let dir = ScratchDir::new("oci-layout");
+        std::fs::write(dir.join("oci-layout"), r#"{"imageLayoutVersion":"1.0.0"}"#).unwrap();
+        let layer_type = "application/vnd.oci.image.layer.v1.tar+gzip";
+
+        let config = br#"{"architecture":"amd64","os":"linux"}"#;
+        let config_digest = blob(&dir, config);
+        let intact = b"intact layer".to_vec();
+        let intact_digest = blob(&dir, &intact);
+        let missing_digest = format!("sha256:{}", hex::encode(sha2::Sha256::digest(b"missing layer")));
+        let corrupt_digest = blob(&dir, b"corrupt layer");
+        let corrupt_path = BlobDigest::parse(&corrupt_digest).unwrap().path_in(dir.path());
+        std::fs::write(&corrupt_path, b"CORRUPT layer").unwrap();
+        let truncated_digest = blob(&dir, b"truncated layer");
+        let truncated_path = BlobDigest::parse(&truncated_digest).unwrap().path_in(dir.path());
+        std::fs::write(&truncated_path, b"truncated").unwrap();
+        let unreferenced_digest = blob(&dir, b"left behind");
+
+        let manifest = serde_json::to_vec(&json!({
+            "schemaVersion": 2,
+            "mediaType": MANIFEST_TYPES[0],
+            "config": descriptor("application/vnd.oci.image.config.v1+json", &config_digest, config.len()),
+            "layers": [
+                descriptor(layer_type, &intact_digest, intact.len()),
+                descriptor(layer_type, &missing_digest, 13),
+                descriptor(layer_type, &corrupt_digest, 13),
+                descriptor(layer_type, &truncated_digest, 15),
+            ],
+        }))
+        .unwrap();
+        let manifest_digest = blob(&dir, &manifest);
+
+        // Shares the intact layer, which is checked only once
+        let untyped = serde_json::to_vec(&json!({
+            "schemaVersion": 2,
+            "config": descriptor("application/vnd.oci.image.config.v1+json", &config_digest, config.len()),
+            "layers": [descriptor(layer_type, &intact_digest, intact.len())],
+        }))
+        .unwrap();
+        let untyped_digest = blob(&dir, &untyped);
+
+        let nested = serde_json::to_vec(&json!({
+            "schemaVersion": 2,
+            "mediaType": INDEX_TYPES[0],
+            "manifests": [descriptor(MANIFEST_TYPES[0], &manifest_digest, manifest.len())],
+        }))
+        .unwrap();
+        let nested_digest = blob(&dir, &nested);
+
+        let index = json!({
+            "schemaVersion": 2,
+            "manifests": [
+                descriptor(INDEX_TYPES[0], &nested_digest, nested.len()),
+                { "digest": untyped_digest, "size": untyped.len() },
+                descriptor(MANIFEST_TYPES[0], "sha256:../../oci-layout", 10),
+            ],
+        });
+        std::fs::write(dir.join("index.json"), serde_json::to_vec(&index).unwrap()).unwrap();
+
+        let report = verify_layout(dir.path()).unwrap();
+        assert_eq!(report.layout_version, "1.0.0");
+        assert_eq!(report.manifests, 3);
+        assert_eq!(report.blobs.len(), 9);
+        assert!(!report.is_ok());
+
+        for digest in [&nested_digest, &untyped_digest, &manifest_digest, &config_digest, &intact_digest] {
+            assert_eq!(status_of(&report, digest), &BlobStatus::Ok, "{}", digest);
+        }
+        assert_eq!(status_of(&report, &missing_digest), &BlobStatus::Missing);
+        let changed = hex::encode(sha2::Sha256::digest(b"CORRUPT layer"));
+        assert_eq!(status_of(&report, &corrupt_digest), &BlobStatus::Corrupt(changed));
+        assert_eq!(status_of(&report, &truncated_digest), &BlobStatus::WrongSize(9));
+        assert!(matches!(status_of(&report, "sha256:../../oci-layout"), BlobStatus::Invalid(_)));
+        assert_eq!(report.unreferenced, vec![unreferenced_digest]);
+    }
+
+    #[test]
+    fn folders_without_the_marker_are_errors() {
+        let dir = ScratchDir::new("oci-no-marker");
+        std::fs::write(dir.join("index.json"), r#"{"schemaVersion":2,"manifests":[]}"#).unwrap();
+        assert!(verify_layout(dir.path()).is_err());
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::hash::io::read_chunks;
+use crate::rdiff::rollsum::WeakSum;
+use crate::rdiff::signature::Signature;
+
+const DELTA_MAGIC: u32 = 0x72730236;
+
+// Delta command bytes: END, literals of 1 to 64 bytes with the length in the
+// command itself, literals with a 1/2/4/8-byte length, then COPY with every
+// combination of 1/2/4/8-byte offset and length
+const OP_END: u8 = 0x00;
+const OP_LITERAL_N1: u8 = 0x41;
+const OP_COPY_N1_N1: u8 = 0x45;
+const MAX_LITERAL: usize = 1024 * 1024; // Literal runs are written out in pieces of at most this size
+
+#[derive(Debug, Clone, Default)]
+pub struct DeltaStats {
+    pub copies: usize, // COPY commands, after merging adjacent blocks
+    pub copied_bytes: u64,
+    pub literals: usize,
+    pub literal_bytes: u64,
+    pub delta_size: u64,
+}
+
+// Smallest of 1, 2, 4 or 8 bytes that holds the value, as an index 0 to 3
+fn int_width(value: u64) -> usize {
+    if value <= 0xff {
+        0
+    } else if value <= 0xffff {
+        1
+    } else if value <= 0xffff_ffff {
+        2
+    } else {
+        3
+    }
+}
+
+fn write_int(out: &mut impl Write, value: u64, width: usize) -> std::io::Result<()> {
+    out.write_all(&value.to_be_bytes()[8 - (1 << width)..])
+}
+
+struct DeltaWriter<W: Write> {
+    out: W,
+    stats: DeltaStats,
+    pending_copy: Option<(u64, u64)>, // Consecutive matching blocks become one COPY
+}
+
+impl<W: Write> DeltaWriter<W> {
+    fn literal(&mut self, data: &[u8]) -> std::io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        self.flush_copy()?;
+        let length = data.len() as u64;
+        if length <= 64 {
+            self.out.write_all(&[length as u8])?;
+            self.stats.delta_size += 1;
+        } else {
+            let width = int_width(length);
+            self.out.write_all(&[OP_LITERAL_N1 + width as u8])?;
+            write_int(&mut self.out, length, width)?;
+            self.stats.delta_size += 1 + (1 << width);
+        }
+        self.out.write_all(data)?;
+        self.stats.delta_size += length;
+        self.stats.literals += 1;
+        self.stats.literal_bytes += length;
+        Ok(())
+    }
+
+    fn copy(&mut self, offset: u64, length: u64) -> std::io::Result<()> {
+        match &mut self.pending_copy {
+            Some((start, pending)) if *start + *pending == offset => *pending += length,
+            _ => {
+                self.flush_copy()?;
+                self.pending_copy = Some((offset, length));
+            }
+        }
+        Ok(())
+    }
+
+    fn flush_copy(&mut self) -> std::io::Result<()> {
+        if let Some((offset, length)) = self.pending_copy.take() {
+            let (offset_width, length_width) = (int_width(offset), int_width(length));
+            self.out.write_all(&[OP_COPY_N1_N1 + (offset_width * 4 + length_width) as u8])?;
+            write_int(&mut self.out, offset, offset_width)?;
+            write_int(&mut self.out, length, length_width)?;
+            self.stats.delta_size += 1 + (1 << offset_width) + (1 << length_width);
+            self.stats.copies += 1;
+            self.stats.copied_bytes += length;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> std::io::Result<DeltaStats> {
+        self.flush_copy()?;
+        self.out.write_all(&[OP_END])?;
+        self.stats.delta_size += 1;
+        self.out.flush()?;
+        Ok(self.stats)
+    }
+}
+
+// Slides a block-sized window over the new file. Where the window matches a
+// block of the signature (weak sum first, then strong sum) a COPY from the
+// basis file is emitted; bytes that match nothing become literals
+struct Scanner<'a, W: Write> {
+    signature: &'a Signature,
+    index: HashMap<u32, Vec<usize>>,
+    buffer: Vec<u8>,
+    start: usize, // Window start in buffer
+    literal_start: usize, // Bytes from here up to `start` are still to be written as a literal
+    sum: Option<WeakSum>, // Weak sum of the current window, rolled forward byte by byte
+    checked: bool, // Whether the current window was already looked up
+    writer: DeltaWriter<W>,
+}
+
+impl<W: Write> Scanner<'_, W> {
+    // The block right after the previous match is preferred, so that runs of
+    // repeated data still become a single COPY
+    fn find_block(&self, window: &[u8], weak: u32) -> Option<usize> {
+        let candidates = self.index.get(&weak)?;
+        let strong = self.signature.format.strong_sum(window);
+        let strong = &strong[..self.signature.strong_length as usize];
+        let next = self
+            .writer
+            .pending_copy
+            .map(|(offset, length)| ((offset + length) / self.signature.block_length as u64) as usize);
+        let matches: Vec<usize> = candidates
+            .iter()
+            .copied()
+            .filter(|&i| self.signature.blocks[i].strong == strong)
+            .collect();
+        matches.iter().copied().find(|&i| Some(i) == next).or(matches.first().copied())
+    }
+
+    // Until the end of the file is reached, a full window plus the byte that
+    // follows it is needed to make progress. At the end the window shrinks so
+    // the shorter last block of the basis can still match
+    fn scan(&mut self, at_end: bool) -> std::io::Result<()> {
+        let block_length = self.signature.block_length as usize;
+
+        loop {
+            let available = self.buffer.len() - self.start;
+            let waiting = available < block_length || (available == block_length && self.checked);
+            if available == 0 || (!at_end && waiting) {
+                break;
+            }
+
+            let end = self.start + available.min(block_length);
+            let kind = self.signature.format.weak_sum();
+            let weak = self
+                .sum
+                .get_or_insert_with(|| WeakSum::over(kind, &self.buffer[self.start..end]))
+                .digest();
+
+            if !self.checked {
+                self.checked = true;
+                if let Some(block) = self.find_block(&self.buffer[self.start..end], weak) {
+                    self.writer.literal(&self.buffer[self.literal_start..self.start])?;
+                    let offset = block as u64 * block_length as u64;
+                    self.writer.copy(offset, (end - self.start) as u64)?;
+                    self.start = end;
+                    self.literal_start = end;
+                    self.sum = None;
+                    self.checked = false;
+                    continue;
+                }
+                if !at_end && end == self.buffer.len() {
+                    break;
+                }
+            }
+
+            let out = self.buffer[self.start];
+            if let Some(sum) = &mut self.sum {
+                if end < self.buffer.len() {
+                    sum.rotate(out, self.buffer[end]);
+                } else {
+                    sum.rollout(out);
+                }
+            }
+            self.start += 1;
+            self.checked = false;
+
+            if self.start - self.literal_start >= MAX_LITERAL {
+                self.writer.literal(&self.buffer[self.literal_start..self.start])?;
+                self.literal_start = self.start;
+            }
+        }
+
+        // Drop what has been written out
+        self.buffer.drain(..self.literal_start);
+        self.start -= self.literal_start;
+        self.literal_start = 0;
+        Ok(())
+    }
+}
+
+// librsync-compatible delta that turns the file the signature was made from
+// into `new_file`; the result can be applied with `rdiff patch` or apply_delta
+pub fn create_delta(signature: &Signature, new_file: &Path, target: &Path) -> Result<DeltaStats, String> {
+    let write_error = |e: std::io::Error| format!("{}: {}", target.display(), e);
+    let file = File::create(target).map_err(write_error)?;
+    let mut out = BufWriter::new(file);
+    out.write_all(&DELTA_MAGIC.to_be_bytes()).map_err(write_error)?;
+
+    let mut scanner = Scanner {
+        signature,
+        index: signature.index(),
+        buffer: Vec::new(),
+        start: 0,
+        literal_start: 0,
+        sum: None,
+        checked: false,
+        writer: DeltaWriter {
+            out,
+            stats: DeltaStats { delta_size: 4, ..DeltaStats::default() },
+            pending_copy: None,
+        },
+    };
+
+    // Write errors inside the read callback are kept until reading is done
+    let mut scan_result = Ok(());
+    read_chunks(&new_file.to_path_buf(), |data| {
+        if scan_result.is_ok() {
+            scanner.buffer.extend_from_slice(data);
+            scan_result = scanner.scan(false);
+        }
+    })
+    .map_err(|e| format!("{}: {}", new_file.display(), e))?;
+    scan_result.map_err(write_error)?;
+
+    scanner.scan(true).map_err(write_error)?;
+    let Scanner { buffer, literal_start, mut writer, .. } = scanner;
+    writer.literal(&buffer[literal_start..]).map_err(write_error)?;
+    writer.finish().map_err(write_error)
+}
+
+fn read_int(delta: &mut impl Read, width: usize) -> Result<u64, String> {
+    let mut bytes = [0u8; 8];
+    delta
+        .read_exact(&mut bytes[8 - (1 << width)..])
+        .map_err(|_| "truncated delta".to_string())?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+// Rebuilds the new file from the basis file and a delta; returns the size written
+pub fn apply_delta(basis: &Path, delta_path: &Path, target: &Path) -> Result<u64, String> {
+    let delta_error = |e: String| format!("{}: {}", delta_path.display(), e);
+    let write_error = |e: std::io::Error| format!("{}: {}", target.display(), e);
+
+    let delta = File::open(delta_path).map_err(|e| delta_error(e.to_string()))?;
+    let mut delta = BufReader::new(delta);
+    let mut magic = [0u8; 4];
+    delta.read_exact(&mut magic).map_err(|_| delta_error("not an rdiff delta (too short)".to_string()))?;
+    if u32::from_be_bytes(magic) != DELTA_MAGIC {
+        return Err(delta_error(format!("not an rdiff delta (magic {})", hex::encode(magic))));
+    }
+
+    let mut basis_file = File::open(basis).map_err(|e| format!("{}: {}", basis.display(), e))?;
+    let out = File::create(target).map_err(write_error)?;
+    let mut out = BufWriter::new(out);
+    let mut written = 0u64;
+
+    loop {
+        let mut op = [0u8; 1];
+        delta.read_exact(&mut op).map_err(|_| delta_error("delta ends without END command".to_string()))?;
+        let op = op[0];
+
+        let copied = match op {
+            OP_END => break,
+            0x01..=0x40 | OP_LITERAL_N1..=0x44 => {
+                let length = if op <= 0x40 {
+                    op as u64
+                } else {
+                    read_int(&mut delta, (op - OP_LITERAL_N1) as usize).map_err(delta_error)?
+                };
+                let copied = std::io::copy(&mut (&mut delta).take(length), &mut out).map_err(write_error)?;
+                if copied != length {
+                    return Err(delta_error("truncated literal".to_string()));
+                }
+                copied
+            }
+            OP_COPY_N1_N1..=0x54 => {
+                let widths = (op - OP_COPY_N1_N1) as usize;
+                let offset = read_int(&mut delta, widths / 4).map_err(delta_error)?;
+                let length = read_int(&mut delta, widths % 4).map_err(delta_error)?;
+                basis_file
+                    .seek(SeekFrom::Start(offset))
+                    .map_err(|e| format!("{}: {}", basis.display(), e))?;
+                let copied = std::io::copy(&mut (&mut basis_file).take(length), &mut out).map_err(write_error)?;
+                if copied != length {
+                    return Err(format!(
+                        "{}: copy of {} bytes at offset {} runs past the end of the basis file; is it the right one?",
+                        delta_path.display(),
+                        length,
+                        offset
+                    ));
+                }
+                copied
+            }
+            other => return Err(delta_error(format!("unknown delta command 0x{:02x}", other))),
+        };
+        written += copied;
+    }
+
+    out.flush().map_err(write_error)?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+    use crate::rdiff::signature::SignatureFormat;
+
+    const BLOCK_LENGTH: u32 = 64;
+
+    // Deterministic bytes that do not repeat within a block
+    fn noise(length: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    // Signature (saved and loaded again), delta and patch; returns the delta statistics
+    fn round_trip(dir: &ScratchDir, format: SignatureFormat, basis: &[u8], new: &[u8]) -> DeltaStats {
+        let basis_path = dir.write("basis", basis);
+        let new_path = dir.write("new", new);
+        let signature_path = dir.join("signature");
+        let delta_path = dir.join("delta");
+        let patched_path = dir.join("patched");
+
+        Signature::compute(&basis_path, format, BLOCK_LENGTH).unwrap().save(&signature_path).unwrap();
+        let signature = Signature::load(&signature_path).unwrap();
+        let stats = create_delta(&signature, &new_path, &delta_path).unwrap();
+        assert_eq!(stats.delta_size, std::fs::metadata(&delta_path).unwrap().len());
+
+        let size = apply_delta(&basis_path, &delta_path, &patched_path).unwrap();
+        assert_eq!(size, new.len() as u64);
+        assert!(std::fs::read(&patched_path).unwrap() == new, "{}: patched file differs", format);
+        stats
+    }
+
+    #[test]
+    fn unchanged_file_is_one_copy_including_the_short_last_block() {
+        let dir = ScratchDir::new("rdiff-unchanged");
+        let basis = noise(5 * BLOCK_LENGTH as usize + 7, 1);
+        for format in SignatureFormat::all() {
+            let stats = round_trip(&dir, format, &basis, &basis);
+            assert_eq!((stats.copies, stats.literals), (1, 0), "{}", format);
+            assert_eq!(stats.copied_bytes, basis.len() as u64, "{}", format);
+        }
+    }
+
+    #[test]
+    fn edited_file_round_trips() {
+        let dir = ScratchDir::new("rdiff-edited");
+        let basis = noise(20 * BLOCK_LENGTH as usize + 23, 2);
+        let block = BLOCK_LENGTH as usize;
+
+        // An insertion, a deletion, a changed byte, a moved block and an
+        // appended tail, with the short last block of the basis kept in the middle
+        let mut new = Vec::new();
+        new.extend_from_slice(&basis[..3 * block]);
+        new.extend_from_slice(b"inserted in the middle of the file");
+        new.extend_from_slice(&basis[3 * block..8 * block]);
+        new.extend_from_slice(&basis[9 * block + 10..15 * block]);
+        new.extend_from_slice(&basis[20 * block..]);
+        new.extend_from_slice(&basis[15 * block..20 * block]);
+        new.extend_from_slice(&noise(3 * block, 3));
+        new[block / 2] ^= 0xff;
+
+        for format in SignatureFormat::all() {
+            let stats = round_trip(&dir, format, &basis, &new);
+            assert!(stats.copied_bytes >= 15 * BLOCK_LENGTH as u64 + 23, "{}: {:?}", format, stats);
+        }
+    }
+
+    #[test]
+    fn empty_files_round_trip() {
+        let dir = ScratchDir::new("rdiff-empty");
+        let data = noise(100, 4);
+        for format in SignatureFormat::all() {
+            round_trip(&dir, format, &[], &data);
+            round_trip(&dir, format, &data, &[]);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+pub mod delta;
+pub mod rollsum;
+pub mod signature;
+
+use std::path::PathBuf;
+
+use crate::hash::Algorithm;
+
+pub use delta::{apply_delta, create_delta, DeltaStats};
+pub use signature::{Signature, SignatureFormat, DEFAULT_BLOCK_LENGTH};
+
+// Block lengths offered in the GUI; librsync's own default is 2048
+pub fn block_lengths() -> Vec<u32> {
+    vec![512, 1024, 2048, 4096, 8192, 16384, 65536]
+}
+
+#[derive(Debug, Clone)]
+pub enum RdiffOutcome {
+    Signature {
+        path: PathBuf,
+        format: SignatureFormat,
+        block_length: u32,
+        blocks: usize,
+    },
+    Delta {
+        path: PathBuf,
+        stats: DeltaStats,
+    },
+    Patched {
+        path: PathBuf,
+        size: u64,
+        hashes: Vec<(Algorithm, String)>, // The rebuilt file hashed with the selected algorithms
+    },
+}
@@ -0,0 +1,124 @@
+// Weak checksums that can be rolled one byte at a time, as used in librsync
+// signatures and deltas
+
+const ROLLSUM_CHAR_OFFSET: u16 = 31;
+
+const RABINKARP_SEED: u32 = 1;
+const RABINKARP_MULT: u32 = 0x08104225;
+const RABINKARP_INVM: u32 = 0x98f009ad; // Inverse of RABINKARP_MULT modulo 2^32
+const RABINKARP_ADJ: u32 = 0x08104224; // RABINKARP_MULT - 1, removes the seed's contribution
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeakSumKind {
+    Rollsum,
+    RabinKarp,
+}
+
+// The rsync/librsync "rollsum": Adler-32's two running sums, but without the
+// modulo and with 31 added to each byte
+#[derive(Debug, Clone, Copy)]
+pub struct Rollsum {
+    count: u16,
+    s1: u16,
+    s2: u16,
+}
+
+impl Rollsum {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.s1 = self.s1.wrapping_add(byte as u16 + ROLLSUM_CHAR_OFFSET);
+            self.s2 = self.s2.wrapping_add(self.s1);
+        }
+        self.count = self.count.wrapping_add(data.len() as u16);
+    }
+
+    fn rotate(&mut self, out: u8, input: u8) {
+        self.s1 = self.s1.wrapping_add(input as u16).wrapping_sub(out as u16);
+        self.s2 = self
+            .s2
+            .wrapping_add(self.s1)
+            .wrapping_sub(self.count.wrapping_mul(out as u16 + ROLLSUM_CHAR_OFFSET));
+    }
+
+    fn rollout(&mut self, out: u8) {
+        self.s1 = self.s1.wrapping_sub(out as u16 + ROLLSUM_CHAR_OFFSET);
+        self.s2 = self.s2.wrapping_sub(self.count.wrapping_mul(out as u16 + ROLLSUM_CHAR_OFFSET));
+        self.count = self.count.wrapping_sub(1);
+    }
+
+    fn digest(&self) -> u32 {
+        ((self.s2 as u32) << 16) | self.s1 as u32
+    }
+}
+
+// Polynomial hash used by librsync 2.3 and later; fewer collisions than rollsum
+#[derive(Debug, Clone, Copy)]
+pub struct RabinKarp {
+    hash: u32,
+    mult: u32, // RABINKARP_MULT to the power of the window length
+}
+
+impl RabinKarp {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.hash = self.hash.wrapping_mul(RABINKARP_MULT).wrapping_add(byte as u32);
+            self.mult = self.mult.wrapping_mul(RABINKARP_MULT);
+        }
+    }
+
+    fn rotate(&mut self, out: u8, input: u8) {
+        self.hash = self
+            .hash
+            .wrapping_mul(RABINKARP_MULT)
+            .wrapping_add(input as u32)
+            .wrapping_sub(self.mult.wrapping_mul(out as u32 + RABINKARP_ADJ));
+    }
+
+    fn rollout(&mut self, out: u8) {
+        self.mult = self.mult.wrapping_mul(RABINKARP_INVM);
+        self.hash = self.hash.wrapping_sub(self.mult.wrapping_mul(out as u32 + RABINKARP_ADJ));
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum WeakSum {
+    Rollsum(Rollsum),
+    RabinKarp(RabinKarp),
+}
+
+impl WeakSum {
+    pub fn over(kind: WeakSumKind, data: &[u8]) -> Self {
+        let mut sum = match kind {
+            WeakSumKind::Rollsum => WeakSum::Rollsum(Rollsum { count: 0, s1: 0, s2: 0 }),
+            WeakSumKind::RabinKarp => WeakSum::RabinKarp(RabinKarp { hash: RABINKARP_SEED, mult: 1 }),
+        };
+        match &mut sum {
+            WeakSum::Rollsum(s) => s.update(data),
+            WeakSum::RabinKarp(s) => s.update(data),
+        }
+        sum
+    }
+
+    // Slides the window one byte: `out` leaves at the front, `input` enters at the back
+    pub fn rotate(&mut self, out: u8, input: u8) {
+        match self {
+            WeakSum::Rollsum(s) => s.rotate(out, input),
+            WeakSum::RabinKarp(s) => s.rotate(out, input),
+        }
+    }
+
+    // Shrinks the window by one byte at the front, for the tail of a file
+    pub fn rollout(&mut self, out: u8) {
+        match self {
+            WeakSum::Rollsum(s) => s.rollout(out),
+            WeakSum::RabinKarp(s) => s.rollout(out),
+        }
+    }
+
+    pub fn digest(&self) -> u32 {
+        match self {
+            WeakSum::Rollsum(s) => s.digest(),
+            WeakSum::RabinKarp(s) => s.hash,
+        }
+    }
+}
@@ -1,15 +1,33 @@
 use std::collections::HashMap;
 use std::fmt;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use iced_multi_window::WindowManager;
-use crate::hash::{Algorithm, GostVariant, TigerVariant};
+use crate::torrent::TorrentReport;
+use crate::oci::LayoutReport;
+use crate::rdiff::{RdiffOutcome, SignatureFormat, DEFAULT_BLOCK_LENGTH};
+use crate::hash::piecewise::{PiecewiseOutcome, WindowSize};
+use crate::hash::cdc::{CdcParams, CdcReport};
+use crate::hash::fuzzy::SimilarityReport;
+use crate::hash::perceptual::HashDistance;
+use crate::binary::BinaryReport;
+use crate::archive::{ArchiveListing, MemberHashes};
+use crate::archive::compression::Compression;
+use crate::hash::normalize::Normalization;
+use crate::hash::range::{parse_size, ByteRange, RangeMode};
+use crate::hash::split::{PartDigests, SplitHashes, SplitSet};
+use crate::hash::checkpoint::{ResumableHashes, ResumeSkipped};
+use crate::hash::follow::{FollowUpdate, Follower};
+use crate::hash::text::{TextEncoding, TrailingNewline};
+use crate::hash::{Algorithm, Blake2Params, CrcModel, Ed2kVariant, GostVariant, HashOptions, HavalVariant, TigerVariant};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum HashFormat {
     HexUpper,
     HexLower,
     Base64,
+    Base32,
 }
 
 impl HashFormat {
@@ -18,6 +36,7 @@ impl HashFormat {
             HashFormat::HexUpper,
             HashFormat::HexLower,
             HashFormat::Base64,
+            HashFormat::Base32,
         ]
     }
     
@@ -26,6 +45,7 @@ impl HashFormat {
             HashFormat::HexUpper => "Hexadecimal (Uppercase)",
             HashFormat::HexLower => "Hexadecimal (Lowercase)",
             HashFormat::Base64 => "Base64",
+            HashFormat::Base32 => "Base32",
         }
     }
 }
@@ -39,10 +59,10 @@ impl HashFormat {
                 use base64::Engine;
                 base64::engine::general_purpose::STANDARD.encode(hash_bytes)
             }
+            HashFormat::Base32 => base32::encode(base32::Alphabet::Rfc4648 { padding: false }, hash_bytes),
         }
     }
     
-    #[allow(dead_code)]
     pub fn parse_hash(&self, hash_str: &str) -> Result<Vec<u8>, String> {
         match self {
             HashFormat::HexUpper | HashFormat::HexLower => {
@@ -54,6 +74,10 @@ impl HashFormat {
                 base64::engine::general_purpose::STANDARD.decode(hash_str.trim().replace(' ', ""))
                     .map_err(|e| format!("Error decoding base64: {}", e))
             }
+            HashFormat::Base32 => {
+                base32::decode(base32::Alphabet::Rfc4648 { padding: false }, &hash_str.trim().replace(' ', ""))
+                    .ok_or_else(|| "Error decoding base32".to_string())
+            }
         }
     }
 }
@@ -64,9 +88,99 @@ impl fmt::Display for HashFormat {
     }
 }
 
+// What the main window hashes: the selected file or the text typed into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    File,
+    Text,
+}
+
+impl InputMode {
+    pub fn all() -> Vec<InputMode> {
+        vec![InputMode::File, InputMode::Text]
+    }
+}
+
+impl fmt::Display for InputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InputMode::File => "File",
+            InputMode::Text => "Text",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Tool windows, opened from the "Tools..." list in the main window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Torrent,
+    Piecewise,
+    Oci,
+    Cdc,
+    Rdiff,
+    Similarity,
+    Images,
+    Archive,
+    Binary,
+    Follow,
+}
+
+impl Tool {
+    pub fn all() -> Vec<Tool> {
+        vec![
+            Tool::Torrent,
+            Tool::Piecewise,
+            Tool::Oci,
+            Tool::Cdc,
+            Tool::Rdiff,
+            Tool::Similarity,
+            Tool::Images,
+            Tool::Archive,
+            Tool::Binary,
+            Tool::Follow,
+        ]
+    }
+
+    // Opening a tool is the same as pressing its window's button
+    pub fn message(self) -> Message {
+        match self {
+            Tool::Torrent => Message::TorrentButtonPressed,
+            Tool::Piecewise => Message::PiecewiseButtonPressed,
+            Tool::Oci => Message::OciButtonPressed,
+            Tool::Cdc => Message::CdcButtonPressed,
+            Tool::Rdiff => Message::RdiffButtonPressed,
+            Tool::Similarity => Message::SimilarityButtonPressed,
+            Tool::Images => Message::ImageCompareButtonPressed,
+            Tool::Archive => Message::ArchiveButtonPressed,
+            Tool::Binary => Message::BinaryButtonPressed,
+            Tool::Follow => Message::FollowButtonPressed,
+        }
+    }
+}
+
+impl fmt::Display for Tool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Tool::Torrent => "Torrent",
+            Tool::Piecewise => "Piecewise",
+            Tool::Oci => "OCI layout",
+            Tool::Cdc => "CDC chunks",
+            Tool::Rdiff => "rdiff",
+            Tool::Similarity => "Similarity",
+            Tool::Images => "Images",
+            Tool::Archive => "Archive",
+            Tool::Binary => "Binary",
+            Tool::Follow => "Follow",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Message {
     BrowseFile,
+    BrowseFolder, // A folder is hashed as a git tree
     FileSelected(Option<PathBuf>),
     CheckHashChanged(String),
     CheckButtonPressed,
@@ -75,42 +189,385 @@ pub enum Message {
     FormatChanged(HashFormat),
     GostVariantChanged(GostVariant),
     TigerVariantChanged(TigerVariant),
+    Blake2bParamsChanged(Blake2Params),
+    Blake2sParamsChanged(Blake2Params),
+    CrcModelChanged(CrcModel),
+    HavalVariantChanged(HavalVariant),
+    NormalizationChanged(Normalization),
+    DecompressChanged(bool),
+    ResumeChanged(bool),
+    Ed2kVariantChanged(Ed2kVariant),
     #[allow(dead_code)]
     HashCalculated(Algorithm, String),
     HashesCalculated(Vec<(Algorithm, String)>), // Message for batch results
+    SplitSetHashed(SplitHashes), // Joined digests plus those of each part
+    ResumableHashed(ResumableHashes),
+    TextHashed(u64, Vec<(Algorithm, String)>), // Generation of the edit the results are for
+    ViewSplitParts,
+    FollowButtonPressed,
+    BrowseFollowFile,
+    FollowFileSelected(Option<PathBuf>),
+    StartFollowing,
+    StopFollowing,
+    FollowPolled(u64, Result<FollowUpdate, String>), // Run it belongs to, and its outcome
     #[allow(dead_code)]
     ProgressUpdate, // Update calculation progress
     CopyHash(Algorithm), // Copy hash to clipboard
+    RangeModeChanged(RangeMode),
+    RangeFirstChanged(String), // Offset or header size
+    RangeSecondChanged(String), // Length or trailer size
+    ExportResults,
+    ExportTargetSelected(Option<PathBuf>),
+    CopyLink(Algorithm), // Copy ed2k:// or magnet: link for the selected file to clipboard
+    InputModeChanged(InputMode),
+    TextInputEdited(iced::widget::text_editor::Action),
+    TextEncodingChanged(TextEncoding),
+    TrailingNewlineChanged(TrailingNewline),
     WindowClosed(iced::window::Id),
+    TorrentButtonPressed,
+    BrowseTorrent,
+    TorrentSelected(Option<PathBuf>),
+    BrowseTorrentContentFile,
+    BrowseTorrentContentFolder,
+    TorrentContentSelected(Option<PathBuf>),
+    VerifyTorrent,
+    TorrentVerified(Result<TorrentReport, String>),
+    PiecewiseButtonPressed,
+    BrowsePiecewiseFile,
+    PiecewiseFileSelected(Option<PathBuf>),
+    PiecewiseAlgorithmChanged(Algorithm),
+    PiecewiseWindowChanged(WindowSize),
+    CreatePiecewiseManifest,
+    PiecewiseManifestTargetSelected(Option<PathBuf>),
+    CompareWithManifest,
+    PiecewiseManifestSelected(Option<PathBuf>),
+    CompareManifests,
+    PiecewiseManifestsSelected(Option<Vec<PathBuf>>),
+    PiecewiseFinished(Result<PiecewiseOutcome, String>),
+    OciButtonPressed,
+    BrowseOciLayout,
+    OciLayoutSelected(Option<PathBuf>),
+    VerifyOciLayout,
+    OciVerified(Result<LayoutReport, String>),
+    CdcButtonPressed,
+    AddCdcFiles,
+    CdcFilesSelected(Option<Vec<PathBuf>>),
+    ClearCdcFiles,
+    CdcMinChanged(String),
+    CdcAvgChanged(String),
+    CdcMaxChanged(String),
+    CdcAlgorithmChanged(Algorithm),
+    AnalyzeCdc,
+    CdcFinished(Result<CdcReport, String>),
+    RdiffButtonPressed,
+    BrowseRdiffFile,
+    RdiffFileSelected(Option<PathBuf>),
+    RdiffFormatChanged(SignatureFormat),
+    RdiffBlockLengthChanged(u32),
+    CreateRdiffSignature,
+    RdiffSignatureTargetSelected(Option<PathBuf>),
+    CreateRdiffDelta,
+    RdiffDeltaSignatureSelected(Option<PathBuf>),
+    RdiffDeltaTargetSelected(Option<PathBuf>),
+    ApplyRdiffDelta,
+    RdiffPatchDeltaSelected(Option<PathBuf>),
+    RdiffPatchTargetSelected(Option<PathBuf>),
+    RdiffFinished(Result<RdiffOutcome, String>),
+    SimilarityButtonPressed,
+    BrowseSimilarityFirst,
+    SimilarityFirstSelected(Option<PathBuf>),
+    BrowseSimilaritySecond,
+    SimilaritySecondSelected(Option<PathBuf>),
+    SimilarityHashChanged(String),
+    CompareSimilarity,
+    SimilarityFinished(Result<SimilarityReport, String>),
+    ImageCompareButtonPressed,
+    BrowseImageFirst,
+    ImageFirstSelected(Option<PathBuf>),
+    BrowseImageSecond,
+    ImageSecondSelected(Option<PathBuf>),
+    ImageHashChanged(String),
+    ImageAlgorithmChanged(Algorithm),
+    CompareImages,
+    ImagesCompared(Result<Vec<HashDistance>, String>),
+    BinaryButtonPressed,
+    BrowseBinaryFile,
+    BinaryFileSelected(Option<PathBuf>),
+    AnalyzeBinary,
+    BinaryAnalyzed(Result<BinaryReport, String>),
+    ViewCanonicalJson,
+    CanonicalJsonLoaded(Result<String, String>),
+    CopyCanonicalJson,
+    ArchiveButtonPressed,
+    BrowseArchive,
+    ArchiveSelected(Option<PathBuf>),
+    ArchiveListed(Result<ArchiveListing, String>),
+    ArchiveMemberToggled(usize, bool),
+    HashSelectedMembers,
+    HashAllMembers,
+    ArchiveMembersHashed(Result<Vec<MemberHashes>, String>),
 }
 
 impl Default for RealOne {
     fn default() -> Self {
         Self {
             file_path: None,
+            file_is_image: false,
             check_hash: String::new(),
             selected_algorithms: Vec::new(),
             hash_results: HashMap::new(),
             window_manager: WindowManager::default(),
             calculation_start: None,
             hash_format: HashFormat::HexLower,
-            gost_variant: GostVariant::CryptoPro,
-            tiger_variant: TigerVariant::Tiger,
+            hash_options: HashOptions::default(),
             file_error: None,
+            input_mode: InputMode::File,
+            text_input: iced::widget::text_editor::Content::new(),
+            text_generation: 0,
+            text_encoding: TextEncoding::Utf8,
+            trailing_newline: TrailingNewline::AsEntered,
+            range_mode: RangeMode::WholeFile,
+            range_first: String::new(),
+            range_second: String::new(),
+            results_range: None,
+            normalization: Normalization::default(),
+            results_normalization: Normalization::default(),
+            decompress: false,
+            results_compression: Compression::None,
+            resume: false,
+            results_resumed_from: None,
+            results_resume_skipped: None,
+            split_set: None,
+            split_parts: Vec::new(),
+            follow_file: None,
+            follower: None,
+            follow_generation: 0,
+            follow_update: None,
+            follow_error: None,
+            export_status: None,
+            torrent_path: None,
+            torrent_content: None,
+            torrent_result: None,
+            torrent_running: false,
+            piecewise_file: None,
+            piecewise_algorithm: Algorithm::SHA256,
+            piecewise_window: WindowSize::Mib1,
+            piecewise_result: None,
+            piecewise_running: false,
+            oci_layout: None,
+            oci_result: None,
+            oci_running: false,
+            cdc_files: Vec::new(),
+            cdc_min: CdcParams::default().min_size.to_string(),
+            cdc_avg: CdcParams::default().avg_size.to_string(),
+            cdc_max: CdcParams::default().max_size.to_string(),
+            cdc_algorithm: Algorithm::SHA256,
+            cdc_result: None,
+            cdc_running: false,
+            rdiff_file: None,
+            rdiff_format: SignatureFormat::Blake2Rollsum,
+            rdiff_block_length: DEFAULT_BLOCK_LENGTH,
+            rdiff_input: None,
+            rdiff_result: None,
+            rdiff_running: false,
+            similarity_first: None,
+            similarity_second: None,
+            similarity_hash: String::new(),
+            similarity_result: None,
+            similarity_running: false,
+            image_first: None,
+            image_second: None,
+            image_hash: String::new(),
+            image_algorithm: Algorithm::PHASH,
+            image_result: None,
+            image_running: false,
+            binary_file: None,
+            binary_result: None,
+            binary_running: false,
+            canonical_json: None,
+            archive_path: None,
+            archive_listing: None,
+            archive_selected: Vec::new(),
+            archive_results: None,
+            archive_running: false,
         }
     }
 }
 
+impl RealOne {
+    // TTH roots are always written in base32, as Direct Connect and Gnutella
+    // clients do; everything else follows the selected result format
+    pub fn result_format(&self, algorithm: &Algorithm) -> HashFormat {
+        match algorithm {
+            Algorithm::TTH => HashFormat::Base32,
+            _ => self.hash_format,
+        }
+    }
+
+    // Switches the result format and rewrites the results already shown in it.
+    // Each result is decoded with the format it was written in, which TTH
+    // roots keep as base32, so base32 results are not misread as hex
+    pub fn change_hash_format(&mut self, format: HashFormat) {
+        let mut new_results = HashMap::new();
+        for (algorithm, hash_str) in &self.hash_results {
+            let old_format = self.result_format(algorithm);
+            let new_format = match algorithm {
+                Algorithm::TTH => old_format,
+                _ => format,
+            };
+            // Similarity digests are text and have no other format, and
+            // anything that cannot be decoded (such as an error) is kept as is
+            let converted = match old_format.parse_hash(hash_str) {
+                Ok(bytes) if !algorithm.is_fuzzy() => new_format.format_hash(&bytes),
+                _ => hash_str.clone(),
+            };
+            new_results.insert(algorithm.clone(), converted);
+        }
+        self.hash_results = new_results;
+        self.hash_format = format;
+    }
+
+    // ed2k:// and magnet: links pair the hash with the file's size, so they are
+    // only offered when the results cover the file's bytes exactly as stored
+    pub fn results_cover_whole_file(&self) -> bool {
+        self.results_range.is_none()
+            && !self.results_normalization.is_active()
+            && self.results_compression == Compression::None
+    }
+
+    // The editor always reports a final newline from `text()`, so the lines
+    // are joined directly to keep the text exactly as entered
+    pub fn entered_text(&self) -> String {
+        self.text_input.lines().map(|line| line.to_string()).collect::<Vec<_>>().join("\n")
+    }
+
+    // Selected algorithms that apply to the current input; perceptual hashes
+    // are left out unless the selected file is an image
+    pub fn active_algorithms(&self) -> Vec<Algorithm> {
+        let image = self.input_mode == InputMode::File && self.file_is_image;
+        self.selected_algorithms
+            .iter()
+            .filter(|algorithm| image || !algorithm.is_perceptual())
+            .cloned()
+            .collect()
+    }
+
+    pub fn cdc_params(&self) -> Result<CdcParams, String> {
+        let params = CdcParams {
+            min_size: parse_size(&self.cdc_min)? as usize,
+            avg_size: parse_size(&self.cdc_avg)? as usize,
+            max_size: parse_size(&self.cdc_max)? as usize,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+}
+
 pub struct RealOne {
     pub file_path: Option<PathBuf>,
+    pub file_is_image: bool, // Checked once when the file is selected
     pub check_hash: String, // Hash that the user wants to compare
     pub selected_algorithms: Vec<Algorithm>,
     pub hash_results: HashMap<Algorithm, String>,
     pub window_manager: WindowManager<RealOne, iced::Theme, Message>,
     pub calculation_start: Option<Instant>, // Calculation start time
     pub hash_format: HashFormat, // Hash format
-    pub gost_variant: GostVariant, // GOST S-box variant
-    pub tiger_variant: TigerVariant, // TIGER padding variant
+    pub hash_options: HashOptions, // Algorithm variants, BLAKE2 parameters and CRC model
     pub file_error: Option<String>, // Error message for the file
+    pub input_mode: InputMode,
+    pub text_input: iced::widget::text_editor::Content, // Data hashed in text mode
+    pub text_generation: u64, // Bumped for each text hash, so results of older ones can be dropped
+    pub text_encoding: TextEncoding,
+    pub trailing_newline: TrailingNewline,
+    pub range_mode: RangeMode,
+    pub range_first: String,
+    pub range_second: String,
+    pub results_range: Option<ByteRange>, // Range the current results were computed over
+    pub normalization: Normalization, // Text clean-up applied before hashing
+    pub results_normalization: Normalization, // Normalization the current results were computed with
+    pub decompress: bool, // Hash compressed files by their decompressed contents
+    pub results_compression: Compression, // Compression undone for the current results, None if hashed as is
+    pub resume: bool, // Checkpoint large files so an interrupted hash can carry on
+    pub results_resumed_from: Option<u64>, // Offset the current results were resumed from
+    pub results_resume_skipped: Option<ResumeSkipped>, // Why a large file, or some of its algorithms, went without checkpoints
+    pub split_set: Option<SplitSet>, // Parts hashed as one file for the current results
+    pub split_parts: Vec<PartDigests>,
+    pub follow_file: Option<PathBuf>, // File hashed while it is still being written
+    pub follower: Option<Arc<Mutex<Follower>>>, // Set while following
+    pub follow_generation: u64, // Tells polls of an earlier run from the current one
+    pub follow_update: Option<FollowUpdate>,
+    pub follow_error: Option<String>,
+    pub export_status: Option<String>,
+    pub torrent_path: Option<PathBuf>, // .torrent file to verify against
+    pub torrent_content: Option<PathBuf>, // Local file or folder holding the torrent's data
+    pub torrent_result: Option<Result<TorrentReport, String>>,
+    pub torrent_running: bool,
+    pub piecewise_file: Option<PathBuf>, // File hashed window by window
+    pub piecewise_algorithm: Algorithm,
+    pub piecewise_window: WindowSize,
+    pub piecewise_result: Option<Result<PiecewiseOutcome, String>>,
+    pub piecewise_running: bool,
+    pub oci_layout: Option<PathBuf>, // OCI image-layout directory
+    pub oci_result: Option<Result<LayoutReport, String>>,
+    pub oci_running: bool,
+    pub cdc_files: Vec<PathBuf>, // Files chunked together by the FastCDC analysis
+    pub cdc_min: String,
+    pub cdc_avg: String,
+    pub cdc_max: String,
+    pub cdc_algorithm: Algorithm, // Digest used to identify chunks
+    pub cdc_result: Option<Result<CdcReport, String>>,
+    pub cdc_running: bool,
+    pub rdiff_file: Option<PathBuf>, // Basis file for signatures and patches, new file for deltas
+    pub rdiff_format: SignatureFormat,
+    pub rdiff_block_length: u32,
+    pub rdiff_input: Option<PathBuf>, // Signature or delta picked before asking where to save
+    pub rdiff_result: Option<Result<RdiffOutcome, String>>,
+    pub rdiff_running: bool,
+    pub similarity_first: Option<PathBuf>,
+    pub similarity_second: Option<PathBuf>, // Ignored while a fuzzy hash is pasted
+    pub similarity_hash: String,
+    pub similarity_result: Option<Result<SimilarityReport, String>>,
+    pub similarity_running: bool,
+    pub image_first: Option<PathBuf>,
+    pub image_second: Option<PathBuf>, // Ignored while a hash is pasted
+    pub image_hash: String,
+    pub image_algorithm: Algorithm, // Which perceptual hash a pasted hash is
+    pub image_result: Option<Result<Vec<HashDistance>, String>>,
+    pub image_running: bool,
+    pub binary_file: Option<PathBuf>,
+    pub binary_result: Option<Result<BinaryReport, String>>,
+    pub binary_running: bool,
+    pub canonical_json: Option<Result<String, String>>, // What the current results were hashed over
+    pub archive_path: Option<PathBuf>,
+    pub archive_listing: Option<Result<ArchiveListing, String>>,
+    pub archive_selected: Vec<usize>, // Indexes of the checked members
+    pub archive_results: Option<Result<Vec<MemberHashes>, String>>,
+    pub archive_running: bool, // Listing or hashing
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base32_results_survive_a_round_trip_through_hex() {
+        let mut state = RealOne { hash_format: HashFormat::Base32, ..RealOne::default() };
+        // SHA-1 and SHA-256 of "abc"
+        let sha1 = "VGMT4NSHA2AWVOR6EVYXQUGCNSONBWE5";
+        let sha256 = "XJ4BNP4PAHH6UQKBIDPF3LRCEOYAGYNDSYLXVHFUCD7WD4QACWWQ";
+        state.hash_results.insert(Algorithm::SHA1, sha1.to_string());
+        state.hash_results.insert(Algorithm::SHA256, sha256.to_string());
+
+        state.change_hash_format(HashFormat::HexLower);
+        assert_eq!(state.hash_results[&Algorithm::SHA1], "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(
+            state.hash_results[&Algorithm::SHA256],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        state.change_hash_format(HashFormat::Base32);
+        assert_eq!(state.hash_results[&Algorithm::SHA1], sha1);
+        assert_eq!(state.hash_results[&Algorithm::SHA256], sha256);
+    }
+}
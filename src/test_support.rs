@@ -0,0 +1,42 @@
+// Helpers shared by the unit tests
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// A folder of its own under the system temp folder, emptied when created and
+// removed when dropped. The process ID in the name keeps concurrent test runs
+// apart, and the name passed in keeps the tests of one run apart
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("realone-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        ScratchDir(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub fn join(&self, name: impl AsRef<Path>) -> PathBuf {
+        self.0.join(name)
+    }
+
+    // Writes a file, creating the folders in `name` that are not there yet
+    pub fn write(&self, name: impl AsRef<Path>, data: &[u8]) -> PathBuf {
+        let path = self.0.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(&path, data).unwrap();
+        path
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
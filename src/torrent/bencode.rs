@@ -0,0 +1,267 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Dict(BTreeMap<Vec<u8>, Value>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(dict) => dict.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes().and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    pub fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&BTreeMap<Vec<u8>, Value>> {
+        match self {
+            Value::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+}
+
+// Decoded document plus the raw byte range of the top-level "info" value,
+// which is what the infohash is computed over
+pub struct Document {
+    pub root: Value,
+    pub info_span: Option<Range<usize>>,
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+    depth: usize,
+    info_span: Option<Range<usize>>,
+}
+
+// Deeply nested input is rejected instead of overflowing the stack
+const MAX_DEPTH: usize = 256;
+
+pub fn decode(data: &[u8]) -> Result<Document, String> {
+    let mut parser = Parser {
+        data,
+        pos: 0,
+        depth: 0,
+        info_span: None,
+    };
+    let root = parser.value()?;
+    if parser.pos != data.len() {
+        return Err(format!("trailing data at offset {}", parser.pos));
+    }
+    Ok(Document {
+        root,
+        info_span: parser.info_span,
+    })
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Result<u8, String> {
+        self.data
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| "unexpected end of data".to_string())
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek()? {
+            b'i' => self.int(),
+            b'l' => self.list(),
+            b'd' => self.dict(),
+            b'0'..=b'9' => self.bytes().map(Value::Bytes),
+            other => Err(format!("unexpected byte 0x{:02x} at offset {}", other, self.pos)),
+        }
+    }
+
+    // Reads digits up to the terminator, returning them as text
+    fn number(&mut self, terminator: u8) -> Result<&str, String> {
+        let start = self.pos;
+        let end = self.data[start..]
+            .iter()
+            .position(|&b| b == terminator)
+            .map(|offset| start + offset)
+            .ok_or_else(|| format!("unterminated number at offset {}", start))?;
+        self.pos = end + 1;
+        std::str::from_utf8(&self.data[start..end])
+            .map_err(|_| format!("invalid number at offset {}", start))
+    }
+
+    fn int(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        self.pos += 1;
+        let text = self.number(b'e')?;
+        text.parse::<i64>()
+            .map(Value::Int)
+            .map_err(|_| format!("invalid integer at offset {}", start))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, String> {
+        let start = self.pos;
+        let len: usize = self
+            .number(b':')?
+            .parse()
+            .map_err(|_| format!("invalid string length at offset {}", start))?;
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| format!("string at offset {} runs past the end", start))?;
+        let bytes = self.data[self.pos..end].to_vec();
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn enter(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("nesting too deep".to_string());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn list(&mut self) -> Result<Value, String> {
+        self.enter()?;
+        let mut list = Vec::new();
+        while self.peek()? != b'e' {
+            list.push(self.value()?);
+        }
+        self.pos += 1;
+        self.depth -= 1;
+        Ok(Value::List(list))
+    }
+
+    fn dict(&mut self) -> Result<Value, String> {
+        self.enter()?;
+        let mut dict = BTreeMap::new();
+        while self.peek()? != b'e' {
+            let key = self.bytes()?;
+            let start = self.pos;
+            let value = self.value()?;
+            if self.depth == 1 && key == b"info" {
+                self.info_span = Some(start..self.pos);
+            }
+            dict.insert(key, value);
+        }
+        self.pos += 1;
+        self.depth -= 1;
+        Ok(Value::Dict(dict))
+    }
+}
+
+// Inverse of `decode`, for building torrents in tests
+#[cfg(test)]
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    match value {
+        Value::Int(value) => out.extend_from_slice(format!("i{}e", value).as_bytes()),
+        Value::Bytes(bytes) => {
+            out.extend_from_slice(format!("{}:", bytes.len()).as_bytes());
+            out.extend_from_slice(bytes);
+        }
+        Value::List(list) => {
+            out.push(b'l');
+            list.iter().for_each(|item| out.extend(encode(item)));
+            out.push(b'e');
+        }
+        Value::Dict(dict) => {
+            out.push(b'd');
+            for (key, item) in dict {
+                out.extend(encode(&Value::Bytes(key.clone())));
+                out.extend(encode(item));
+            }
+            out.push(b'e');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_decode() {
+        let document = decode(b"d3:agei-42e4:listl1:ai0ee4:name4:spame").unwrap();
+        assert_eq!(document.root.get("age").and_then(Value::as_int), Some(-42));
+        assert_eq!(document.root.get("name").and_then(Value::as_str), Some("spam"));
+        let list = document.root.get("list").and_then(Value::as_list).unwrap();
+        assert_eq!(list, &[Value::Bytes(b"a".to_vec()), Value::Int(0)]);
+        assert_eq!(document.info_span, None);
+    }
+
+    #[test]
+    fn info_span_covers_the_raw_info_value() {
+        let data = b"d8:announce3:url4:infod6:lengthi5eee";
+        let document = decode(data).unwrap();
+        assert_eq!(&data[document.info_span.unwrap()], b"d6:lengthi5ee");
+
+        // Only the top-level "info" counts
+        let document = decode(b"d1:xd4:infoi1eee").unwrap();
+        assert_eq!(document.info_span, None);
+    }
+
+    #[test]
+    fn encode_is_the_inverse_of_decode() {
+        let data = b"d1:ai1e1:bl0:d1:ci-1eeee";
+        assert_eq!(encode(&decode(data).unwrap().root), data);
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        for data in [
+            &b""[..],
+            b"i12",
+            b"ixe",
+            b"5:abc",
+            b"99999999999999999999:a",
+            b"l1:a",
+            b"d1:ae",
+            b"di1ei2ee",
+            b"i1ei2e",
+            b"x",
+        ] {
+            assert!(decode(data).is_err(), "{:?}", String::from_utf8_lossy(data));
+        }
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let mut data = vec![b'l'; MAX_DEPTH + 1];
+        data.extend(vec![b'e'; MAX_DEPTH + 1]);
+        assert!(decode(&data).is_err());
+
+        let mut data = vec![b'l'; MAX_DEPTH];
+        data.extend(vec![b'e'; MAX_DEPTH]);
+        assert!(decode(&data).is_ok());
+    }
+}
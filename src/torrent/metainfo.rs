@@ -0,0 +1,399 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use digest::Digest;
+
+use crate::torrent::bencode::{self, Value};
+
+// Pieces are read into memory whole, so the piece length from the torrent is
+// capped; real clients do not go past 64 MiB
+const MAX_PIECE_LENGTH: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct TorrentFile {
+    pub path: PathBuf, // Relative to the torrent's content root
+    pub length: u64,
+    pub padding: bool, // BEP 47 pad file, all zeros and never on disk
+    pub pieces_root: Option<[u8; 32]>, // v2 merkle root, None for empty files
+}
+
+#[derive(Debug, Clone)]
+pub struct Torrent {
+    pub name: String,
+    pub piece_length: u64,
+    pub single_file: bool,
+    pub v1_files: Vec<TorrentFile>,
+    pub v1_pieces: Vec<[u8; 20]>,
+    pub v2_files: Vec<TorrentFile>,
+    pub piece_layers: HashMap<[u8; 32], Vec<[u8; 32]>>,
+    pub infohash_v1: Option<[u8; 20]>,
+    pub infohash_v2: Option<[u8; 32]>,
+}
+
+impl Torrent {
+    pub fn load(path: &Path) -> Result<Torrent, String> {
+        let data = std::fs::read(path).map_err(|e| e.to_string())?;
+        Torrent::parse(&data)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Torrent, String> {
+        let document = bencode::decode(data).map_err(|e| format!("invalid torrent: {}", e))?;
+        let info = document.root.get("info").ok_or("torrent has no info dictionary")?;
+        let info_bytes = &data[document.info_span.ok_or("torrent has no info dictionary")?];
+
+        let name = info
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or("torrent has no name")?
+            .to_string();
+        check_component(&name)?;
+
+        let piece_length = info
+            .get("piece length")
+            .and_then(Value::as_int)
+            .filter(|&len| len > 0)
+            .ok_or("torrent has no valid piece length")? as u64;
+        if piece_length > MAX_PIECE_LENGTH {
+            return Err(format!(
+                "piece length {} is larger than the {} MiB limit",
+                piece_length,
+                MAX_PIECE_LENGTH / (1024 * 1024)
+            ));
+        }
+
+        let is_v1 = info.get("pieces").is_some();
+        let is_v2 = info.get("meta version").and_then(Value::as_int) == Some(2);
+        if !is_v1 && !is_v2 {
+            return Err("torrent has neither v1 pieces nor a v2 file tree".to_string());
+        }
+
+        let mut torrent = Torrent {
+            name,
+            piece_length,
+            single_file: false,
+            v1_files: Vec::new(),
+            v1_pieces: Vec::new(),
+            v2_files: Vec::new(),
+            piece_layers: HashMap::new(),
+            infohash_v1: None,
+            infohash_v2: None,
+        };
+
+        if is_v1 {
+            torrent.parse_v1(info)?;
+            torrent.infohash_v1 = Some(sha1::Sha1::digest(info_bytes).into());
+        }
+
+        if is_v2 {
+            if piece_length < 16384 || !piece_length.is_power_of_two() {
+                return Err("v2 piece length must be a power of two of at least 16 KiB".to_string());
+            }
+            let tree = info.get("file tree").ok_or("v2 torrent has no file tree")?;
+            let mut files = Vec::new();
+            collect_file_tree(tree, PathBuf::new(), &mut files, 0)?;
+            // A single-file v2 torrent's tree holds just the file named after the torrent
+            torrent.single_file = files.len() == 1 && files[0].path == Path::new(&torrent.name);
+            torrent.v2_files = files;
+            torrent.piece_layers = parse_piece_layers(document.root.get("piece layers"))?;
+            torrent.infohash_v2 = Some(sha2::Sha256::digest(info_bytes).into());
+        }
+
+        Ok(torrent)
+    }
+
+    fn parse_v1(&mut self, info: &Value) -> Result<(), String> {
+        let pieces = info.get("pieces").and_then(Value::as_bytes).ok_or("invalid pieces field")?;
+        if pieces.len() % 20 != 0 {
+            return Err("pieces field is not a multiple of 20 bytes".to_string());
+        }
+        self.v1_pieces = pieces
+            .chunks_exact(20)
+            .map(|chunk| chunk.try_into().unwrap())
+            .collect();
+
+        if let Some(length) = info.get("length").and_then(Value::as_int) {
+            self.single_file = true;
+            self.v1_files.push(TorrentFile {
+                path: PathBuf::from(&self.name),
+                length: non_negative(length)?,
+                padding: false,
+                pieces_root: None,
+            });
+        } else {
+            let files = info.get("files").and_then(Value::as_list).ok_or("torrent has no files")?;
+            for file in files {
+                let length = file.get("length").and_then(Value::as_int).ok_or("file has no length")?;
+                let components = file.get("path").and_then(Value::as_list).ok_or("file has no path")?;
+                let mut path = PathBuf::new();
+                for component in components {
+                    let component = component.as_str().ok_or("file path is not UTF-8")?;
+                    check_component(component)?;
+                    path.push(component);
+                }
+                let padding = file
+                    .get("attr")
+                    .and_then(Value::as_str)
+                    .is_some_and(|attr| attr.contains('p'));
+                self.v1_files.push(TorrentFile {
+                    path,
+                    length: non_negative(length)?,
+                    padding,
+                    pieces_root: None,
+                });
+            }
+        }
+
+        let total = self
+            .v1_files
+            .iter()
+            .try_fold(0u64, |total, f| total.checked_add(f.length))
+            .ok_or("total size of the files is too large")?;
+        if total.div_ceil(self.piece_length) != self.v1_pieces.len() as u64 {
+            return Err("number of pieces does not match the total size".to_string());
+        }
+        Ok(())
+    }
+
+    // Where a torrent file is expected on disk. `target` may be the file itself
+    // (single-file torrents), the content folder, or the folder containing it.
+    pub fn local_path(&self, target: &Path, file: &TorrentFile) -> PathBuf {
+        if self.single_file {
+            if target.is_file() {
+                target.to_path_buf()
+            } else {
+                target.join(&file.path)
+            }
+        } else if target.join(&self.name).is_dir() {
+            target.join(&self.name).join(&file.path)
+        } else {
+            target.join(&file.path)
+        }
+    }
+}
+
+fn non_negative(value: i64) -> Result<u64, String> {
+    u64::try_from(value).map_err(|_| "negative file length".to_string())
+}
+
+// Rejects names that would escape the content folder
+fn check_component(component: &str) -> Result<(), String> {
+    if component.is_empty()
+        || component == "."
+        || component == ".."
+        || component.contains('/')
+        || component.contains('\\')
+    {
+        return Err(format!("unsafe path component \"{}\"", component));
+    }
+    Ok(())
+}
+
+// Walks the BEP 52 file tree; a file is a dictionary with an empty key
+fn collect_file_tree(
+    node: &Value,
+    path: PathBuf,
+    files: &mut Vec<TorrentFile>,
+    depth: usize,
+) -> Result<(), String> {
+    let dict = node.as_dict().ok_or("invalid file tree")?;
+
+    if let Some(leaf) = dict.get(b"".as_slice()) {
+        let length = leaf.get("length").and_then(Value::as_int).ok_or("file has no length")?;
+        let pieces_root = match leaf.get("pieces root").and_then(Value::as_bytes) {
+            Some(root) => Some(root.try_into().map_err(|_| "pieces root is not 32 bytes")?),
+            None => None,
+        };
+        let length = non_negative(length)?;
+        if length > 0 && pieces_root.is_none() {
+            return Err(format!("{} has no pieces root", path.display()));
+        }
+        files.push(TorrentFile {
+            path,
+            length,
+            padding: false,
+            pieces_root,
+        });
+        return Ok(());
+    }
+
+    if depth > 64 {
+        return Err("file tree is nested too deeply".to_string());
+    }
+
+    for (name, child) in dict {
+        let name = std::str::from_utf8(name).map_err(|_| "file path is not UTF-8")?;
+        check_component(name)?;
+        collect_file_tree(child, path.join(name), files, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn parse_piece_layers(layers: Option<&Value>) -> Result<HashMap<[u8; 32], Vec<[u8; 32]>>, String> {
+    let mut result = HashMap::new();
+    let Some(layers) = layers.and_then(Value::as_dict) else {
+        return Ok(result);
+    };
+
+    for (root, hashes) in layers {
+        let root: [u8; 32] = root.as_slice().try_into().map_err(|_| "invalid piece layer key")?;
+        let hashes = hashes.as_bytes().ok_or("invalid piece layer")?;
+        if hashes.len() % 32 != 0 {
+            return Err("piece layer is not a multiple of 32 bytes".to_string());
+        }
+        result.insert(
+            root,
+            hashes.chunks_exact(32).map(|chunk| chunk.try_into().unwrap()).collect(),
+        );
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::torrent::bencode::encode;
+
+    fn dict(entries: Vec<(&str, Value)>) -> Value {
+        Value::Dict(entries.into_iter().map(|(key, value)| (key.as_bytes().to_vec(), value)).collect())
+    }
+
+    fn bytes(data: &[u8]) -> Value {
+        Value::Bytes(data.to_vec())
+    }
+
+    fn file(path: &[&str], length: i64) -> Value {
+        dict(vec![
+            ("length", Value::Int(length)),
+            ("path", Value::List(path.iter().map(|c| bytes(c.as_bytes())).collect())),
+        ])
+    }
+
+    fn torrent(info: Value) -> Vec<u8> {
+        encode(&dict(vec![("announce", bytes(b"http://tracker/")), ("info", info)]))
+    }
+
+    #[test]
+    fn single_file_v1() {
+        let info = dict(vec![
+            ("length", Value::Int(40)),
+            ("name", bytes(b"file.bin")),
+            ("piece length", Value::Int(16)),
+            ("pieces", bytes(&[7u8; 60])),
+        ]);
+        let parsed = Torrent::parse(&torrent(info.clone())).unwrap();
+        assert!(parsed.single_file);
+        assert_eq!(parsed.piece_length, 16);
+        assert_eq!(parsed.v1_pieces, vec![[7u8; 20]; 3]);
+        assert_eq!(parsed.v1_files[0].path, Path::new("file.bin"));
+        assert_eq!(parsed.infohash_v1, Some(sha1::Sha1::digest(encode(&info)).into()));
+        assert_eq!(parsed.infohash_v2, None);
+    }
+
+    #[test]
+    fn multi_file_v1_with_pad_file() {
+        let mut pad = file(&[".pad", "6"], 6);
+        if let Value::Dict(entries) = &mut pad {
+            entries.insert(b"attr".to_vec(), bytes(b"p"));
+        }
+        let info = dict(vec![
+            ("files", Value::List(vec![file(&["a", "one"], 10), pad, file(&["two"], 1)])),
+            ("name", bytes(b"folder")),
+            ("piece length", Value::Int(16)),
+            ("pieces", bytes(&[0u8; 40])),
+        ]);
+        let parsed = Torrent::parse(&torrent(info)).unwrap();
+        assert!(!parsed.single_file);
+        let files: Vec<(&Path, u64, bool)> =
+            parsed.v1_files.iter().map(|f| (f.path.as_path(), f.length, f.padding)).collect();
+        assert_eq!(
+            files,
+            vec![
+                (Path::new("a/one"), 10, false),
+                (Path::new(".pad/6"), 6, true),
+                (Path::new("two"), 1, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_piece_lengths_are_errors() {
+        for piece_length in [0, -16, 1 << 62, MAX_PIECE_LENGTH as i64 * 2] {
+            let info = dict(vec![
+                ("length", Value::Int(1)),
+                ("name", bytes(b"file.bin")),
+                ("piece length", Value::Int(piece_length)),
+                ("pieces", bytes(&[0u8; 20])),
+            ]);
+            assert!(Torrent::parse(&torrent(info)).is_err(), "{}", piece_length);
+        }
+    }
+
+    #[test]
+    fn oversized_file_lengths_are_errors() {
+        let info = dict(vec![
+            ("files", Value::List(vec![file(&["a"], i64::MAX), file(&["b"], i64::MAX), file(&["c"], 2)])),
+            ("name", bytes(b"folder")),
+            ("piece length", Value::Int(16)),
+            ("pieces", bytes(&[0u8; 20])),
+        ]);
+        assert!(Torrent::parse(&torrent(info)).is_err());
+    }
+
+    #[test]
+    fn piece_count_must_match_the_total_size() {
+        let info = dict(vec![
+            ("length", Value::Int(33)),
+            ("name", bytes(b"file.bin")),
+            ("piece length", Value::Int(16)),
+            ("pieces", bytes(&[0u8; 40])),
+        ]);
+        assert!(Torrent::parse(&torrent(info)).is_err());
+    }
+
+    #[test]
+    fn paths_that_escape_the_folder_are_errors() {
+        for path in [&["..", "etc"][..], &["a/b"], &[""]] {
+            let info = dict(vec![
+                ("files", Value::List(vec![file(path, 1)])),
+                ("name", bytes(b"folder")),
+                ("piece length", Value::Int(16)),
+                ("pieces", bytes(&[0u8; 20])),
+            ]);
+            assert!(Torrent::parse(&torrent(info)).is_err(), "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn v2_file_tree() {
+        let leaf = |length, root: &[u8]| {
+            dict(vec![("", dict(vec![("length", Value::Int(length)), ("pieces root", bytes(root))]))])
+        };
+        let tree = dict(vec![
+            ("dir", dict(vec![("big", leaf(40000, &[1u8; 32]))])),
+            ("small", leaf(5, &[2u8; 32])),
+        ]);
+        let info = dict(vec![
+            ("file tree", tree),
+            ("meta version", Value::Int(2)),
+            ("name", bytes(b"folder")),
+            ("piece length", Value::Int(16384)),
+        ]);
+        let layers = Value::Dict([([1u8; 32].to_vec(), bytes(&[3u8; 96]))].into());
+        let data = dict(vec![("info", info.clone()), ("piece layers", layers)]);
+        let parsed = Torrent::parse(&encode(&data)).unwrap();
+        assert_eq!(parsed.v2_files.len(), 2);
+        assert_eq!(parsed.v2_files[0].path, Path::new("dir/big"));
+        assert_eq!(parsed.v2_files[1].pieces_root, Some([2u8; 32]));
+        assert_eq!(parsed.piece_layers[&[1u8; 32]], vec![[3u8; 32]; 3]);
+        assert_eq!(parsed.infohash_v2, Some(sha2::Sha256::digest(encode(&info)).into()));
+
+        // v2 needs a power of two of at least 16 KiB
+        let info = dict(vec![
+            ("file tree", dict(vec![("small", leaf(5, &[2u8; 32]))])),
+            ("meta version", Value::Int(2)),
+            ("name", bytes(b"folder")),
+            ("piece length", Value::Int(20000)),
+        ]);
+        assert!(Torrent::parse(&torrent(info)).is_err());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod bencode;
+pub mod metainfo;
+pub mod verify;
+
+pub use verify::{verify_torrent, FileStatus, TorrentReport};
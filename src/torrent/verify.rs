@@ -0,0 +1,474 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use digest::Digest;
+
+use crate::hash::io::LARGE_BUFFER_SIZE;
+use crate::torrent::metainfo::{Torrent, TorrentFile};
+
+// BEP 52 hashes files in 16 KiB blocks
+const BLOCK_SIZE: usize = 16384;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileStatus {
+    Ok,
+    Corrupt(Vec<usize>), // Indices of the failed pieces
+    Missing,
+    WrongSize(u64), // Size found on disk
+    Unverifiable(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub path: String,
+    pub length: u64,
+    pub status: FileStatus,
+}
+
+#[derive(Debug, Clone)]
+pub struct TorrentReport {
+    pub name: String,
+    pub version: &'static str, // Which piece hashes were checked
+    pub infohash_v1: Option<String>,
+    pub infohash_v2: Option<String>,
+    pub total_pieces: usize,
+    pub bad_pieces: usize,
+    pub files: Vec<FileReport>,
+}
+
+impl TorrentReport {
+    pub fn is_ok(&self) -> bool {
+        self.files.iter().all(|file| file.status == FileStatus::Ok)
+    }
+}
+
+// Loads a .torrent and checks the local copy at `target`; hybrid torrents
+// are checked with the v2 merkle hashes, which are per file
+pub fn verify_torrent(torrent_path: &Path, target: &Path) -> Result<TorrentReport, String> {
+    let torrent = Torrent::load(torrent_path)?;
+
+    let (version, total_pieces, bad_pieces, files) = if torrent.infohash_v2.is_some() {
+        let (total, bad, files) = verify_v2(&torrent, target);
+        ("v2 (SHA-256 merkle)", total, bad, files)
+    } else {
+        let (bad, files) = verify_v1(&torrent, target);
+        ("v1 (SHA-1 pieces)", torrent.v1_pieces.len(), bad, files)
+    };
+
+    Ok(TorrentReport {
+        name: torrent.name.clone(),
+        version,
+        infohash_v1: torrent.infohash_v1.map(hex::encode),
+        infohash_v2: torrent.infohash_v2.map(hex::encode),
+        total_pieces,
+        bad_pieces,
+        files,
+    })
+}
+
+fn pieces_in(torrent: &Torrent, length: u64) -> usize {
+    length.div_ceil(torrent.piece_length) as usize
+}
+
+// Opens a file and checks its size; the status is set when it can't be used as-is
+fn open_file(path: &Path, expected: u64) -> (Option<File>, Option<FileStatus>) {
+    match File::open(path) {
+        Ok(file) => {
+            let actual = file.metadata().map(|m| m.len()).unwrap_or(0);
+            if actual == expected {
+                (Some(file), None)
+            } else {
+                (Some(file), Some(FileStatus::WrongSize(actual)))
+            }
+        }
+        Err(_) => (None, Some(FileStatus::Missing)),
+    }
+}
+
+// Fills `buf` as far as the reader allows; returns how many bytes were read
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> usize {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => filled += n,
+        }
+    }
+    filled
+}
+
+// v1 pieces run across file boundaries, so the files are read back to back
+// as one stream; pad files and unreadable ranges are fed as zeros
+fn verify_v1(torrent: &Torrent, target: &Path) -> (usize, Vec<FileReport>) {
+    let piece_length = torrent.piece_length as usize;
+    let mut piece = Vec::with_capacity(piece_length);
+    let mut piece_files: Vec<usize> = Vec::new();
+    let mut piece_unavailable = false;
+    let mut piece_index = 0;
+    let mut bad_pieces = 0;
+
+    let mut statuses: Vec<Option<FileStatus>> = vec![None; torrent.v1_files.len()];
+    let mut bad: Vec<Vec<usize>> = vec![Vec::new(); torrent.v1_files.len()];
+    let mut buffer = vec![0u8; LARGE_BUFFER_SIZE];
+
+    let mut finish_piece = |piece: &mut Vec<u8>, files: &mut Vec<usize>, unavailable: &mut bool| {
+        let matches = !*unavailable
+            && torrent
+                .v1_pieces
+                .get(piece_index)
+                .is_some_and(|expected| sha1::Sha1::digest(&piece[..]).as_slice() == expected);
+        if !matches {
+            bad_pieces += 1;
+            for &file in files.iter() {
+                bad[file].push(piece_index);
+            }
+        }
+        piece_index += 1;
+        piece.clear();
+        files.clear();
+        *unavailable = false;
+    };
+
+    for (index, file) in torrent.v1_files.iter().enumerate() {
+        let mut reader = None;
+        if !file.padding && file.length > 0 {
+            let (handle, status) = open_file(&torrent.local_path(target, file), file.length);
+            reader = handle.map(|f| BufReader::with_capacity(LARGE_BUFFER_SIZE, f));
+            statuses[index] = status;
+        }
+
+        let mut remaining = file.length;
+        while remaining > 0 {
+            if !file.padding && piece_files.last() != Some(&index) {
+                piece_files.push(index);
+            }
+
+            let want = (piece_length - piece.len()).min(remaining as usize).min(buffer.len());
+            let got = match reader.as_mut() {
+                Some(reader) => read_full(reader, &mut buffer[..want]),
+                None if file.padding => {
+                    buffer[..want].fill(0);
+                    want
+                }
+                None => 0,
+            };
+            if got < want {
+                // Short or missing file: keep the layout aligned with zeros
+                buffer[got..want].fill(0);
+                piece_unavailable = true;
+            }
+            piece.extend_from_slice(&buffer[..want]);
+            remaining -= want as u64;
+
+            if piece.len() == piece_length {
+                finish_piece(&mut piece, &mut piece_files, &mut piece_unavailable);
+            }
+        }
+    }
+    if !piece.is_empty() {
+        finish_piece(&mut piece, &mut piece_files, &mut piece_unavailable);
+    }
+
+    let reports = torrent
+        .v1_files
+        .iter()
+        .enumerate()
+        .filter(|(_, file)| !file.padding)
+        .map(|(index, file)| FileReport {
+            path: file.path.display().to_string(),
+            length: file.length,
+            status: match statuses[index].take() {
+                Some(status) => status,
+                None if bad[index].is_empty() => FileStatus::Ok,
+                None => FileStatus::Corrupt(std::mem::take(&mut bad[index])),
+            },
+        })
+        .collect();
+
+    (bad_pieces, reports)
+}
+
+// Root of a merkle tree over `leaves`, padded with `pad` up to `width` leaves
+fn merkle_root(mut leaves: Vec<[u8; 32]>, width: usize, pad: [u8; 32]) -> [u8; 32] {
+    leaves.resize(width.max(1), pad);
+    while leaves.len() > 1 {
+        leaves = leaves
+            .chunks_exact(2)
+            .map(|pair| {
+                let mut hasher = sha2::Sha256::new();
+                hasher.update(pair[0]);
+                hasher.update(pair[1]);
+                hasher.finalize().into()
+            })
+            .collect();
+    }
+    leaves[0]
+}
+
+fn block_hashes(data: &[u8]) -> Vec<[u8; 32]> {
+    data.chunks(BLOCK_SIZE)
+        .map(|block| sha2::Sha256::digest(block).into())
+        .collect()
+}
+
+// v2 pieces never span files: each file is checked against its own piece
+// layer, or against its pieces root when it fits in a single piece
+fn verify_v2(torrent: &Torrent, target: &Path) -> (usize, usize, Vec<FileReport>) {
+    let piece_length = torrent.piece_length as usize;
+    let blocks_per_piece = piece_length / BLOCK_SIZE;
+    let mut total_pieces = 0;
+    let mut bad_pieces = 0;
+    let mut buffer = vec![0u8; piece_length];
+
+    let reports = torrent
+        .v2_files
+        .iter()
+        .map(|file| {
+            let pieces = pieces_in(torrent, file.length);
+            let status = verify_v2_file(torrent, target, file, &mut buffer, blocks_per_piece);
+            total_pieces += pieces;
+            bad_pieces += match &status {
+                FileStatus::Ok => 0,
+                FileStatus::Corrupt(bad) => bad.len(),
+                _ => pieces,
+            };
+            FileReport {
+                path: file.path.display().to_string(),
+                length: file.length,
+                status,
+            }
+        })
+        .collect();
+
+    (total_pieces, bad_pieces, reports)
+}
+
+fn verify_v2_file(
+    torrent: &Torrent,
+    target: &Path,
+    file: &TorrentFile,
+    buffer: &mut [u8],
+    blocks_per_piece: usize,
+) -> FileStatus {
+    let Some(pieces_root) = file.pieces_root else {
+        // Empty files have nothing to hash
+        return FileStatus::Ok;
+    };
+
+    let single_piece = file.length <= torrent.piece_length;
+    let layer = torrent.piece_layers.get(&pieces_root);
+    if !single_piece {
+        let Some(layer) = layer else {
+            return FileStatus::Unverifiable("piece layer missing from torrent".to_string());
+        };
+        // Piece layers sit outside the info dict, so the infohash does not
+        // cover them; a layer is only trusted once it hashes up to the root.
+        // Missing pieces count as pieces of zero leaves
+        let pad = merkle_root(Vec::new(), blocks_per_piece, [0u8; 32]);
+        let width = layer.len().next_power_of_two();
+        if layer.len() != pieces_in(torrent, file.length) || merkle_root(layer.clone(), width, pad) != pieces_root {
+            return FileStatus::Unverifiable("piece layer does not match the pieces root".to_string());
+        }
+    }
+
+    let (handle, status) = open_file(&torrent.local_path(target, file), file.length);
+    if let Some(status) = status {
+        return status;
+    }
+    let Some(handle) = handle else {
+        return FileStatus::Missing;
+    };
+    let mut reader = BufReader::with_capacity(LARGE_BUFFER_SIZE, handle);
+
+    let mut bad = Vec::new();
+    let mut remaining = file.length;
+    let mut index = 0;
+    while remaining > 0 {
+        let want = (buffer.len() as u64).min(remaining) as usize;
+        let got = read_full(&mut reader, &mut buffer[..want]);
+        let leaves = block_hashes(&buffer[..got]);
+
+        let matches = if single_piece {
+            let width = leaves.len().next_power_of_two();
+            got == want && merkle_root(leaves, width, [0u8; 32]) == pieces_root
+        } else {
+            let expected = layer.and_then(|layer| layer.get(index));
+            got == want && expected == Some(&merkle_root(leaves, blocks_per_piece, [0u8; 32]))
+        };
+        if !matches {
+            bad.push(index);
+        }
+
+        remaining -= want as u64;
+        index += 1;
+    }
+
+    if bad.is_empty() {
+        FileStatus::Ok
+    } else {
+        FileStatus::Corrupt(bad)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::ScratchDir;
+    use crate::torrent::bencode::{encode, Value};
+    use std::path::PathBuf;
+
+    fn dict(entries: Vec<(&str, Value)>) -> Value {
+        Value::Dict(entries.into_iter().map(|(key, value)| (key.as_bytes().to_vec(), value)).collect())
+    }
+
+    fn bytes(data: &[u8]) -> Value {
+        Value::Bytes(data.to_vec())
+    }
+
+    fn noise(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i * 7 + i / 251) as u8).collect()
+    }
+
+    fn status_of<'a>(report: &'a TorrentReport, path: &str) -> &'a FileStatus {
+        &report.files.iter().find(|file| file.path == path).unwrap().status
+    }
+
+    // Files "a" (10 bytes) and "b" (25 bytes) in 16-byte pieces, so piece 0
+    // spans both files
+    fn v1_torrent(dir: &ScratchDir) -> (PathBuf, Vec<u8>) {
+        let content = noise(35);
+        let pieces: Vec<u8> = content.chunks(16).flat_map(|piece| sha1::Sha1::digest(piece).to_vec()).collect();
+        let file = |name: &str, length| {
+            dict(vec![("length", Value::Int(length)), ("path", Value::List(vec![bytes(name.as_bytes())]))])
+        };
+        let info = dict(vec![
+            ("files", Value::List(vec![file("a", 10), file("b", 25)])),
+            ("name", bytes(b"content")),
+            ("piece length", Value::Int(16)),
+            ("pieces", bytes(&pieces)),
+        ]);
+        (dir.write("v1.torrent", &encode(&dict(vec![("info", info)]))), content)
+    }
+
+    #[test]
+    fn v1_pieces_are_checked_across_files() {
+        let dir = ScratchDir::new("torrent-v1");
+        let (torrent, content) = v1_torrent(&dir);
+        dir.write("content/a", &content[..10]);
+        dir.write("content/b", &content[10..]);
+
+        let report = verify_torrent(&torrent, dir.path()).unwrap();
+        assert!(report.is_ok());
+        assert_eq!((report.total_pieces, report.bad_pieces), (3, 0));
+
+        // The folder itself works as the target too
+        assert!(verify_torrent(&torrent, &dir.join("content")).unwrap().is_ok());
+
+        let mut b = content[10..].to_vec();
+        b[10] ^= 1; // Byte 20 of the stream, in piece 1
+        dir.write("content/b", &b);
+        let report = verify_torrent(&torrent, dir.path()).unwrap();
+        assert_eq!(report.bad_pieces, 1);
+        assert_eq!(status_of(&report, "a"), &FileStatus::Ok);
+        assert_eq!(status_of(&report, "b"), &FileStatus::Corrupt(vec![1]));
+
+        let mut a = content[..10].to_vec();
+        a[0] ^= 1;
+        dir.write("content/a", &a);
+        let report = verify_torrent(&torrent, dir.path()).unwrap();
+        assert_eq!(status_of(&report, "a"), &FileStatus::Corrupt(vec![0]));
+        assert_eq!(status_of(&report, "b"), &FileStatus::Corrupt(vec![0, 1]));
+    }
+
+    #[test]
+    fn v1_missing_and_resized_files() {
+        let dir = ScratchDir::new("torrent-v1-missing");
+        let (torrent, content) = v1_torrent(&dir);
+        dir.write("content/a", &content[..10]);
+
+        let report = verify_torrent(&torrent, dir.path()).unwrap();
+        assert_eq!(status_of(&report, "b"), &FileStatus::Missing);
+        // Piece 0 also needs the start of "b"
+        assert_eq!(status_of(&report, "a"), &FileStatus::Corrupt(vec![0]));
+
+        dir.write("content/b", &content[10..30]);
+        let report = verify_torrent(&torrent, dir.path()).unwrap();
+        assert_eq!(status_of(&report, "b"), &FileStatus::WrongSize(20));
+    }
+
+    // A 40000-byte file ("big", three 16 KiB pieces) and a 5-byte one
+    // ("small", a single block), written out by hand rather than with
+    // merkle_root
+    fn v2_torrent(dir: &ScratchDir, big: &[u8], small: &[u8], layer_pieces: usize) -> PathBuf {
+        let sha256 = |parts: &[&[u8]]| -> [u8; 32] {
+            let mut hasher = sha2::Sha256::new();
+            parts.iter().for_each(|part| hasher.update(part));
+            hasher.finalize().into()
+        };
+        let layer: Vec<[u8; 32]> = big.chunks(16384).map(|piece| sha256(&[piece])).collect();
+        let big_root = sha256(&[&sha256(&[&layer[0], &layer[1]]), &sha256(&[&layer[2], &[0u8; 32]])]);
+        let small_root = sha256(&[small]);
+
+        let leaf = |length: usize, root: &[u8]| {
+            let file = dict(vec![("length", Value::Int(length as i64)), ("pieces root", bytes(root))]);
+            dict(vec![("", file)])
+        };
+        let info = dict(vec![
+            ("file tree", dict(vec![("big", leaf(big.len(), &big_root)), ("small", leaf(small.len(), &small_root))])),
+            ("meta version", Value::Int(2)),
+            ("name", bytes(b"content")),
+            ("piece length", Value::Int(16384)),
+        ]);
+        let layers = Value::Dict([(big_root.to_vec(), bytes(&layer[..layer_pieces].concat()))].into());
+        dir.write("v2.torrent", &encode(&dict(vec![("info", info), ("piece layers", layers)])))
+    }
+
+    #[test]
+    fn v2_pieces_are_checked_per_file() {
+        let dir = ScratchDir::new("torrent-v2");
+        let (big, small) = (noise(40000), b"small".to_vec());
+        let torrent = v2_torrent(&dir, &big, &small, 3);
+        dir.write("content/big", &big);
+        dir.write("content/small", &small);
+
+        let report = verify_torrent(&torrent, dir.path()).unwrap();
+        assert!(report.is_ok());
+        assert_eq!((report.total_pieces, report.bad_pieces), (4, 0));
+
+        let mut changed = big.clone();
+        changed[20000] ^= 1;
+        dir.write("content/big", &changed);
+        dir.write("content/small", b"SMALL");
+        let report = verify_torrent(&torrent, dir.path()).unwrap();
+        assert_eq!(status_of(&report, "big"), &FileStatus::Corrupt(vec![1]));
+        assert_eq!(status_of(&report, "small"), &FileStatus::Corrupt(vec![0]));
+        assert_eq!(report.bad_pieces, 2);
+    }
+
+    #[test]
+    fn v2_piece_layer_must_hash_to_the_root() {
+        let dir = ScratchDir::new("torrent-v2-layer");
+        let (big, small) = (noise(40000), b"small".to_vec());
+        let torrent = v2_torrent(&dir, &big, &small, 2);
+        dir.write("content/big", &big);
+        dir.write("content/small", &small);
+
+        let report = verify_torrent(&torrent, dir.path()).unwrap();
+        assert!(matches!(status_of(&report, "big"), FileStatus::Unverifiable(_)));
+        assert_eq!(status_of(&report, "small"), &FileStatus::Ok);
+    }
+
+    #[test]
+    fn huge_piece_length_is_refused_before_allocating() {
+        let dir = ScratchDir::new("torrent-huge");
+        let info = dict(vec![
+            ("length", Value::Int(1)),
+            ("name", bytes(b"content")),
+            ("piece length", Value::Int(1 << 62)),
+            ("pieces", bytes(&[0u8; 20])),
+        ]);
+        let torrent = dir.write("huge.torrent", &encode(&dict(vec![("info", info)])));
+        assert!(verify_torrent(&torrent, dir.path()).is_err());
+    }
+}
@@ -9,4 +9,4 @@ pub const ACCENT_PURPLE_PRESSED: Color = Color::from_rgb(0x9D as f32 / 255.0, 0x
 pub const BG_INPUT: Color = Color::from_rgb(0x27 as f32 / 255.0, 0x27 as f32 / 255.0, 0x27 as f32 / 255.0); // #272727 - input background
 pub const BORDER_GRAY: Color = Color::from_rgb(0x40 as f32 / 255.0, 0x40 as f32 / 255.0, 0x40 as f32 / 255.0); // #404040 - gray border for inputs
 pub const ERROR_RED: Color = Color::from_rgb(0xFF as f32 / 255.0, 0x55 as f32 / 255.0, 0x55 as f32 / 255.0); // #FF5555 - red for errors
-
+pub const SUCCESS_GREEN: Color = Color::from_rgb(0x49 as f32 / 255.0, 0xEB as f32 / 255.0, 0x7A as f32 / 255.0); // #49EB7A - green for matches
@@ -38,6 +38,18 @@ pub fn text_light_style(_theme: &Theme) -> iced::widget::text::Style {
     }
 }
 
+pub fn success_text_style(_theme: &Theme) -> iced::widget::text::Style {
+    iced::widget::text::Style {
+        color: Some(SUCCESS_GREEN),
+    }
+}
+
+pub fn error_text_style(_theme: &Theme) -> iced::widget::text::Style {
+    iced::widget::text::Style {
+        color: Some(ERROR_RED),
+    }
+}
+
 pub fn purple_checkbox_style(_theme: &Theme, status: iced::widget::checkbox::Status) -> iced::widget::checkbox::Style {
     let (border_color, border_width) = match status {
         iced::widget::checkbox::Status::Active { is_checked: true } => (ACCENT_PURPLE, 2.0),
@@ -79,6 +91,27 @@ pub fn dark_text_input_style(_theme: &Theme, status: iced::widget::text_input::S
     }
 }
 
+// Same look as dark_text_input_style for the multi-line text editor
+pub fn dark_text_editor_style(_theme: &Theme, status: iced::widget::text_editor::Status) -> iced::widget::text_editor::Style {
+    let (border_color, border_width) = match status {
+        iced::widget::text_editor::Status::Focused => (ACCENT_PURPLE_HOVER, 2.0),
+        _ => (ACCENT_PURPLE, 1.0),
+    };
+    
+    iced::widget::text_editor::Style {
+        background: iced::Background::Color(BG_INPUT),
+        border: iced::Border {
+            color: border_color,
+            width: border_width,
+            radius: 4.0.into(),
+        },
+        icon: TEXT_LIGHT,
+        placeholder: Color::from_rgb(0x80 as f32 / 255.0, 0x80 as f32 / 255.0, 0x80 as f32 / 255.0),
+        selection: ACCENT_PURPLE,
+        value: TEXT_LIGHT,
+    }
+}
+
 // Style for text_input with error (red border)
 pub fn error_text_input_style(_theme: &Theme, status: iced::widget::text_input::Status) -> iced::widget::text_input::Style {
     let (border_color, border_width) = match status {
@@ -100,3 +133,21 @@ pub fn error_text_input_style(_theme: &Theme, status: iced::widget::text_input::
     }
 }
 
+
+// Dark background with purple border, matching the text inputs
+pub fn purple_pick_list_style(_theme: &Theme, status: iced::widget::pick_list::Status) -> iced::widget::pick_list::Style {
+    iced::widget::pick_list::Style {
+        text_color: TEXT_LIGHT,
+        background: iced::Background::Color(BG_INPUT),
+        border: iced::Border {
+            color: match status {
+                iced::widget::pick_list::Status::Hovered => ACCENT_PURPLE_HOVER,
+                _ => ACCENT_PURPLE,
+            },
+            width: 1.0,
+            radius: 4.0.into(),
+        },
+        placeholder_color: TEXT_LIGHT,
+        handle_color: ACCENT_PURPLE,
+    }
+}
@@ -1,9 +1,22 @@
-use iced::{widget::{button, checkbox, column, container, row, text, text_input, Column, scrollable, pick_list}, Element, Length};
-use crate::state::HashFormat;
+use iced::{widget::{button, checkbox, column, container, row, text, text_editor, text_input, Column, scrollable, pick_list}, Element, Length};
+use crate::state::{HashFormat, InputMode, Tool};
 use crate::state::RealOne;
 use crate::state::Message;
+use crate::torrent::FileStatus;
+use crate::oci::BlobStatus;
+use crate::binary::BinaryReport;
+use crate::archive::MemberHashes;
+use crate::archive::compression::Compression;
+use crate::rdiff::{block_lengths, RdiffOutcome, SignatureFormat};
+use crate::hash::cdc::CdcParams;
+use crate::hash::fuzzy::{FuzzyDigests, SimilarityReport};
+use crate::hash::perceptual::{HASH_BITS, NEAR_DUPLICATE_BITS};
+use crate::hash::piecewise::{PiecewiseOutcome, WindowSize};
+use crate::hash::normalize::Normalization;
+use crate::hash::range::RangeMode;
+use crate::hash::text::{TextEncoding, TrailingNewline};
 use crate::ui::styles::*;
-use crate::hash::{Algorithm, GostVariant, TigerVariant};
+use crate::hash::{Algorithm, Blake2Params, CrcModel, Ed2kVariant, GostVariant, HavalVariant, TigerVariant};
 
 pub fn view_main(state: &RealOne) -> Element<'_, Message, iced::Theme> {
     let file_path_text = if let Some(ref error) = state.file_error {
@@ -28,13 +41,24 @@ pub fn view_main(state: &RealOne) -> Element<'_, Message, iced::Theme> {
         .width(Length::Fill)
         .style(file_input_style);
 
+    let mode_picker = pick_list(
+        InputMode::all(),
+        Some(state.input_mode),
+        Message::InputModeChanged,
+    )
+    .style(purple_pick_list_style);
+
     let file_row = row![
-        text("File:")
+        text("Input:")
             .size(16)
             .style(text_light_style),
+        mode_picker,
         file_input.width(Length::Fill),
         button("Browse...")
             .on_press(Message::BrowseFile)
+            .style(purple_button_style),
+        button("Folder...")
+            .on_press(Message::BrowseFolder)
             .style(purple_button_style)
     ]
     .spacing(10);
@@ -53,6 +77,43 @@ pub fn view_main(state: &RealOne) -> Element<'_, Message, iced::Theme> {
     ]
     .spacing(10);
 
+    // Optional byte range; the two fields mean offset/length or header/trailer sizes
+    let mut range_row = row![
+        text("Range:")
+            .size(16)
+            .style(text_light_style),
+        pick_list(
+            RangeMode::all(),
+            Some(state.range_mode),
+            Message::RangeModeChanged,
+        )
+        .style(purple_pick_list_style)
+    ]
+    .spacing(10);
+
+    let range_placeholders = match state.range_mode {
+        RangeMode::WholeFile => None,
+        RangeMode::OffsetLength => Some(("Offset (e.g. 512 or 0x200)", "Length (empty = to end)")),
+        RangeMode::SkipHeadTail => Some(("Header bytes to skip", "Trailer bytes to skip")),
+    };
+    if let Some((first, second)) = range_placeholders {
+        range_row = range_row
+            .push(
+                text_input(first, &state.range_first)
+                    .size(16)
+                    .width(Length::Fill)
+                    .style(dark_text_input_style)
+                    .on_input(Message::RangeFirstChanged)
+            )
+            .push(
+                text_input(second, &state.range_second)
+                    .size(16)
+                    .width(Length::Fill)
+                    .style(dark_text_input_style)
+                    .on_input(Message::RangeSecondChanged)
+            );
+    }
+
     let check_button = button("Check")
         .on_press(Message::CheckButtonPressed)
         .style(purple_button_style)
@@ -63,14 +124,27 @@ pub fn view_main(state: &RealOne) -> Element<'_, Message, iced::Theme> {
         .style(purple_button_style)
         .width(Length::Shrink);
 
-    let buttons_row = row![check_button, settings_button]
+    // The tool windows share one list, so the row fits the main window
+    let tools_picker = pick_list(Tool::all(), None::<Tool>, Tool::message)
+        .placeholder("Tools...")
+        .style(purple_pick_list_style);
+
+    let export_button = button("Export...")
+        .on_press_maybe(
+            (state.input_mode == InputMode::File && !state.hash_results.is_empty())
+                .then_some(Message::ExportResults)
+        )
+        .style(purple_button_style)
+        .width(Length::Shrink);
+
+    let buttons_row = row![check_button, settings_button, export_button, tools_picker]
         .spacing(10);
 
     let mut results_column = Column::new()
         .spacing(10)
         .padding(10);
 
-    for algorithm in &state.selected_algorithms {
+    for algorithm in &state.active_algorithms() {
         let hash_value = state
             .hash_results
             .get(algorithm)
@@ -78,32 +152,38 @@ pub fn view_main(state: &RealOne) -> Element<'_, Message, iced::Theme> {
             .unwrap_or_else(|| String::new());
 
         // Normalize hashes for comparison
-        // Convert both to bytes to compare independently of format
+        // Convert both to bytes to compare independently of format, reading
+        // the entered hash in the result's own format first so a base32 hash
+        // made only of digits and A-F is not taken for hex
         use base64::Engine;
-        let check_hash_bytes = if let Ok(bytes) = hex::decode(state.check_hash.trim().replace(' ', "")) {
+        let check_hash_bytes = if let Ok(bytes) = state.result_format(algorithm).parse_hash(&state.check_hash) {
+            Some(bytes)
+        } else if let Ok(bytes) = hex::decode(state.check_hash.trim().replace(' ', "")) {
             Some(bytes)
         } else if let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(state.check_hash.trim().replace(' ', "")) {
             Some(bytes)
+        } else if let Ok(bytes) = HashFormat::Base32.parse_hash(&state.check_hash.to_uppercase()) {
+            Some(bytes)
         } else {
             None
         };
         
-        let calculated_hash_bytes = if let Ok(bytes) = hex::decode(hash_value.trim().replace(' ', "")) {
-            Some(bytes)
-        } else if let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(hash_value.trim().replace(' ', "")) {
-            Some(bytes)
+        let calculated_hash_bytes = state.result_format(algorithm).parse_hash(&hash_value).ok();
+        
+        // Compare bytes directly; similarity digests are compared as text
+        let matches = if algorithm.is_fuzzy() {
+            !hash_value.is_empty()
+                && !hash_value.starts_with("Error")
+                && hash_value.eq_ignore_ascii_case(state.check_hash.trim())
         } else {
-            None
+            check_hash_bytes.is_some()
+                && calculated_hash_bytes.is_some()
+                && !hash_value.is_empty()
+                && hash_value != "Error: Not implemented"
+                && check_hash_bytes == calculated_hash_bytes
         };
-        
-        // Compare bytes directly
-        let matches = check_hash_bytes.is_some()
-            && calculated_hash_bytes.is_some()
-            && !hash_value.is_empty()
-            && hash_value != "Error: Not implemented"
-            && check_hash_bytes == calculated_hash_bytes;
 
-        let algorithm_name = format!("{}:", algorithm.to_string());
+        let algorithm_name = format!("{}:", algorithm.label(&state.hash_options));
         let hash_input = text_input("", &hash_value)
             .size(14)
             .width(Length::Fill)
@@ -126,6 +206,21 @@ pub fn view_main(state: &RealOne) -> Element<'_, Message, iced::Theme> {
         ]
         .spacing(10);
 
+        // ed2k and TTH results can also be copied as a complete ed2k:// or magnet: link
+        if algorithm.has_link()
+            && state.input_mode == InputMode::File
+            && state.results_cover_whole_file()
+            && !hash_value.is_empty()
+            && !hash_value.starts_with("Error")
+        {
+            result_row = result_row.push(
+                button("Copy Link")
+                    .on_press(Message::CopyLink(algorithm.clone()))
+                    .style(purple_button_style)
+                    .width(Length::Shrink)
+            );
+        }
+
         // Add green match indicator if they match
         if matches {
             result_row = result_row.push(
@@ -157,9 +252,144 @@ pub fn view_main(state: &RealOne) -> Element<'_, Message, iced::Theme> {
                 .style(text_light_style)
         );
     }
+    if let Some(range) = &state.results_range {
+        if !state.hash_results.is_empty() {
+            progress_section = progress_section.push(
+                text(format!("Range: {}", range))
+                    .size(14)
+                    .style(text_light_style)
+            );
+        }
+    }
+    if let Some(set) = &state.split_set {
+        if !state.split_parts.is_empty() {
+            progress_section = progress_section.push(
+                row![
+                    text(format!("Joined: {} parts of {} ({} bytes)", set.parts.len(), set.name, set.total_size()))
+                        .size(14)
+                        .style(text_light_style),
+                    button("Parts...")
+                        .on_press(Message::ViewSplitParts)
+                        .style(purple_button_style)
+                ]
+                .spacing(10)
+                .align_y(iced::alignment::Vertical::Center)
+            );
+        }
+    }
+    if state.results_compression != Compression::None && !state.hash_results.is_empty() {
+        progress_section = progress_section.push(
+            text(format!("Decompressed: {}", state.results_compression))
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+    if let Some(offset) = state.results_resumed_from {
+        if !state.hash_results.is_empty() {
+            progress_section = progress_section.push(
+                text(format!("Resumed: from checkpoint at {} bytes", offset))
+                    .size(14)
+                    .style(text_light_style)
+            );
+        }
+    }
+    if let Some(skipped) = &state.results_resume_skipped {
+        if !state.hash_results.is_empty() {
+            progress_section = progress_section.push(
+                text(format!("Resume skipped: {}", skipped))
+                    .size(14)
+                    .style(text_light_style)
+            );
+        }
+    }
+    if state.results_normalization.is_active() && !state.hash_results.is_empty() {
+        let normalized = text(format!("Normalized: {}", state.results_normalization))
+            .size(14)
+            .style(text_light_style);
+        if state.results_normalization.canonical_json {
+            progress_section = progress_section.push(
+                row![
+                    normalized,
+                    button("View...")
+                        .on_press(Message::ViewCanonicalJson)
+                        .style(purple_button_style)
+                ]
+                .spacing(10)
+                .align_y(iced::alignment::Vertical::Center)
+            );
+        } else {
+            progress_section = progress_section.push(normalized);
+        }
+    }
+    if let Some(status) = &state.export_status {
+        progress_section = progress_section.push(
+            text(status)
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+
+    // Text mode replaces the file and range rows with an editor whose contents
+    // are hashed as they are typed
+    let input_section: Element<'_, Message, iced::Theme> = match state.input_mode {
+        InputMode::File => column![file_row, range_row].spacing(15).into(),
+        InputMode::Text => {
+            let mut options_row = row![
+                text("Input:")
+                    .size(16)
+                    .style(text_light_style),
+                pick_list(
+                    InputMode::all(),
+                    Some(state.input_mode),
+                    Message::InputModeChanged,
+                )
+                .style(purple_pick_list_style),
+                pick_list(
+                    TextEncoding::all(),
+                    Some(state.text_encoding),
+                    Message::TextEncodingChanged,
+                )
+                .style(purple_pick_list_style)
+            ]
+            .spacing(10);
+
+            if state.text_encoding.is_text() {
+                options_row = options_row.push(
+                    pick_list(
+                        TrailingNewline::all(),
+                        Some(state.trailing_newline),
+                        Message::TrailingNewlineChanged,
+                    )
+                    .style(purple_pick_list_style)
+                );
+            }
+
+            let placeholder = match state.text_encoding {
+                TextEncoding::Hex => "Paste hex bytes...",
+                TextEncoding::Base64 => "Paste base64 data...",
+                _ => "Type or paste text...",
+            };
+            let editor = text_editor(&state.text_input)
+                .placeholder(placeholder)
+                .on_action(Message::TextInputEdited)
+                .size(14)
+                .height(100)
+                .style(dark_text_editor_style);
+
+            let mut section = column![options_row, editor].spacing(10);
+            if let Some(error) = &state.file_error {
+                section = section.push(
+                    text(error)
+                        .size(14)
+                        .style(error_text_style)
+                );
+            }
+            section.into()
+        }
+    };
 
     let content = column![
-        file_row,
+        input_section,
         check_row,
         buttons_row,
         text("Hash Results:")
@@ -194,23 +424,7 @@ pub fn view_settings(state: &RealOne) -> Element<'_, Message, iced::Theme> {
         Message::FormatChanged,
     )
     .width(Length::Fill)
-    .style(|_theme: &iced::Theme, status| {
-        use crate::ui::colors::*;
-        iced::widget::pick_list::Style {
-            text_color: TEXT_LIGHT,
-            background: iced::Background::Color(BG_INPUT),
-            border: iced::Border {
-                color: match status {
-                    iced::widget::pick_list::Status::Hovered => ACCENT_PURPLE_HOVER,
-                    _ => ACCENT_PURPLE,
-                },
-                width: 1.0,
-                radius: 4.0.into(),
-            },
-            placeholder_color: TEXT_LIGHT,
-            handle_color: ACCENT_PURPLE,
-        }
-    });
+    .style(purple_pick_list_style);
 
     let gost_label = text("GOST Variant:")
         .size(16)
@@ -218,27 +432,11 @@ pub fn view_settings(state: &RealOne) -> Element<'_, Message, iced::Theme> {
     
     let gost_picker = pick_list(
         GostVariant::all(),
-        Some(state.gost_variant),
+        Some(state.hash_options.gost_variant),
         Message::GostVariantChanged,
     )
     .width(Length::Fill)
-    .style(|_theme: &iced::Theme, status| {
-        use crate::ui::colors::*;
-        iced::widget::pick_list::Style {
-            text_color: TEXT_LIGHT,
-            background: iced::Background::Color(BG_INPUT),
-            border: iced::Border {
-                color: match status {
-                    iced::widget::pick_list::Status::Hovered => ACCENT_PURPLE_HOVER,
-                    _ => ACCENT_PURPLE,
-                },
-                width: 1.0,
-                radius: 4.0.into(),
-            },
-            placeholder_color: TEXT_LIGHT,
-            handle_color: ACCENT_PURPLE,
-        }
-    });
+    .style(purple_pick_list_style);
 
     let tiger_label = text("TIGER Variant:")
         .size(16)
@@ -246,98 +444,400 @@ pub fn view_settings(state: &RealOne) -> Element<'_, Message, iced::Theme> {
     
     let tiger_picker = pick_list(
         TigerVariant::all(),
-        Some(state.tiger_variant),
+        Some(state.hash_options.tiger_variant),
         Message::TigerVariantChanged,
     )
     .width(Length::Fill)
-    .style(|_theme: &iced::Theme, status| {
-        use crate::ui::colors::*;
-        iced::widget::pick_list::Style {
-            text_color: TEXT_LIGHT,
-            background: iced::Background::Color(BG_INPUT),
-            border: iced::Border {
-                color: match status {
-                    iced::widget::pick_list::Status::Hovered => ACCENT_PURPLE_HOVER,
-                    _ => ACCENT_PURPLE,
-                },
-                width: 1.0,
-                radius: 4.0.into(),
-            },
-            placeholder_color: TEXT_LIGHT,
-            handle_color: ACCENT_PURPLE,
-        }
-    });
-
-    // Split algorithms into two columns
-    let mid_point = (all_algorithms.len() + 1) / 2; // Split approximately in half
-    let (left_algorithms, right_algorithms) = all_algorithms.split_at(mid_point);
-
-    // Left column
-    let mut left_column = Column::new()
-        .spacing(10)
-        .padding(10)
-        .width(Length::Fill);
-
-    for algorithm in left_algorithms {
-        let is_selected = state.selected_algorithms.contains(algorithm);
-        let alg_clone = algorithm.clone();
-        let checkbox_widget = checkbox(
-            algorithm.to_string(),
-            is_selected,
-        )
-        .style(purple_checkbox_style)
-        .on_toggle(move |enabled| Message::AlgorithmToggled(alg_clone.clone(), enabled));
-        left_column = left_column.push(checkbox_widget);
-    }
+    .style(purple_pick_list_style);
 
-    // Right column
-    let mut right_column = Column::new()
-        .spacing(10)
-        .padding(10)
-        .width(Length::Fill);
-
-    for algorithm in right_algorithms {
-        let is_selected = state.selected_algorithms.contains(algorithm);
-        let alg_clone = algorithm.clone();
-        let checkbox_widget = checkbox(
-            algorithm.to_string(),
-            is_selected,
-        )
-        .style(purple_checkbox_style)
-        .on_toggle(move |enabled| Message::AlgorithmToggled(alg_clone.clone(), enabled));
-        right_column = right_column.push(checkbox_widget);
+    let crc_label = text("CRC Model:")
+        .size(16)
+        .style(text_light_style);
+    
+    let crc_picker = pick_list(
+        CrcModel::all(),
+        Some(state.hash_options.crc_model),
+        Message::CrcModelChanged,
+    )
+    .width(Length::Fill)
+    .style(purple_pick_list_style);
+
+    // Custom CRCs come from the config file; show whether they pass their check value
+    let mut crc_section = column![crc_label, crc_picker].spacing(15);
+    if state.hash_options.crc_model == CrcModel::Custom {
+        let custom = &state.hash_options.custom_crc;
+        let status = match custom.resolve() {
+            Ok(params) => format!(
+                "width={} poly=0x{} init=0x{} refin={} refout={} xorout=0x{} (check OK)",
+                params.width,
+                params.format_value(params.poly),
+                params.format_value(params.init),
+                params.refin,
+                params.refout,
+                params.format_value(params.xorout),
+            ),
+            Err(e) => e,
+        };
+        crc_section = crc_section.push(
+            text(status)
+                .size(14)
+                .style(text_light_style)
+        );
     }
 
-    // Create the two columns side by side
-    let checkboxes_row = row![
-        left_column,
-        right_column
-    ]
-    .spacing(20)
-    .width(Length::Fill);
+    let haval_label = text("HAVAL Variant (passes/length):")
+        .size(16)
+        .style(text_light_style);
+    
+    let haval_picker = pick_list(
+        HavalVariant::all(),
+        Some(state.hash_options.haval_variant),
+        Message::HavalVariantChanged,
+    )
+    .width(Length::Fill)
+    .style(purple_pick_list_style);
 
-    let scrollable_content = scrollable(
-        checkboxes_row
-            .width(Length::Fill)
+    let ed2k_label = text("ed2k End-of-File Convention:")
+        .size(16)
+        .style(text_light_style);
+    
+    let ed2k_picker = pick_list(
+        Ed2kVariant::all(),
+        Some(state.hash_options.ed2k_variant),
+        Message::Ed2kVariantChanged,
     )
     .width(Length::Fill)
-    .height(Length::Fill);
+    .style(purple_pick_list_style);
 
-    let content = column![
+    // Text clean-up before hashing; each checkbox produces the updated settings
+    let normalization = state.normalization;
+    let normalization_section = column![
+        text("Text Normalization (applied before hashing):")
+            .size(16)
+            .style(text_light_style),
+        checkbox("Convert CRLF and CR line endings to LF", normalization.line_endings)
+            .style(purple_checkbox_style)
+            .on_toggle(move |line_endings| Message::NormalizationChanged(Normalization { line_endings, ..normalization })),
+        checkbox("Strip UTF-8 byte order mark", normalization.strip_bom)
+            .style(purple_checkbox_style)
+            .on_toggle(move |strip_bom| Message::NormalizationChanged(Normalization { strip_bom, ..normalization })),
+        checkbox("Strip trailing spaces and tabs", normalization.strip_trailing_whitespace)
+            .style(purple_checkbox_style)
+            .on_toggle(move |strip_trailing_whitespace| {
+                Message::NormalizationChanged(Normalization { strip_trailing_whitespace, ..normalization })
+            }),
+        checkbox("Hash JSON in RFC 8785 canonical form (ignores layout and key order)", normalization.canonical_json)
+            .style(purple_checkbox_style)
+            .on_toggle(move |canonical_json| Message::NormalizationChanged(Normalization { canonical_json, ..normalization }))
+    ]
+    .spacing(10);
+
+    let decompress_section = column![
+        text("Compressed Files:")
+            .size(16)
+            .style(text_light_style),
+        checkbox("Hash the decompressed contents of gzip, bzip2, xz and zstd files", state.decompress)
+            .style(purple_checkbox_style)
+            .on_toggle(Message::DecompressChanged)
+    ]
+    .spacing(10);
+
+    let resume_section = column![
+        text("Large Files:")
+            .size(16)
+            .style(text_light_style),
+        checkbox("Resume interrupted hashes: checkpoint files over 1 GB every 1 GB (MD5, SHA-1, SHA-2, SHA-3, CRC, Adler-32, HAVAL, Snefru, TTH and git blob IDs)", state.resume)
+            .style(purple_checkbox_style)
+            .on_toggle(Message::ResumeChanged)
+    ]
+    .spacing(10);
+
+    let blake2b_section = blake2_settings(
+        "BLAKE2b Parameters:",
+        Blake2Params::blake2b_lengths(),
+        &state.hash_options.blake2b,
+        Message::Blake2bParamsChanged,
+    );
+
+    let blake2s_section = blake2_settings(
+        "BLAKE2s Parameters:",
+        Blake2Params::blake2s_lengths(),
+        &state.hash_options.blake2s,
+        Message::Blake2sParamsChanged,
+    );
+
+    // Current algorithms first; similarity, image and legacy ones get their own sections below
+    let (fuzzy_algorithms, all_algorithms): (Vec<Algorithm>, Vec<Algorithm>) =
+        all_algorithms.into_iter().partition(|alg| alg.is_fuzzy());
+    let (perceptual_algorithms, all_algorithms): (Vec<Algorithm>, Vec<Algorithm>) =
+        all_algorithms.into_iter().partition(|alg| alg.is_perceptual());
+    let (legacy_algorithms, current_algorithms): (Vec<Algorithm>, Vec<Algorithm>) =
+        all_algorithms.into_iter().partition(|alg| alg.is_legacy());
+
+    let checkboxes_row = algorithm_checkboxes(state, &current_algorithms);
+    let fuzzy_checkboxes_row = algorithm_checkboxes(state, &fuzzy_algorithms);
+    let perceptual_checkboxes_row = algorithm_checkboxes(state, &perceptual_algorithms);
+    let legacy_checkboxes_row = algorithm_checkboxes(state, &legacy_algorithms);
+
+    let settings_column = column![
         format_label,
         format_picker,
         gost_label,
         gost_picker,
         tiger_label,
         tiger_picker,
+        blake2b_section,
+        blake2s_section,
+        crc_section,
+        ed2k_label,
+        ed2k_picker,
+        haval_label,
+        haval_picker,
+        normalization_section,
+        decompress_section,
+        resume_section,
         text("Select Hash Algorithms:")
             .size(18)
             .style(text_light_style),
-        scrollable_content
+        checkboxes_row,
+        text("Similarity Hashes (similar files give similar digests):")
+            .size(18)
+            .style(text_light_style),
+        fuzzy_checkboxes_row,
+        text("Perceptual Image Hashes (only shown when the file is an image):")
+            .size(18)
+            .style(text_light_style),
+        perceptual_checkboxes_row,
+        text("Legacy Algorithms (compatibility only, not collision resistant):")
+            .size(18)
+            .style(text_light_style),
+        legacy_checkboxes_row
     ]
     .spacing(15)
-    .width(Length::Fill)
-    .height(Length::Fill);
+    .width(Length::Fill);
+
+    // Everything scrolls together now that the parameter sections make the page taller
+    let content = scrollable(settings_column)
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+
+// Digest length picker plus key/salt/personalization inputs for one BLAKE2 variant
+fn blake2_settings<'a>(
+    title: &'a str,
+    lengths: Vec<usize>,
+    params: &'a Blake2Params,
+    on_change: fn(Blake2Params) -> Message,
+) -> Element<'a, Message, iced::Theme> {
+    let length_picker = pick_list(
+        lengths,
+        Some(params.digest_bits),
+        move |digest_bits| on_change(Blake2Params { digest_bits, ..params.clone() }),
+    )
+    .width(Length::Fixed(90.0))
+    .style(purple_pick_list_style);
+
+    let key_input = text_input("Key (hex)", &params.key)
+        .size(14)
+        .width(Length::Fill)
+        .style(dark_text_input_style)
+        .on_input(move |key| on_change(Blake2Params { key, ..params.clone() }));
+
+    let salt_input = text_input("Salt (hex)", &params.salt)
+        .size(14)
+        .width(Length::Fill)
+        .style(dark_text_input_style)
+        .on_input(move |salt| on_change(Blake2Params { salt, ..params.clone() }));
+
+    let personal_input = text_input("Personalization (hex)", &params.personal)
+        .size(14)
+        .width(Length::Fill)
+        .style(dark_text_input_style)
+        .on_input(move |personal| on_change(Blake2Params { personal, ..params.clone() }));
+
+    column![
+        text(title)
+            .size(16)
+            .style(text_light_style),
+        row![length_picker, key_input, salt_input, personal_input]
+            .spacing(10)
+    ]
+    .spacing(15)
+    .into()
+}
+
+// Two columns of algorithm checkboxes, split approximately in half
+fn algorithm_checkboxes<'a>(state: &'a RealOne, algorithms: &[Algorithm]) -> Element<'a, Message, iced::Theme> {
+    let mid_point = algorithms.len().div_ceil(2);
+    let (left_algorithms, right_algorithms) = algorithms.split_at(mid_point);
+
+    let mut columns = row![].spacing(20).width(Length::Fill);
+    for half in [left_algorithms, right_algorithms] {
+        let mut column = Column::new()
+            .spacing(10)
+            .padding(10)
+            .width(Length::Fill);
+
+        for algorithm in half {
+            let is_selected = state.selected_algorithms.contains(algorithm);
+            let alg_clone = algorithm.clone();
+            let checkbox_widget = checkbox(
+                algorithm.to_string(),
+                is_selected,
+            )
+            .style(purple_checkbox_style)
+            .on_toggle(move |enabled| Message::AlgorithmToggled(alg_clone.clone(), enabled));
+            column = column.push(checkbox_widget);
+        }
+
+        columns = columns.push(column);
+    }
+
+    columns.into()
+}
+
+pub fn view_torrent(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let path_text = |path: &Option<std::path::PathBuf>, empty: &str| {
+        path.as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| empty.to_string())
+    };
+
+    let torrent_row = row![
+        text("Torrent:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select a .torrent file...", &path_text(&state.torrent_path, "No torrent selected"))
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Browse...")
+            .on_press(Message::BrowseTorrent)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let content_row = row![
+        text("Data:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select the downloaded file or folder...", &path_text(&state.torrent_content, "No data selected"))
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("File...")
+            .on_press(Message::BrowseTorrentContentFile)
+            .style(purple_button_style),
+        button("Folder...")
+            .on_press(Message::BrowseTorrentContentFolder)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    // Disabled while a verification is running or something is not selected
+    let can_verify = state.torrent_path.is_some() && state.torrent_content.is_some() && !state.torrent_running;
+    let verify_button = button("Verify")
+        .on_press_maybe(can_verify.then_some(Message::VerifyTorrent))
+        .style(purple_button_style)
+        .width(Length::Shrink);
+
+    let mut results_column = Column::new().spacing(8);
+
+    if state.torrent_running {
+        results_column = results_column.push(
+            text("Verifying...")
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+
+    match &state.torrent_result {
+        Some(Err(error)) => {
+            results_column = results_column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        Some(Ok(report)) => {
+            results_column = results_column.push(
+                text(format!("{} - checked with {} hashes", report.name, report.version))
+                    .size(16)
+                    .style(text_light_style)
+            );
+
+            for (label, infohash) in [("Infohash v1:", &report.infohash_v1), ("Infohash v2:", &report.infohash_v2)] {
+                if let Some(infohash) = infohash {
+                    results_column = results_column.push(
+                        row![
+                            text(label)
+                                .size(14)
+                                .style(text_light_style),
+                            text_input("", infohash)
+                                .size(14)
+                                .width(Length::Fill)
+                                .style(dark_text_input_style)
+                        ]
+                        .spacing(10)
+                    );
+                }
+            }
+
+            let summary = if report.is_ok() {
+                text(format!("All {} pieces OK", report.total_pieces))
+                    .size(14)
+                    .style(success_text_style)
+            } else {
+                text(format!("{} of {} pieces failed", report.bad_pieces, report.total_pieces))
+                    .size(14)
+                    .style(error_text_style)
+            };
+            results_column = results_column.push(summary);
+
+            let mut files_column = Column::new().spacing(4);
+            for file in &report.files {
+                let line = match &file.status {
+                    FileStatus::Ok => text(format!("[OK] {}", file.path)).style(success_text_style),
+                    FileStatus::Corrupt(pieces) => {
+                        // Long lists are cut short; the count is what matters
+                        let mut listed: Vec<String> = pieces.iter().take(10).map(|p| p.to_string()).collect();
+                        if pieces.len() > listed.len() {
+                            listed.push(format!("... ({} total)", pieces.len()));
+                        }
+                        text(format!("[CORRUPT] {} - pieces {}", file.path, listed.join(", "))).style(error_text_style)
+                    }
+                    FileStatus::Missing => text(format!("[MISSING] {}", file.path)).style(error_text_style),
+                    FileStatus::WrongSize(actual) => text(format!(
+                        "[WRONG SIZE] {} - {} bytes, expected {}",
+                        file.path, actual, file.length
+                    ))
+                    .style(error_text_style),
+                    FileStatus::Unverifiable(reason) => text(format!("[UNVERIFIED] {} - {}", file.path, reason)).style(error_text_style),
+                };
+                files_column = files_column.push(line.size(13));
+            }
+            results_column = results_column.push(scrollable(files_column).height(Length::Fill));
+        }
+        None => {}
+    }
+
+    let content = column![
+        torrent_row,
+        content_row,
+        verify_button,
+        results_column
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
 
     container(content)
         .width(Length::Fill)
@@ -347,3 +847,1377 @@ pub fn view_settings(state: &RealOne) -> Element<'_, Message, iced::Theme> {
         .into()
 }
 
+pub fn view_piecewise(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let file_text = state
+        .piecewise_file
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "No file selected".to_string());
+
+    let file_row = row![
+        text("File:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select a file...", &file_text)
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Browse...")
+            .on_press(Message::BrowsePiecewiseFile)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let options_row = row![
+        text("Algorithm:")
+            .size(16)
+            .style(text_light_style),
+        pick_list(
+            Algorithm::all().into_iter().filter(|alg| !alg.is_fuzzy() && !alg.is_perceptual()).collect::<Vec<_>>(),
+            Some(state.piecewise_algorithm.clone()),
+            Message::PiecewiseAlgorithmChanged,
+        )
+        .width(Length::Fill)
+        .style(purple_pick_list_style),
+        text("Window:")
+            .size(16)
+            .style(text_light_style),
+        pick_list(
+            WindowSize::all(),
+            Some(state.piecewise_window),
+            Message::PiecewiseWindowChanged,
+        )
+        .width(Length::Fill)
+        .style(purple_pick_list_style)
+    ]
+    .spacing(10);
+
+    // File actions need a file; comparing two manifests does not
+    let file_action = |label, message| {
+        button(label)
+            .on_press_maybe((state.piecewise_file.is_some() && !state.piecewise_running).then_some(message))
+            .style(purple_button_style)
+    };
+    let actions_row = row![
+        file_action("Create Manifest...", Message::CreatePiecewiseManifest),
+        file_action("Compare with Manifest...", Message::CompareWithManifest),
+        button("Compare Two Manifests...")
+            .on_press_maybe((!state.piecewise_running).then_some(Message::CompareManifests))
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let mut results_column = Column::new().spacing(8);
+
+    if state.piecewise_running {
+        results_column = results_column.push(
+            text("Computing...")
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+
+    match &state.piecewise_result {
+        Some(Err(error)) => {
+            results_column = results_column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        Some(Ok(PiecewiseOutcome::Created { path, windows, total })) => {
+            results_column = results_column.push(
+                text(format!("Saved {} window hashes to {}", windows, path.display()))
+                    .size(14)
+                    .style(success_text_style)
+            );
+            results_column = results_column.push(
+                row![
+                    text("Total:")
+                        .size(14)
+                        .style(text_light_style),
+                    text_input("", total)
+                        .size(14)
+                        .width(Length::Fill)
+                        .style(dark_text_input_style)
+                ]
+                .spacing(10)
+            );
+        }
+        Some(Ok(PiecewiseOutcome::Compared(comparison))) => {
+            let (expected_size, actual_size) = comparison.sizes;
+            if comparison.differing.is_empty() && comparison.total_match {
+                results_column = results_column.push(
+                    text(format!("All {} windows match", comparison.windows_compared))
+                        .size(14)
+                        .style(success_text_style)
+                );
+            } else {
+                let mut summary = format!(
+                    "{} differing range(s) in {} windows of {} bytes",
+                    comparison.differing.len(),
+                    comparison.windows_compared,
+                    comparison.window
+                );
+                if expected_size != actual_size {
+                    summary.push_str(&format!(" (size {} expected, {} found)", expected_size, actual_size));
+                }
+                results_column = results_column.push(
+                    text(summary)
+                        .size(14)
+                        .style(error_text_style)
+                );
+
+                let mut ranges_column = Column::new().spacing(4);
+                for range in &comparison.differing {
+                    ranges_column = ranges_column.push(
+                        text(format!(
+                            "bytes {} - {} (0x{:x} - 0x{:x}, {} bytes)",
+                            range.start,
+                            range.end,
+                            range.start,
+                            range.end,
+                            range.end - range.start
+                        ))
+                        .size(13)
+                        .style(text_light_style)
+                    );
+                }
+                results_column = results_column.push(scrollable(ranges_column).height(Length::Fill));
+            }
+        }
+        None => {}
+    }
+
+    let content = column![
+        file_row,
+        options_row,
+        actions_row,
+        results_column
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+pub fn view_oci(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let layout_text = state
+        .oci_layout
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "No layout selected".to_string());
+
+    let layout_row = row![
+        text("Layout:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select an OCI image-layout folder...", &layout_text)
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Folder...")
+            .on_press(Message::BrowseOciLayout)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let can_verify = state.oci_layout.is_some() && !state.oci_running;
+    let verify_button = button("Verify")
+        .on_press_maybe(can_verify.then_some(Message::VerifyOciLayout))
+        .style(purple_button_style)
+        .width(Length::Shrink);
+
+    let mut results_column = Column::new().spacing(8);
+
+    if state.oci_running {
+        results_column = results_column.push(
+            text("Verifying...")
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+
+    match &state.oci_result {
+        Some(Err(error)) => {
+            results_column = results_column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        Some(Ok(report)) => {
+            let failed = report.blobs.iter().filter(|blob| blob.status != BlobStatus::Ok).count();
+            results_column = results_column.push(
+                text(format!(
+                    "Layout version {} - {} blobs referenced by {} indexes and manifests",
+                    report.layout_version,
+                    report.blobs.len(),
+                    report.manifests
+                ))
+                .size(16)
+                .style(text_light_style)
+            );
+
+            let summary = if report.is_ok() {
+                text(format!("All {} blobs OK", report.blobs.len()))
+                    .size(14)
+                    .style(success_text_style)
+            } else {
+                text(format!("{} of {} blobs failed", failed, report.blobs.len()))
+                    .size(14)
+                    .style(error_text_style)
+            };
+            results_column = results_column.push(summary);
+
+            // Problems first, so they are visible without scrolling
+            let mut blobs_column = Column::new().spacing(4);
+            let (good, bad): (Vec<_>, Vec<_>) = report.blobs.iter().partition(|blob| blob.status == BlobStatus::Ok);
+            for blob in bad.into_iter().chain(good) {
+                let line = match &blob.status {
+                    BlobStatus::Ok => text(format!("[OK] {} ({})", blob.digest, blob.media_type)).style(success_text_style),
+                    BlobStatus::Missing => text(format!("[MISSING] {} ({})", blob.digest, blob.media_type)).style(error_text_style),
+                    BlobStatus::WrongSize(actual) => text(format!(
+                        "[WRONG SIZE] {} - {} bytes, expected {}",
+                        blob.digest, actual, blob.size
+                    ))
+                    .style(error_text_style),
+                    BlobStatus::Corrupt(actual) => text(format!("[CORRUPT] {} - content hashes to {}", blob.digest, actual)).style(error_text_style),
+                    BlobStatus::Invalid(reason) => text(format!("[INVALID] {} - {}", blob.digest, reason)).style(error_text_style),
+                };
+                blobs_column = blobs_column.push(line.size(13));
+            }
+            for digest in &report.unreferenced {
+                blobs_column = blobs_column.push(
+                    text(format!("[UNREFERENCED] {}", digest))
+                        .size(13)
+                        .style(text_light_style)
+                );
+            }
+            results_column = results_column.push(scrollable(blobs_column).height(Length::Fill));
+        }
+        None => {}
+    }
+
+    let content = column![
+        layout_row,
+        verify_button,
+        results_column
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+pub fn view_cdc(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let mut files_column = Column::new().spacing(4);
+    if state.cdc_files.is_empty() {
+        files_column = files_column.push(
+            text("No files selected")
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+    for path in &state.cdc_files {
+        files_column = files_column.push(
+            text(path.display().to_string())
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+
+    let files_row = row![
+        button("Add Files...")
+            .on_press_maybe((!state.cdc_running).then_some(Message::AddCdcFiles))
+            .style(purple_button_style),
+        button("Clear")
+            .on_press_maybe((!state.cdc_running && !state.cdc_files.is_empty()).then_some(Message::ClearCdcFiles))
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let size_input = |label, value: &str, on_change: fn(String) -> Message| {
+        row![
+            text(label)
+                .size(16)
+                .style(text_light_style),
+            text_input("e.g. 64K", value)
+                .on_input(on_change)
+                .size(16)
+                .width(Length::Fixed(90.0))
+                .style(dark_text_input_style)
+        ]
+        .spacing(10)
+    };
+    let sizes_row = row![
+        size_input("Min:", &state.cdc_min, Message::CdcMinChanged),
+        size_input("Avg:", &state.cdc_avg, Message::CdcAvgChanged),
+        size_input("Max:", &state.cdc_max, Message::CdcMaxChanged),
+        text("Digest:")
+            .size(16)
+            .style(text_light_style),
+        pick_list(
+            CdcParams::algorithms(),
+            Some(state.cdc_algorithm.clone()),
+            Message::CdcAlgorithmChanged,
+        )
+        .style(purple_pick_list_style)
+    ]
+    .spacing(20);
+
+    let can_analyze = !state.cdc_files.is_empty() && !state.cdc_running;
+    let analyze_button = button("Analyze")
+        .on_press_maybe(can_analyze.then_some(Message::AnalyzeCdc))
+        .style(purple_button_style)
+        .width(Length::Shrink);
+
+    let mut results_column = Column::new().spacing(8);
+
+    if state.cdc_running {
+        results_column = results_column.push(
+            text("Chunking...")
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+
+    match &state.cdc_result {
+        Some(Err(error)) => {
+            results_column = results_column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        Some(Ok(report)) => {
+            results_column = results_column.push(
+                text(format!(
+                    "{} chunks, {} bytes in {} files ({} chunk digests)",
+                    report.total_chunks,
+                    report.total_bytes,
+                    report.files.len(),
+                    report.label
+                ))
+                .size(16)
+                .style(text_light_style)
+            );
+            results_column = results_column.push(
+                text(format!(
+                    "Unique: {} chunks, {} bytes - {} chunks occur more than once",
+                    report.unique_chunks, report.unique_bytes, report.repeated_chunks
+                ))
+                .size(14)
+                .style(text_light_style)
+            );
+            results_column = results_column.push(
+                text(format!(
+                    "Chunk sizes: {} to {} bytes, average {}",
+                    report.smallest_chunk,
+                    report.largest_chunk,
+                    report.total_bytes / report.total_chunks.max(1) as u64
+                ))
+                .size(14)
+                .style(text_light_style)
+            );
+            results_column = results_column.push(
+                text(format!("Estimated dedup ratio: {:.3}", report.dedup_ratio()))
+                    .size(14)
+                    .style(success_text_style)
+            );
+
+            let mut per_file_column = Column::new().spacing(4);
+            for file in &report.files {
+                per_file_column = per_file_column.push(
+                    text(format!(
+                        "{} - {} bytes, {} chunks, {} shared with other files",
+                        file.path.display(),
+                        file.size,
+                        file.chunks,
+                        file.shared_chunks
+                    ))
+                    .size(13)
+                    .style(text_light_style)
+                );
+            }
+            results_column = results_column.push(scrollable(per_file_column).height(Length::Fill));
+        }
+        None => {}
+    }
+
+    let content = column![
+        scrollable(files_column).height(Length::Fixed(100.0)),
+        files_row,
+        sizes_row,
+        analyze_button,
+        results_column
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+pub fn view_rdiff(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let file_text = state
+        .rdiff_file
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "No file selected".to_string());
+
+    let file_row = row![
+        text("File:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select a file...", &file_text)
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Browse...")
+            .on_press(Message::BrowseRdiffFile)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let hint = text("Signatures and patches start from the old file; deltas are made for the new file.")
+        .size(14)
+        .style(text_light_style);
+
+    let options_row = row![
+        text("Signature:")
+            .size(16)
+            .style(text_light_style),
+        pick_list(
+            SignatureFormat::all(),
+            Some(state.rdiff_format),
+            Message::RdiffFormatChanged,
+        )
+        .width(Length::Fill)
+        .style(purple_pick_list_style),
+        text("Block bytes:")
+            .size(16)
+            .style(text_light_style),
+        pick_list(
+            block_lengths(),
+            Some(state.rdiff_block_length),
+            Message::RdiffBlockLengthChanged,
+        )
+        .width(Length::Fill)
+        .style(purple_pick_list_style)
+    ]
+    .spacing(10);
+
+    let file_action = |label, message| {
+        button(label)
+            .on_press_maybe((state.rdiff_file.is_some() && !state.rdiff_running).then_some(message))
+            .style(purple_button_style)
+    };
+    let actions_row = row![
+        file_action("Create Signature...", Message::CreateRdiffSignature),
+        file_action("Create Delta...", Message::CreateRdiffDelta),
+        file_action("Apply Delta...", Message::ApplyRdiffDelta)
+    ]
+    .spacing(10);
+
+    let mut results_column = Column::new().spacing(8);
+
+    if state.rdiff_running {
+        results_column = results_column.push(
+            text("Working...")
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+
+    match &state.rdiff_result {
+        Some(Err(error)) => {
+            results_column = results_column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        Some(Ok(RdiffOutcome::Signature { path, format, block_length, blocks })) => {
+            results_column = results_column.push(
+                text(format!(
+                    "Signature saved to {} ({}, {} blocks of {} bytes)",
+                    path.display(),
+                    format,
+                    blocks,
+                    block_length
+                ))
+                .size(14)
+                .style(success_text_style)
+            );
+        }
+        Some(Ok(RdiffOutcome::Delta { path, stats })) => {
+            results_column = results_column.push(
+                text(format!("Delta saved to {} ({} bytes)", path.display(), stats.delta_size))
+                    .size(14)
+                    .style(success_text_style)
+            );
+            results_column = results_column.push(
+                text(format!(
+                    "{} bytes copied from the old file in {} commands, {} new bytes in {} literals",
+                    stats.copied_bytes, stats.copies, stats.literal_bytes, stats.literals
+                ))
+                .size(14)
+                .style(text_light_style)
+            );
+        }
+        Some(Ok(RdiffOutcome::Patched { path, size, hashes })) => {
+            results_column = results_column.push(
+                text(format!("Rebuilt {} ({} bytes)", path.display(), size))
+                    .size(14)
+                    .style(success_text_style)
+            );
+            for (algorithm, hex) in hashes {
+                let label = algorithm.label(&state.hash_options);
+                let line = match hex::decode(hex) {
+                    _ if algorithm.is_fuzzy() && !hex.starts_with("Error") => {
+                        text(format!("{}: {}", label, hex)).style(text_light_style)
+                    }
+                    Ok(bytes) => text(format!("{}: {}", label, state.result_format(algorithm).format_hash(&bytes)))
+                        .style(text_light_style),
+                    Err(_) => text(format!("{}: {}", label, hex)).style(error_text_style),
+                };
+                results_column = results_column.push(line.size(14));
+            }
+        }
+        None => {}
+    }
+
+    let content = column![
+        file_row,
+        hint,
+        options_row,
+        actions_row,
+        scrollable(results_column).height(Length::Fill)
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+pub fn view_similarity(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let path_text = |path: &Option<std::path::PathBuf>| {
+        path.as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "No file selected".to_string())
+    };
+
+    let first_row = row![
+        text("File:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select a file...", &path_text(&state.similarity_first))
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Browse...")
+            .on_press(Message::BrowseSimilarityFirst)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let second_row = row![
+        text("Compare with:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select a file...", &path_text(&state.similarity_second))
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Browse...")
+            .on_press(Message::BrowseSimilaritySecond)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let hash_row = row![
+        text("Or hash:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Paste an ssdeep or TLSH hash instead of a second file...", &state.similarity_hash)
+            .on_input(Message::SimilarityHashChanged)
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style)
+    ]
+    .spacing(10);
+
+    let ready = state.similarity_first.is_some()
+        && (state.similarity_second.is_some() || !state.similarity_hash.trim().is_empty())
+        && !state.similarity_running;
+    let compare_button = button("Compare")
+        .on_press_maybe(ready.then_some(Message::CompareSimilarity))
+        .style(purple_button_style);
+
+    let mut results_column = Column::new().spacing(8);
+
+    if state.similarity_running {
+        results_column = results_column.push(
+            text("Hashing...")
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+
+    match &state.similarity_result {
+        Some(Err(error)) => {
+            results_column = results_column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        Some(Ok(report)) => {
+            // Scores are shown for whichever digests both sides have
+            match &report.ssdeep_score {
+                Some(Ok(score)) => {
+                    results_column = results_column.push(
+                        text(format!("ssdeep similarity: {} / 100", score))
+                            .size(16)
+                            .style(success_text_style)
+                    );
+                }
+                Some(Err(e)) => {
+                    results_column = results_column.push(
+                        text(format!("ssdeep: {}", e))
+                            .size(14)
+                            .style(error_text_style)
+                    );
+                }
+                None => {}
+            }
+            match &report.tlsh_distance {
+                Some(Ok(distance)) => {
+                    results_column = results_column.push(
+                        text(format!(
+                            "TLSH similarity: {} / 100 (distance {})",
+                            SimilarityReport::tlsh_score(*distance),
+                            distance
+                        ))
+                        .size(16)
+                        .style(success_text_style)
+                    );
+                }
+                Some(Err(e)) => {
+                    results_column = results_column.push(
+                        text(format!("TLSH: {}", e))
+                            .size(14)
+                            .style(error_text_style)
+                    );
+                }
+                None => {}
+            }
+
+            for (side, digests) in [("First", &report.first), ("Second", &report.second)] {
+                results_column = results_column.push(fuzzy_digest_lines(side, digests));
+            }
+        }
+        None => {}
+    }
+
+    let content = column![
+        first_row,
+        second_row,
+        hash_row,
+        compare_button,
+        scrollable(results_column).height(Length::Fill)
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+fn fuzzy_digest_lines<'a>(side: &str, digests: &FuzzyDigests) -> Element<'a, Message, iced::Theme> {
+    let mut lines = Column::new().spacing(4);
+    for (name, digest) in [("ssdeep", &digests.ssdeep), ("TLSH", &digests.tlsh)] {
+        if let Some(digest) = digest {
+            lines = lines.push(
+                text(format!("{} {}: {}", side, name, digest))
+                    .size(14)
+                    .style(text_light_style)
+            );
+        }
+    }
+    lines.into()
+}
+
+pub fn view_images(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let path_text = |path: &Option<std::path::PathBuf>| {
+        path.as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "No image selected".to_string())
+    };
+
+    let first_row = row![
+        text("Image:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select an image...", &path_text(&state.image_first))
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Browse...")
+            .on_press(Message::BrowseImageFirst)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let second_row = row![
+        text("Compare with:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select an image...", &path_text(&state.image_second))
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Browse...")
+            .on_press(Message::BrowseImageSecond)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let hash_row = row![
+        text("Or hash:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Paste a 16-digit hex hash instead of a second image...", &state.image_hash)
+            .on_input(Message::ImageHashChanged)
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        pick_list(
+            crate::hash::perceptual::algorithms(),
+            Some(state.image_algorithm.clone()),
+            Message::ImageAlgorithmChanged,
+        )
+        .style(purple_pick_list_style)
+    ]
+    .spacing(10);
+
+    let ready = state.image_first.is_some()
+        && (state.image_second.is_some() || !state.image_hash.trim().is_empty())
+        && !state.image_running;
+    let compare_button = button("Compare")
+        .on_press_maybe(ready.then_some(Message::CompareImages))
+        .style(purple_button_style);
+
+    let mut results_column = Column::new().spacing(8);
+
+    if state.image_running {
+        results_column = results_column.push(
+            text("Decoding...")
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+
+    match &state.image_result {
+        Some(Err(error)) => {
+            results_column = results_column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        Some(Ok(distances)) => {
+            // A handful of differing bits is what re-encoding or resizing leaves behind
+            for row in distances {
+                let style = if row.distance <= NEAR_DUPLICATE_BITS { success_text_style } else { text_light_style };
+                results_column = results_column.push(
+                    text(format!(
+                        "{}: {:016x} vs {:016x}, {} of {} bits differ",
+                        row.algorithm, row.first, row.second, row.distance, HASH_BITS
+                    ))
+                    .size(14)
+                    .style(style)
+                );
+            }
+        }
+        None => {}
+    }
+
+    let content = column![
+        first_row,
+        second_row,
+        hash_row,
+        compare_button,
+        scrollable(results_column).height(Length::Fill)
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+pub fn view_binary(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let file_text = state
+        .binary_file
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "No file selected".to_string());
+
+    let file_row = row![
+        text("Executable:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select an ELF or PE file...", &file_text)
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Browse...")
+            .on_press(Message::BrowseBinaryFile)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let can_analyze = state.binary_file.is_some() && !state.binary_running;
+    let analyze_button = button("Analyze")
+        .on_press_maybe(can_analyze.then_some(Message::AnalyzeBinary))
+        .style(purple_button_style)
+        .width(Length::Shrink);
+
+    let mut results_column = Column::new().spacing(8);
+
+    if state.binary_running {
+        results_column = results_column.push(
+            text("Analyzing...")
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+
+    // Hashes go in read-only text inputs so they can be selected and copied
+    let hash_row = |label: &str, value: String| {
+        row![
+            text(label.to_string())
+                .size(14)
+                .width(Length::Fixed(120.0))
+                .style(text_light_style),
+            text_input("", &value)
+                .size(14)
+                .width(Length::Fill)
+                .style(dark_text_input_style)
+        ]
+        .spacing(10)
+    };
+
+    match &state.binary_result {
+        Some(Err(error)) => {
+            results_column = results_column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        Some(Ok(BinaryReport::Elf(report))) => {
+            results_column = results_column.push(
+                text(format!("{} {}, {} sections with contents", report.class, report.machine, report.sections.len()))
+                    .size(16)
+                    .style(text_light_style)
+            );
+            match &report.build_id {
+                Some(build_id) => results_column = results_column.push(hash_row("Build ID:", build_id.clone())),
+                None => {
+                    results_column = results_column.push(
+                        text("No GNU build ID note")
+                            .size(14)
+                            .style(text_light_style)
+                    );
+                }
+            }
+            for section in &report.sections {
+                let label = if section.name.is_empty() { "(unnamed)" } else { section.name.as_str() };
+                results_column = results_column.push(
+                    text(format!("{} - {} bytes at offset {}", label, section.size, section.offset))
+                        .size(14)
+                        .style(text_light_style)
+                );
+                results_column = results_column.push(hash_row("SHA-256:", section.sha256.clone()));
+            }
+        }
+        Some(Ok(BinaryReport::Pe(report))) => {
+            results_column = results_column.push(
+                text(format!("{} {}", report.format, report.machine))
+                    .size(16)
+                    .style(text_light_style)
+            );
+            // The certificate table is only detected; its signature is not checked
+            let signed = if report.signed {
+                text("Has an Authenticode signature (not verified)").style(success_text_style)
+            } else {
+                text("Not signed").style(text_light_style)
+            };
+            results_column = results_column.push(signed.size(14));
+            results_column = results_column.push(hash_row("Authentihash:", report.authentihash.clone()));
+            match &report.imphash {
+                Some(imphash) => {
+                    results_column = results_column.push(hash_row("Imphash:", imphash.clone()));
+                    results_column = results_column.push(
+                        text(format!("{} imported functions", report.imports))
+                            .size(14)
+                            .style(text_light_style)
+                    );
+                }
+                None => {
+                    results_column = results_column.push(
+                        text("No import table, so no imphash")
+                            .size(14)
+                            .style(text_light_style)
+                    );
+                }
+            }
+        }
+        None => {}
+    }
+
+    let content = column![
+        file_row,
+        analyze_button,
+        scrollable(results_column).height(Length::Fill)
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+// Large documents are cut short on screen; "Copy" still copies all of it
+const CANONICAL_JSON_PREVIEW: usize = 256 * 1024;
+
+pub fn view_canonical_json(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let copy_button = button("Copy")
+        .on_press_maybe(matches!(state.canonical_json, Some(Ok(_))).then_some(Message::CopyCanonicalJson))
+        .style(purple_button_style)
+        .width(Length::Shrink);
+
+    let mut results_column = Column::new().spacing(8);
+
+    match &state.canonical_json {
+        None => {
+            results_column = results_column.push(
+                text("Loading...")
+                    .size(14)
+                    .style(text_light_style)
+            );
+        }
+        Some(Err(error)) => {
+            results_column = results_column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        Some(Ok(canonical)) => {
+            results_column = results_column.push(
+                text(format!("{} bytes", canonical.len()))
+                    .size(14)
+                    .style(text_light_style)
+            );
+            let preview = if canonical.len() > CANONICAL_JSON_PREVIEW {
+                let mut end = CANONICAL_JSON_PREVIEW;
+                while !canonical.is_char_boundary(end) {
+                    end -= 1;
+                }
+                format!("{}...", &canonical[..end])
+            } else {
+                canonical.clone()
+            };
+            results_column = results_column.push(
+                text(preview)
+                    .size(14)
+                    .font(iced::Font::MONOSPACE)
+                    .style(text_light_style)
+            );
+        }
+    }
+
+    let content = column![
+        copy_button,
+        scrollable(results_column).height(Length::Fill)
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+pub fn view_archive(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let archive_text = state
+        .archive_path
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "No archive selected".to_string());
+
+    let archive_row = row![
+        text("Archive:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select a zip or tar archive...", &archive_text)
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Browse...")
+            .on_press_maybe((!state.archive_running).then_some(Message::BrowseArchive))
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let listed = matches!(state.archive_listing, Some(Ok(_))) && !state.archive_running;
+    let buttons_row = row![
+        button("Hash Selected")
+            .on_press_maybe((listed && !state.archive_selected.is_empty()).then_some(Message::HashSelectedMembers))
+            .style(purple_button_style),
+        button("Hash All")
+            .on_press_maybe(listed.then_some(Message::HashAllMembers))
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let mut members_column = Column::new().spacing(8);
+
+    if state.archive_running {
+        members_column = members_column.push(
+            text("Reading archive...")
+                .size(14)
+                .style(text_light_style)
+        );
+    }
+    if let Some(Err(error)) = &state.archive_results {
+        members_column = members_column.push(
+            text(format!("Error: {}", error))
+                .size(14)
+                .style(error_text_style)
+        );
+    }
+
+    match &state.archive_listing {
+        Some(Err(error)) => {
+            members_column = members_column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        Some(Ok(listing)) => {
+            let mismatches = match &state.archive_results {
+                Some(Ok(hashes)) => hashes.iter().filter(|hashes| hashes.crc_matches() == Some(false)).count(),
+                _ => 0,
+            };
+            let summary = if mismatches > 0 {
+                text(format!("{}, {} files - {} failed the CRC-32 check", listing.format, listing.members.len(), mismatches))
+                    .style(error_text_style)
+            } else {
+                text(format!("{}, {} files", listing.format, listing.members.len())).style(text_light_style)
+            };
+            members_column = members_column.push(summary.size(16));
+
+            for member in &listing.members {
+                let index = member.index;
+                members_column = members_column.push(
+                    checkbox(format!("{} ({} bytes)", member.name, member.size), state.archive_selected.contains(&index))
+                        .style(purple_checkbox_style)
+                        .on_toggle(move |checked| Message::ArchiveMemberToggled(index, checked))
+                );
+                let hashes = match &state.archive_results {
+                    Some(Ok(hashes)) => hashes.iter().find(|hashes| hashes.member.index == index),
+                    _ => None,
+                };
+                if let Some(hashes) = hashes {
+                    members_column = members_column.push(member_hashes(state, hashes));
+                }
+            }
+        }
+        None => {}
+    }
+
+    let content = column![
+        archive_row,
+        buttons_row,
+        scrollable(members_column).height(Length::Fill)
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+// Digests of one member, indented under its checkbox, and the outcome of the
+// CRC-32 check for zip members
+pub fn view_follow(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let file_text = state
+        .follow_file
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+
+    let file_row = row![
+        text("File:")
+            .size(16)
+            .style(text_light_style),
+        text_input("Select a log or capture file...", &file_text)
+            .size(16)
+            .width(Length::Fill)
+            .style(dark_text_input_style),
+        button("Browse...")
+            .on_press(Message::BrowseFollowFile)
+            .style(purple_button_style)
+    ]
+    .spacing(10);
+
+    let following = state.follower.is_some();
+    let action_button = if following {
+        button("Stop")
+            .on_press(Message::StopFollowing)
+            .style(purple_button_style)
+    } else {
+        button("Start")
+            .on_press_maybe(state.follow_file.as_ref().map(|_| Message::StartFollowing))
+            .style(purple_button_style)
+    };
+    let status = if following {
+        "Following: new data is hashed as it is appended (checked every second)"
+    } else {
+        "Stopped"
+    };
+    let action_row = row![
+        action_button,
+        text(status)
+            .size(14)
+            .style(text_light_style)
+    ]
+    .spacing(10)
+    .align_y(iced::alignment::Vertical::Center);
+
+    let mut results_column = Column::new().spacing(8);
+
+    if let Some(error) = &state.follow_error {
+        results_column = results_column.push(
+            text(format!("Error: {}", error))
+                .size(14)
+                .style(error_text_style)
+        );
+    }
+    if let Some(update) = &state.follow_update {
+        results_column = results_column.push(
+            text(format!("Hashed: {} bytes ({})", update.length, update.event))
+                .size(14)
+                .style(text_light_style)
+        );
+        if let Some(restart) = update.last_restart {
+            results_column = results_column.push(
+                text(format!("Hashing restarted {} time(s); the file was last {}", update.restarts, restart))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        for (algorithm, value) in update.results.iter().flatten() {
+            let shown = match hex::decode(value) {
+                Ok(bytes) if !algorithm.is_fuzzy() => state.result_format(algorithm).format_hash(&bytes),
+                _ => value.clone(),
+            };
+            results_column = results_column.push(
+                row![
+                    text(algorithm.label(&state.hash_options))
+                        .size(14)
+                        .width(Length::Fixed(120.0))
+                        .style(text_light_style),
+                    text_input("", &shown)
+                        .size(14)
+                        .width(Length::Fill)
+                        .style(dark_text_input_style)
+                ]
+                .spacing(10)
+            );
+        }
+    }
+
+    let content = column![
+        file_row,
+        action_row,
+        scrollable(results_column).height(Length::Fill)
+    ]
+    .spacing(15)
+    .padding(20)
+    .width(Length::Fill);
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+// Digests of each part of a split set, in the order the parts are joined
+pub fn view_split_parts(state: &RealOne) -> Element<'_, Message, iced::Theme> {
+    let mut parts_column = Column::new().spacing(8);
+
+    match &state.split_set {
+        Some(set) if !state.split_parts.is_empty() => {
+            parts_column = parts_column.push(
+                text(format!("{}: {} parts, {} bytes joined", set.name, set.parts.len(), set.total_size()))
+                    .size(16)
+                    .style(text_light_style)
+            );
+            for part in &state.split_parts {
+                let name = part
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| part.path.display().to_string());
+                parts_column = parts_column.push(
+                    text(format!("{} ({} bytes)", name, part.size))
+                        .size(14)
+                        .style(text_light_style)
+                );
+                let mut digests = Column::new().spacing(4).padding(iced::Padding::ZERO.left(30));
+                for (algorithm, value) in &part.results {
+                    let shown = match hex::decode(value) {
+                        Ok(bytes) if !algorithm.is_fuzzy() => state.result_format(algorithm).format_hash(&bytes),
+                        _ => value.clone(),
+                    };
+                    digests = digests.push(
+                        row![
+                            text(algorithm.label(&state.hash_options))
+                                .size(14)
+                                .width(Length::Fixed(120.0))
+                                .style(text_light_style),
+                            text_input("", &shown)
+                                .size(14)
+                                .width(Length::Fill)
+                                .style(dark_text_input_style)
+                        ]
+                        .spacing(10)
+                    );
+                }
+                parts_column = parts_column.push(digests);
+            }
+        }
+        _ => {
+            parts_column = parts_column.push(
+                text("Select one part of a split file (.001, .part01...) in the main window and click Check")
+                    .size(14)
+                    .style(text_light_style)
+            );
+        }
+    }
+
+    container(scrollable(parts_column).height(Length::Fill))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(20)
+        .style(dark_container_style)
+        .into()
+}
+
+fn member_hashes<'a>(state: &RealOne, hashes: &MemberHashes) -> Column<'a, Message, iced::Theme> {
+    let mut column = Column::new().spacing(4).padding(iced::Padding::ZERO.left(30));
+    match &hashes.results {
+        Ok(results) => {
+            for (algorithm, value) in results {
+                let shown = match hex::decode(value) {
+                    Ok(bytes) if !algorithm.is_fuzzy() => state.result_format(algorithm).format_hash(&bytes),
+                    _ => value.clone(),
+                };
+                column = column.push(
+                    row![
+                        text(algorithm.label(&state.hash_options))
+                            .size(14)
+                            .width(Length::Fixed(120.0))
+                            .style(text_light_style),
+                        text_input("", &shown)
+                            .size(14)
+                            .width(Length::Fill)
+                            .style(dark_text_input_style)
+                    ]
+                    .spacing(10)
+                );
+            }
+        }
+        Err(error) => {
+            column = column.push(
+                text(format!("Error: {}", error))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+    }
+    match (hashes.crc_matches(), hashes.member.stored_crc32, hashes.computed_crc32) {
+        (Some(true), _, _) => {
+            column = column.push(
+                text("Stored CRC-32 matches")
+                    .size(14)
+                    .style(success_text_style)
+            );
+        }
+        (Some(false), Some(stored), Some(computed)) => {
+            column = column.push(
+                text(format!("CRC-32 MISMATCH: stored {:08x}, contents give {:08x}", stored, computed))
+                    .size(14)
+                    .style(error_text_style)
+            );
+        }
+        _ => {}
+    }
+    column
+}
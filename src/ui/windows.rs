@@ -1,7 +1,7 @@
 use iced_multi_window::Window;
 use crate::state::{RealOne, Message};
 use crate::ui::theme::custom_dark_theme;
-use crate::ui::views::{view_main, view_settings};
+use crate::ui::views::{view_archive, view_binary, view_canonical_json, view_cdc, view_follow, view_images, view_main, view_oci, view_piecewise, view_rdiff, view_settings, view_similarity, view_split_parts, view_torrent};
 
 #[derive(Debug, Clone)]
 pub struct MainWindow;
@@ -21,7 +21,7 @@ impl Window<RealOne, iced::Theme, Message> for MainWindow {
 
     fn settings(&self) -> iced::window::Settings {
         iced::window::Settings {
-            size: iced::Size::new(800.0, 410.0),
+            size: iced::Size::new(800.0, 520.0),
             ..Default::default()
         }
     }
@@ -51,3 +51,291 @@ impl Window<RealOne, iced::Theme, Message> for SettingsWindow {
     }
 }
 
+
+#[derive(Debug, Clone)]
+pub struct TorrentWindow;
+
+impl Window<RealOne, iced::Theme, Message> for TorrentWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_torrent(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Torrent Verification - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(700.0, 560.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OciWindow;
+
+impl Window<RealOne, iced::Theme, Message> for OciWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_oci(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "OCI Image Layout Verification - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(760.0, 560.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CdcWindow;
+
+impl Window<RealOne, iced::Theme, Message> for CdcWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_cdc(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Chunking Analysis - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(760.0, 560.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RdiffWindow;
+
+impl Window<RealOne, iced::Theme, Message> for RdiffWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_rdiff(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Signatures and Deltas - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(760.0, 480.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimilarityWindow;
+
+impl Window<RealOne, iced::Theme, Message> for SimilarityWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_similarity(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Similarity - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(760.0, 440.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageCompareWindow;
+
+impl Window<RealOne, iced::Theme, Message> for ImageCompareWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_images(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Image Comparison - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(760.0, 440.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BinaryWindow;
+
+impl Window<RealOne, iced::Theme, Message> for BinaryWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_binary(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Executable Analysis - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(760.0, 560.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CanonicalJsonWindow;
+
+impl Window<RealOne, iced::Theme, Message> for CanonicalJsonWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_canonical_json(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Canonical JSON - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(760.0, 480.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitPartsWindow;
+
+impl Window<RealOne, iced::Theme, Message> for SplitPartsWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_split_parts(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Split Parts - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(760.0, 520.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FollowWindow;
+
+impl Window<RealOne, iced::Theme, Message> for FollowWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_follow(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Follow Growing File - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(760.0, 480.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveWindow;
+
+impl Window<RealOne, iced::Theme, Message> for ArchiveWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_archive(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Archive Members - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(800.0, 600.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PiecewiseWindow;
+
+impl Window<RealOne, iced::Theme, Message> for PiecewiseWindow {
+    fn view<'a>(&'a self, app: &'a RealOne) -> iced::Element<'a, Message, iced::Theme> {
+        view_piecewise(app)
+    }
+
+    fn title(&self, _app: &RealOne) -> String {
+        "Piecewise Hashing - Real One".to_string()
+    }
+
+    fn theme(&self, _app: &RealOne) -> iced::Theme {
+        custom_dark_theme()
+    }
+
+    fn settings(&self) -> iced::window::Settings {
+        iced::window::Settings {
+            size: iced::Size::new(700.0, 520.0),
+            ..Default::default()
+        }
+    }
+}